@@ -0,0 +1,79 @@
+use crate::syntax::{
+    CustomFieldType, Field, FieldType, Output, ParseResult, RepackError, RepackErrorKind,
+    RepackStruct,
+};
+
+use super::Blueprint;
+
+/// The schema features a blueprint can list in `[meta supports]`.
+pub const FEATURES: [&str; 6] = [
+    "arrays",
+    "optionals",
+    "enums",
+    "nested",
+    "unions",
+    "array_columns",
+];
+
+/// Whether a field uses a feature, by the feature's name in `[meta supports]`.
+fn uses(feature: &str, strct: &RepackStruct, field: &Field) -> bool {
+    let custom = match &field.field_type {
+        Some(FieldType::Custom(_, typ)) => Some(typ),
+        _ => None,
+    };
+    match feature {
+        "arrays" => field.array,
+        "optionals" => field.optional,
+        "enums" => matches!(custom, Some(CustomFieldType::Enum)),
+        "nested" => matches!(custom, Some(CustomFieldType::Object)),
+        "unions" => matches!(custom, Some(CustomFieldType::Union)),
+        "array_columns" => field.array && strct.table_name.is_some(),
+        _ => false,
+    }
+}
+
+/// Finds the schema features an output uses that its blueprint does not support.
+///
+/// A blueprint without `[meta supports]` is taken to support every feature.
+/// Otherwise each feature it leaves out is checked against the fields of the
+/// structs the output includes, so every unsupported feature is reported
+/// before anything is rendered.
+///
+/// # Returns
+/// One error for each unsupported feature, listing the fields that use it
+pub fn unsupported_features(
+    blueprint: &Blueprint,
+    parse_result: &ParseResult,
+    config: &Output,
+) -> Vec<RepackError> {
+    if blueprint.supports.is_empty() {
+        return Vec::new();
+    }
+    let strcts = parse_result.included_strcts(config.categories.as_ref(), &config.exclude);
+    let mut errors = Vec::new();
+    for feature in FEATURES.iter().filter(|f| !blueprint.supports(f)) {
+        let used_by = strcts
+            .iter()
+            .flat_map(|strct| {
+                strct
+                    .fields
+                    .iter()
+                    .filter(|field| field.included_in(config.categories.as_ref()))
+                    .filter(|field| uses(feature, strct, field))
+                    .map(|field| format!("{}.{}", strct.name, field.name))
+            })
+            .collect::<Vec<_>>();
+        if !used_by.is_empty() {
+            errors.push(RepackError::from_lang_with_msg(
+                RepackErrorKind::FeatureNotSupported,
+                config,
+                format!(
+                    "{feature} not supported by '{}' (used by {})",
+                    blueprint.id,
+                    used_by.join(", ")
+                ),
+            ));
+        }
+    }
+    errors
+}
@@ -0,0 +1,204 @@
+use crate::syntax::{CoreType, RepackError, RepackErrorKind};
+
+use super::{
+    Blueprint, BlueprintFileReader, BlueprintKind, BlueprintSnippetDetails, BlueprintToken,
+    FEATURES, SnippetMainTokenName, SnippetSecondaryTokenName,
+};
+
+/// Checks a blueprint for mistakes that would otherwise only show up when it
+/// is rendered, without needing a schema.
+///
+/// Reports blocks that are never closed, close tags that match no open block,
+/// `[else]` outside an `[if]`, tags that are not recognized, loops over
+/// unknown collections, and `[import]`s and `[render]`s naming links and
+/// snippets the blueprint does not define. Unknown features in
+/// `[meta supports]` get a warning. A build blueprint that leaves out a
+/// `[define]` for a core type gets a warning, since any field of that type
+/// fails to render.
+///
+/// Unknown tags, unclosed blocks, close tags that match no open block, and a
+/// misplaced `[else]` are ignored when rendering unless `--strict` is given,
+/// so they are only warnings when `strict` is false.
+///
+/// # Returns
+/// Every problem found, in the order it appears in the blueprint
+pub fn check_blueprint(contents: &[u8], strict: bool) -> Vec<RepackError> {
+    let ignored = |e: RepackError| if strict { e } else { e.warning() };
+    let blueprint = match Blueprint::new(BlueprintFileReader::new(contents)) {
+        Ok(blueprint) => blueprint,
+        Err(e) => return vec![e],
+    };
+    let mut reader = BlueprintFileReader::new(contents);
+    let mut tokens = Vec::new();
+    while let Some(token) = reader.next() {
+        tokens.push(token);
+    }
+
+    let mut errors = Vec::new();
+    let mut open: Vec<&BlueprintSnippetDetails> = Vec::new();
+    for (idx, token) in tokens.iter().enumerate() {
+        match token {
+            BlueprintToken::Snippet(snip) => {
+                let main = snip.main_token_name();
+                let found = match &main {
+                    SnippetMainTokenName::Variable(name)
+                        if name.is_empty()
+                            || (!name.contains('|') && !snip.secondary_token.is_empty()) =>
+                    {
+                        Some(ignored(RepackError::global(
+                            RepackErrorKind::UnknownBlueprintTag,
+                            format!("[{} {}]", snip.main_token, snip.secondary_token),
+                        )))
+                    }
+                    SnippetMainTokenName::Ref => Some(ignored(RepackError::global(
+                        RepackErrorKind::UnknownBlueprintTag,
+                        format!("[{}] (not used when rendering)", snip.main_token),
+                    ))),
+                    SnippetMainTokenName::Each
+                    | SnippetMainTokenName::Eachr
+                    | SnippetMainTokenName::FilePer
+                        if !is_collection(&snip.secondary_token) =>
+                    {
+                        Some(RepackError::global(
+                            RepackErrorKind::UnknownBlueprintTag,
+                            format!(
+                                "[{} {}] ('{}' is not a collection)",
+                                snip.main_token, snip.secondary_token, snip.secondary_token
+                            ),
+                        ))
+                    }
+                    SnippetMainTokenName::FilePer if snip.positional.is_empty() => {
+                        Some(RepackError::global(
+                            RepackErrorKind::ParseIncomplete,
+                            "file name pattern in file_per".to_string(),
+                        ))
+                    }
+                    SnippetMainTokenName::Else
+                        if !open.last().is_some_and(|o| {
+                            matches!(o.main_token.as_str(), "if" | "ifn" | "match")
+                        }) =>
+                    {
+                        Some(ignored(RepackError::global(
+                            RepackErrorKind::SyntaxError,
+                            "[else] outside an [if], [ifn], or [match] block".to_string(),
+                        )))
+                    }
+                    SnippetMainTokenName::Import
+                        if !blueprint.links.contains_key(&snip.secondary_token) =>
+                    {
+                        Some(RepackError::global(
+                            RepackErrorKind::UnknownLink,
+                            snip.secondary_token.to_string(),
+                        ))
+                    }
+                    SnippetMainTokenName::Render => {
+                        literal_block(&tokens[idx + 1..], &snip.main_token)
+                            .filter(|name| !blueprint.snippets.contains_key(name))
+                            .map(|name| RepackError::global(RepackErrorKind::UnknownSnippet, name))
+                    }
+                    _ => None,
+                };
+                if let Some(mut e) = found {
+                    e.add_to_stack(snip);
+                    errors.push(e);
+                }
+                if !snip.autoclose {
+                    open.push(snip);
+                }
+            }
+            BlueprintToken::Close(name) => match open.iter().rposition(|o| o.main_token == *name) {
+                Some(at) => {
+                    for unclosed in open.drain(at..).skip(1) {
+                        errors.push(ignored(not_closed(unclosed)));
+                    }
+                }
+                None => errors.push(ignored(RepackError::global(
+                    RepackErrorKind::SyntaxError,
+                    format!("[/{name}] does not close an open block"),
+                ))),
+            },
+            _ => {}
+        }
+    }
+    for unclosed in open.into_iter().rev() {
+        errors.push(ignored(not_closed(unclosed)));
+    }
+
+    for feature in &blueprint.supports {
+        if !FEATURES.contains(&feature.as_str()) {
+            errors.push(
+                RepackError::global(
+                    RepackErrorKind::SyntaxError,
+                    format!("supports: unknown feature '{feature}'"),
+                )
+                .warning(),
+            );
+        }
+    }
+
+    if blueprint.kind == BlueprintKind::Build {
+        for typ in CoreType::ALL {
+            let key = (
+                SnippetMainTokenName::TypeDef,
+                SnippetSecondaryTokenName::from_type(&typ),
+            );
+            if !blueprint.utilities.contains_key(&key) {
+                errors.push(
+                    RepackError::global(
+                        RepackErrorKind::TypeNotSupported,
+                        format!("{typ} (no [define {typ}])"),
+                    )
+                    .warning(),
+                );
+            }
+        }
+    }
+    errors
+}
+
+fn not_closed(snip: &BlueprintSnippetDetails) -> RepackError {
+    let mut e = RepackError::global(
+        RepackErrorKind::SnippetNotClosed,
+        snip.main_token.to_string(),
+    );
+    e.add_to_stack(snip);
+    e
+}
+
+/// Whether `[each name]` loops over something the renderer knows.
+fn is_collection(name: &str) -> bool {
+    matches!(
+        SnippetSecondaryTokenName::from_string(name),
+        SnippetSecondaryTokenName::Struct
+            | SnippetSecondaryTokenName::Field
+            | SnippetSecondaryTokenName::Subfield
+            | SnippetSecondaryTokenName::Query
+            | SnippetSecondaryTokenName::Enum
+            | SnippetSecondaryTokenName::Category
+            | SnippetSecondaryTokenName::Join
+            | SnippetSecondaryTokenName::Config
+            | SnippetSecondaryTokenName::Case
+            | SnippetSecondaryTokenName::Union
+            | SnippetSecondaryTokenName::Variant
+            | SnippetSecondaryTokenName::Const
+            | SnippetSecondaryTokenName::Validation
+            | SnippetSecondaryTokenName::Arg
+            | SnippetSecondaryTokenName::File
+    )
+}
+
+/// The text of a block, if it holds nothing but text up to its close tag.
+///
+/// `[render]` names are only known ahead of time when they are written out;
+/// a name built from variables is checked when the blueprint is rendered.
+fn literal_block(tokens: &[BlueprintToken], main_token: &str) -> Option<String> {
+    let mut text = String::new();
+    for token in tokens {
+        match token {
+            BlueprintToken::Literal(val) | BlueprintToken::Raw(val) => text.push_str(val),
+            BlueprintToken::Close(name) if name == main_token => return Some(text),
+            _ => return None,
+        }
+    }
+    None
+}
@@ -0,0 +1,189 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::syntax::{Output, ParseResult, RepackError, RepackErrorKind};
+
+use super::{
+    Blueprint, BlueprintToken, SnippetMainTokenName, SnippetSecondaryTokenName, apply_modifier,
+    pattern_tokens,
+};
+
+/// The names a `[name]` tag stands for, decided by the loop it is in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum NameScope {
+    /// Struct, enum, and union names, which share one namespace in most languages
+    Type,
+    /// The field names of one struct
+    Field,
+    /// The case names of one enum
+    Case,
+}
+impl NameScope {
+    fn for_loop(collection: &str) -> Option<NameScope> {
+        match SnippetSecondaryTokenName::from_string(collection) {
+            SnippetSecondaryTokenName::Struct
+            | SnippetSecondaryTokenName::Enum
+            | SnippetSecondaryTokenName::Union => Some(NameScope::Type),
+            SnippetSecondaryTokenName::Field => Some(NameScope::Field),
+            SnippetSecondaryTokenName::Case => Some(NameScope::Case),
+            _ => None,
+        }
+    }
+}
+
+/// Finds names that differ in the schema but are written the same by an output.
+///
+/// Each chain of modifiers the blueprint applies to `[name]` inside a loop over
+/// structs, enums, or unions, fields, or enum cases (such as `[name.snakecase]`) is
+/// applied to every name that loop covers, so `UserAccount` and `user_account`
+/// collide in an output that writes `[name.snakecase]` for structs. Fields are
+/// compared within their struct, and cases within their enum.
+///
+/// # Returns
+/// One error for each set of names that collide
+pub fn name_collisions(
+    blueprint: &Blueprint,
+    parse_result: &ParseResult,
+    config: &Output,
+) -> Vec<RepackError> {
+    let chains = modifier_chains(&blueprint.tokens);
+    let mut targets = vec![config.profile.as_str(), blueprint.id.as_str()];
+    targets.extend(blueprint.aliases.iter().map(|a| a.as_str()));
+
+    let strcts = parse_result.included_strcts(config.categories.as_ref(), &config.exclude);
+    let enums = parse_result.included_enums(config.categories.as_ref(), &config.exclude);
+    let unions = parse_result.included_unions(config.categories.as_ref(), &config.exclude);
+    let mut groups: Vec<(NameScope, String, Vec<&str>)> = Vec::new();
+    groups.push((
+        NameScope::Type,
+        "struct, enum, and union names".to_string(),
+        strcts
+            .iter()
+            .map(|s| s.name.as_str())
+            .chain(enums.iter().map(|e| e.name.as_str()))
+            .chain(unions.iter().map(|u| u.name.as_str()))
+            .collect(),
+    ));
+    for strct in &strcts {
+        groups.push((
+            NameScope::Field,
+            format!("fields of {}", strct.name),
+            strct
+                .fields
+                .iter()
+                .filter(|field| field.included_in(config.categories.as_ref()))
+                .map(|field| field.name_for(&targets))
+                .collect(),
+        ));
+    }
+    for enm in &enums {
+        groups.push((
+            NameScope::Case,
+            format!("cases of {}", enm.name),
+            enm.options.iter().map(|case| case.name.as_str()).collect(),
+        ));
+    }
+
+    let mut errors = Vec::new();
+    for (scope, description, names) in groups {
+        'chains: for (_, chain) in chains.iter().filter(|(s, _)| *s == scope) {
+            let mut written: HashMap<String, BTreeSet<&str>> = HashMap::new();
+            for name in &names {
+                let Some(value) = chain
+                    .iter()
+                    .try_fold(name.to_string(), |value, m| apply_modifier(&value, m))
+                else {
+                    // An unknown modifier fails when the blueprint is rendered.
+                    continue 'chains;
+                };
+                written.entry(value).or_default().insert(name);
+            }
+            let mut collisions = written
+                .into_iter()
+                .filter(|(_, names)| names.len() > 1)
+                .collect::<Vec<_>>();
+            collisions.sort();
+            for (value, names) in collisions {
+                errors.push(RepackError::from_lang_with_msg(
+                    RepackErrorKind::NameCollision,
+                    config,
+                    format!(
+                        "{} become '{value}' with [name.{}] ({description})",
+                        names.into_iter().collect::<Vec<_>>().join(", "),
+                        chain.join(".")
+                    ),
+                ));
+            }
+        }
+    }
+    errors
+}
+
+/// Lists the modifier chains applied to `[name]` in each scope, such as
+/// `["snakecase"]` for `[name.snakecase]`.
+///
+/// Tags outside a loop over structs, enums, fields, or cases are skipped, as
+/// is a bare `[name]`, which writes every name as it is.
+fn modifier_chains(tokens: &[BlueprintToken]) -> BTreeSet<(NameScope, Vec<String>)> {
+    let mut chains = BTreeSet::new();
+    // Open blocks, with the scope of each loop; loops over anything else have none.
+    let mut open: Vec<(&str, Option<Option<NameScope>>)> = Vec::new();
+    for token in tokens {
+        match token {
+            BlueprintToken::Snippet(snip) => {
+                let main = snip.main_token_name();
+                let scope = open.iter().rev().find_map(|(_, scope)| *scope).flatten();
+                match &main {
+                    SnippetMainTokenName::Variable(var) => {
+                        if let Some(scope) = scope {
+                            add_chain(&mut chains, scope, var);
+                        }
+                    }
+                    SnippetMainTokenName::FilePer => {
+                        // The file name pattern is rendered for each item of the loop.
+                        let scope = NameScope::for_loop(&snip.secondary_token);
+                        for pattern in snip.positional.iter().take(1) {
+                            for token in pattern_tokens(pattern) {
+                                if let (BlueprintToken::Snippet(part), Some(scope)) = (token, scope)
+                                {
+                                    add_chain(&mut chains, scope, &part.main_token);
+                                }
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+                if !snip.autoclose {
+                    let scope = match main {
+                        SnippetMainTokenName::Each
+                        | SnippetMainTokenName::Eachr
+                        | SnippetMainTokenName::FilePer => {
+                            Some(NameScope::for_loop(&snip.secondary_token))
+                        }
+                        _ => None,
+                    };
+                    open.push((&snip.main_token, scope));
+                }
+            }
+            BlueprintToken::Close(name) => {
+                if let Some(at) = open.iter().rposition(|(main, _)| main == name) {
+                    open.truncate(at);
+                }
+            }
+            _ => {}
+        }
+    }
+    chains
+}
+
+/// Records the modifiers of a variable tag if it is `name` with at least one.
+fn add_chain(chains: &mut BTreeSet<(NameScope, Vec<String>)>, scope: NameScope, var: &str) {
+    let var = var.split('|').next().unwrap_or_default();
+    let mut parts = var.split('.');
+    if parts.next() != Some("name") {
+        return;
+    }
+    let chain = parts.map(|p| p.to_string()).collect::<Vec<_>>();
+    if !chain.is_empty() {
+        chains.insert((scope, chain));
+    }
+}
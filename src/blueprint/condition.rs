@@ -0,0 +1,196 @@
+/// Comparison operators available inside `[if ...]` and `[ifn ...]` snippets.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ComparisonOperator {
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+}
+
+/// A value on either side of a conditional comparison.
+///
+/// Variables are resolved against the execution context at render time, so
+/// the same parsed condition can be evaluated for every iteration of a loop.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConditionOperand {
+    /// A name resolved at render time (variable, flag, `opt.key`, or `field.count`)
+    Variable(String),
+    /// A double-quoted string literal
+    Text(String),
+    /// An integer literal
+    Number(i64),
+    Add(Box<ConditionOperand>, Box<ConditionOperand>),
+    Subtract(Box<ConditionOperand>, Box<ConditionOperand>),
+}
+
+/// A parsed conditional expression attached to `if`/`ifn` snippets.
+///
+/// Conditions are parsed once when the blueprint is read, rather than on
+/// every evaluation, and syntax errors are reported when the blueprint loads.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlueprintCondition {
+    /// A bare name such as `[if optional]` or `[if opt.package]`
+    Flag(String),
+    /// A comparison such as `[if field.count > 0]` or `[if name == "User"]`
+    Compare(ConditionOperand, ComparisonOperator, ConditionOperand),
+    /// The condition could not be parsed; holds a description of the problem
+    Invalid(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ConditionToken {
+    Word(String),
+    Text(String),
+    Number(i64),
+    Plus,
+    Minus,
+    Comparison(ComparisonOperator),
+}
+
+impl BlueprintCondition {
+    /// Parses the condition of an `if`/`ifn` snippet.
+    ///
+    /// The first word of the snippet (its secondary token) is combined with the
+    /// remaining snippet contents. When the contents don't continue with an
+    /// operator the snippet is treated as a plain flag check, leaving the
+    /// contents to be written as before.
+    ///
+    /// # Arguments
+    /// * `secondary` - The secondary token of the snippet
+    /// * `contents` - Everything after the secondary token
+    ///
+    /// # Returns
+    /// The parsed condition, or `Invalid` describing why parsing failed
+    pub fn parse(secondary: &str, contents: &str) -> BlueprintCondition {
+        let rest = contents.trim_start();
+        let continues_expression = ["==", "!=", ">", "<", "+", "-"]
+            .iter()
+            .any(|op| rest.starts_with(op));
+        if !continues_expression {
+            return BlueprintCondition::Flag(secondary.to_string());
+        }
+
+        let source = format!("{secondary} {contents}");
+        let tokens = match Self::tokenize(&source) {
+            Ok(tokens) => tokens,
+            Err(e) => return BlueprintCondition::Invalid(e),
+        };
+
+        let mut index = 0;
+        let left = match Self::parse_operand(&tokens, &mut index) {
+            Ok(val) => val,
+            Err(e) => return BlueprintCondition::Invalid(e),
+        };
+        let Some(ConditionToken::Comparison(op)) = tokens.get(index) else {
+            return BlueprintCondition::Invalid(format!("expected a comparison in '{source}'"));
+        };
+        index += 1;
+        let right = match Self::parse_operand(&tokens, &mut index) {
+            Ok(val) => val,
+            Err(e) => return BlueprintCondition::Invalid(e),
+        };
+        if index < tokens.len() {
+            return BlueprintCondition::Invalid(format!("unexpected input after '{source}'"));
+        }
+        BlueprintCondition::Compare(left, op.clone(), right)
+    }
+
+    fn parse_operand(
+        tokens: &[ConditionToken],
+        index: &mut usize,
+    ) -> Result<ConditionOperand, String> {
+        let mut value = Self::parse_atom(tokens, index)?;
+        loop {
+            match tokens.get(*index) {
+                Some(ConditionToken::Plus) => {
+                    *index += 1;
+                    let rhs = Self::parse_atom(tokens, index)?;
+                    value = ConditionOperand::Add(Box::new(value), Box::new(rhs));
+                }
+                Some(ConditionToken::Minus) => {
+                    *index += 1;
+                    let rhs = Self::parse_atom(tokens, index)?;
+                    value = ConditionOperand::Subtract(Box::new(value), Box::new(rhs));
+                }
+                _ => break,
+            }
+        }
+        Ok(value)
+    }
+
+    fn parse_atom(
+        tokens: &[ConditionToken],
+        index: &mut usize,
+    ) -> Result<ConditionOperand, String> {
+        let atom = match tokens.get(*index) {
+            Some(ConditionToken::Word(w)) => ConditionOperand::Variable(w.clone()),
+            Some(ConditionToken::Text(t)) => ConditionOperand::Text(t.clone()),
+            Some(ConditionToken::Number(n)) => ConditionOperand::Number(*n),
+            Some(other) => return Err(format!("expected a value, found {other:?}")),
+            None => return Err("expected a value at end of condition".to_string()),
+        };
+        *index += 1;
+        Ok(atom)
+    }
+
+    fn tokenize(source: &str) -> Result<Vec<ConditionToken>, String> {
+        let mut tokens = Vec::new();
+        let mut chars = source.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                ' ' | '\t' | '\n' => {}
+                '"' => {
+                    let mut text = String::new();
+                    let mut closed = false;
+                    for n in chars.by_ref() {
+                        if n == '"' {
+                            closed = true;
+                            break;
+                        }
+                        text.push(n);
+                    }
+                    if !closed {
+                        return Err(format!("unterminated string in '{source}'"));
+                    }
+                    tokens.push(ConditionToken::Text(text));
+                }
+                '+' => tokens.push(ConditionToken::Plus),
+                '-' => tokens.push(ConditionToken::Minus),
+                '=' | '!' | '>' | '<' => {
+                    let has_eq = chars.peek() == Some(&'=');
+                    if has_eq {
+                        chars.next();
+                    }
+                    let op = match (c, has_eq) {
+                        ('=', true) => ComparisonOperator::Equal,
+                        ('!', true) => ComparisonOperator::NotEqual,
+                        ('>', true) => ComparisonOperator::GreaterOrEqual,
+                        ('<', true) => ComparisonOperator::LessOrEqual,
+                        ('>', false) => ComparisonOperator::Greater,
+                        ('<', false) => ComparisonOperator::Less,
+                        _ => return Err(format!("unknown operator '{c}' in '{source}'")),
+                    };
+                    tokens.push(ConditionToken::Comparison(op));
+                }
+                _ => {
+                    let mut word = String::from(c);
+                    while let Some(n) = chars.peek() {
+                        if n.is_alphanumeric() || *n == '_' || *n == '.' {
+                            word.push(*n);
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                    match word.parse::<i64>() {
+                        Ok(num) => tokens.push(ConditionToken::Number(num)),
+                        Err(_) => tokens.push(ConditionToken::Word(word)),
+                    }
+                }
+            }
+        }
+        Ok(tokens)
+    }
+}
@@ -1,14 +1,20 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::OsStr,
+    path::Path,
+};
 
 use crate::syntax::{
-    CoreType, CustomFieldType, Field, FieldType, Output, ParseResult, Query, QueryArg, QueryReturn,
-    RepackEnum, RepackEnumCase, RepackError, RepackErrorKind, RepackStruct,
+    ConfigEntry, CoreType, CustomFieldType, Field, FieldFunction, FieldType, Output, ParseResult,
+    Query, QueryArg, QueryKind, QueryReturn, RepackConfig, RepackConst, RepackEnum, RepackEnumCase,
+    RepackError, RepackErrorKind, RepackStruct, RepackStructJoin, RepackUnion,
 };
 
 use super::{Blueprint, SnippetMainTokenName, SnippetSecondaryTokenName};
 
 pub(crate) trait TokenConsumer {
     fn set_file_name(&mut self, filename: &str);
+    fn use_markers(&mut self);
     fn import_point(&mut self);
     fn write(&mut self, value: &dyn AsRef<str>);
     fn delete_trailing(&mut self, value: &dyn AsRef<str>);
@@ -18,6 +24,7 @@ impl TokenConsumer for HashSet<String> {
     fn set_file_name(&mut self, filename: &str) {
         self.insert(filename.to_string());
     }
+    fn use_markers(&mut self) {}
     fn delete_trailing(&mut self, _value: &dyn AsRef<str>) {}
     fn write(&mut self, _value: &dyn AsRef<str>) {}
     fn import(&mut self, _value: String) {}
@@ -25,6 +32,7 @@ impl TokenConsumer for HashSet<String> {
 }
 impl TokenConsumer for String {
     fn set_file_name(&mut self, _filename: &str) {}
+    fn use_markers(&mut self) {}
     fn write(&mut self, value: &dyn AsRef<str>) {
         self.push_str(value.as_ref());
     }
@@ -51,8 +59,16 @@ pub(crate) struct BlueprintExecutionContext<'a> {
     pub strct: Option<&'a RepackStruct>,
     pub field: Option<&'a Field>,
     pub enm: Option<&'a RepackEnum>,
+    pub union: Option<&'a RepackUnion>,
+    pub cnst: Option<&'a RepackConst>,
     pub func_args: Option<&'a Vec<String>>,
     pub query: Option<&'a Query>,
+    pub config: Option<&'a RepackConfig>,
+    /// Set inside `[each category]`; restricts struct and enum loops to this category.
+    pub category: Option<String>,
+    /// Imports required by the item this context was created for. These are
+    /// only written once the item is known to be rendered.
+    pub imports: Vec<String>,
 }
 impl<'a> BlueprintExecutionContext<'a> {
     pub fn new() -> BlueprintExecutionContext<'a> {
@@ -62,8 +78,13 @@ impl<'a> BlueprintExecutionContext<'a> {
             strct: None,
             field: None,
             enm: None,
+            union: None,
+            cnst: None,
             func_args: None,
             query: None,
+            config: None,
+            category: None,
+            imports: Vec::new(),
         }
     }
     pub fn with_strct(&self, obj: &'a RepackStruct) -> Self {
@@ -74,6 +95,14 @@ impl<'a> BlueprintExecutionContext<'a> {
             variables.insert("table_name".to_string(), tn.to_string());
         }
         flags.insert("queries", !obj.queries.is_empty());
+        flags.insert("routes", obj.queries.iter().any(|q| q.http.is_some()));
+        flags.insert("table", obj.table_name.is_some());
+        flags.insert("inherits", obj.inherits.is_some());
+        flags.insert("has_timestamps", obj.has_timestamps());
+        flags.insert(
+            "has_sensitive",
+            obj.fields.iter().any(|field| field.is_sensitive()),
+        );
 
         Self {
             variables,
@@ -88,16 +117,22 @@ impl<'a> BlueprintExecutionContext<'a> {
         field: &'a Field,
         blueprint: &'a Blueprint,
         config: &Output,
-        writer: &mut dyn TokenConsumer,
     ) -> Result<Self, RepackError> {
         let mut variables = self.variables.clone();
         let mut flags = self.flags.clone();
+        let mut imports = Vec::new();
+        let mut targets = vec![config.profile.as_str(), blueprint.id.as_str()];
+        targets.extend(blueprint.aliases.iter().map(|a| a.as_str()));
+        let type_override = field.type_override(&targets);
 
         let (resolved_type, resolved_entity_type) = match field.field_type.as_ref() {
-            Some(field_type) => match field_type {
-                FieldType::Core(typ) => {
+            Some(field_type) => match (field_type, type_override) {
+                // An overridden type is written as given, without the blueprint's imports.
+                (FieldType::Core(_), Some(typ)) => (typ, None),
+                (FieldType::Custom(_, ent_typ), Some(typ)) => (typ, Some(ent_typ)),
+                (FieldType::Core(typ), None) => {
                     if let Some(link) = blueprint.links.get(&typ.to_string()) {
-                        writer.import(link.replace("$", &typ.to_string()))
+                        imports.push(link.replace("$", &typ.to_string()))
                     }
                     (
                         blueprint
@@ -112,17 +147,18 @@ impl<'a> BlueprintExecutionContext<'a> {
                                     config,
                                     obj,
                                     field,
-                                    typ.to_string(),
+                                    unmapped_type(blueprint, typ),
                                 )
-                            })?,
+                            })?
+                            .as_str(),
                         None,
                     )
                 }
-                FieldType::Custom(typ, ent_typ) => {
+                (FieldType::Custom(typ, ent_typ), None) => {
                     if let Some(link) = blueprint.links.get("custom") {
-                        writer.import(link.replace("$", typ))
+                        imports.push(link.replace("$", typ))
                     }
-                    (typ, Some(ent_typ))
+                    (typ.as_str(), Some(ent_typ))
                 }
             },
             None => {
@@ -135,7 +171,18 @@ impl<'a> BlueprintExecutionContext<'a> {
         };
 
         variables.insert("struct_name".to_string(), obj.name.to_string());
-        variables.insert("name".to_string(), field.name.to_string());
+        let name = field.name_for(&targets);
+        let identifier = blueprint.identifier(name).ok_or_else(|| {
+            RepackError::from_lang_with_obj_field_msg(
+                RepackErrorKind::ReservedWord,
+                config,
+                obj,
+                field,
+                name.to_string(),
+            )
+        })?;
+        variables.insert("name".to_string(), identifier);
+        variables.insert("name_raw".to_string(), name.to_string());
         variables.insert("type".to_string(), resolved_type.to_string());
         variables.insert(
             "type_raw".to_string(),
@@ -155,13 +202,28 @@ impl<'a> BlueprintExecutionContext<'a> {
             "object",
             matches!(resolved_entity_type, Some(CustomFieldType::Object)),
         );
-        flags.insert("core", matches!(resolved_entity_type, None));
+        flags.insert(
+            "union",
+            matches!(resolved_entity_type, Some(CustomFieldType::Union)),
+        );
+        flags.insert("core", resolved_entity_type.is_none());
+        if matches!(resolved_entity_type, Some(CustomFieldType::Object)) {
+            variables.insert("object_name".to_string(), resolved_type.to_string());
+        }
+        flags.insert("sensitive", field.is_sensitive());
+        annotate(
+            &mut variables,
+            &mut flags,
+            field.deprecation(),
+            field.renamed_from(),
+        );
 
         Ok(Self {
             variables,
             flags,
             strct: Some(obj),
             field: Some(field),
+            imports,
             ..Default::default()
         })
     }
@@ -170,13 +232,41 @@ impl<'a> BlueprintExecutionContext<'a> {
         obj: &'a RepackStruct,
         q: &'a Query,
         result: &'a ParseResult,
+        blueprint: &Blueprint,
+        config: &Output,
     ) -> Result<Self, RepackError> {
         let mut new = self.clone();
-        new.variables
-            .insert("query".to_string(), q.render(obj, &result.strcts)?);
+        let schema = config.options.get("schema").map(|s| s.as_str());
+        new.variables.insert(
+            "query".to_string(),
+            q.render(obj, &result.strcts, schema, blueprint.column_types)?,
+        );
         new.variables.insert("name".to_string(), q.name.to_string());
         new.variables
             .insert("struct_name".to_string(), obj.name.to_string());
+        let kind = q.kind();
+        new.variables
+            .insert("query_kind".to_string(), kind.as_str().to_string());
+        new.variables.insert(
+            "tables".to_string(),
+            q.tables(obj, &result.strcts).join(", "),
+        );
+        new.flags.insert("read_only", kind == QueryKind::Select);
+        new.flags.insert("writes", kind.writes());
+        new.flags.insert("http", q.http.is_some());
+        if let Some(route) = &q.http {
+            new.variables
+                .insert("http_method".to_string(), route.method.to_string());
+            new.variables
+                .insert("http_path".to_string(), route.path.to_string());
+            new.variables
+                .insert("http_template".to_string(), route.template());
+        }
+        let bound = |arg: &QueryArg| q.http.as_ref().is_some_and(|r| r.binds(&arg.name));
+        new.flags.insert("has_args", !q.args.is_empty());
+        new.flags.insert("has_path_args", q.args.iter().any(bound));
+        new.flags
+            .insert("has_other_args", !q.args.iter().all(bound));
         new.flags
             .insert("returns_many", matches!(q.ret_type, QueryReturn::Many));
         new.flags
@@ -191,15 +281,24 @@ impl<'a> BlueprintExecutionContext<'a> {
         &self,
         arg: &'a QueryArg,
         blueprint: &'a Blueprint,
-        writer: &mut dyn TokenConsumer,
+        config: &Output,
     ) -> Result<Self, RepackError> {
         let mut new = self.clone();
+        new.imports.clear();
+        let identifier = blueprint.identifier(&arg.name).ok_or_else(|| {
+            RepackError::from_lang_with_msg(
+                RepackErrorKind::ReservedWord,
+                config,
+                format!("{} (query argument)", arg.name),
+            )
+        })?;
+        new.variables.insert("name".to_string(), identifier);
         new.variables
-            .insert("name".to_string(), arg.name.to_string());
+            .insert("name_raw".to_string(), arg.name.to_string());
         let resolved_type = match CoreType::from_string(&arg.typ) {
             Some(typ) => {
                 if let Some(link) = blueprint.links.get(&typ.to_string()) {
-                    writer.import(link.replace("$", &typ.to_string()))
+                    new.imports.push(link.replace("$", &typ.to_string()))
                 }
                 blueprint
                     .utilities
@@ -208,40 +307,274 @@ impl<'a> BlueprintExecutionContext<'a> {
                         SnippetSecondaryTokenName::from_type(&typ),
                     ))
                     .ok_or_else(|| {
-                        RepackError::global(RepackErrorKind::TypeNotSupported, typ.to_string())
+                        RepackError::from_lang_with_msg(
+                            RepackErrorKind::TypeNotSupported,
+                            config,
+                            format!(
+                                "{} (query argument {})",
+                                unmapped_type(blueprint, &typ),
+                                arg.name
+                            ),
+                        )
                     })?
             }
             None => {
                 if let Some(link) = blueprint.links.get("custom") {
-                    writer.import(link.replace("$", &arg.typ))
+                    new.imports.push(link.replace("$", &arg.typ))
                 }
                 &arg.typ
             }
         };
         new.variables
             .insert("type".to_string(), resolved_type.to_string());
+        new.flags
+            .insert("core", CoreType::from_string(&arg.typ).is_some());
         new.flags.insert("optional", arg.optional);
         new.flags.insert("array", arg.array);
+        let route = self.query.and_then(|q| q.http.as_ref());
+        let path_param = route.is_some_and(|route| route.binds(&arg.name));
+        new.flags.insert("path_param", path_param);
+        new.variables.insert(
+            "param".to_string(),
+            arg.name.trim_start_matches('_').to_string(),
+        );
 
         Ok(new)
     }
     pub fn with_enum(&self, enm: &'a RepackEnum) -> Result<Self, RepackError> {
         let mut variables = self.variables.clone();
         variables.insert("name".to_string(), enm.name.to_string());
+        let mut flags = HashMap::new();
+        flags.insert(
+            "has_deprecated",
+            enm.options.iter().any(|case| case.deprecation().is_some()),
+        );
         Ok(Self {
             variables,
-            flags: HashMap::new(),
+            flags,
             enm: Some(enm),
             ..Default::default()
         })
     }
+    pub fn with_union(&self, union: &'a RepackUnion) -> Self {
+        let mut variables = self.variables.clone();
+        variables.insert("name".to_string(), union.name.to_string());
+        Self {
+            variables,
+            flags: HashMap::new(),
+            union: Some(union),
+            ..Default::default()
+        }
+    }
+    /// Creates a context for one variant of a union: the variant's struct, as
+    /// in `[each struct]`, with `union_name` set to the union.
+    pub fn with_variant(&self, union: &'a RepackUnion, strct: &'a RepackStruct) -> Self {
+        let mut new = self.with_strct(strct);
+        new.variables
+            .insert("union_name".to_string(), union.name.to_string());
+        new.union = Some(union);
+        new
+    }
+    pub fn with_join(
+        &self,
+        obj: &'a RepackStruct,
+        join: &'a RepackStructJoin,
+        result: &'a ParseResult,
+    ) -> Self {
+        let mut new = self.clone();
+        new.imports.clear();
+        new.variables
+            .insert("struct_name".to_string(), obj.name.to_string());
+        new.variables
+            .insert("name".to_string(), join.name.to_string());
+        new.variables
+            .insert("entity".to_string(), join.foreign_entity.to_string());
+        new.variables
+            .insert("condition".to_string(), join.contents.to_string());
+        let entity_table = result
+            .strcts
+            .iter()
+            .find(|x| x.name == join.foreign_entity)
+            .and_then(|x| x.table_name.as_ref());
+        if let Some(table) = entity_table {
+            new.variables
+                .insert("entity_table".to_string(), table.to_string());
+        }
+        new.flags.insert("entity_has_table", entity_table.is_some());
+        new
+    }
+    pub fn with_category(&self, category: &str) -> Self {
+        let mut variables = self.variables.clone();
+        variables.insert("name".to_string(), category.to_string());
+        Self {
+            variables,
+            category: Some(category.to_string()),
+            ..Default::default()
+        }
+    }
+    /// A generated file listed by `[each file]`: its path as `name`, with the
+    /// `stem` and `extension` of its file name.
+    pub fn with_file(&self, path: &str) -> Self {
+        let mut variables = self.variables.clone();
+        let file = Path::new(path);
+        let part = |p: Option<&OsStr>| p.map(|p| p.to_string_lossy().to_string());
+        variables.insert("name".to_string(), path.to_string());
+        variables.insert(
+            "stem".to_string(),
+            part(file.file_stem()).unwrap_or_default(),
+        );
+        variables.insert(
+            "extension".to_string(),
+            part(file.extension()).unwrap_or_default(),
+        );
+        Self {
+            variables,
+            ..Default::default()
+        }
+    }
+    pub fn with_config(&self, cfg: &'a RepackConfig) -> Self {
+        let mut variables = self.variables.clone();
+        variables.insert("name".to_string(), cfg.name.to_string());
+        Self {
+            variables,
+            config: Some(cfg),
+            ..Default::default()
+        }
+    }
+    pub fn with_config_entry(
+        &self,
+        cfg: &'a RepackConfig,
+        entry: &'a ConfigEntry,
+        blueprint: &'a Blueprint,
+        config: &Output,
+    ) -> Result<Self, RepackError> {
+        let mut new = self.clone();
+        new.imports.clear();
+        let resolved_type = match &entry.field_type {
+            Some(FieldType::Core(typ)) => {
+                if let Some(link) = blueprint.links.get(&typ.to_string()) {
+                    new.imports.push(link.replace("$", &typ.to_string()))
+                }
+                blueprint
+                    .utilities
+                    .get(&(
+                        SnippetMainTokenName::TypeDef,
+                        SnippetSecondaryTokenName::from_type(typ),
+                    ))
+                    .ok_or_else(|| {
+                        RepackError::from_lang_with_msg(
+                            RepackErrorKind::TypeNotSupported,
+                            config,
+                            format!(
+                                "{} (config {}.{})",
+                                unmapped_type(blueprint, typ),
+                                cfg.name,
+                                entry.name
+                            ),
+                        )
+                    })?
+            }
+            Some(FieldType::Custom(typ, _)) => {
+                if let Some(link) = blueprint.links.get("custom") {
+                    new.imports.push(link.replace("$", typ))
+                }
+                typ
+            }
+            None => {
+                return Err(RepackError::global(
+                    RepackErrorKind::TypeNotResolved,
+                    format!("{}.{}", cfg.name, entry.name),
+                ));
+            }
+        };
+        new.variables
+            .insert("config_name".to_string(), cfg.name.to_string());
+        new.variables
+            .insert("name".to_string(), entry.name.to_string());
+        new.variables
+            .insert("type".to_string(), resolved_type.to_string());
+        new.variables
+            .insert("type_raw".to_string(), entry.field_type_string.to_string());
+        if let Some(default) = &entry.default {
+            new.variables
+                .insert("default".to_string(), default.to_string());
+        }
+        new.flags.insert("optional", entry.optional);
+        new.flags.insert("has_default", entry.default.is_some());
+        new.flags.insert(
+            "enum",
+            matches!(entry.field_type, Some(FieldType::Custom(..))),
+        );
+        new.flags
+            .insert("core", matches!(entry.field_type, Some(FieldType::Core(_))));
+        Ok(new)
+    }
+    /// A constant listed by `[each const]`: its `name`, `type`, `value`, and
+    /// `literal`, with `string` set for string constants and `enum` for enum ones.
+    pub fn with_const(
+        &self,
+        cnst: &'a RepackConst,
+        blueprint: &'a Blueprint,
+        config: &Output,
+    ) -> Result<Self, RepackError> {
+        let mut variables = self.variables.clone();
+        let mut flags = HashMap::new();
+        let mut imports = Vec::new();
+        let resolved_type = match &cnst.field_type {
+            Some(FieldType::Core(typ)) => blueprint
+                .utilities
+                .get(&(
+                    SnippetMainTokenName::TypeDef,
+                    SnippetSecondaryTokenName::from_type(typ),
+                ))
+                .ok_or_else(|| {
+                    RepackError::from_lang_with_msg(
+                        RepackErrorKind::TypeNotSupported,
+                        config,
+                        format!("{} (const {})", unmapped_type(blueprint, typ), cnst.name),
+                    )
+                })?,
+            Some(FieldType::Custom(typ, _)) => {
+                if let Some(link) = blueprint.links.get("custom") {
+                    imports.push(link.replace("$", typ))
+                }
+                typ
+            }
+            None => {
+                return Err(RepackError::global(
+                    RepackErrorKind::TypeNotResolved,
+                    format!("const {}", cnst.name),
+                ));
+            }
+        };
+        variables.insert("name".to_string(), cnst.name.to_string());
+        variables.insert("type".to_string(), resolved_type.to_string());
+        variables.insert("type_raw".to_string(), cnst.field_type_string.to_string());
+        variables.insert("value".to_string(), cnst.value.to_string());
+        variables.insert("literal".to_string(), cnst.literal());
+        flags.insert(
+            "string",
+            matches!(cnst.field_type, Some(FieldType::Core(CoreType::String))),
+        );
+        flags.insert(
+            "enum",
+            matches!(cnst.field_type, Some(FieldType::Custom(..))),
+        );
+        Ok(Self {
+            variables,
+            flags,
+            cnst: Some(cnst),
+            imports,
+            ..Default::default()
+        })
+    }
     pub fn with_enum_case(
         &self,
         enm: &'a RepackEnum,
         val: &'a RepackEnumCase,
     ) -> Result<Self, RepackError> {
-        let mut variables = HashMap::new();
-        let flags = HashMap::new();
+        let mut variables = self.variables.clone();
+        let mut flags = HashMap::new();
 
         variables.insert("enum_name".to_string(), enm.name.to_string());
         variables.insert("name".to_string(), val.name.to_string());
@@ -249,10 +582,17 @@ impl<'a> BlueprintExecutionContext<'a> {
             "value".to_string(),
             val.value.as_ref().unwrap_or(&val.name).to_string(),
         );
+        annotate(
+            &mut variables,
+            &mut flags,
+            val.deprecation(),
+            val.renamed_from(),
+        );
 
         Ok(Self {
             variables,
             flags,
+            enm: Some(enm),
             ..Default::default()
         })
     }
@@ -273,6 +613,19 @@ impl<'a> BlueprintExecutionContext<'a> {
             ..self.clone()
         })
     }
+    /// Creates a context for a single `validate:` rule on the current field.
+    ///
+    /// `name` is set to the rule name, `args` to its comma-separated arguments, and
+    /// each argument is available positionally (`[0]`, `[1]`, ...) or via `[each arg]`.
+    pub fn with_validation(&self, func: &'a FieldFunction) -> Result<Self, RepackError> {
+        let mut new = self.with_func_args(&func.args)?;
+        new.imports.clear();
+        new.variables
+            .insert("name".to_string(), func.name.to_string());
+        new.variables
+            .insert("args".to_string(), func.args.join(", "));
+        Ok(new)
+    }
     pub fn with_func_arg(&self, arg: &'a String) -> Result<Self, RepackError> {
         let mut variables = HashMap::new();
         let flags = HashMap::new();
@@ -286,3 +639,30 @@ impl<'a> BlueprintExecutionContext<'a> {
         })
     }
 }
+
+/// Sets the `deprecated` and `renamed` flags of a field or enum case, with its
+/// `deprecation` note and the name it was `renamed_from`.
+fn annotate(
+    variables: &mut HashMap<String, String>,
+    flags: &mut HashMap<&str, bool>,
+    deprecation: Option<&str>,
+    renamed_from: Option<&str>,
+) {
+    flags.insert("deprecated", deprecation.is_some());
+    flags.insert("renamed", renamed_from.is_some());
+    for (key, value) in [("deprecation", deprecation), ("renamed_from", renamed_from)] {
+        match value {
+            Some(value) => variables.insert(key.to_string(), value.to_string()),
+            None => variables.remove(key),
+        };
+    }
+}
+
+/// Explains that a blueprint has no `[define]` for a core type, such as
+/// `uint64` in the `postgres` blueprint.
+fn unmapped_type(blueprint: &Blueprint, typ: &CoreType) -> String {
+    format!(
+        "{typ} has no mapping in the {} blueprint; use another type or a type_override",
+        blueprint.id
+    )
+}
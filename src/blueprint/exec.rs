@@ -0,0 +1,147 @@
+use std::{
+    fs,
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use crate::syntax::{Output, RepackError, RepackErrorKind};
+
+/// How `[exec]` blocks are handled when a blueprint is rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExecPolicy {
+    /// Prompt on stdin before running each command.
+    #[default]
+    Ask,
+    /// Run commands without prompting.
+    Yes,
+    /// Leave commands unrun and continue.
+    Skip,
+    /// Fail the output when a blueprint tries to run a command.
+    Deny,
+}
+impl ExecPolicy {
+    pub fn from_string(val: &str) -> Option<ExecPolicy> {
+        Some(match val {
+            "ask" => ExecPolicy::Ask,
+            "yes" => ExecPolicy::Yes,
+            "skip" => ExecPolicy::Skip,
+            "deny" => ExecPolicy::Deny,
+            _ => return None,
+        })
+    }
+
+    /// Resolves the policy for an output from its `exec_policy` option and the
+    /// command-line flag (`--yes` or `--no-exec`).
+    ///
+    /// The flag overrides `ask` and `yes`, but an output set to `skip` or
+    /// `deny` keeps that setting, so `--yes` cannot run commands the schema
+    /// has ruled out.
+    ///
+    /// # Returns
+    /// * `Ok(ExecPolicy)` to apply to the output's `[exec]` blocks
+    /// * `Err(RepackError)` if the option is not a known policy
+    pub fn for_output(
+        config: &Output,
+        flag: Option<ExecPolicy>,
+    ) -> Result<ExecPolicy, RepackError> {
+        let option = match config.options.get("exec_policy") {
+            Some(val) => ExecPolicy::from_string(val).ok_or_else(|| {
+                RepackError::from_lang_with_msg(
+                    RepackErrorKind::InvalidExecPolicy,
+                    config,
+                    val.to_string(),
+                )
+            })?,
+            None => ExecPolicy::Ask,
+        };
+        Ok(match (option, flag) {
+            (ExecPolicy::Skip | ExecPolicy::Deny, _) => option,
+            (_, Some(flag)) => flag,
+            (option, None) => option,
+        })
+    }
+}
+
+/// The shell `[exec]` scripts run in, set with `[meta shell]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Sh,
+    Bash,
+    Cmd,
+    Powershell,
+    Pwsh,
+}
+impl Shell {
+    pub fn from_string(val: &str) -> Option<Shell> {
+        Some(match val {
+            "sh" => Shell::Sh,
+            "bash" => Shell::Bash,
+            "cmd" => Shell::Cmd,
+            "powershell" => Shell::Powershell,
+            "pwsh" => Shell::Pwsh,
+            _ => return None,
+        })
+    }
+
+    /// The shell used when a blueprint doesn't name one: `cmd` on Windows, `sh` elsewhere.
+    pub fn platform_default() -> Shell {
+        if cfg!(windows) { Shell::Cmd } else { Shell::Sh }
+    }
+
+    /// Quotes an argument so the shell passes it through unchanged.
+    pub fn quote(&self, arg: &str) -> String {
+        match self {
+            Shell::Cmd => format!("\"{arg}\""),
+            Shell::Powershell | Shell::Pwsh => format!("'{}'", arg.replace('\'', "''")),
+            _ => format!("'{}'", arg.replace('\'', "'\\''")),
+        }
+    }
+
+    /// Runs a script, waiting for it to finish. Its output is discarded and
+    /// errors go to stderr.
+    ///
+    /// `sh`, `bash`, and PowerShell read the script from stdin. `cmd` cannot,
+    /// so the script is written to a temporary batch file run with `cmd /C`.
+    ///
+    /// # Returns
+    /// * `Ok(bool)` whether the script exited successfully
+    /// * `Err(RepackError)` if the shell could not be started
+    pub fn run(&self, script: &str) -> Result<bool, RepackError> {
+        let (program, args): (&str, &[&str]) = match self {
+            Shell::Sh => ("sh", &["-s"]),
+            Shell::Bash => ("bash", &["-s"]),
+            Shell::Powershell => ("powershell", &["-NoProfile", "-Command", "-"]),
+            Shell::Pwsh => ("pwsh", &["-NoProfile", "-Command", "-"]),
+            Shell::Cmd => return run_batch(script),
+        };
+        let mut exec = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(exec_failed)?;
+        if let Some(mut stdin) = exec.stdin.take() {
+            stdin.write_all(script.as_bytes()).map_err(exec_failed)?;
+        }
+        Ok(exec.wait().map_err(exec_failed)?.success())
+    }
+}
+
+fn run_batch(script: &str) -> Result<bool, RepackError> {
+    let name = format!("repack-exec-{}.cmd", std::process::id());
+    let path = std::env::temp_dir().join(name);
+    fs::write(&path, script.replace('\n', "\r\n")).map_err(exec_failed)?;
+    let status = Command::new("cmd")
+        .arg("/C")
+        .arg(&path)
+        .stdout(Stdio::null())
+        .stderr(Stdio::inherit())
+        .status();
+    _ = fs::remove_file(&path);
+    Ok(status.map_err(exec_failed)?.success())
+}
+
+fn exec_failed(e: std::io::Error) -> RepackError {
+    RepackError::global(RepackErrorKind::ProcessExecutionFailed, e.to_string())
+}
@@ -1,7 +1,10 @@
-use super::BlueprintSnippetDetails;
+use super::{
+    BlueprintCondition, BlueprintSnippetDetails, Shell, Version, requirements_ignored, split_words,
+    version_satisfies,
+};
 use crate::{
     blueprint::{BlueprintFileReader, BlueprintToken},
-    syntax::{CoreType, RepackError},
+    syntax::{ColumnTypes, CoreType, RepackError, RepackErrorKind},
 };
 use std::collections::HashMap;
 
@@ -13,8 +16,11 @@ use std::collections::HashMap;
 pub enum SnippetMainTokenName {
     Meta,
     File,
+    FilePer,
+    Index,
     If,
     Ifn,
+    Else,
     Each,
     Eachr,
     TypeDef,
@@ -29,6 +35,11 @@ pub enum SnippetMainTokenName {
     Break,
     Exec,
     Increment,
+    Decrement,
+    Reset,
+    Set,
+    Match,
+    Case,
     Snippet,
     Render,
     Variable(String),
@@ -40,6 +51,7 @@ impl SnippetMainTokenName {
             "meta" => Self::Meta,
             "if" => Self::If,
             "ifn" => Self::Ifn,
+            "else" => Self::Else,
             "each" => Self::Each,
             "eachr" => Self::Eachr,
             "define" => Self::TypeDef,
@@ -48,12 +60,19 @@ impl SnippetMainTokenName {
             "join" => Self::Join,
             "ref" => Self::Ref,
             "file" => Self::File,
+            "file_per" => Self::FilePer,
+            "index" => Self::Index,
             "link" => Self::Link,
             "import" => Self::Import,
             "imports" => Self::PlaceImports,
             "br" => Self::Break,
             "exec" => Self::Exec,
             "increment" => Self::Increment,
+            "decrement" => Self::Decrement,
+            "reset" => Self::Reset,
+            "set" => Self::Set,
+            "match" => Self::Match,
+            "case" => Self::Case,
             "snippet" => Self::Snippet,
             "render" => Self::Render,
             "trim" => Self::Trim,
@@ -71,16 +90,37 @@ pub enum SnippetSecondaryTokenName {
     Id,
     Name,
     Kind,
+    Version,
+    RequiresRepack,
+    Shell,
+    Aliases,
+    Reserved,
+    Escape,
+    Supports,
+    Extension,
+    FileCase,
+    ColumnTypes,
     Struct,
     Field,
+    Subfield,
     Enum,
     Case,
+    Union,
+    Variant,
+    Const,
+    Validation,
+    Config,
+    Category,
+    File,
     Debug,
 
     // TypeDef
     String,
     Int32,
     Int64,
+    Int16,
+    Uint32,
+    Uint64,
     Float64,
     Uuid,
     DateTime,
@@ -94,7 +134,7 @@ pub enum SnippetSecondaryTokenName {
     Arbitrary(String),
 }
 impl SnippetSecondaryTokenName {
-    fn from_string(val: &str) -> Self {
+    pub(crate) fn from_string(val: &str) -> Self {
         if let Some(ct) = CoreType::from_string(val) {
             return Self::from_type(&ct);
         }
@@ -102,10 +142,28 @@ impl SnippetSecondaryTokenName {
             "id" => Self::Id,
             "name" => Self::Name,
             "kind" => Self::Kind,
+            "version" => Self::Version,
+            "requires_repack" => Self::RequiresRepack,
+            "shell" => Self::Shell,
+            "aliases" => Self::Aliases,
+            "reserved" => Self::Reserved,
+            "escape" => Self::Escape,
+            "supports" => Self::Supports,
+            "extension" => Self::Extension,
+            "file_case" => Self::FileCase,
+            "column_types" => Self::ColumnTypes,
             "struct" => Self::Struct,
             "field" => Self::Field,
+            "subfield" => Self::Subfield,
             "enum" => Self::Enum,
             "case" => Self::Case,
+            "union" => Self::Union,
+            "variant" => Self::Variant,
+            "const" => Self::Const,
+            "validation" => Self::Validation,
+            "config" => Self::Config,
+            "category" => Self::Category,
+            "file" => Self::File,
             "join" => Self::Join,
             "arg" => Self::Arg,
             "debug" => Self::Debug,
@@ -118,6 +176,9 @@ impl SnippetSecondaryTokenName {
             CoreType::Uuid => Self::Uuid,
             CoreType::Int64 => Self::Int64,
             CoreType::Int32 => Self::Int32,
+            CoreType::Int16 => Self::Int16,
+            CoreType::Uint64 => Self::Uint64,
+            CoreType::Uint32 => Self::Uint32,
             CoreType::String => Self::String,
             CoreType::Float64 => Self::Float64,
             CoreType::Boolean => Self::Boolean,
@@ -135,13 +196,81 @@ pub struct SnippetReference<'a> {
 }
 impl<'a> SnippetReference<'a> {
     pub fn main_token(&self) -> SnippetMainTokenName {
-        SnippetMainTokenName::from_string(&self.details.main_token)
+        self.details.main_token_name()
     }
     pub fn secondary_token(&self) -> SnippetSecondaryTokenName {
         SnippetSecondaryTokenName::from_string(&self.details.secondary_token)
     }
 }
 
+/// Determines which command renders a blueprint, set with `[meta kind]`.
+#[derive(Debug, Default, PartialEq)]
+pub enum BlueprintKind {
+    /// Rendered by `repack build` (the default)
+    #[default]
+    Build,
+    /// Rendered by `repack configure` from `config` declarations
+    Configure,
+    /// Rendered by `repack document`
+    Document,
+}
+impl BlueprintKind {
+    pub fn from_string(val: &str) -> Option<BlueprintKind> {
+        match val {
+            "build" => Some(Self::Build),
+            "configure" => Some(Self::Configure),
+            "document" => Some(Self::Document),
+            _ => None,
+        }
+    }
+}
+
+/// How a blueprint's file names are written, set with `[meta file_case]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileCase {
+    /// `user_account`
+    Snake,
+    /// `user-account`
+    Kebab,
+    /// `userAccount`
+    Camel,
+    /// `UserAccount`
+    Pascal,
+}
+impl FileCase {
+    pub fn from_string(val: &str) -> Option<FileCase> {
+        Some(match val {
+            "snake" => FileCase::Snake,
+            "kebab" => FileCase::Kebab,
+            "camel" => FileCase::Camel,
+            "pascal" => FileCase::Pascal,
+            _ => return None,
+        })
+    }
+
+    /// Writes a name in this case.
+    pub fn apply(&self, name: &str) -> String {
+        let words = split_words(name);
+        let capitalize = |word: &String| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        };
+        match self {
+            FileCase::Snake => words.join("_"),
+            FileCase::Kebab => words.join("-"),
+            FileCase::Camel => words
+                .iter()
+                .enumerate()
+                .map(|(i, w)| if i == 0 { w.clone() } else { capitalize(w) })
+                .collect(),
+            FileCase::Pascal => words.iter().map(capitalize).collect(),
+        }
+    }
+}
+
 /// Represents a complete blueprint definition for code generation.
 ///
 /// Blueprint contains all the template logic, type mappings, and metadata needed
@@ -154,6 +283,29 @@ pub struct Blueprint {
     pub id: String,
     /// Human-readable name for this blueprint
     pub name: String,
+    /// The command this blueprint is rendered by
+    pub kind: BlueprintKind,
+    /// The blueprint's own version, from `[meta version]`
+    pub version: Option<String>,
+    /// The repack versions this blueprint works with, from `[meta requires_repack]`
+    pub requires_repack: Option<String>,
+    /// The shell `[exec]` blocks run in, from `[meta shell]`
+    pub shell: Shell,
+    /// Short names `rename` functions may target, from `[meta aliases]`
+    pub aliases: Vec<String>,
+    /// Words that can't be used as names in the generated code, from `[meta reserved]`
+    pub reserved: Vec<String>,
+    /// How a reserved name is escaped, from `[meta escape]`, with `{name}` standing for it
+    pub escape: Option<String>,
+    /// Schema features the target accepts, from `[meta supports]`
+    pub supports: Vec<String>,
+    /// The extension of the files the blueprint writes, without the dot, from
+    /// `[meta extension]`
+    pub extension: Option<String>,
+    /// How the blueprint's file names are written, from `[meta file_case]`
+    pub file_case: Option<FileCase>,
+    /// How rendered queries declare their column types, from `[meta column_types]`
+    pub column_types: Option<ColumnTypes>,
     /// Import statements and dependencies needed for generated code
     pub links: HashMap<String, String>,
     /// Type mappings from repack types to target language types
@@ -168,19 +320,35 @@ impl Blueprint {
         let mut lang = Blueprint {
             id: String::new(),
             name: String::new(),
+            kind: BlueprintKind::Build,
+            version: None,
+            requires_repack: None,
+            shell: Shell::platform_default(),
+            aliases: Vec::new(),
+            reserved: Vec::new(),
+            escape: None,
+            supports: Vec::new(),
+            extension: None,
+            file_case: None,
+            column_types: None,
             links: HashMap::new(),
             utilities: HashMap::new(),
             tokens: Vec::new(),
             snippets: HashMap::new(),
         };
 
-        loop {
-            let Some(next) = reader.next() else {
-                break;
-            };
+        while let Some(next) = reader.next() {
             if let BlueprintToken::Snippet(snip) = &next {
+                if let Some(BlueprintCondition::Invalid(msg)) = &snip.condition {
+                    let mut e = RepackError::global(
+                        RepackErrorKind::SyntaxError,
+                        format!("condition: {msg}"),
+                    );
+                    e.add_to_stack(snip);
+                    return Err(e);
+                }
                 let (main, secondary) = (
-                    SnippetMainTokenName::from_string(&snip.main_token),
+                    snip.main_token_name(),
                     SnippetSecondaryTokenName::from_string(&snip.secondary_token),
                 );
 
@@ -201,7 +369,7 @@ impl Blueprint {
                         }
                         let mut literal_string_value = snip.contents.clone();
                         for t in &participating_tokens {
-                            if let BlueprintToken::Literal(val) = t {
+                            if let BlueprintToken::Literal(val) | BlueprintToken::Raw(val) = t {
                                 literal_string_value.push_str(val);
                             }
                         }
@@ -225,7 +393,7 @@ impl Blueprint {
                         }
                         let mut literal_string_value = snip.contents.clone();
                         for t in &participating_tokens {
-                            if let BlueprintToken::Literal(val) = t {
+                            if let BlueprintToken::Literal(val) | BlueprintToken::Raw(val) = t {
                                 literal_string_value.push_str(val);
                             }
                         }
@@ -248,7 +416,7 @@ impl Blueprint {
                         }
                         let mut literal_string_value = snip.contents.clone();
                         for t in &participating_tokens {
-                            if let BlueprintToken::Literal(val) = t {
+                            if let BlueprintToken::Literal(val) | BlueprintToken::Raw(val) = t {
                                 literal_string_value.push_str(val);
                             }
                         }
@@ -262,32 +430,6 @@ impl Blueprint {
             }
         }
 
-        // Trim extra chars
-        let mut i = 0;
-        while i + 1 < lang.tokens.len() {
-            match &lang.tokens[i + 1] {
-                BlueprintToken::Snippet(snip) => {
-                    let autoclose = snip.autoclose;
-                    if let BlueprintToken::Literal(lit) = &mut lang.tokens[i] {
-                        if !autoclose {
-                            while lit.ends_with('\n') || lit.ends_with('\t') {
-                                lit.pop();
-                            }
-                        }
-                    }
-                }
-                BlueprintToken::Close(_) => {
-                    if let BlueprintToken::Literal(lit) = &mut lang.tokens[i] {
-                        while lit.ends_with('\n') || lit.ends_with('\t') {
-                            lit.pop();
-                        }
-                    }
-                }
-                _ => {}
-            }
-            i += 1;
-        }
-
         if let Some(id) = lang
             .utilities
             .get(&(SnippetMainTokenName::Meta, SnippetSecondaryTokenName::Id))
@@ -301,6 +443,108 @@ impl Blueprint {
             lang.name = name.clone();
         }
 
+        if let Some(kind) = lang
+            .utilities
+            .get(&(SnippetMainTokenName::Meta, SnippetSecondaryTokenName::Kind))
+        {
+            lang.kind = BlueprintKind::from_string(kind).ok_or_else(|| {
+                RepackError::global(RepackErrorKind::SyntaxError, format!("kind: {kind}"))
+            })?;
+        }
+
+        lang.version = lang
+            .utilities
+            .get(&(
+                SnippetMainTokenName::Meta,
+                SnippetSecondaryTokenName::Version,
+            ))
+            .map(|v| v.trim().to_string());
+        lang.requires_repack = lang
+            .utilities
+            .get(&(
+                SnippetMainTokenName::Meta,
+                SnippetSecondaryTokenName::RequiresRepack,
+            ))
+            .map(|v| v.trim().to_string());
+        if let Some(shell) = lang
+            .utilities
+            .get(&(SnippetMainTokenName::Meta, SnippetSecondaryTokenName::Shell))
+        {
+            lang.shell = Shell::from_string(shell.trim()).ok_or_else(|| {
+                RepackError::global(RepackErrorKind::SyntaxError, format!("shell: {shell}"))
+            })?;
+        }
+        if let Some(aliases) = lang.utilities.get(&(
+            SnippetMainTokenName::Meta,
+            SnippetSecondaryTokenName::Aliases,
+        )) {
+            lang.aliases = aliases.split_whitespace().map(|a| a.to_string()).collect();
+        }
+        if let Some(reserved) = lang.utilities.get(&(
+            SnippetMainTokenName::Meta,
+            SnippetSecondaryTokenName::Reserved,
+        )) {
+            lang.reserved = reserved.split_whitespace().map(|w| w.to_string()).collect();
+        }
+        lang.escape = lang
+            .utilities
+            .get(&(
+                SnippetMainTokenName::Meta,
+                SnippetSecondaryTokenName::Escape,
+            ))
+            .map(|e| e.trim().to_string());
+        if let Some(supports) = lang.utilities.get(&(
+            SnippetMainTokenName::Meta,
+            SnippetSecondaryTokenName::Supports,
+        )) {
+            lang.supports = supports.split_whitespace().map(|f| f.to_string()).collect();
+        }
+        lang.extension = lang
+            .utilities
+            .get(&(
+                SnippetMainTokenName::Meta,
+                SnippetSecondaryTokenName::Extension,
+            ))
+            .map(|e| e.trim().trim_start_matches('.').to_string())
+            .filter(|e| !e.is_empty());
+        if let Some(case) = lang.utilities.get(&(
+            SnippetMainTokenName::Meta,
+            SnippetSecondaryTokenName::FileCase,
+        )) {
+            lang.file_case = Some(FileCase::from_string(case.trim()).ok_or_else(|| {
+                RepackError::global(RepackErrorKind::SyntaxError, format!("file_case: {case}"))
+            })?);
+        }
+        if let Some(types) = lang.utilities.get(&(
+            SnippetMainTokenName::Meta,
+            SnippetSecondaryTokenName::ColumnTypes,
+        )) {
+            lang.column_types = Some(ColumnTypes::from_string(types.trim()).ok_or_else(|| {
+                RepackError::global(
+                    RepackErrorKind::SyntaxError,
+                    format!("column_types: {types}"),
+                )
+            })?);
+        }
+        if let Some(requirement) = &lang.requires_repack {
+            let current = Version::current();
+            let satisfied = version_satisfies(requirement, &current).map_err(|msg| {
+                RepackError::global(
+                    RepackErrorKind::SyntaxError,
+                    format!("requires_repack in '{}': {msg}", lang.id),
+                )
+            })?;
+            if !satisfied && !requirements_ignored() {
+                return Err(RepackError::global(
+                    RepackErrorKind::IncompatibleBlueprint,
+                    format!(
+                        "'{}' requires repack {requirement}, but this is repack {current}.",
+                        lang.id
+                    ),
+                ));
+            }
+        }
+
         if lang
             .utilities
             .contains_key(&(SnippetMainTokenName::Meta, SnippetSecondaryTokenName::Debug))
@@ -310,4 +554,50 @@ impl Blueprint {
 
         Ok(lang)
     }
+
+    /// The name to write for an identifier, escaped if it is a reserved word.
+    ///
+    /// # Returns
+    /// * `Some(String)` with the name as it should be written
+    /// * `None` if the name is reserved and the blueprint has no `[meta escape]`
+    pub fn identifier(&self, name: &str) -> Option<String> {
+        if !self.reserved.iter().any(|word| word == name) {
+            return Some(name.to_string());
+        }
+        self.escape
+            .as_ref()
+            .map(|escape| escape.replace("{name}", name))
+    }
+
+    /// Applies the blueprint's naming conventions to a generated file's name.
+    ///
+    /// A name without an extension gets `[meta extension]`, so `[file][name][/file]`
+    /// is enough. With `[meta file_case]`, the file's stem (its name without
+    /// directories or extension) must already be in that case.
+    ///
+    /// # Returns
+    /// * `Ok(String)` with the name to write
+    /// * `Err(String)` describing a stem that is not in the blueprint's case
+    pub fn file_name(&self, name: &str) -> Result<String, String> {
+        let base = name.rsplit('/').next().unwrap_or(name);
+        let (stem, has_extension) = match base.rsplit_once('.') {
+            Some((stem, _)) if !stem.is_empty() => (stem, true),
+            _ => (base, false),
+        };
+        if let Some(case) = self.file_case {
+            let expected = case.apply(stem);
+            if expected != stem {
+                return Err(format!("{name} (expected {expected})"));
+            }
+        }
+        Ok(match &self.extension {
+            Some(extension) if !has_extension => format!("{name}.{extension}"),
+            _ => name.to_string(),
+        })
+    }
+
+    /// Whether the blueprint lists a feature in `[meta supports]`.
+    pub fn supports(&self, feature: &str) -> bool {
+        self.supports.iter().any(|f| f == feature)
+    }
 }
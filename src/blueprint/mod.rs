@@ -1,13 +1,25 @@
-mod lang;
+mod capability;
+mod check;
+mod collision;
+mod condition;
 mod context;
+mod exec;
+mod lang;
 mod reader;
 mod renderer;
 mod store;
 mod syntax;
+mod version;
 
+pub use capability::*;
+pub use check::*;
+pub use collision::*;
+pub use condition::*;
 pub(crate) use context::*;
+pub use exec::*;
 pub use lang::*;
 pub use reader::*;
 pub use renderer::*;
 pub use store::*;
 pub use syntax::*;
+pub use version::*;
@@ -2,117 +2,203 @@ use std::iter::Peekable;
 
 use crate::blueprint::BlueprintToken;
 
-use super::{BlueprintSnippetDetails, SnippetMainTokenName};
+use super::{BlueprintCondition, BlueprintSnippetDetails, SnippetMainTokenName};
 
 pub struct BlueprintFileReader<'a> {
     pub reader: Peekable<std::slice::Iter<'a, u8>>,
+    source: &'a [u8],
+    /// The last position located: its byte offset, line, and the offset the line starts at.
+    located: (usize, usize, usize),
 }
 impl<'a> BlueprintFileReader<'a> {
+    pub fn new(source: &'a [u8]) -> BlueprintFileReader<'a> {
+        BlueprintFileReader {
+            reader: source.iter().peekable(),
+            source,
+            located: (0, 1, 0),
+        }
+    }
+
+    /// The line and column (both starting at 1) of the byte `back` bytes
+    /// before the next one to be read.
+    ///
+    /// Reading only moves forward, so this scans on from the last position
+    /// located rather than from the start of the file.
+    fn location(&mut self, back: usize) -> (usize, usize) {
+        let offset = self.source.len() - self.reader.len() - back;
+        let (from, mut line, mut line_start) = self.located;
+        for (idx, byte) in self.source[from..offset].iter().enumerate() {
+            if *byte == b'\n' {
+                line += 1;
+                line_start = from + idx + 1;
+            }
+        }
+        self.located = (offset, line, line_start);
+        (line, offset - line_start + 1)
+    }
+
+    /// Reads the next token.
+    ///
+    /// Outside tags, `\[`, `\]`, and `\\` write a literal `[`, `]`, and `\`.
+    /// `\n` and `\t` write a line break and a tab, and `[raw]...[/raw]` writes
+    /// its contents without reading any tags inside. Both come back as
+    /// [`BlueprintToken::Raw`], so `~` markers never trim them. Other text is
+    /// read as written, except whitespace removed by a `[~` or `~]` marker.
     pub fn next(&mut self) -> Option<BlueprintToken> {
         let mut temp = String::new();
-        let mut last_ignore: bool = false;
         while let Some(next) = self.reader.next() {
-            if temp.is_empty() && *next == b'\n' {
-                continue;
-            }
             if *next == b'[' {
-                if !last_ignore {
-                    let mut sd = BlueprintSnippetDetails::default();
-                    if matches!(self.reader.peek(), Some(b']')) {
-                        temp.push('[');
-                        continue;
-                    }
-                    if matches!(self.reader.peek(), Some(b' ')) {
-                        self.reader.next();
-                    }
+                if self.take_raw_open() {
+                    return Some(BlueprintToken::Raw(self.read_raw()));
+                }
+                let (line, column) = self.location(1);
+                let mut sd = BlueprintSnippetDetails {
+                    line,
+                    column,
+                    ..Default::default()
+                };
+                let mut trim_after = false;
+                if matches!(self.reader.peek(), Some(b']')) {
+                    temp.push('[');
+                    continue;
+                }
+                // The literal before a `[~` tag was already trimmed.
+                if matches!(self.reader.peek(), Some(b'~')) {
+                    self.reader.next();
+                }
+                if matches!(self.reader.peek(), Some(b' ')) {
+                    self.reader.next();
+                }
 
-                    if matches!(self.reader.peek(), Some(b'/')) {
-                        self.reader.next();
-                        for in_block_read in self.reader.by_ref() {
-                            match *in_block_read as char {
-                                ']' => return Some(BlueprintToken::Close(temp)),
-                                ' ' => {}
-                                _ => {
-                                    temp.push(*in_block_read as char);
+                if matches!(self.reader.peek(), Some(b'/')) {
+                    self.reader.next();
+                    while let Some(in_block_read) = self.reader.next() {
+                        match *in_block_read as char {
+                            ']' => {
+                                if trim_after {
+                                    self.skip_whitespace();
                                 }
+                                return Some(BlueprintToken::Close(temp));
+                            }
+                            '~' if matches!(self.reader.peek(), Some(b']')) => trim_after = true,
+                            ' ' => {}
+                            _ => {
+                                temp.push(*in_block_read as char);
                             }
                         }
                     }
+                }
 
-                    while let Some(in_block_read) = self.reader.next() {
-                        match *in_block_read as char {
-                            ' ' => {
-                                if sd.main_token.is_empty() {
-                                    sd.main_token = temp;
-                                } else if sd.secondary_token.is_empty() {
-                                    sd.secondary_token = temp;
-                                } else {
-                                    sd.contents.push_str(&temp);
-                                    match self.reader.peek() {
-                                        Some(b'}') => {}
-                                        _ => {
-                                            sd.contents.push(' ');
-                                        }
+                while let Some(in_block_read) = self.reader.next() {
+                    match *in_block_read as char {
+                        ' ' => {
+                            if sd.main_token.is_empty() {
+                                sd.main_token = temp;
+                            } else if sd.secondary_token.is_empty() {
+                                sd.secondary_token = temp;
+                            } else {
+                                sd.contents.push_str(&temp);
+                                match self.reader.peek() {
+                                    Some(b'}') => {}
+                                    _ => {
+                                        sd.contents.push(' ');
                                     }
                                 }
-                                temp = String::new();
                             }
-                            ']' => {
-                                if sd.main_token.is_empty() {
-                                    sd.main_token = temp;
-                                } else if sd.secondary_token.is_empty() {
-                                    sd.secondary_token = temp;
-                                } else {
-                                    sd.contents.push_str(&temp);
+                            temp = String::new();
+                        }
+                        ']' => {
+                            if sd.main_token.is_empty() {
+                                sd.main_token = temp;
+                            } else if sd.secondary_token.is_empty() {
+                                sd.secondary_token = temp;
+                            } else {
+                                sd.contents.push_str(&temp);
+                            }
+                            match sd.main_token_name() {
+                                SnippetMainTokenName::Variable(_)
+                                | SnippetMainTokenName::PlaceImports
+                                | SnippetMainTokenName::Import
+                                | SnippetMainTokenName::Break => sd.autoclose = true,
+                                SnippetMainTokenName::Increment
+                                | SnippetMainTokenName::Decrement
+                                | SnippetMainTokenName::Reset => {
+                                    sd.autoclose = true;
+                                    sd.parse_arguments();
                                 }
-                                match SnippetMainTokenName::from_string(&sd.main_token) {
-                                    SnippetMainTokenName::Variable(_)
-                                    | SnippetMainTokenName::PlaceImports
-                                    | SnippetMainTokenName::Import
-                                    | SnippetMainTokenName::Increment
-                                    | SnippetMainTokenName::Break => sd.autoclose = true,
-                                    _ => {}
+                                SnippetMainTokenName::Else => sd.autoclose = true,
+                                // `[meta version 1.2.0]` carries its value inline.
+                                SnippetMainTokenName::Meta if !sd.contents.is_empty() => {
+                                    sd.autoclose = true
                                 }
-                                if !sd.autoclose {
-                                    while let Some(tok) = self.reader.peek() {
-                                        match tok {
-                                            b'\n' => _ = self.reader.next(),
-                                            _ => break,
-                                        }
-                                    }
+                                SnippetMainTokenName::Each
+                                | SnippetMainTokenName::Eachr
+                                | SnippetMainTokenName::FilePer => {
+                                    sd.parse_arguments();
                                 }
-                                break;
-                            }
-                            ':' if sd.secondary_token.is_empty() => {
-                                sd.secondary_token = temp;
-                                temp = String::new();
-                                if matches!(self.reader.peek(), Some(b' ')) {
-                                    self.reader.next();
+                                // `[join ", "]` has no collection, so its separator
+                                // starts at the secondary token.
+                                SnippetMainTokenName::Join => {
+                                    let separator = std::mem::take(&mut sd.secondary_token);
+                                    sd.contents = if sd.contents.is_empty() {
+                                        separator
+                                    } else {
+                                        format!("{separator} {}", sd.contents)
+                                    };
+                                    sd.parse_arguments();
+                                }
+                                SnippetMainTokenName::If | SnippetMainTokenName::Ifn => {
+                                    sd.condition = Some(BlueprintCondition::parse(
+                                        &sd.secondary_token,
+                                        &sd.contents,
+                                    ));
                                 }
+                                _ => {}
                             }
-                            _ => {
-                                temp.push(*in_block_read as char);
+                            break;
+                        }
+                        '~' if matches!(self.reader.peek(), Some(b']')) => trim_after = true,
+                        ':' if sd.secondary_token.is_empty() => {
+                            sd.secondary_token = temp;
+                            temp = String::new();
+                            if matches!(self.reader.peek(), Some(b' ')) {
+                                self.reader.next();
                             }
                         }
+                        _ => {
+                            temp.push(*in_block_read as char);
+                        }
                     }
-                    return Some(BlueprintToken::Snippet(sd));
-                } else {
-                    temp.pop();
                 }
+                if trim_after {
+                    self.skip_whitespace();
+                }
+                return Some(BlueprintToken::Snippet(sd));
             }
-            last_ignore = *next == b'\\';
-            match self.reader.peek() {
-                Some(b'[') if !last_ignore => {
-                    temp.push(*next as char);
-                    while temp.ends_with('\n') {
-                        temp.pop();
-                    }
-                    // End of a token, just before a block specifier.
-                    return Some(BlueprintToken::Literal(temp));
+            let c = match (*next, self.reader.peek()) {
+                (b'\\', Some(b'n')) => {
+                    self.reader.next();
+                    return Some(BlueprintToken::Raw("\n".to_string()));
+                }
+                (b'\\', Some(b't')) => {
+                    self.reader.next();
+                    return Some(BlueprintToken::Raw("\t".to_string()));
                 }
-                _ => {
-                    temp.push(*next as char);
+                (b'\\', Some(b'[' | b']' | b'\\')) => {
+                    self.reader.next().map_or('\\', |b| *b as char)
                 }
+                (c, _) => c as char,
+            };
+            temp.push(c);
+            if matches!(self.reader.peek(), Some(b'[')) {
+                if self.at_trim_marker() {
+                    temp.truncate(temp.trim_end().len());
+                }
+                // End of a token, just before a block specifier.
+                return Some(BlueprintToken::Literal(temp));
+            }
+            if self.at_whitespace_escape() {
+                return Some(BlueprintToken::Literal(temp));
             }
         }
 
@@ -122,4 +208,49 @@ impl<'a> BlueprintFileReader<'a> {
             None
         }
     }
+
+    /// Whether the next bytes are a `\n` or `\t` escape, which is read as its own token.
+    fn at_whitespace_escape(&self) -> bool {
+        let mut ahead = self.reader.clone();
+        ahead.next() == Some(&b'\\') && matches!(ahead.next(), Some(b'n' | b't'))
+    }
+
+    /// Whether the next tag starts with `[~`, trimming the whitespace before it.
+    fn at_trim_marker(&self) -> bool {
+        let mut ahead = self.reader.clone();
+        ahead.next() == Some(&b'[') && ahead.next() == Some(&b'~')
+    }
+
+    /// Skips the whitespace after a tag ending in `~]`.
+    fn skip_whitespace(&mut self) {
+        while self.reader.peek().is_some_and(|b| b.is_ascii_whitespace()) {
+            self.reader.next();
+        }
+    }
+
+    /// Consumes `raw]` if the `[` just read opens a raw block.
+    fn take_raw_open(&mut self) -> bool {
+        let mut ahead = self.reader.clone();
+        if !b"raw]".iter().all(|b| ahead.next() == Some(b)) {
+            return false;
+        }
+        self.reader = ahead;
+        true
+    }
+
+    /// Reads the contents of a raw block up to `[/raw]`, dropping a line break
+    /// right after the opening tag and right before the closing one.
+    fn read_raw(&mut self) -> String {
+        let mut raw = Vec::new();
+        for byte in self.reader.by_ref() {
+            raw.push(*byte);
+            if raw.ends_with(b"[/raw]") {
+                raw.truncate(raw.len() - b"[/raw]".len());
+                break;
+            }
+        }
+        let text = String::from_utf8_lossy(&raw);
+        let text = text.strip_prefix('\n').unwrap_or(&text);
+        text.strip_suffix('\n').unwrap_or(text).to_string()
+    }
 }
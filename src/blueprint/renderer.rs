@@ -1,19 +1,25 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeSet, HashMap, HashSet},
     env::current_dir,
     fs::{self},
-    io::Write,
-    process::{Command, Stdio},
+    path::{Component, Path, PathBuf},
+    time::{Duration, Instant},
 };
 
 use crate::{
-    Console,
-    syntax::{Output, ParseResult, RepackError, RepackErrorKind},
+    console::Console,
+    diff::unified_diff,
+    syntax::{
+        CustomFieldType, Field, FieldType, Output, ParseResult, RepackError, RepackErrorKind,
+        RepackStruct, quote_ident,
+    },
 };
 
 use super::{
-    Blueprint, BlueprintExecutionContext, BlueprintToken, SnippetMainTokenName, SnippetReference,
-    SnippetSecondaryTokenName, TokenConsumer,
+    Blueprint, BlueprintCondition, BlueprintExecutionContext, BlueprintSnippetDetails,
+    BlueprintToken, ComparisonOperator, ConditionOperand, ExecPolicy, SnippetMainTokenName,
+    SnippetReference, SnippetSecondaryTokenName, TokenConsumer, name_collisions,
+    unsupported_features,
 };
 
 /// Represents different types of content that can be written to output files.
@@ -38,15 +44,23 @@ enum DeliveryUnit {
 struct BlueprintBuildResult {
     /// Map of filenames to their ordered content units (text and import placeholders)
     contents: HashMap<String, Vec<DeliveryUnit>>,
-    /// Map of filenames to their sets of import statements
-    imports: HashMap<String, HashSet<String>>,
+    /// Map of filenames to their import statements, sorted so files are the
+    /// same on every run
+    imports: HashMap<String, BTreeSet<String>>,
     /// The currently active output file for new content
     current_file_name: Option<String>,
+    /// Files whose content replaces the lines between their markers
+    markers: HashSet<String>,
 }
 impl TokenConsumer for BlueprintBuildResult {
     fn set_file_name(&mut self, filename: &str) {
         self.current_file_name = Some(filename.to_string());
     }
+    fn use_markers(&mut self) {
+        if let Some(file) = &self.current_file_name {
+            self.markers.insert(file.to_string());
+        }
+    }
     fn write(&mut self, value: &dyn AsRef<str>) {
         if value.as_ref().is_empty() {
             return;
@@ -91,9 +105,8 @@ impl TokenConsumer for BlueprintBuildResult {
             if let Some(current) = self.imports.get_mut(file) {
                 current.insert(value);
             } else {
-                let mut new = HashSet::new();
-                new.insert(value);
-                self.imports.insert(file.to_string(), new);
+                self.imports
+                    .insert(file.to_string(), BTreeSet::from([value]));
             }
         }
     }
@@ -109,6 +122,59 @@ impl TokenConsumer for BlueprintBuildResult {
     }
 }
 
+/// Collects the text of one `[join]` item, passing imports to the writer the
+/// joined items are written to.
+struct JoinItem<'w> {
+    text: String,
+    writer: &'w mut dyn TokenConsumer,
+}
+impl<'w> JoinItem<'w> {
+    fn new(writer: &'w mut dyn TokenConsumer) -> JoinItem<'w> {
+        JoinItem {
+            text: String::new(),
+            writer,
+        }
+    }
+}
+impl TokenConsumer for JoinItem<'_> {
+    fn set_file_name(&mut self, _filename: &str) {}
+    fn use_markers(&mut self) {}
+    fn import_point(&mut self) {}
+    fn write(&mut self, value: &dyn AsRef<str>) {
+        self.text.write(value);
+    }
+    fn delete_trailing(&mut self, value: &dyn AsRef<str>) {
+        self.text.delete_trailing(value);
+    }
+    fn import(&mut self, value: String) {
+        self.writer.import(value);
+    }
+}
+
+/// Durations and sizes of one output's build, reported by `--timings`.
+#[derive(Debug, Default)]
+pub struct RenderStats {
+    /// Time spent rendering the blueprint's templates
+    pub render: Duration,
+    /// Time spent assembling and writing (or printing) files
+    pub write: Duration,
+    /// Time spent running `format_cmd`
+    pub format: Duration,
+    /// Number of files generated
+    pub files: usize,
+    /// Total size of the generated files
+    pub bytes: usize,
+}
+
+/// A counter kept by `[increment]`, `[decrement]`, and `[reset]`, whose
+/// value `[name]` writes.
+#[derive(Debug, Default)]
+pub struct Counter {
+    pub value: i64,
+    /// The struct, enum, or file a scoped counter last counted in.
+    scope: Option<String>,
+}
+
 /// Orchestrates the code generation process using a blueprint and parsed schema.
 ///
 /// BlueprintRenderer takes a parsed schema, a target language blueprint, and output
@@ -124,7 +190,38 @@ pub struct BlueprintRenderer<'a> {
     pub config: &'a Output,
     /// Filter: differs in context, but used to reject certain builds.
     pub filter: Option<String>,
-    pub global_counters: HashMap<String, usize>,
+    pub counters: HashMap<String, Counter>,
+    /// Print generated files to stdout instead of writing them to disk.
+    pub stdout: bool,
+    /// Print a unified diff against the files on disk instead of writing them.
+    /// `[exec]` blocks are skipped.
+    pub diff: bool,
+    /// Files that differ from those on disk, with their diffs, recorded by
+    /// `build` in diff mode. `clean` records the files it would remove, with
+    /// no diff.
+    pub changed: Vec<(PathBuf, String)>,
+    /// Permit files that resolve outside the output location.
+    pub allow_outside_root: bool,
+    /// The exec policy given on the command line, if any.
+    pub exec_policy: Option<ExecPolicy>,
+    /// Fail on tags the renderer would otherwise ignore: unknown tags, and
+    /// close tags that don't close an open block.
+    pub strict: bool,
+    /// The files the last `build` or `clean` covered.
+    pub generated: Vec<PathBuf>,
+    /// Files the output's manifest lists from an earlier build that are not
+    /// in `generated`, found by the last `build` or `clean`. Another output
+    /// may still generate them.
+    pub ungenerated: Vec<PathBuf>,
+    /// Durations and sizes recorded by the last `build`.
+    pub stats: RenderStats,
+    /// `[index]` blocks found while rendering, with the file each writes.
+    /// They are rendered last, once every other file is known.
+    pending_indexes: Vec<(String, Vec<BlueprintToken>)>,
+    /// The files `[each file]` lists; only set while `[index]` blocks render.
+    generated_files: Option<Vec<String>>,
+    /// The file being rendered into, for counters with `scope=file`.
+    current_file: String,
 }
 impl<'a> BlueprintRenderer<'a> {
     /// Creates a new BlueprintRenderer with the necessary components for code generation.
@@ -146,7 +243,19 @@ impl<'a> BlueprintRenderer<'a> {
             blueprint,
             config,
             filter: None,
-            global_counters: HashMap::new(),
+            counters: HashMap::new(),
+            stdout: false,
+            diff: false,
+            changed: Vec::new(),
+            allow_outside_root: false,
+            exec_policy: None,
+            strict: false,
+            generated: Vec::new(),
+            ungenerated: Vec::new(),
+            stats: RenderStats::default(),
+            pending_indexes: Vec::new(),
+            generated_files: None,
+            current_file: String::new(),
         }
     }
 
@@ -160,56 +269,54 @@ impl<'a> BlueprintRenderer<'a> {
         while index < content.len() {
             let c = &content[index];
             match c {
-                BlueprintToken::Literal(lit_val) => {
+                BlueprintToken::Literal(lit_val) | BlueprintToken::Raw(lit_val) => {
                     writer.write(&lit_val);
                     index += 1;
                 }
                 BlueprintToken::Snippet(snip) => {
                     index += 1;
                     let starting_at = index;
-                    let mut embed_count = 1;
                     if !snip.autoclose {
-                        index += 1;
-                        while index < content.len() {
-                            let in_block = &content[index];
-                            match &in_block {
-                                BlueprintToken::Close(close) => {
-                                    if *close == snip.main_token {
-                                        embed_count -= 1;
-                                        if embed_count == 0 {
-                                            break;
-                                        }
-                                    }
-                                }
-                                BlueprintToken::Snippet(embedded)
-                                    if embedded.main_token == snip.main_token =>
-                                {
-                                    embed_count += 1;
-                                }
-                                _ => {}
-                            }
-                            index += 1;
-                        }
-                        if index > content.len() {
-                            // NOT FOUND!
-                            return Err(RepackError::from_lang_with_msg(
+                        index = block_end(content, index, &snip.main_token);
+                        // Without --strict, an unclosed block runs to the end of its parent.
+                        if index >= content.len() && self.strict {
+                            let mut e = RepackError::from_lang_with_msg(
                                 RepackErrorKind::SnippetNotClosed,
                                 self.config,
                                 snip.main_token.to_string(),
-                            ));
+                            );
+                            e.add_to_stack(snip);
+                            return Err(e);
                         }
                     }
-                    if let Err(mut e) = self.render_snippet(
-                        SnippetReference {
-                            details: snip,
-                            contents: &content[starting_at..index],
-                        },
-                        context,
-                        writer,
-                    ) {
+                    let block = SnippetReference {
+                        details: snip,
+                        contents: &content[starting_at..index],
+                    };
+                    if let SnippetMainTokenName::Set = block.main_token() {
+                        // The variable is in scope for the rest of the enclosing block.
+                        let scoped = self.set_variable(block, context).map_err(|mut e| {
+                            e.add_to_stack(snip);
+                            e
+                        })?;
+                        let rest = (index + 1).min(content.len());
+                        return self.render_tokens(&content[rest..], &scoped, writer);
+                    }
+                    if let Err(mut e) = self.render_snippet(block, context, writer) {
                         e.add_to_stack(snip);
                         return Err(e);
                     }
+                    if !snip.autoclose {
+                        // Step past the close tag.
+                        index += 1;
+                    }
+                }
+                BlueprintToken::Close(name) if self.strict => {
+                    return Err(RepackError::from_lang_with_msg(
+                        RepackErrorKind::SyntaxError,
+                        self.config,
+                        format!("[/{name}] does not close an open block"),
+                    ));
                 }
                 _ => {
                     index += 1;
@@ -219,6 +326,90 @@ impl<'a> BlueprintRenderer<'a> {
         Ok(())
     }
 
+    /// Renders the items of a `[join]` block: each iteration of an `[each]`
+    /// or `[eachr]` directly inside it is an item, as is the text between
+    /// them. Items are trimmed, and empty ones are left out.
+    ///
+    /// Imports are passed to `writer`.
+    fn join_items(
+        &mut self,
+        content: &[BlueprintToken],
+        context: &BlueprintExecutionContext,
+        writer: &mut dyn TokenConsumer,
+    ) -> Result<Vec<String>, RepackError> {
+        let mut items = Vec::new();
+        let mut text = JoinItem::new(writer);
+        let mut index = 0;
+        while index < content.len() {
+            let end = match &content[index] {
+                BlueprintToken::Snippet(snip) if !snip.autoclose => {
+                    block_end(content, index + 1, &snip.main_token)
+                }
+                _ => index,
+            };
+            let tokens = &content[index..(end + 1).min(content.len())];
+            index = end + 1;
+            let loop_block = match tokens.first() {
+                Some(BlueprintToken::Snippet(snip))
+                    if matches!(
+                        snip.main_token_name(),
+                        SnippetMainTokenName::Each | SnippetMainTokenName::Eachr
+                    ) =>
+                {
+                    let close = usize::from(end < content.len());
+                    Some(SnippetReference {
+                        details: snip,
+                        contents: &tokens[1..tokens.len() - close],
+                    })
+                }
+                _ => None,
+            };
+            let Some(block) = loop_block else {
+                self.render_tokens(tokens, context, &mut text)?;
+                continue;
+            };
+            items.push(std::mem::take(&mut text.text));
+            for mut ctx in self.loop_contexts(&block, context).map_err(|mut e| {
+                e.add_to_stack(block.details);
+                e
+            })? {
+                for import in std::mem::take(&mut ctx.imports) {
+                    text.import(import);
+                }
+                self.render_tokens(block.contents, &ctx, &mut text)?;
+                items.push(std::mem::take(&mut text.text));
+            }
+        }
+        items.push(text.text);
+        Ok(items
+            .iter()
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect())
+    }
+
+    /// Renders a `[set name]` block and returns a copy of the context with
+    /// its result stored in the `name` variable.
+    fn set_variable<'b>(
+        &mut self,
+        content: SnippetReference,
+        context: &BlueprintExecutionContext<'b>,
+    ) -> Result<BlueprintExecutionContext<'b>, RepackError> {
+        let name = &content.details.secondary_token;
+        if name.is_empty() {
+            return Err(RepackError::from_lang_with_msg(
+                RepackErrorKind::ParseIncomplete,
+                self.config,
+                "variable name in set".to_string(),
+            ));
+        }
+        let mut value = String::new();
+        self.render_tokens(content.contents, context, &mut value)?;
+        let mut scoped = context.clone();
+        scoped.variables.insert(name.to_string(), value);
+        Ok(scoped)
+    }
+
     fn render_snippet<'b>(
         &mut self,
         content: SnippetReference<'b>,
@@ -231,118 +422,132 @@ impl<'a> BlueprintRenderer<'a> {
                 if file_name.is_empty() {
                     self.render_tokens(content.contents, context, &mut file_name)?;
                 }
-                writer.set_file_name(&file_name);
+                self.current_file = self.file_name(&file_name)?;
+                writer.set_file_name(&self.current_file);
+                if content.details.secondary_token == "markers" {
+                    writer.use_markers();
+                }
             }
-            SnippetMainTokenName::Each | SnippetMainTokenName::Eachr => {
-                let rev = matches!(content.main_token(), SnippetMainTokenName::Eachr);
-                let iter_options: Vec<_> = match content.secondary_token() {
-                    SnippetSecondaryTokenName::Struct => self
-                        .parse_result
-                        .included_strcts(&self.config.categories, &self.config.exclude)
-                        .into_iter()
-                        .map(|x| Ok(context.with_strct(x)))
-                        .collect(),
-                    SnippetSecondaryTokenName::Field => {
-                        let Some(obj) = context.strct else {
-                            return Err(RepackError::from_lang_with_msg(
-                                RepackErrorKind::CannotCreateContext,
-                                self.config,
-                                "field in non-struct context.".to_string(),
-                            ));
-                        };
-                        obj.fields
-                            .iter()
-                            .map(|field| {
-                                context.with_field(obj, field, self.blueprint, self.config, writer)
-                            })
-                            .collect()
+            SnippetMainTokenName::FilePer => {
+                // Renders the block once per item, each into the file its pattern names.
+                let Some(pattern) = content.details.positional.first() else {
+                    return Err(RepackError::from_lang_with_msg(
+                        RepackErrorKind::ParseIncomplete,
+                        self.config,
+                        "file name pattern in file_per".to_string(),
+                    ));
+                };
+                let pattern = pattern_tokens(pattern);
+                let index = content.details.argument("index");
+                let entry =
+                    pattern_tokens(content.details.argument("entry").unwrap_or("{file_name}"));
+                for mut ctx in self.loop_contexts(&content, context)? {
+                    let mut file_name = String::new();
+                    self.render_tokens(&pattern, &ctx, &mut file_name)?;
+                    let file_name = self.file_name(&file_name)?;
+                    ctx.variables
+                        .insert("file_name".to_string(), file_name.clone());
+                    writer.set_file_name(&file_name);
+                    self.current_file = file_name;
+                    for import in std::mem::take(&mut ctx.imports) {
+                        writer.import(import);
                     }
-                    SnippetSecondaryTokenName::Query => {
-                        let Some(obj) = context.strct else {
-                            return Err(RepackError::from_lang_with_msg(
-                                RepackErrorKind::CannotCreateContext,
-                                self.config,
-                                "field in non-struct context.".to_string(),
-                            ));
-                        };
-                        obj.queries
-                            .iter()
-                            .map(|field| context.with_query(obj, field, self.parse_result))
-                            .collect()
+                    self.render_tokens(content.contents, &ctx, writer)?;
+                    if let Some(index) = index {
+                        writer.set_file_name(index);
+                        self.current_file = index.to_string();
+                        self.render_tokens(&entry, &ctx, writer)?;
+                        writer.write(&"\n");
                     }
-                    SnippetSecondaryTokenName::Enum => self
-                        .parse_result
-                        .included_enums(&self.config.categories, &self.config.exclude)
-                        .iter()
-                        .map(|enm| context.with_enum(enm))
-                        .collect(),
-                    SnippetSecondaryTokenName::Case => {
-                        let Some(enm) = context.enm else {
-                            return Err(RepackError::from_lang_with_msg(
-                                RepackErrorKind::CannotCreateContext,
-                                self.config,
-                                "case in non-enum context.".to_string(),
-                            ));
-                        };
-                        enm.options
-                            .iter()
-                            .map(|case| context.with_enum_case(enm, case))
-                            .collect()
+                }
+            }
+            SnippetMainTokenName::Index => {
+                // `[index mod.rs]` is deferred until the other files are written.
+                let mut file_name = String::new();
+                self.render_tokens(
+                    &pattern_tokens(&content.details.secondary_token),
+                    context,
+                    &mut file_name,
+                )?;
+                if file_name.is_empty() {
+                    return Err(RepackError::from_lang_with_msg(
+                        RepackErrorKind::ParseIncomplete,
+                        self.config,
+                        "file name in index".to_string(),
+                    ));
+                }
+                self.pending_indexes
+                    .push((self.file_name(&file_name)?, content.contents.to_vec()));
+            }
+            SnippetMainTokenName::Each | SnippetMainTokenName::Eachr => {
+                let separator = content.details.argument("sep").map(unescape_separator);
+                let contexts = self.loop_contexts(&content, context)?;
+                for (idx, mut ctx) in contexts.into_iter().enumerate() {
+                    if let Some(separator) = separator.as_ref().filter(|_| idx > 0) {
+                        writer.write(separator);
                     }
-                    SnippetSecondaryTokenName::Arg => {
-                        if let Some(args) = context.func_args {
-                            args.iter().map(|x| context.with_func_arg(x)).collect()
-                        } else if let Some(query) = context.query {
-                            query
-                                .args
-                                .iter()
-                                .map(|x| context.with_query_arg(x, self.blueprint, writer))
-                                .collect()
-                        } else {
-                            return Err(RepackError::from_lang_with_msg(
-                                RepackErrorKind::CannotCreateContext,
-                                self.config,
-                                "args in non-func context".to_string(),
-                            ));
-                        }
+                    for import in std::mem::take(&mut ctx.imports) {
+                        writer.import(import);
                     }
-                    _ => {
+                    self.render_tokens(content.contents, &ctx, writer)?;
+                }
+            }
+            SnippetMainTokenName::Match => {
+                if content.details.secondary_token.is_empty() {
+                    return Err(RepackError::from_lang_with_msg(
+                        RepackErrorKind::ParseIncomplete,
+                        self.config,
+                        "variable name in match".to_string(),
+                    ));
+                }
+                let value = self.resolve_operand(
+                    &ConditionOperand::Variable(content.details.secondary_token.clone()),
+                    context,
+                )?;
+                let (arms, fallback) = split_else(content.contents);
+                if let Some(arm) = match_arm(arms, &value).or(fallback) {
+                    self.render_tokens(arm, context, writer)?;
+                }
+            }
+            SnippetMainTokenName::Join => {
+                let Some(separator) = content.details.positional.first() else {
+                    return Err(RepackError::from_lang_with_msg(
+                        RepackErrorKind::ParseIncomplete,
+                        self.config,
+                        "separator in join".to_string(),
+                    ));
+                };
+                let separator = unescape_separator(separator);
+                let items = self.join_items(content.contents, context, writer)?;
+                writer.write(&items.join(&separator));
+            }
+            SnippetMainTokenName::If | SnippetMainTokenName::Ifn => {
+                let negate = matches!(content.main_token(), SnippetMainTokenName::Ifn);
+                let (matched, inline) = match &content.details.condition {
+                    Some(BlueprintCondition::Compare(lhs, op, rhs)) => {
+                        (self.compare(lhs, op, rhs, context)?, false)
+                    }
+                    Some(BlueprintCondition::Invalid(msg)) => {
                         return Err(RepackError::from_lang_with_msg(
-                            RepackErrorKind::VariableNotInScope,
+                            RepackErrorKind::SyntaxError,
                             self.config,
-                            content.details.secondary_token.to_string(),
+                            format!("condition: {msg}"),
                         ));
                     }
+                    _ => (
+                        self.flag_enabled(&content.details.secondary_token, context),
+                        true,
+                    ),
                 };
-                let len = iter_options.len();
-                if !rev {
-                    for (idx, ctx) in iter_options.into_iter().enumerate() {
-                        let mut ctx = ctx?;
-                        ctx.flags.insert("sep", idx + 1 < len);
-                        self.render_tokens(content.contents, &ctx, writer)?;
-                    }
-                } else {
-                    for (idx, ctx) in iter_options.into_iter().rev().enumerate() {
-                        let mut ctx = ctx?;
-                        ctx.flags.insert("sep", idx + 1 < len);
-                        self.render_tokens(content.contents, &ctx, writer)?;
-                    }
-                }
-            }
-            SnippetMainTokenName::If => {
-                let token = &content.details.secondary_token;
-
-                if context.flags.get(token.as_str()).copied().unwrap_or(false) {
-                    writer.write(&content.details.contents);
-                    self.render_tokens(content.contents, context, writer)?;
-                }
-            }
-            SnippetMainTokenName::Ifn => {
-                let token = &content.details.secondary_token;
 
-                if !context.flags.get(token.as_str()).copied().unwrap_or(false) {
-                    writer.write(&content.details.contents);
-                    self.render_tokens(content.contents, context, writer)?;
+                let (when_true, when_false) = split_else(content.contents);
+                if matched != negate {
+                    if inline {
+                        writer.write(&content.details.contents);
+                    }
+                    self.render_tokens(when_true, context, writer)?;
+                } else if let Some(when_false) = when_false {
+                    self.render_tokens(when_false, context, writer)?;
                 }
             }
             SnippetMainTokenName::Func => {
@@ -422,36 +627,16 @@ impl<'a> BlueprintRenderer<'a> {
             SnippetMainTokenName::Exec => {
                 let mut exec_reader = String::new();
                 self.render_tokens(content.contents, context, &mut exec_reader)?;
-                Console::update_msg(&format!(
-                    "{} would like to run a command. [y/N]",
-                    self.blueprint.name
-                ));
-                let confirm = Console::ask_confirmation();
-                if confirm {
+                if self.diff {
+                    // Commands may write files, so previews never run them.
+                    Console::update_msg(&format!(
+                        "Skipped a command from {}.",
+                        self.blueprint.name
+                    ));
+                } else if self.confirm_exec(&exec_reader, "would like to run a command")? {
                     Console::update_msg("Executing...");
-                    let mut exec = Command::new("sh")
-                        .arg("-s")
-                        .stdin(Stdio::piped())
-                        .stdout(Stdio::null())
-                        .stderr(Stdio::inherit())
-                        .spawn()
-                        .map_err(|e| {
-                            RepackError::global(
-                                RepackErrorKind::ProcessExecutionFailed,
-                                e.to_string(),
-                            )
-                        })?;
-                    if let Some(stdin) = exec.stdin.as_mut() {
-                        stdin.write_all(exec_reader.as_bytes()).map_err(|e| {
-                            RepackError::global(
-                                RepackErrorKind::ProcessExecutionFailed,
-                                e.to_string(),
-                            )
-                        })?;
-                    }
-                    exec.wait().map_err(|e| {
-                        RepackError::global(RepackErrorKind::ProcessExecutionFailed, e.to_string())
-                    })?;
+                    Console::detail(exec_reader.trim());
+                    self.blueprint.shell.run(&exec_reader)?;
                 }
             }
             SnippetMainTokenName::PlaceImports => {
@@ -477,13 +662,38 @@ impl<'a> BlueprintRenderer<'a> {
             SnippetMainTokenName::Break => {
                 writer.write(&"\n");
             }
-            SnippetMainTokenName::Increment => {
-                // Global counter increment; variable of same name outputs current value
+            SnippetMainTokenName::Increment
+            | SnippetMainTokenName::Decrement
+            | SnippetMainTokenName::Reset => {
+                // Counters are global; a variable of the same name outputs the current value.
                 let name = &content.details.secondary_token;
-                if let Some(glob) = self.global_counters.get_mut(name) {
-                    *glob += 1
-                } else {
-                    self.global_counters.insert(name.to_string(), 1);
+                let reset = matches!(content.main_token(), SnippetMainTokenName::Reset);
+                let amount = match content.details.positional.first() {
+                    Some(amount) => amount.parse::<i64>().map_err(|_| {
+                        RepackError::from_lang_with_msg(
+                            RepackErrorKind::SyntaxError,
+                            self.config,
+                            format!("{name}: {amount} is not a number"),
+                        )
+                    })?,
+                    None if reset => 0,
+                    None => 1,
+                };
+                let scope = self.counter_scope(&content, context)?;
+                let counter = self.counters.entry(name.to_string()).or_default();
+                if let Some(scope) = scope {
+                    // A scoped counter starts over in each struct, enum, or file.
+                    if counter.scope.as_ref().is_some_and(|s| *s != scope) {
+                        counter.value = 0;
+                    }
+                    counter.scope = Some(scope);
+                } else if reset {
+                    counter.scope = None;
+                }
+                match content.main_token() {
+                    SnippetMainTokenName::Increment => counter.value += amount,
+                    SnippetMainTokenName::Decrement => counter.value -= amount,
+                    _ => counter.value = amount,
                 }
             }
             SnippetMainTokenName::Render => {
@@ -499,91 +709,79 @@ impl<'a> BlueprintRenderer<'a> {
                     ));
                 }
             }
+            SnippetMainTokenName::Variable(var)
+                if self.strict
+                    && !var.contains('|')
+                    && !content.details.secondary_token.is_empty() =>
+            {
+                return Err(RepackError::from_lang_with_msg(
+                    RepackErrorKind::UnknownBlueprintTag,
+                    self.config,
+                    content.details.main_token.to_string(),
+                ));
+            }
             SnippetMainTokenName::Variable(var) => {
-                let mut components = var.split(".");
-                let name = components.next().ok_or_else(|| {
-                    RepackError::global(
-                        RepackErrorKind::ParseIncomplete,
-                        format!("variable '{var}'"),
-                    )
-                })?;
-                if let Some(glob) = self.global_counters.get(name) {
-                    writer.write(&glob.to_string());
-                } else if let Some(mut res) = context.variables.get(name).map(|x| x.to_string()) {
+                // `[name|default text]` writes the text when the variable is not set.
+                let (var, fallback) = match var.split_once('|') {
+                    Some((var, "default")) => {
+                        let text = format!(
+                            "{} {}",
+                            content.details.secondary_token, content.details.contents
+                        );
+                        (var, Some(text.trim().to_string()))
+                    }
+                    Some((_, filter)) => {
+                        return Err(RepackError::from_lang_with_msg(
+                            RepackErrorKind::InvalidVariableModifier,
+                            self.config,
+                            filter.to_string(),
+                        ));
+                    }
+                    None => (var.as_str(), None),
+                };
+                let mut components = var.split(".").peekable();
+                let mut name = components.next().unwrap_or_default().to_string();
+                // `opt.key` reads the output's options, which bare names may shadow.
+                let value = if name == "opt" {
+                    let key = components.next().ok_or_else(|| {
+                        RepackError::global(
+                            RepackErrorKind::ParseIncomplete,
+                            format!("option name in variable '{var}'"),
+                        )
+                    })?;
+                    name = format!("opt.{key}");
+                    self.config.options.get(key).cloned()
+                } else if name == "schema" {
+                    let key = components.next().ok_or_else(|| {
+                        RepackError::global(
+                            RepackErrorKind::ParseIncomplete,
+                            format!("metadata name in variable '{var}'"),
+                        )
+                    })?;
+                    name = format!("schema.{key}");
+                    self.schema_meta(key).map(|v| v.to_string())
+                } else if let Some(key) = components.peek().copied()
+                    && let Some(value) = self.scoped_value(&name, key, context)?
+                {
+                    // `struct.name` reads the enclosing struct's variables,
+                    // which a field's would otherwise hide.
+                    components.next();
+                    name = format!("{name}.{key}");
+                    Some(value)
+                } else {
+                    context.variables.get(&name).cloned()
+                };
+                if let Some(counter) = self.counters.get(&name) {
+                    writer.write(&counter.value.to_string());
+                } else if let Some(mut res) = value.or(fallback) {
                     for transform in components {
-                        match transform {
-                            "uppercase" => res = res.to_uppercase(),
-                            "lowercase" => res = res.to_lowercase(),
-                            "titlecase" => {
-                                res = res
-                                    .split('_')
-                                    .map(|x| {
-                                        let mut chars = x.chars();
-                                        match chars.next() {
-                                            None => String::new(),
-                                            Some(first) => {
-                                                first.to_uppercase().collect::<String>()
-                                                    + &chars.as_str().to_lowercase()
-                                            }
-                                        }
-                                    })
-                                    .collect::<Vec<_>>()
-                                    .join("")
-                            }
-                            "firstlower" => {
-                                res = res
-                                    .chars()
-                                    .enumerate()
-                                    .map(|(i, x)| {
-                                        if i == 0 {
-                                            return x.to_ascii_lowercase();
-                                        } else {
-                                            return x;
-                                        }
-                                    })
-                                    .collect()
-                            }
-                            "camelcase" => {
-                                res = res
-                                    .split('_')
-                                    .enumerate()
-                                    .map(|(i, x)| {
-                                        if i > 0 {
-                                            let mut chars = x.chars();
-                                            match chars.next() {
-                                                None => String::new(),
-                                                Some(first) => {
-                                                    first.to_uppercase().collect::<String>()
-                                                        + &chars.as_str().to_lowercase()
-                                                }
-                                            }
-                                        } else {
-                                            x.to_string()
-                                        }
-                                    })
-                                    .collect::<Vec<_>>()
-                                    .join("")
-                            }
-                            "split_period_first" => {
-                                res = res.split(".").next().unwrap_or("").to_string()
-                            }
-                            "split_period_last" => {
-                                res = res.split(".").last().unwrap_or("").to_string()
-                            }
-                            "split_dash_first" => {
-                                res = res.split("-").next().unwrap_or("").to_string()
-                            }
-                            "split_dash_last" => {
-                                res = res.split("-").last().unwrap_or("").to_string()
-                            }
-                            _ => {
-                                return Err(RepackError::from_lang_with_msg(
-                                    RepackErrorKind::InvalidVariableModifier,
-                                    self.config,
-                                    transform.to_string(),
-                                ));
-                            }
-                        }
+                        res = apply_modifier(&res, transform).ok_or_else(|| {
+                            RepackError::from_lang_with_msg(
+                                RepackErrorKind::InvalidVariableModifier,
+                                self.config,
+                                transform.to_string(),
+                            )
+                        })?;
                     }
                     writer.write(&res);
                 } else {
@@ -594,23 +792,758 @@ impl<'a> BlueprintRenderer<'a> {
                     ));
                 }
             }
+            _ if self.strict => {
+                return Err(RepackError::from_lang_with_msg(
+                    RepackErrorKind::UnknownBlueprintTag,
+                    self.config,
+                    content.details.main_token.to_string(),
+                ));
+            }
             _ => {}
         };
 
         Ok(())
     }
 
+    /// Lists the contexts an `[each]` or `[file_per]` loop renders its block
+    /// with, after applying its `filter` and `sort` arguments.
+    ///
+    /// Each context has the `sep`, `first`, and `last` flags and the `index`
+    /// and `index1` variables set for its position in the loop.
+    fn loop_contexts<'b>(
+        &self,
+        content: &SnippetReference<'b>,
+        context: &'b BlueprintExecutionContext<'b>,
+    ) -> Result<Vec<BlueprintExecutionContext<'b>>, RepackError>
+    where
+        'a: 'b,
+    {
+        let rev = matches!(content.main_token(), SnippetMainTokenName::Eachr);
+        let kind = content.secondary_token();
+        let iter_options: Vec<_> = match kind {
+            SnippetSecondaryTokenName::Struct => self
+                .parse_result
+                .included_strcts(self.config.categories.as_ref(), &self.config.exclude)
+                .into_iter()
+                .filter(|x| in_category(context, &x.categories))
+                .map(|x| Ok(context.with_strct(x)))
+                .collect(),
+            SnippetSecondaryTokenName::Field => {
+                if let Some(obj) = context.strct {
+                    self.included_fields(obj)
+                        .map(|field| context.with_field(obj, field, self.blueprint, self.config))
+                        .collect()
+                } else if let Some(cfg) = context.config {
+                    cfg.entries
+                        .iter()
+                        .map(|entry| {
+                            context.with_config_entry(cfg, entry, self.blueprint, self.config)
+                        })
+                        .collect()
+                } else {
+                    return Err(RepackError::from_lang_with_msg(
+                        RepackErrorKind::CannotCreateContext,
+                        self.config,
+                        "field in non-struct context.".to_string(),
+                    ));
+                }
+            }
+            SnippetSecondaryTokenName::Subfield => {
+                let obj = self.referenced_strct(context)?;
+                self.included_fields(obj)
+                    .map(|field| context.with_field(obj, field, self.blueprint, self.config))
+                    .collect()
+            }
+            SnippetSecondaryTokenName::Query => {
+                let Some(obj) = context.strct else {
+                    return Err(RepackError::from_lang_with_msg(
+                        RepackErrorKind::CannotCreateContext,
+                        self.config,
+                        "field in non-struct context.".to_string(),
+                    ));
+                };
+                obj.queries
+                    .iter()
+                    .map(|field| {
+                        context.with_query(
+                            obj,
+                            field,
+                            self.parse_result,
+                            self.blueprint,
+                            self.config,
+                        )
+                    })
+                    .collect()
+            }
+            SnippetSecondaryTokenName::Enum => self
+                .parse_result
+                .included_enums(self.config.categories.as_ref(), &self.config.exclude)
+                .iter()
+                .filter(|enm| in_category(context, &enm.categories))
+                .map(|enm| context.with_enum(enm))
+                .collect(),
+            SnippetSecondaryTokenName::Union => self
+                .parse_result
+                .included_unions(self.config.categories.as_ref(), &self.config.exclude)
+                .into_iter()
+                .filter(|union| in_category(context, &union.categories))
+                .map(|union| Ok(context.with_union(union)))
+                .collect(),
+            SnippetSecondaryTokenName::Variant => {
+                // Inside a field, the variants are those of the field's union.
+                let field_union = match context.field.and_then(|f| f.field_type.as_ref()) {
+                    Some(FieldType::Custom(name, CustomFieldType::Union)) => {
+                        self.parse_result.unions.iter().find(|u| u.name == *name)
+                    }
+                    _ => None,
+                };
+                let Some(union) = context.union.or(field_union) else {
+                    return Err(RepackError::from_lang_with_msg(
+                        RepackErrorKind::CannotCreateContext,
+                        self.config,
+                        "variant in non-union context.".to_string(),
+                    ));
+                };
+                union
+                    .variants
+                    .iter()
+                    .filter_map(|variant| {
+                        self.parse_result.strcts.iter().find(|s| s.name == *variant)
+                    })
+                    .map(|strct| Ok(context.with_variant(union, strct)))
+                    .collect()
+            }
+            SnippetSecondaryTokenName::Category => self
+                .categories(context)
+                .iter()
+                .map(|cat| Ok(context.with_category(cat)))
+                .collect(),
+            SnippetSecondaryTokenName::Join => {
+                let Some(obj) = context.strct else {
+                    return Err(RepackError::from_lang_with_msg(
+                        RepackErrorKind::CannotCreateContext,
+                        self.config,
+                        "join in non-struct context.".to_string(),
+                    ));
+                };
+                obj.joins
+                    .iter()
+                    .map(|join| Ok(context.with_join(obj, join, self.parse_result)))
+                    .collect()
+            }
+            SnippetSecondaryTokenName::File => {
+                let Some(files) = &self.generated_files else {
+                    return Err(RepackError::from_lang_with_msg(
+                        RepackErrorKind::CannotCreateContext,
+                        self.config,
+                        "file outside an index block.".to_string(),
+                    ));
+                };
+                files
+                    .iter()
+                    .map(|file| Ok(context.with_file(file)))
+                    .collect()
+            }
+            SnippetSecondaryTokenName::Config => self
+                .parse_result
+                .included_configs(self.config.categories.as_ref(), &self.config.exclude)
+                .into_iter()
+                .map(|cfg| Ok(context.with_config(cfg)))
+                .collect(),
+            SnippetSecondaryTokenName::Const => self
+                .parse_result
+                .included_consts(self.config.categories.as_ref(), &self.config.exclude)
+                .into_iter()
+                .filter(|cnst| in_category(context, &cnst.categories))
+                .map(|cnst| context.with_const(cnst, self.blueprint, self.config))
+                .collect(),
+            SnippetSecondaryTokenName::Case => {
+                let Some(enm) = context.enm else {
+                    return Err(RepackError::from_lang_with_msg(
+                        RepackErrorKind::CannotCreateContext,
+                        self.config,
+                        "case in non-enum context.".to_string(),
+                    ));
+                };
+                enm.options
+                    .iter()
+                    .map(|case| context.with_enum_case(enm, case))
+                    .collect()
+            }
+            SnippetSecondaryTokenName::Validation => {
+                let Some(field) = context.field else {
+                    return Err(RepackError::from_lang_with_msg(
+                        RepackErrorKind::CannotCreateContext,
+                        self.config,
+                        "validation in non-field context.".to_string(),
+                    ));
+                };
+                field
+                    .functions_in_namespace("validate")
+                    .into_iter()
+                    .map(|func| context.with_validation(func))
+                    .collect()
+            }
+            SnippetSecondaryTokenName::Arg => {
+                if let Some(args) = context.func_args {
+                    args.iter().map(|x| context.with_func_arg(x)).collect()
+                } else if let Some(query) = context.query {
+                    query
+                        .args
+                        .iter()
+                        .map(|x| context.with_query_arg(x, self.blueprint, self.config))
+                        .collect()
+                } else {
+                    return Err(RepackError::from_lang_with_msg(
+                        RepackErrorKind::CannotCreateContext,
+                        self.config,
+                        "args in non-func context".to_string(),
+                    ));
+                }
+            }
+            _ => {
+                return Err(RepackError::from_lang_with_msg(
+                    RepackErrorKind::VariableNotInScope,
+                    self.config,
+                    content.details.secondary_token.to_string(),
+                ));
+            }
+        };
+        let mut iter_options = iter_options
+            .into_iter()
+            .collect::<Result<Vec<_>, RepackError>>()?;
+        if matches!(
+            kind,
+            SnippetSecondaryTokenName::Field | SnippetSecondaryTokenName::Subfield
+        ) && self.alphabetical_fields()?
+        {
+            iter_options.sort_by_cached_key(|ctx| {
+                ctx.variables.get("name").map(|name| name.to_lowercase())
+            });
+        }
+        for filter in content.details.arguments_named("filter") {
+            iter_options.retain(|ctx| item_matches(filter, &kind, ctx));
+        }
+        if let Some(sort) = content.details.argument("sort") {
+            let (key, descending) = match sort.strip_prefix('-') {
+                Some(key) => (key, true),
+                None => (sort, false),
+            };
+            iter_options.sort_by(|a, b| {
+                let ordering = compare_sort_keys(a.variables.get(key), b.variables.get(key));
+                if descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+        }
+        if rev {
+            iter_options.reverse();
+        }
+        let len = iter_options.len();
+        for (idx, ctx) in iter_options.iter_mut().enumerate() {
+            ctx.flags.insert("sep", idx + 1 < len);
+            ctx.flags.insert("first", idx == 0);
+            ctx.flags.insert("last", idx + 1 == len);
+            ctx.variables.insert("index".to_string(), idx.to_string());
+            ctx.variables
+                .insert("index1".to_string(), (idx + 1).to_string());
+        }
+        Ok(iter_options)
+    }
+
+    /// The scope a counter tag's `scope=` argument names: the struct or enum
+    /// being rendered, or the file being written.
+    ///
+    /// # Returns
+    /// * `Ok(Some(String))` identifying the current struct, enum, or file
+    /// * `Ok(None)` if the tag has no `scope=`
+    /// * `Err(RepackError)` if the scope is unknown, or there is no struct or
+    ///   enum to count in
+    fn counter_scope(
+        &self,
+        content: &SnippetReference,
+        context: &BlueprintExecutionContext,
+    ) -> Result<Option<String>, RepackError> {
+        let Some(requested) = content.details.argument("scope") else {
+            return Ok(None);
+        };
+        let scope = match requested {
+            "file" => Some(format!("file:{}", self.current_file)),
+            "struct" => context.strct.map(|s| format!("struct:{}", s.name)),
+            "enum" => context.enm.map(|e| format!("enum:{}", e.name)),
+            other => {
+                return Err(RepackError::from_lang_with_msg(
+                    RepackErrorKind::SyntaxError,
+                    self.config,
+                    format!("scope: {other} (expected struct, enum, or file)"),
+                ));
+            }
+        };
+        scope.map(Some).ok_or_else(|| {
+            RepackError::from_lang_with_msg(
+                RepackErrorKind::VariableNotInScope,
+                self.config,
+                requested.to_string(),
+            )
+        })
+    }
+
+    /// Whether the output's `field_order` option lists fields by name rather
+    /// than in the order the schema declares them.
+    fn alphabetical_fields(&self) -> Result<bool, RepackError> {
+        match self.config.options.get("field_order").map(|o| o.as_str()) {
+            None | Some("schema") => Ok(false),
+            Some("alpha") => Ok(true),
+            Some(other) => Err(RepackError::from_lang_with_msg(
+                RepackErrorKind::InvalidFieldOrder,
+                self.config,
+                other.to_string(),
+            )),
+        }
+    }
+
+    /// Applies the blueprint's `[meta extension]` and `[meta file_case]` to a
+    /// file name from `[file]`, `[file_per]`, or `[index]`.
+    fn file_name(&self, name: &str) -> Result<String, RepackError> {
+        self.blueprint.file_name(name).map_err(|msg| {
+            RepackError::from_lang_with_msg(RepackErrorKind::InvalidFileName, self.config, msg)
+        })
+    }
+
+    /// The manifest listing the files this output has written under `root`,
+    /// one name per line, so files it stops generating can be found later.
+    fn manifest_path(&self, root: &Path) -> PathBuf {
+        let id = self
+            .blueprint
+            .id
+            .chars()
+            .map(
+                |c| match c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                    true => c,
+                    false => '_',
+                },
+            )
+            .collect::<String>();
+        root.join(format!(".repack-{id}.manifest"))
+    }
+
+    /// Lists the files, relative to the output location, that the output's
+    /// manifest says it wrote but that are not among `generated` and are
+    /// still on disk, such as those left behind by a renamed struct.
+    ///
+    /// # Arguments
+    /// * `root` - The output location on disk
+    /// * `generated` - The names of the files the output generates
+    fn ungenerated_files(&self, root: &Path, generated: &HashSet<String>) -> Vec<String> {
+        let Ok(manifest) = fs::read_to_string(self.manifest_path(root)) else {
+            return Vec::new();
+        };
+        let mut found = manifest
+            .lines()
+            .map(str::trim)
+            .filter(|name| !name.is_empty() && !name.starts_with('#'))
+            .filter(|name| !generated.contains(*name))
+            // A name that leaves the output location is never listed, even if
+            // the manifest was edited to hold one.
+            .filter(|name| {
+                self.output_file(root, name)
+                    .is_ok_and(|file| file.is_file())
+            })
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        found.sort();
+        found.dedup();
+        found
+    }
+
+    /// Records the output's files in `generated` and `ungenerated`.
+    ///
+    /// # Returns
+    /// The names of the ungenerated files, relative to the output location
+    fn record_files(
+        &mut self,
+        root: &Path,
+        display: &Path,
+        generated: &HashSet<String>,
+    ) -> Vec<String> {
+        let ungenerated = self.ungenerated_files(root, generated);
+        self.generated = generated.iter().map(|f| display.join(f)).collect();
+        self.ungenerated = ungenerated.iter().map(|f| display.join(f)).collect();
+        ungenerated
+    }
+
+    /// Writes `names` to the output's manifest, or removes the manifest when
+    /// there are none.
+    fn write_manifest(&self, root: &Path, mut names: Vec<&String>) -> Result<(), RepackError> {
+        let manifest = self.manifest_path(root);
+        if names.is_empty() {
+            _ = fs::remove_file(manifest);
+            return Ok(());
+        }
+        names.sort();
+        names.dedup();
+        let mut contents = format!(
+            "# Files repack wrote for the {} output. `repack build --prune`\n\
+             # removes the ones it no longer generates.\n",
+            self.blueprint.name
+        );
+        for name in names {
+            contents.push_str(name);
+            contents.push('\n');
+        }
+        fs::write(&manifest, contents).map_err(|_| {
+            RepackError::from_lang_with_msg(
+                RepackErrorKind::CannotWrite,
+                self.config,
+                manifest.display().to_string(),
+            )
+        })
+    }
+
+    /// The value of a key in the schema's `schema { ... }` metadata.
+    fn schema_meta(&self, key: &str) -> Option<&str> {
+        self.parse_result
+            .meta
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// The output's location, with each `{schema.key}` in it replaced by the
+    /// schema's metadata so a layout can follow the schema, as in
+    /// `@"gen/{schema.project}/models"`.
+    ///
+    /// # Returns
+    /// * `Ok(Option<String>)` with the location, if the output has one
+    /// * `Err(RepackError)` if it refers to metadata the schema doesn't define
+    fn location(&self) -> Result<Option<String>, RepackError> {
+        let Some(location) = &self.config.location else {
+            return Ok(None);
+        };
+        let mut resolved = String::new();
+        let mut rest = location.as_str();
+        while let Some(start) = rest.find("{schema.") {
+            resolved.push_str(&rest[..start]);
+            let Some(end) = rest[start..].find('}') else {
+                break;
+            };
+            let key = &rest[start + "{schema.".len()..start + end];
+            let value = self.schema_meta(key).ok_or_else(|| {
+                RepackError::from_lang_with_msg(
+                    RepackErrorKind::SchemaMetaNotFound,
+                    self.config,
+                    format!("{key} (in location {location})"),
+                )
+            })?;
+            resolved.push_str(value);
+            rest = &rest[start + end + 1..];
+        }
+        resolved.push_str(rest);
+        Ok(Some(resolved))
+    }
+
+    /// Checks whether a bare condition name is enabled in the current context.
+    ///
+    /// Names are checked as flags first, then `opt.key` checks whether the output
+    /// defines the option, and `field.count` style names are true when the
+    /// collection is non-empty.
+    fn flag_enabled(&self, name: &str, context: &BlueprintExecutionContext) -> bool {
+        if let Some(flag) = context.flags.get(name) {
+            return *flag;
+        }
+        if let Some(opt) = name.strip_prefix("opt.") {
+            return self.config.options.contains_key(opt);
+        }
+        if let Some(key) = name.strip_prefix("schema.") {
+            return self.schema_meta(key).is_some();
+        }
+        if let Some((scope, key)) = name.split_once('.')
+            && let Ok(Some(scoped)) = self.scope_context(scope, context)
+            && let Some(flag) = scoped.flags.get(key)
+        {
+            return *flag;
+        }
+        self.collection_count(name, context).unwrap_or(0) > 0
+    }
+
+    /// The context of the struct, field, enum, or query being rendered, with
+    /// only its own variables and flags, for names like `struct.name`.
+    ///
+    /// # Returns
+    /// * `Ok(Some(BlueprintExecutionContext))` for the innermost item of that kind
+    /// * `Ok(None)` if `scope` is not one of these, or no such item encloses `context`
+    fn scope_context<'c>(
+        &self,
+        scope: &str,
+        context: &BlueprintExecutionContext<'c>,
+    ) -> Result<Option<BlueprintExecutionContext<'c>>, RepackError>
+    where
+        'a: 'c,
+    {
+        let outer = BlueprintExecutionContext::new();
+        Ok(match scope {
+            "struct" => context.strct.map(|obj| outer.with_strct(obj)),
+            "field" => match (context.strct, context.field) {
+                (Some(obj), Some(field)) => {
+                    Some(outer.with_field(obj, field, self.blueprint, self.config)?)
+                }
+                _ => None,
+            },
+            "query" => match (context.strct, context.query) {
+                (Some(obj), Some(query)) => Some(outer.with_query(
+                    obj,
+                    query,
+                    self.parse_result,
+                    self.blueprint,
+                    self.config,
+                )?),
+                _ => None,
+            },
+            "enum" => context.enm.map(|enm| outer.with_enum(enm)).transpose()?,
+            _ => None,
+        })
+    }
+
+    /// A variable or flag of an enclosing item, read as `struct.name`,
+    /// `field.name`, `enum.name`, or `query.name`.
+    fn scoped_value(
+        &self,
+        scope: &str,
+        key: &str,
+        context: &BlueprintExecutionContext,
+    ) -> Result<Option<String>, RepackError> {
+        let Some(scoped) = self.scope_context(scope, context)? else {
+            return Ok(None);
+        };
+        Ok(scoped
+            .variables
+            .get(key)
+            .cloned()
+            .or_else(|| scoped.flags.get(key).map(|flag| flag.to_string())))
+    }
+
+    /// Lists the categories for `[each category]`.
+    ///
+    /// Inside a struct or enum these are the item's own categories; otherwise they
+    /// are every category used by an included struct or enum, sorted by name.
+    fn categories(&self, context: &BlueprintExecutionContext) -> Vec<String> {
+        if let Some(obj) = context.strct {
+            return obj.categories.clone();
+        }
+        if let Some(enm) = context.enm {
+            return enm.categories.clone();
+        }
+        let mut categories = self
+            .parse_result
+            .included_strcts(self.config.categories.as_ref(), &self.config.exclude)
+            .into_iter()
+            .flat_map(|x| x.categories.iter())
+            .chain(
+                self.parse_result
+                    .included_enums(self.config.categories.as_ref(), &self.config.exclude)
+                    .into_iter()
+                    .flat_map(|x| x.categories.iter()),
+            )
+            .cloned()
+            .collect::<Vec<_>>();
+        categories.sort();
+        categories.dedup();
+        categories
+    }
+
+    /// Finds the struct referenced by the current field, for `[each subfield]`.
+    fn referenced_strct(
+        &self,
+        context: &BlueprintExecutionContext,
+    ) -> Result<&'a RepackStruct, RepackError> {
+        let Some(FieldType::Custom(name, CustomFieldType::Object)) =
+            context.field.and_then(|f| f.field_type.as_ref())
+        else {
+            return Err(RepackError::from_lang_with_msg(
+                RepackErrorKind::CannotCreateContext,
+                self.config,
+                "subfield in non-object field context.".to_string(),
+            ));
+        };
+        self.parse_result
+            .strcts
+            .iter()
+            .find(|s| s.name == *name)
+            .ok_or_else(|| {
+                RepackError::from_lang_with_msg(
+                    RepackErrorKind::UnknownObject,
+                    self.config,
+                    name.to_string(),
+                )
+            })
+    }
+
+    /// The struct's fields, without those whose category tags this output leaves out.
+    fn included_fields<'b>(&self, obj: &'b RepackStruct) -> impl Iterator<Item = &'b Field> {
+        obj.fields
+            .iter()
+            .filter(|field| field.included_in(self.config.categories.as_ref()))
+    }
+
+    /// Returns the size of the collection named by `<collection>.count`, if any.
+    fn collection_count(&self, name: &str, context: &BlueprintExecutionContext) -> Option<i64> {
+        let collection = name.strip_suffix(".count")?;
+        let count = match SnippetSecondaryTokenName::from_string(collection) {
+            SnippetSecondaryTokenName::Struct => self
+                .parse_result
+                .included_strcts(self.config.categories.as_ref(), &self.config.exclude)
+                .into_iter()
+                .filter(|x| in_category(context, &x.categories))
+                .count(),
+            SnippetSecondaryTokenName::Enum => self
+                .parse_result
+                .included_enums(self.config.categories.as_ref(), &self.config.exclude)
+                .into_iter()
+                .filter(|x| in_category(context, &x.categories))
+                .count(),
+            SnippetSecondaryTokenName::Category => self.categories(context).len(),
+            SnippetSecondaryTokenName::Config => self
+                .parse_result
+                .included_configs(self.config.categories.as_ref(), &self.config.exclude)
+                .len(),
+            SnippetSecondaryTokenName::Const => self
+                .parse_result
+                .included_consts(self.config.categories.as_ref(), &self.config.exclude)
+                .into_iter()
+                .filter(|x| in_category(context, &x.categories))
+                .count(),
+            SnippetSecondaryTokenName::Field => match (context.strct, context.config) {
+                (Some(obj), _) => self.included_fields(obj).count(),
+                (None, Some(cfg)) => cfg.entries.len(),
+                _ => return None,
+            },
+            SnippetSecondaryTokenName::Subfield => self
+                .included_fields(self.referenced_strct(context).ok()?)
+                .count(),
+            SnippetSecondaryTokenName::Query => context.strct?.queries.len(),
+            SnippetSecondaryTokenName::Join => context.strct?.joins.len(),
+            SnippetSecondaryTokenName::File => self.generated_files.as_ref()?.len(),
+            SnippetSecondaryTokenName::Case => context.enm?.options.len(),
+            SnippetSecondaryTokenName::Validation => {
+                context.field?.functions_in_namespace("validate").len()
+            }
+            SnippetSecondaryTokenName::Arg => match (context.func_args, context.query) {
+                (Some(args), _) => args.len(),
+                (None, Some(query)) => query.args.len(),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        Some(count as i64)
+    }
+
+    /// Resolves a condition operand into its string value.
+    fn resolve_operand(
+        &self,
+        operand: &ConditionOperand,
+        context: &BlueprintExecutionContext,
+    ) -> Result<String, RepackError> {
+        let as_number = |value: String| {
+            value.parse::<i64>().map_err(|_| {
+                RepackError::from_lang_with_msg(
+                    RepackErrorKind::SyntaxError,
+                    self.config,
+                    format!("'{value}' is not a number"),
+                )
+            })
+        };
+        Ok(match operand {
+            ConditionOperand::Text(text) => text.clone(),
+            ConditionOperand::Number(num) => num.to_string(),
+            ConditionOperand::Add(lhs, rhs) => (as_number(self.resolve_operand(lhs, context)?)?
+                + as_number(self.resolve_operand(rhs, context)?)?)
+            .to_string(),
+            ConditionOperand::Subtract(lhs, rhs) => {
+                (as_number(self.resolve_operand(lhs, context)?)?
+                    - as_number(self.resolve_operand(rhs, context)?)?)
+                .to_string()
+            }
+            ConditionOperand::Variable(name) => {
+                if let Some(count) = self.collection_count(name, context) {
+                    count.to_string()
+                } else if let Some(opt) = name.strip_prefix("opt.") {
+                    self.config.options.get(opt).cloned().unwrap_or_default()
+                } else if let Some(key) = name.strip_prefix("schema.") {
+                    self.schema_meta(key).unwrap_or_default().to_string()
+                } else if let Some(counter) = self.counters.get(name) {
+                    counter.value.to_string()
+                } else if let Some(var) = context.variables.get(name) {
+                    var.clone()
+                } else if let Some((scope, key)) = name.split_once('.')
+                    && let Some(value) = self.scoped_value(scope, key, context)?
+                {
+                    value
+                } else if let Some(flag) = context.flags.get(name.as_str()) {
+                    flag.to_string()
+                } else {
+                    return Err(RepackError::from_lang_with_msg(
+                        RepackErrorKind::VariableNotInScope,
+                        self.config,
+                        name.to_string(),
+                    ));
+                }
+            }
+        })
+    }
+
+    /// Evaluates a comparison, numerically when both sides are integers.
+    fn compare(
+        &self,
+        lhs: &ConditionOperand,
+        op: &ComparisonOperator,
+        rhs: &ConditionOperand,
+        context: &BlueprintExecutionContext,
+    ) -> Result<bool, RepackError> {
+        let left = self.resolve_operand(lhs, context)?;
+        let right = self.resolve_operand(rhs, context)?;
+        let ordering = match (left.parse::<i64>(), right.parse::<i64>()) {
+            (Ok(l), Ok(r)) => l.cmp(&r),
+            _ => left.cmp(&right),
+        };
+        Ok(match op {
+            ComparisonOperator::Equal => ordering.is_eq(),
+            ComparisonOperator::NotEqual => ordering.is_ne(),
+            ComparisonOperator::Greater => ordering.is_gt(),
+            ComparisonOperator::GreaterOrEqual => ordering.is_ge(),
+            ComparisonOperator::Less => ordering.is_lt(),
+            ComparisonOperator::LessOrEqual => ordering.is_le(),
+        })
+    }
+
     /// Executes the complete code generation process and writes output files.
     ///
     /// This method processes the blueprint templates with the parsed schema data,
     /// generates all target source code files, handles import management, and
-    /// writes the final files to the configured output location.
+    /// writes the final files to the configured output location. When `stdout`
+    /// is set, files are printed in name order instead, each after a
+    /// `==> path <==` line. When `diff` is set, nothing is written; files that
+    /// would change are recorded in `changed` with their unified diffs, after
+    /// running `format_cmd` over them as a build would.
     ///
     /// # Returns
     /// * `Ok(())` if code generation completes successfully
     /// * `Err(RepackError)` if any step in the generation process fails
     pub fn build(&mut self, filter: Option<String>) -> Result<(), RepackError> {
         self.filter = filter;
+        let mut problems = unsupported_features(self.blueprint, self.parse_result, self.config);
+        problems.extend(name_collisions(
+            self.blueprint,
+            self.parse_result,
+            self.config,
+        ));
+        if let Some(last) = problems.pop() {
+            for problem in problems {
+                Console::report(problem);
+            }
+            return Err(last);
+        }
         let mut files = BlueprintBuildResult::default();
         let mut context = BlueprintExecutionContext::new();
         for opt in &self.config.options {
@@ -618,16 +1551,44 @@ impl<'a> BlueprintRenderer<'a> {
                 .variables
                 .insert(opt.0.to_string(), opt.1.to_string());
         }
+        let render_start = Instant::now();
         _ = &self.render_tokens(&self.blueprint.tokens, &context, &mut files)?;
+        let indexes = std::mem::take(&mut self.pending_indexes);
+        if !indexes.is_empty() {
+            let mut generated = files
+                .contents
+                .keys()
+                .filter(|file| !indexes.iter().any(|(index, _)| index == *file))
+                .cloned()
+                .collect::<Vec<_>>();
+            generated.sort();
+            self.generated_files = Some(generated);
+            for (index, tokens) in &indexes {
+                files.set_file_name(index);
+                self.current_file = index.to_string();
+                self.render_tokens(tokens, &context, &mut files)?;
+            }
+            self.generated_files = None;
+        }
+        self.stats.render = render_start.elapsed();
+        let write_start = Instant::now();
         let mut path = current_dir()
             .map_err(|_| RepackError::global(RepackErrorKind::PathNotValid, String::new()))?;
-        if let Some(loc) = &self.config.location {
-            path.push(loc);
+        let mut display = PathBuf::new();
+        if let Some(loc) = self.location()?.filter(|l| l != "-") {
+            path.push(&loc);
+            display.push(&loc);
         }
-        _ = fs::create_dir_all(&path);
-        for f in files.contents {
-            let mut file = path.clone();
-            file.push(&f.0);
+        if !self.stdout && !self.diff {
+            _ = fs::create_dir_all(&path);
+        }
+        let mut contents: Vec<_> = files.contents.into_iter().collect();
+        contents.sort_by(|a, b| a.0.cmp(&b.0));
+        let generated: HashSet<String> = contents.iter().map(|f| f.0.clone()).collect();
+        let mut written = Vec::new();
+        let mut rendered = Vec::new();
+        for f in contents {
+            let file = self.output_file(&path, &f.0)?;
 
             let mut write_value = String::new();
             for part in f.1 {
@@ -645,7 +1606,35 @@ impl<'a> BlueprintRenderer<'a> {
                     }
                 }
             }
+            if let Ok(previous) = fs::read_to_string(&file) {
+                let (restored, removed) = restore_keep_regions(&write_value, &previous);
+                if let Some(region) = removed.first() {
+                    return Err(RepackError::from_lang_with_msg(
+                        RepackErrorKind::KeptCodeRemoved,
+                        self.config,
+                        format!("keep:{region} in {}", f.0),
+                    ));
+                }
+                write_value = restored;
+            }
+            if files.markers.contains(&f.0) {
+                write_value = self.write_between_markers(&file, &f.0, &write_value)?;
+            }
 
+            self.stats.files += 1;
+            self.stats.bytes += write_value.len();
+            if self.diff {
+                rendered.push((f.0, file, write_value));
+                continue;
+            }
+            if self.stdout {
+                println!("==> {} <==", display.join(&f.0).display());
+                print!("{write_value}");
+                if !write_value.ends_with('\n') {
+                    println!();
+                }
+                continue;
+            }
             fs::write(file, write_value).map_err(|_| {
                 RepackError::from_lang_with_msg(
                     RepackErrorKind::CannotWrite,
@@ -653,10 +1642,249 @@ impl<'a> BlueprintRenderer<'a> {
                     f.0.to_string(),
                 )
             })?;
+            Console::detail(&format!("Wrote {}", display.join(&f.0).display()));
+            written.push(display.join(&f.0));
+        }
+        if self.diff {
+            self.record_changes(rendered, &display)?;
         }
+        if !self.stdout {
+            let ungenerated = self.record_files(&path, &display, &generated);
+            if !self.diff {
+                // Files with markers are partly written by hand, so they are
+                // never listed for removal.
+                let listed = generated
+                    .iter()
+                    .filter(|name| !files.markers.contains(*name))
+                    .chain(&ungenerated)
+                    .collect();
+                self.write_manifest(&path, listed)?;
+            }
+        }
+        self.stats.write = write_start.elapsed();
+        let format_start = Instant::now();
+        self.format_files(&written)?;
+        self.stats.format = format_start.elapsed();
         Ok(())
     }
 
+    /// Records the rendered files that differ from those on disk in `changed`.
+    ///
+    /// Files on disk were formatted by `format_cmd` when they were built, so
+    /// with a formatter the rendered files are first written to a temporary
+    /// directory under the same names and formatted there.
+    ///
+    /// # Arguments
+    /// * `rendered` - Each file's name in the output, path on disk, and contents
+    /// * `display` - The output's location, prefixed to the names in diffs
+    ///
+    /// # Returns
+    /// * `Ok(())` once every changed file is recorded
+    /// * `Err(RepackError)` if the formatter could not be run
+    fn record_changes(
+        &mut self,
+        mut rendered: Vec<(String, PathBuf, String)>,
+        display: &Path,
+    ) -> Result<(), RepackError> {
+        if self.config.options.contains_key("format_cmd") && !rendered.is_empty() {
+            let temp = std::env::temp_dir().join(format!(
+                "repack-diff-{}-{}",
+                std::process::id(),
+                self.config.profile
+            ));
+            let formatted = self.format_rendered(&temp, &mut rendered);
+            _ = fs::remove_dir_all(&temp);
+            formatted?;
+        }
+        for (name, file, value) in rendered {
+            let name = display.join(&name);
+            let previous = fs::read_to_string(&file).ok();
+            if previous.as_deref() != Some(&value) {
+                let diff = unified_diff(previous.as_deref(), &value, &name.to_string_lossy());
+                self.changed.push((name, diff));
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs `format_cmd` over copies of the rendered files in `temp`, replacing
+    /// each file's contents with the formatted result.
+    fn format_rendered(
+        &self,
+        temp: &Path,
+        rendered: &mut [(String, PathBuf, String)],
+    ) -> Result<(), RepackError> {
+        let cannot_write = |name: &str| {
+            RepackError::from_lang_with_msg(
+                RepackErrorKind::CannotWrite,
+                self.config,
+                temp.join(name).display().to_string(),
+            )
+        };
+        let mut copies = Vec::new();
+        for (name, _, value) in rendered.iter() {
+            let copy = temp.join(name);
+            if let Some(parent) = copy.parent() {
+                fs::create_dir_all(parent).map_err(|_| cannot_write(name))?;
+            }
+            fs::write(&copy, value.as_bytes()).map_err(|_| cannot_write(name))?;
+            copies.push(copy);
+        }
+        self.format_files(&copies)?;
+        for ((name, _, value), copy) in rendered.iter_mut().zip(&copies) {
+            *value = fs::read_to_string(copy).map_err(|_| {
+                RepackError::from_lang_with_msg(
+                    RepackErrorKind::CannotRead,
+                    self.config,
+                    name.to_string(),
+                )
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Places generated content between the markers of an existing file.
+    ///
+    /// # Returns
+    /// * `Ok(String)` with the file's contents, the generated lines in place of the old ones
+    /// * `Err(RepackError)` if the file cannot be read or has no markers
+    fn write_between_markers(
+        &self,
+        file: &Path,
+        name: &str,
+        generated: &str,
+    ) -> Result<String, RepackError> {
+        let existing = fs::read_to_string(file).map_err(|_| {
+            RepackError::from_lang_with_msg(
+                RepackErrorKind::CannotRead,
+                self.config,
+                name.to_string(),
+            )
+        })?;
+        replace_between_markers(&existing, generated).ok_or_else(|| {
+            RepackError::from_lang_with_msg(
+                RepackErrorKind::MissingMarkers,
+                self.config,
+                name.to_string(),
+            )
+        })
+    }
+
+    /// Runs the output's `format_cmd` option over the files just written.
+    ///
+    /// The file paths, usually relative to the working directory, are appended to the
+    /// command. It runs in the blueprint's shell under the same exec policy as
+    /// `[exec]` blocks.
+    ///
+    /// # Returns
+    /// * `Ok(())` if there is no formatter, it was skipped, or it succeeded
+    /// * `Err(RepackError)` if it was denied, could not start, or failed
+    fn format_files(&self, files: &[PathBuf]) -> Result<(), RepackError> {
+        let Some(format_cmd) = self.config.options.get("format_cmd") else {
+            return Ok(());
+        };
+        if files.is_empty() {
+            return Ok(());
+        }
+        let shell = self.blueprint.shell;
+        let mut script = format_cmd.to_string();
+        for file in files {
+            script.push(' ');
+            script.push_str(&shell.quote(&file.to_string_lossy()));
+        }
+        if !self.confirm_exec(format_cmd, &format!("would like to run `{format_cmd}`"))? {
+            return Ok(());
+        }
+        Console::update_msg("Formatting...");
+        Console::detail(&script);
+        if !shell.run(&script)? {
+            return Err(RepackError::from_lang_with_msg(
+                RepackErrorKind::ProcessExecutionFailed,
+                self.config,
+                format!("format_cmd `{format_cmd}` failed"),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Decides whether a command may run under the output's exec policy,
+    /// prompting if the policy is `ask`.
+    ///
+    /// # Arguments
+    /// * `command` - The command, reported if the policy is `deny`
+    /// * `request` - Completes the prompt "<blueprint> ..."
+    ///
+    /// # Returns
+    /// * `Ok(bool)` whether to run the command
+    /// * `Err(RepackError)` if the policy is `deny` or not valid
+    fn confirm_exec(&self, command: &str, request: &str) -> Result<bool, RepackError> {
+        Ok(
+            match ExecPolicy::for_output(self.config, self.exec_policy)? {
+                ExecPolicy::Ask => {
+                    Console::ask_confirmation(&format!("{} {request}. [y/N]", self.blueprint.name))
+                }
+                ExecPolicy::Yes => true,
+                ExecPolicy::Skip => {
+                    Console::update_msg(&format!(
+                        "Skipped a command from {}.",
+                        self.blueprint.name
+                    ));
+                    false
+                }
+                ExecPolicy::Deny => {
+                    return Err(RepackError::from_lang_with_msg(
+                        RepackErrorKind::ExecDenied,
+                        self.config,
+                        command.trim().to_string(),
+                    ));
+                }
+            },
+        )
+    }
+
+    /// Joins a file name from the blueprint onto the output location.
+    ///
+    /// Names that are absolute or climb out with `..` are rejected, as are names
+    /// whose existing directories resolve elsewhere through a symlink, and names
+    /// of existing symlinks, unless `allow_outside_root` is set.
+    ///
+    /// # Returns
+    /// * `Ok(PathBuf)` with the path to write
+    /// * `Err(RepackError)` if the path escapes the output location
+    fn output_file(&self, root: &Path, name: &str) -> Result<PathBuf, RepackError> {
+        let file = root.join(name);
+        if self.allow_outside_root {
+            return Ok(file);
+        }
+        let outside = || {
+            RepackError::from_lang_with_msg(
+                RepackErrorKind::OutputOutsideRoot,
+                self.config,
+                name.to_string(),
+            )
+        };
+        let mut depth = 0usize;
+        for component in Path::new(name).components() {
+            match component {
+                Component::Normal(_) => depth += 1,
+                Component::CurDir => {}
+                Component::ParentDir => depth = depth.checked_sub(1).ok_or_else(outside)?,
+                Component::RootDir | Component::Prefix(_) => return Err(outside()),
+            }
+        }
+        if let (Ok(root), Some(Ok(parent))) =
+            (root.canonicalize(), file.parent().map(Path::canonicalize))
+            && !parent.starts_with(&root)
+        {
+            return Err(outside());
+        }
+        // Writing through a symlink would follow it wherever it points.
+        if fs::symlink_metadata(&file).is_ok_and(|meta| meta.file_type().is_symlink()) {
+            return Err(outside());
+        }
+        Ok(file)
+    }
+
     /// Removes all previously generated files from the output directory.
     ///
     /// This method identifies which files would be generated by the current
@@ -667,21 +1895,52 @@ impl<'a> BlueprintRenderer<'a> {
     /// * `Ok(())` if cleanup completes successfully
     /// * `Err(RepackError)` if files cannot be removed
     pub fn clean(&mut self) -> Result<(), RepackError> {
+        if self.stdout {
+            return Ok(());
+        }
         let mut files = HashSet::<String>::new();
         self.render_tokens(
             &self.blueprint.tokens,
             &BlueprintExecutionContext::new(),
             &mut files,
         )?;
+        files.extend(self.pending_indexes.drain(..).map(|(index, _)| index));
         let mut path = current_dir()
             .map_err(|_| RepackError::global(RepackErrorKind::PathNotValid, String::new()))?;
-        if let Some(loc) = &self.config.location {
-            path.push(loc);
+        let mut display = PathBuf::new();
+        if let Some(loc) = self.location()? {
+            path.push(&loc);
+            display.push(&loc);
+        }
+        let ungenerated = self.record_files(&path, &display, &files);
+        if self.diff {
+            let mut existing = files
+                .iter()
+                .filter(|f| self.output_file(&path, f).is_ok_and(|file| file.exists()))
+                .map(|f| (display.join(f), String::new()))
+                .collect::<Vec<_>>();
+            existing.sort();
+            self.changed = existing;
+            return Ok(());
         }
         _ = fs::create_dir_all(&path);
         for f in &files {
-            let mut file = path.clone();
-            file.push(f);
+            let file = self.output_file(&path, f)?;
+            // A file with markers was written by hand around them, so only its
+            // generated lines are removed.
+            if let Some(emptied) = fs::read_to_string(&file)
+                .ok()
+                .and_then(|existing| replace_between_markers(&existing, ""))
+            {
+                fs::write(&file, emptied).map_err(|_| {
+                    RepackError::from_lang_with_msg(
+                        RepackErrorKind::CannotWrite,
+                        self.config,
+                        f.to_string(),
+                    )
+                })?;
+                continue;
+            }
             fs::remove_file(file).map_err(|_| {
                 RepackError::from_lang_with_msg(
                     RepackErrorKind::CannotWrite,
@@ -691,9 +1950,423 @@ impl<'a> BlueprintRenderer<'a> {
             })?;
         }
 
+        self.write_manifest(&path, ungenerated.iter().collect())?;
         // Will not delete if dir is not empty.
         _ = fs::remove_dir(&path);
 
         Ok(())
     }
 }
+
+/// Replaces the lines between the first line containing `repack:begin` and the
+/// next line containing `repack:end` with `generated`.
+///
+/// The marker lines themselves are kept, so they can be comments in whatever
+/// syntax the file uses, like `// repack:begin` or `-- repack:begin`.
+///
+/// # Returns
+/// * `Some(String)` with the new contents
+/// * `None` if `existing` has no pair of markers
+fn replace_between_markers(existing: &str, generated: &str) -> Option<String> {
+    let mut begin = None;
+    let mut offset = 0;
+    for line in existing.split_inclusive('\n') {
+        match begin {
+            None if line.contains("repack:begin") => begin = Some(offset + line.len()),
+            Some(begin) if line.contains("repack:end") => {
+                let mut out = existing[..begin].to_string();
+                out.push_str(generated);
+                if !generated.is_empty() && !generated.ends_with('\n') {
+                    out.push('\n');
+                }
+                out.push_str(&existing[offset..]);
+                return Some(out);
+            }
+            _ => {}
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// The name of the keep region a line opens, as in `// keep:methods`.
+fn keep_region_name(line: &str) -> Option<&str> {
+    line.split_whitespace()
+        .find_map(|word| word.strip_prefix("keep:"))
+        .filter(|name| !name.is_empty())
+}
+
+/// Whether a line closes a keep region, as in `// endkeep`.
+fn ends_keep_region(line: &str) -> bool {
+    line.split_whitespace().any(|word| word == "endkeep")
+}
+
+/// The lines inside each keep region of a file, by region name.
+fn keep_regions(contents: &str) -> HashMap<String, String> {
+    let mut regions = HashMap::new();
+    let mut open: Option<(&str, String)> = None;
+    for line in contents.split_inclusive('\n') {
+        if let Some((name, kept)) = open.as_mut() {
+            if ends_keep_region(line) {
+                regions
+                    .entry(name.to_string())
+                    .or_insert(std::mem::take(kept));
+                open = None;
+            } else {
+                kept.push_str(line);
+            }
+        } else if let Some(name) = keep_region_name(line) {
+            open = Some((name, String::new()));
+        }
+    }
+    regions
+}
+
+/// Puts the lines kept in each region of the previous version of a file back
+/// into the region of the same name in the newly generated one.
+///
+/// A region the previous version did not have keeps whatever the blueprint
+/// generated inside it.
+///
+/// # Returns
+/// The new contents, and the names of regions that held code in the previous
+/// version but are no longer generated
+fn restore_keep_regions(generated: &str, previous: &str) -> (String, Vec<String>) {
+    let mut kept = keep_regions(previous);
+    let closed = keep_regions(generated);
+    let mut out = String::new();
+    let mut restoring = false;
+    for line in generated.split_inclusive('\n') {
+        if restoring {
+            if ends_keep_region(line) {
+                restoring = false;
+                out.push_str(line);
+            }
+            continue;
+        }
+        out.push_str(line);
+        if let Some(code) = keep_region_name(line)
+            .filter(|name| closed.contains_key(*name))
+            .and_then(|name| kept.remove(name))
+        {
+            out.push_str(&code);
+            restoring = true;
+        }
+    }
+    let mut removed = kept
+        .into_iter()
+        .filter(|(_, code)| !code.trim().is_empty())
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    removed.sort();
+    (out, removed)
+}
+
+/// Whether an item with these categories belongs to the current `[each category]`.
+fn in_category(context: &BlueprintExecutionContext, categories: &[String]) -> bool {
+    context
+        .category
+        .as_ref()
+        .is_none_or(|cat| categories.contains(cat))
+}
+
+/// Finds the close tag of a block whose contents begin at `start`, counting
+/// nested blocks of the same kind.
+///
+/// # Returns
+/// The index of the close tag, or the length of `content` if there is none
+fn block_end(content: &[BlueprintToken], start: usize, main_token: &str) -> usize {
+    let mut embed_count = 1;
+    let mut index = start;
+    while index < content.len() {
+        match &content[index] {
+            BlueprintToken::Close(close) if close == main_token => {
+                embed_count -= 1;
+                if embed_count == 0 {
+                    break;
+                }
+            }
+            BlueprintToken::Snippet(embedded) if embedded.main_token == main_token => {
+                embed_count += 1;
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+    index
+}
+
+/// Finds the `[case]` arm of a `[match]` block listing `value`. An arm may
+/// list several values, as in `[case int32 int64]`.
+fn match_arm<'t>(arms: &'t [BlueprintToken], value: &str) -> Option<&'t [BlueprintToken]> {
+    let mut index = 0;
+    while index < arms.len() {
+        if let BlueprintToken::Snippet(snip) = &arms[index]
+            && !snip.autoclose
+        {
+            let end = block_end(arms, index + 1, &snip.main_token);
+            let matched = std::iter::once(snip.secondary_token.as_str())
+                .chain(snip.contents.split_whitespace())
+                .any(|case| case.trim_matches('"') == value);
+            if matches!(snip.main_token_name(), SnippetMainTokenName::Case) && matched {
+                return Some(&arms[index + 1..end]);
+            }
+            index = end;
+        }
+        index += 1;
+    }
+    None
+}
+
+/// Reads the `sep=` argument of an `each` block, where `\n` and `\t` stand
+/// for a newline and a tab.
+fn unescape_separator(separator: &str) -> String {
+    separator.replace("\\n", "\n").replace("\\t", "\t")
+}
+
+/// Orders two values of a `sort=` key: numerically when both are numbers, so
+/// `9` comes before `10`, and as text otherwise. Items without the key come first.
+fn compare_sort_keys(a: Option<&String>, b: Option<&String>) -> std::cmp::Ordering {
+    match (
+        a.and_then(|a| a.parse::<f64>().ok()),
+        b.and_then(|b| b.parse::<f64>().ok()),
+    ) {
+        (Some(x), Some(y)) => x.total_cmp(&y),
+        _ => a.cmp(&b),
+    }
+}
+
+/// Checks an iteration item against a `filter=` argument of an `each` block.
+///
+/// Supported filters:
+/// - `category:name` - the struct, enum, or union is tagged with the category
+/// - `func:namespace.name` - the field or struct defines the function
+/// - `flag` - the flag is set for the item (e.g. `optional`, `queries`)
+///
+/// Any filter may be prefixed with `!` to invert it.
+fn item_matches(
+    filter: &str,
+    kind: &SnippetSecondaryTokenName,
+    ctx: &BlueprintExecutionContext,
+) -> bool {
+    let (filter, negate) = match filter.strip_prefix('!') {
+        Some(rest) => (rest, true),
+        None => (filter, false),
+    };
+    let matched = if let Some(category) = filter.strip_prefix("category:") {
+        let categories = match kind {
+            SnippetSecondaryTokenName::Struct => ctx.strct.map(|s| &s.categories),
+            SnippetSecondaryTokenName::Enum => ctx.enm.map(|e| &e.categories),
+            SnippetSecondaryTokenName::Union => ctx.union.map(|u| &u.categories),
+            SnippetSecondaryTokenName::Const => ctx.cnst.map(|c| &c.categories),
+            SnippetSecondaryTokenName::Field => ctx.field.map(|f| &f.categories),
+            _ => None,
+        };
+        categories.is_some_and(|c| c.iter().any(|x| x == category))
+    } else if let Some(func) = filter.strip_prefix("func:") {
+        let (namespace, name) = func.split_once('.').unwrap_or((func, ""));
+        match kind {
+            SnippetSecondaryTokenName::Field => ctx
+                .field
+                .is_some_and(|f| f.function(namespace, name).is_some()),
+            SnippetSecondaryTokenName::Struct => ctx.strct.is_some_and(|s| {
+                s.functions_in_namespace(namespace)
+                    .iter()
+                    .any(|f| f.name == name)
+            }),
+            _ => false,
+        }
+    } else {
+        ctx.flags.get(filter).copied().unwrap_or(false)
+    };
+    matched != negate
+}
+
+/// Splits the body of an `if`/`ifn` block at its top-level `[else]`.
+///
+/// `[else]` snippets inside nested conditionals belong to those blocks and
+/// are skipped.
+///
+/// # Returns
+/// The tokens rendered when the condition matches, and the `[else]` branch if present
+fn split_else(tokens: &[BlueprintToken]) -> (&[BlueprintToken], Option<&[BlueprintToken]>) {
+    let mut depth = 0;
+    for (idx, token) in tokens.iter().enumerate() {
+        match token {
+            BlueprintToken::Snippet(snip) => match snip.main_token_name() {
+                SnippetMainTokenName::If
+                | SnippetMainTokenName::Ifn
+                | SnippetMainTokenName::Match => depth += 1,
+                SnippetMainTokenName::Else if depth == 0 => {
+                    return (&tokens[..idx], Some(&tokens[idx + 1..]));
+                }
+                _ => {}
+            },
+            BlueprintToken::Close(close) if close == "if" || close == "ifn" || close == "match" => {
+                depth -= 1
+            }
+            _ => {}
+        }
+    }
+    (tokens, None)
+}
+
+/// Reads a `[file_per]` pattern such as `"{name.snakecase}.rs"` into tokens,
+/// where each `{...}` is rendered like the tag `[...]`.
+pub(crate) fn pattern_tokens(pattern: &str) -> Vec<BlueprintToken> {
+    let mut tokens = Vec::new();
+    let mut rest = pattern;
+    while let Some((text, after)) = rest.split_once('{') {
+        let Some((var, after)) = after.split_once('}') else {
+            break;
+        };
+        if !text.is_empty() {
+            tokens.push(BlueprintToken::Literal(text.to_string()));
+        }
+        tokens.push(BlueprintToken::Snippet(BlueprintSnippetDetails {
+            main_token: var.to_string(),
+            autoclose: true,
+            ..Default::default()
+        }));
+        rest = after;
+    }
+    if !rest.is_empty() {
+        tokens.push(BlueprintToken::Literal(rest.to_string()));
+    }
+    tokens
+}
+
+/// Applies one variable modifier, such as `snakecase` in `[name.snakecase]`.
+///
+/// # Returns
+/// * `Some(String)` with the modified value
+/// * `None` if the modifier is not recognized
+pub(crate) fn apply_modifier(value: &str, modifier: &str) -> Option<String> {
+    let capitalize = |word: &str| {
+        let mut chars = word.chars();
+        match chars.next() {
+            None => String::new(),
+            Some(first) => {
+                first.to_uppercase().collect::<String>() + &chars.as_str().to_lowercase()
+            }
+        }
+    };
+    Some(match modifier {
+        "uppercase" => value.to_uppercase(),
+        "lowercase" => value.to_lowercase(),
+        "titlecase" => value.split('_').map(capitalize).collect(),
+        "firstlower" => value
+            .chars()
+            .enumerate()
+            .map(|(i, x)| if i == 0 { x.to_ascii_lowercase() } else { x })
+            .collect(),
+        "camelcase" => value
+            .split('_')
+            .enumerate()
+            .map(|(i, x)| if i > 0 { capitalize(x) } else { x.to_string() })
+            .collect(),
+        "snakecase" => split_words(value).join("_"),
+        "kebabcase" => split_words(value).join("-"),
+        "quote_ident" => quote_ident(value),
+        "split_period_first" => value.split('.').next().unwrap_or("").to_string(),
+        "split_period_last" => value.split('.').next_back().unwrap_or("").to_string(),
+        "split_dash_first" => value.split('-').next().unwrap_or("").to_string(),
+        "split_dash_last" => value.split('-').next_back().unwrap_or("").to_string(),
+        _ => return None,
+    })
+}
+
+/// Splits an identifier into lowercase words for case conversion.
+///
+/// Word boundaries are underscores, dashes, spaces, and case changes, so
+/// `UserAccount`, `userAccount`, and `user_account` all produce
+/// `["user", "account"]`. Runs of capitals are kept together as an
+/// acronym (`HTTPServer` becomes `["http", "server"]`).
+pub(crate) fn split_words(value: &str) -> Vec<String> {
+    let chars: Vec<char> = value.chars().collect();
+    let mut words = Vec::new();
+    let mut current = String::new();
+    for (idx, c) in chars.iter().enumerate() {
+        if *c == '_' || *c == '-' || c.is_whitespace() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        if c.is_uppercase() && !current.is_empty() {
+            let prev = chars[idx - 1];
+            let next_is_lower = chars.get(idx + 1).is_some_and(|n| n.is_lowercase());
+            if prev.is_lowercase() || prev.is_numeric() || (prev.is_uppercase() && next_is_lower) {
+                words.push(std::mem::take(&mut current));
+            }
+        }
+        current.extend(c.to_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blueprint::BlueprintFileReader;
+    use crate::syntax::FileContents;
+
+    /// Renders `blueprint` for the first output of `schema`, without writing
+    /// any files.
+    fn render(schema: &str, blueprint: &str) -> Result<String, RepackError> {
+        let mut contents = FileContents::empty();
+        contents.add_string(schema);
+        let result = ParseResult::read_contents(contents)
+            .and_then(ParseResult::resolve)
+            .unwrap();
+        let blueprint = Blueprint::new(BlueprintFileReader::new(blueprint.as_bytes()))?;
+        let mut renderer = BlueprintRenderer::new(&result, &blueprint, &result.languages[0]);
+        let mut out = String::new();
+        renderer.render_tokens(
+            &blueprint.tokens,
+            &BlueprintExecutionContext::new(),
+            &mut out,
+        )?;
+        Ok(out)
+    }
+
+    #[test]
+    fn index_inside_each_is_the_position() {
+        let out = render(
+            "struct User {\n\tid int32\n\tname string\n}\noutput test;\n",
+            "[meta id]test[/meta][meta name]Test[/meta~]\n\
+             [define int32]i32[/define][define string]str[/define~]\n\
+             [each struct][each field][index]:[name] [/each][/each]",
+        )
+        .unwrap();
+        assert_eq!(out, "0:id 1:name ");
+    }
+
+    fn sorted(keys: &[Option<&str>]) -> Vec<Option<String>> {
+        let mut keys = keys
+            .iter()
+            .map(|key| key.map(str::to_string))
+            .collect::<Vec<_>>();
+        keys.sort_by(|a, b| compare_sort_keys(a.as_ref(), b.as_ref()));
+        keys
+    }
+
+    #[test]
+    fn sorts_numeric_keys_as_numbers() {
+        assert_eq!(
+            sorted(&[Some("10"), Some("9"), Some("-1")]),
+            [Some("-1"), Some("9"), Some("10")].map(|k| k.map(str::to_string))
+        );
+    }
+
+    #[test]
+    fn sorts_other_keys_as_text() {
+        assert_eq!(
+            sorted(&[Some("b"), None, Some("10"), Some("a")]),
+            [None, Some("10"), Some("a"), Some("b")].map(|k| k.map(str::to_string))
+        );
+    }
+}
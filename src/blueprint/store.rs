@@ -1,4 +1,4 @@
-use std::{collections::HashMap, fs::File, io::Read, path::PathBuf};
+use std::{collections::HashMap, env, fs, fs::File, io::Read, path::PathBuf, process::Command};
 
 use crate::{
     blueprint::{Blueprint, BlueprintFileReader},
@@ -6,20 +6,30 @@ use crate::{
 };
 
 /// Embedded core blueprint definitions for built-in language support.
-/// 
+///
 /// These blueprints are compiled into the binary and provide immediate support
 /// for common target languages without requiring external blueprint files.
 /// Each blueprint defines the code generation templates and rules for its language.
 const CORE_BLUEPRINTS: &[&str] = &[
     include_str!("core/rust.blueprint"),
+    include_str!("core/rust-serde.blueprint"),
+    include_str!("core/rust-sqlx.blueprint"),
+    include_str!("core/rust-axum.blueprint"),
     include_str!("core/postgres.blueprint"),
     include_str!("core/typescript.blueprint"),
+    include_str!("core/typescript-zod.blueprint"),
+    include_str!("core/typescript-express.blueprint"),
     include_str!("core/go.blueprint"),
+    include_str!("core/csharp.blueprint"),
     include_str!("core/markdown.blueprint"),
+    include_str!("core/mermaid.blueprint"),
+    include_str!("core/env.blueprint"),
+    include_str!("core/configmap.blueprint"),
+    include_str!("core/typescript-config.blueprint"),
 ];
 
 /// Central repository for managing and accessing blueprint definitions.
-/// 
+///
 /// BlueprintStore handles loading, storing, and retrieving blueprints for different
 /// target languages. It manages both core built-in blueprints and user-defined
 /// external blueprints loaded from files.
@@ -29,10 +39,10 @@ pub struct BlueprintStore {
 }
 impl BlueprintStore {
     /// Creates a new BlueprintStore with all core blueprints loaded.
-    /// 
+    ///
     /// This constructor initializes the store and loads all embedded core blueprints
     /// (Rust, PostgreSQL, TypeScript, Go) making them immediately available for use.
-    /// 
+    ///
     /// # Returns
     /// * `Ok(BlueprintStore)` if all core blueprints load successfully
     /// * `Err(RepackError)` if any core blueprint fails to parse
@@ -49,14 +59,14 @@ impl BlueprintStore {
     }
 
     /// Loads a blueprint from an external file and adds it to the store.
-    /// 
+    ///
     /// This method reads a blueprint file from disk, parses it, and adds it to
     /// the available blueprints. The blueprint's ID from the file is used as
     /// the key for later retrieval.
-    /// 
+    ///
     /// # Arguments
     /// * `path` - Path to the blueprint file to load
-    /// 
+    ///
     /// # Returns
     /// * `Ok(())` if the blueprint loads successfully
     /// * `Err(RepackError)` if the file cannot be read or parsed
@@ -70,9 +80,7 @@ impl BlueprintStore {
         let mut contents = vec![];
         _ = file.read_to_end(&mut contents);
 
-        let reader = BlueprintFileReader {
-            reader: contents.iter().peekable(),
-        };
+        let reader = BlueprintFileReader::new(&contents);
 
         let lang = Blueprint::new(reader)?;
         self.languages.insert(lang.id.clone(), lang);
@@ -80,10 +88,56 @@ impl BlueprintStore {
         Ok(())
     }
 
+    /// Loads a blueprint from an `http://` or `https://` URL.
+    ///
+    /// Downloads are cached (see [`remote_cache_path`]) and reused on later runs,
+    /// so a URL is only fetched once. The download is done with `curl`.
+    ///
+    /// # Arguments
+    /// * `url` - The blueprint URL
+    /// * `offline` - When true, never download; the blueprint must already be cached
+    ///
+    /// # Returns
+    /// * `Ok(())` if the blueprint loads successfully
+    /// * `Err(RepackError)` if it is not cached while offline, cannot be downloaded, or fails to parse
+    pub fn load_remote(&mut self, url: &str, offline: bool) -> Result<(), RepackError> {
+        let path = remote_cache_path(url);
+        if !path.exists() {
+            if offline {
+                return Err(RepackError::global(
+                    RepackErrorKind::BlueprintNotCached,
+                    url.to_string(),
+                ));
+            }
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir).map_err(|_| {
+                    RepackError::global(
+                        RepackErrorKind::CannotWrite,
+                        dir.to_str().unwrap_or("<invalid path>").to_string(),
+                    )
+                })?;
+            }
+            // Download next to the cache entry so a failed transfer never leaves a partial file behind.
+            let download = path.with_extension("download");
+            let fetched = Command::new("curl")
+                .args(["-fsSL", "-o"])
+                .arg(&download)
+                .arg(url)
+                .status()
+                .is_ok_and(|status| status.success());
+            if !fetched || fs::rename(&download, &path).is_err() {
+                _ = fs::remove_file(&download);
+                return Err(RepackError::global(
+                    RepackErrorKind::ProcessExecutionFailed,
+                    format!("curl could not download {url}"),
+                ));
+            }
+        }
+        self.load_file(&path)
+    }
+
     pub fn load_string(&mut self, contents: &str) -> Result<(), RepackError> {
-        let reader = BlueprintFileReader {
-            reader: contents.as_bytes().iter().peekable(),
-        };
+        let reader = BlueprintFileReader::new(contents.as_bytes());
         let lang = Blueprint::new(reader)?;
         self.languages.insert(lang.id.clone(), lang);
 
@@ -91,17 +145,50 @@ impl BlueprintStore {
     }
 
     /// Retrieves a blueprint by its identifier.
-    /// 
+    ///
     /// This method looks up a loaded blueprint by its ID/tag, which is typically
     /// used as the profile name in output configurations.
-    /// 
+    ///
     /// # Arguments
     /// * `tag` - The blueprint identifier to look up
-    /// 
+    ///
     /// # Returns
     /// * `Some(&Blueprint)` if a blueprint with the given ID exists
     /// * `None` if no blueprint with the given ID is found
     pub fn blueprint(&self, tag: &str) -> Option<&Blueprint> {
         self.languages.get(tag)
     }
+
+    /// Lists every loaded blueprint, sorted by identifier.
+    pub fn blueprints(&self) -> Vec<&Blueprint> {
+        let mut blueprints = self.languages.values().collect::<Vec<_>>();
+        blueprints.sort_by(|a, b| a.id.cmp(&b.id));
+        blueprints
+    }
+}
+
+/// Returns where a remote blueprint is cached.
+///
+/// The cache lives in `$REPACK_CACHE_DIR`, or `$XDG_CACHE_HOME/repack`, or
+/// `~/.cache/repack`, falling back to `.repack-cache` in the working directory.
+/// Each URL gets its own file, named by a stable hash of the URL followed by
+/// the URL's last path segment so the cache stays readable.
+fn remote_cache_path(url: &str) -> PathBuf {
+    let mut path = env::var_os("REPACK_CACHE_DIR")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("XDG_CACHE_HOME").map(|dir| PathBuf::from(dir).join("repack")))
+        .or_else(|| env::var_os("HOME").map(|dir| PathBuf::from(dir).join(".cache/repack")))
+        .unwrap_or_else(|| PathBuf::from(".repack-cache"));
+    // FNV-1a, which unlike `DefaultHasher` is stable across Rust releases.
+    let hash = url.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    let name = url
+        .rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty())
+        .unwrap_or("remote.blueprint");
+    path.push("blueprints");
+    path.push(format!("{hash:016x}-{name}"));
+    path
 }
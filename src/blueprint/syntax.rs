@@ -1,8 +1,12 @@
+use super::{BlueprintCondition, SnippetMainTokenName};
+
 #[derive(Debug, Clone)]
 pub enum BlueprintToken {
     Literal(String),
+    /// Text from an escape or `[raw]` block, written exactly as given
+    Raw(String),
     Snippet(BlueprintSnippetDetails),
-    Close(String)
+    Close(String),
 }
 
 #[derive(Debug, Clone, Default)]
@@ -11,4 +15,100 @@ pub struct BlueprintSnippetDetails {
     pub secondary_token: String,
     pub contents: String,
     pub autoclose: bool,
+    pub condition: Option<BlueprintCondition>,
+    /// `key=value` arguments following the secondary token, e.g. `[each field sort=name]`
+    pub arguments: Vec<(String, String)>,
+    /// Values following the secondary token without a key, e.g. the file name in
+    /// `[file_per struct "{name}.rs"]`
+    pub positional: Vec<String>,
+    /// Where the tag starts in the blueprint file, counting from 1
+    pub line: usize,
+    pub column: usize,
+}
+impl BlueprintSnippetDetails {
+    /// The kind of tag this is. `[index]` without a file name is the loop
+    /// position variable rather than an `[index name]` block.
+    pub fn main_token_name(&self) -> SnippetMainTokenName {
+        match SnippetMainTokenName::from_string(&self.main_token) {
+            SnippetMainTokenName::Index if self.secondary_token.is_empty() => {
+                SnippetMainTokenName::Variable(self.main_token.to_string())
+            }
+            main => main,
+        }
+    }
+
+    /// Parses `key=value` pairs from the snippet contents into `arguments`.
+    ///
+    /// Values may be wrapped in double quotes to include spaces. Values
+    /// without a key are added to `positional`.
+    pub fn parse_arguments(&mut self) {
+        let mut chars = self.contents.chars().peekable();
+        loop {
+            while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                chars.next();
+            }
+            if chars.peek().is_none() {
+                break;
+            }
+            if chars.peek() == Some(&'"') {
+                self.positional.push(read_value(&mut chars));
+                continue;
+            }
+            let mut key = String::new();
+            while let Some(c) = chars.peek() {
+                if *c == '=' || c.is_whitespace() {
+                    break;
+                }
+                key.push(*c);
+                chars.next();
+            }
+            if chars.peek() != Some(&'=') {
+                self.positional.push(key);
+                continue;
+            }
+            chars.next();
+            let value = read_value(&mut chars);
+            self.arguments.push((key, value));
+        }
+    }
+
+    /// Returns the first value given for an argument.
+    pub fn argument(&self, key: &str) -> Option<&str> {
+        self.arguments
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Returns every value given for an argument, in order.
+    pub fn arguments_named<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> {
+        self.arguments
+            .iter()
+            .filter(move |(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Reads an argument value, which ends at whitespace unless it is wrapped in
+/// double quotes.
+fn read_value(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut value = String::new();
+    if chars.peek() == Some(&'"') {
+        chars.next();
+        for c in chars.by_ref() {
+            if c == '"' {
+                break;
+            }
+            value.push(c);
+        }
+    } else {
+        while let Some(c) = chars.peek() {
+            if c.is_whitespace() {
+                break;
+            }
+            value.push(*c);
+            chars.next();
+        }
+    }
+    value
 }
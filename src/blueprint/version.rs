@@ -0,0 +1,138 @@
+use std::fmt::Display;
+
+/// A `major.minor.patch` version, as used by `[meta version]` and repack itself.
+///
+/// Missing components are treated as zero, so `0.4` is `0.4.0`. Pre-release
+/// and build suffixes (`-beta`, `+sha`) are ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+impl Version {
+    pub fn from_string(val: &str) -> Option<Version> {
+        let core = val.trim().split(['-', '+']).next()?;
+        if core.split('.').count() > 3 {
+            return None;
+        }
+        let mut parts = core.split('.');
+        let mut next = || {
+            parts
+                .next()
+                .map(|p| p.parse::<u64>().ok())
+                .unwrap_or(Some(0))
+        };
+        Some(Version {
+            major: next()?,
+            minor: next()?,
+            patch: next()?,
+        })
+    }
+
+    /// The version of the running repack binary.
+    pub fn current() -> Version {
+        Version::from_string(env!("CARGO_PKG_VERSION")).unwrap_or(Version {
+            major: 0,
+            minor: 0,
+            patch: 0,
+        })
+    }
+}
+impl Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Whether `REPACK_IGNORE_REQUIREMENTS` is set, so blueprints load whatever
+/// their `[meta requires_repack]` says, such as when trying an unreleased build.
+pub fn requirements_ignored() -> bool {
+    std::env::var_os("REPACK_IGNORE_REQUIREMENTS").is_some_and(|v| !v.is_empty())
+}
+
+/// Checks a version against a requirement such as `>=0.4` or `>=0.4, <1.0`.
+///
+/// Each comma-separated clause is an operator (`>=`, `>`, `<=`, `<`, `=`)
+/// followed by a version; a bare version means `=`. All clauses must hold.
+///
+/// # Arguments
+/// * `requirement` - The requirement string from `[meta requires_repack]`
+/// * `version` - The version to check
+///
+/// # Returns
+/// * `Ok(bool)` whether the version satisfies the requirement
+/// * `Err(String)` describing the clause that could not be parsed
+pub fn version_satisfies(requirement: &str, version: &Version) -> Result<bool, String> {
+    let mut satisfied = true;
+    for clause in requirement.split(',') {
+        let clause = clause.trim();
+        let (op, rest) = ["<=", ">=", "<", ">", "="]
+            .iter()
+            .find_map(|op| clause.strip_prefix(op).map(|rest| (*op, rest)))
+            .unwrap_or(("=", clause));
+        let Some(bound) = Version::from_string(rest) else {
+            return Err(format!("'{clause}' is not a valid version requirement"));
+        };
+        satisfied &= match op {
+            "<=" => *version <= bound,
+            ">=" => *version >= bound,
+            "<" => *version < bound,
+            ">" => *version > bound,
+            _ => *version == bound,
+        };
+    }
+    Ok(satisfied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(val: &str) -> Version {
+        Version::from_string(val).unwrap()
+    }
+
+    #[test]
+    fn parses_partial_and_suffixed_versions() {
+        assert_eq!(v("1.2.3").to_string(), "1.2.3");
+        assert_eq!(v("0.4").to_string(), "0.4.0");
+        assert_eq!(v("2").to_string(), "2.0.0");
+        assert_eq!(v(" 1.2.3-beta+sha ").to_string(), "1.2.3");
+        assert_eq!(Version::from_string("1.2.3.4"), None);
+        assert_eq!(Version::from_string("1.x"), None);
+        assert_eq!(Version::from_string(""), None);
+    }
+
+    #[test]
+    fn checks_each_operator() {
+        let version = v("0.4.2");
+        assert_eq!(version_satisfies(">=0.4", &version), Ok(true));
+        assert_eq!(version_satisfies(">=0.5", &version), Ok(false));
+        assert_eq!(version_satisfies(">0.4.2", &version), Ok(false));
+        assert_eq!(version_satisfies(">0.4.1", &version), Ok(true));
+        assert_eq!(version_satisfies("<=0.4.2", &version), Ok(true));
+        assert_eq!(version_satisfies("<0.4.2", &version), Ok(false));
+        assert_eq!(version_satisfies("=0.4.2", &version), Ok(true));
+        assert_eq!(version_satisfies("0.4.2", &version), Ok(true));
+        assert_eq!(version_satisfies("0.4", &version), Ok(false));
+    }
+
+    #[test]
+    fn requires_every_clause() {
+        assert_eq!(version_satisfies(">=0.4, <1.0", &v("0.9.9")), Ok(true));
+        assert_eq!(version_satisfies(">=0.4, <1.0", &v("1.0.0")), Ok(false));
+        assert_eq!(version_satisfies(">=0.4, <1.0", &v("0.3.0")), Ok(false));
+        assert_eq!(version_satisfies(" >= 0.4 ,< 1.0 ", &v("0.5.0")), Ok(true));
+    }
+
+    #[test]
+    fn rejects_malformed_requirements() {
+        let version = v("1.0.0");
+        assert!(version_satisfies(">=abc", &version).is_err());
+        assert!(version_satisfies(">=1.0, ", &version).is_err());
+        assert!(version_satisfies("", &version).is_err());
+        assert!(version_satisfies("~1.0", &version).is_err());
+        assert!(version_satisfies(">=1.0.0.0", &version).is_err());
+    }
+}
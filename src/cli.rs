@@ -0,0 +1,458 @@
+use crate::blueprint::ExecPolicy;
+use crate::console::{ErrorFormat, Verbosity};
+use crate::mock::MockFormat;
+use crate::syntax::{CompatPolicy, LintRule};
+
+/// Defines the operational mode for the repack code generator.
+///
+/// This enum determines what action the tool will take when executed.
+/// The behavior is determined by command-line arguments passed to the application.
+pub enum Behavior {
+    /// Generate code files from the schema using blueprint templates.
+    /// This is the default mode that creates output files in target languages
+    /// like Rust, TypeScript, PostgreSQL, Go, and Markdown.
+    Build,
+    /// Remove previously generated code files, cleaning up the output directories.
+    /// Uses blueprint metadata to determine which files to delete.
+    Clean,
+    /// Generate configuration files (such as `.env.example`) from `config`
+    /// declarations, using only blueprints with `[meta kind]configure`.
+    Configure,
+    /// Generate documentation, using only blueprints with `[meta kind]document`.
+    Document,
+    /// Render every output in memory and print unified diffs against the files
+    /// on disk, without writing anything or running commands.
+    Diff,
+    /// Render the build outputs in memory like `Diff`, and fail if any
+    /// generated file differs from the one on disk.
+    Check,
+    /// Write a GraphViz DOT file of struct and enum dependencies next to the schema.
+    /// No blueprints are rendered.
+    Graph,
+    /// Rewrite the schema file in canonical formatting. With `check`, only
+    /// report whether the file is already formatted.
+    Format { check: bool },
+    /// Check the schema against lint rules and print any findings.
+    /// Rules passed with `--allow` are skipped in addition to those in `lint` blocks.
+    Lint { allow: Vec<LintRule> },
+    /// List the built-in blueprints and those loaded by the schema, with their versions.
+    Blueprints,
+    /// Print the parsed schema as JSON to stdout for use by external tools.
+    /// Progress output is suppressed and diagnostics go to stderr.
+    Inspect,
+    /// Prepare and `EXPLAIN` every query against a Postgres database given by
+    /// a `psql` connection string, reporting those that fail to plan.
+    VerifyQueries { connection: String },
+    /// Write the schema's JSON IR to `out`, or next to the schema as
+    /// `name.snapshot.json`, to compare later versions against.
+    Snapshot { out: Option<String> },
+    /// Print the structs, fields, enum cases, and unions that changed since
+    /// the `old` schema, usually a snapshot.
+    DiffSchema { old: String },
+    /// Compare the schema with the `against` schema, usually a snapshot, and
+    /// fail if a change is not allowed by the `policy`.
+    CheckCompat {
+        against: String,
+        policy: CompatPolicy,
+    },
+    /// Print the version that should follow the `against` schema's, given the
+    /// changes since it, and with `write` set it in the schema's `schema` block.
+    NextVersion { against: String, write: bool },
+    /// Print `count` fake records for every struct to stdout, as JSON or SQL
+    /// inserts. The same `seed` always produces the same records.
+    Mock {
+        count: usize,
+        format: MockFormat,
+        seed: u64,
+    },
+}
+
+/// Where `repack import` reads existing definitions from.
+pub enum ImportSource {
+    /// A PostgreSQL database, given by a `psql` connection string
+    Postgres { connection: String },
+    /// Sample JSON payloads, inferred as one struct named `name` (or after the file)
+    Json { file: String, name: Option<String> },
+    /// The component schemas of an OpenAPI spec
+    OpenApi { file: String },
+}
+
+/// What the command line asks repack to do.
+pub enum Command {
+    /// Run a [`Behavior`] against a schema file (`-` for stdin).
+    Schema { behavior: Behavior, file: String },
+    /// List the built-in blueprints, without a schema.
+    Blueprints,
+    /// Check a blueprint file without a schema.
+    BlueprintCheck { file: String, strict: bool },
+    /// Write a starter schema into `dir`, using the comma-separated `blueprints`.
+    Init {
+        dir: Option<String>,
+        blueprints: Option<String>,
+    },
+    /// Print a schema imported from another source.
+    Import(ImportSource),
+}
+
+/// Flags every command accepts, wherever they appear on the command line.
+#[derive(Debug, Default)]
+pub struct GlobalFlags {
+    /// `--deny-warnings`: fail the run if there are warnings
+    pub deny_warnings: bool,
+    /// `--offline`: load remote blueprints only from the cache
+    pub offline: bool,
+    /// `--stdout`: print generated files instead of writing them
+    pub stdout: bool,
+    /// `--allow-outside-root`: let blueprints write outside the output location
+    pub allow_outside_root: bool,
+    /// `--strict`: fail on blueprint tags that would otherwise be ignored
+    pub strict: bool,
+    /// `--yes` or `--no-exec`. `--no-exec` wins if both are given, so a
+    /// command is never run by accident.
+    pub exec_policy: Option<ExecPolicy>,
+    /// `--timings` prints a table to stderr (`Some(None)`); `--timings=file.json`
+    /// writes JSON instead.
+    pub timings: Option<Option<String>>,
+    /// `--quiet`/`-q` or `--verbose`/`-v`
+    pub verbosity: Verbosity,
+    /// `--dry-run`: report the files that would be written or removed, without
+    /// touching them or running commands
+    pub dry_run: bool,
+    /// `--prune`: remove files an output no longer generates
+    pub prune: bool,
+    /// `--define key=value`, in the order given: options set on every output,
+    /// replacing any the schema sets
+    pub defines: Vec<(String, String)>,
+    /// `--error-format human|json`
+    pub error_format: ErrorFormat,
+    /// `(blueprint, option, value)` from a settings file's `[output.<blueprint>]`
+    /// sections, set on the outputs using that blueprint. An option named
+    /// `location` replaces the output's location.
+    pub output_overrides: Vec<(String, String, String)>,
+}
+
+/// The parsed command line.
+pub struct Cli {
+    pub command: Command,
+    pub flags: GlobalFlags,
+}
+impl Cli {
+    /// Parses the arguments after the program name.
+    ///
+    /// Global flags are taken out first, wherever they appear, and may be
+    /// written `--flag value` or `--flag=value`. They are applied over
+    /// `flags`, which holds the defaults from a settings file, so the command
+    /// line wins. The first remaining argument names the subcommand; a lone
+    /// file with no subcommand builds it, as in `repack file.repack`.
+    ///
+    /// # Returns
+    /// * `Ok(Cli)` with the command and flags
+    /// * `Err(String)` describing the first argument that could not be used
+    pub fn parse(args: &[String], mut flags: GlobalFlags) -> Result<Cli, String> {
+        let mut no_exec = false;
+        let mut rest = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let (name, inline) = match arg.split_once('=') {
+                Some((name, value)) if name.starts_with("--") => (name, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+            let mut value = |flag: &str| {
+                inline
+                    .clone()
+                    .or_else(|| args.next().cloned())
+                    .ok_or_else(|| format!("{flag} needs a value"))
+            };
+            match name {
+                "--deny-warnings" => flags.deny_warnings = true,
+                "--offline" => flags.offline = true,
+                "--stdout" => flags.stdout = true,
+                "--allow-outside-root" => flags.allow_outside_root = true,
+                "--strict" => flags.strict = true,
+                "--dry-run" => flags.dry_run = true,
+                "--prune" => flags.prune = true,
+                // Read before parsing, to skip the settings file.
+                "--no-config" => {}
+                "--yes" if !no_exec => flags.exec_policy = Some(ExecPolicy::Yes),
+                "--yes" => {}
+                "--no-exec" => {
+                    no_exec = true;
+                    flags.exec_policy = Some(ExecPolicy::Skip);
+                }
+                "--timings" => flags.timings = Some(inline),
+                "--quiet" | "-q" => flags.verbosity = Verbosity::Quiet,
+                "--verbose" | "-v" => flags.verbosity = Verbosity::Verbose,
+                "--define" => {
+                    let define = value("--define")?;
+                    let Some((key, val)) = define.split_once('=') else {
+                        return Err(format!("--define {define} is not key=value"));
+                    };
+                    flags.defines.push((key.to_string(), val.to_string()));
+                }
+                "--error-format" => {
+                    let format = value("--error-format")?;
+                    flags.error_format = ErrorFormat::from_string(&format).ok_or_else(|| {
+                        format!("--error-format {format} is not one of human, json")
+                    })?;
+                }
+                _ => rest.push(arg.clone()),
+            }
+        }
+        Ok(Cli {
+            command: Cli::command(rest)?,
+            flags,
+        })
+    }
+
+    /// Parses the subcommand and its own arguments.
+    fn command(args: Vec<String>) -> Result<Command, String> {
+        let Some((name, args)) = args.split_first() else {
+            return Err("no command or schema given".to_string());
+        };
+        let schema = |behavior: Behavior, args: Args| {
+            Ok(Command::Schema {
+                behavior,
+                file: args.one_file(name)?,
+            })
+        };
+        match name.as_str() {
+            "build" => {
+                let args = Args::split(name, args, &["--check"], &[])?;
+                if args.has("--check") {
+                    schema(Behavior::Check, args)
+                } else {
+                    schema(Behavior::Build, args)
+                }
+            }
+            "check" => schema(Behavior::Check, Args::split(name, args, &[], &[])?),
+            "clean" => schema(Behavior::Clean, Args::split(name, args, &[], &[])?),
+            "configure" => schema(Behavior::Configure, Args::split(name, args, &[], &[])?),
+            "document" => schema(Behavior::Document, Args::split(name, args, &[], &[])?),
+            "diff" => schema(Behavior::Diff, Args::split(name, args, &[], &[])?),
+            "graph" => schema(Behavior::Graph, Args::split(name, args, &[], &[])?),
+            "fmt" => {
+                let args = Args::split(name, args, &["--check"], &[])?;
+                let check = args.has("--check");
+                schema(Behavior::Format { check }, args)
+            }
+            "lint" => {
+                let args = Args::split(name, args, &[], &["--allow"])?;
+                let allow = args
+                    .values("--allow")
+                    .map(|rule| {
+                        LintRule::from_string(rule)
+                            .ok_or_else(|| format!("{rule} is not a lint rule"))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                schema(Behavior::Lint { allow }, args)
+            }
+            "inspect" => {
+                let args = Args::split(name, args, &[], &["--format"])?;
+                if let Some(format) = args.values("--format").find(|f| *f != "json") {
+                    return Err(format!("--format {format} is not supported (only json)"));
+                }
+                schema(Behavior::Inspect, args)
+            }
+            "snapshot" => {
+                let args = Args::split(name, args, &[], &["--out"])?;
+                let out = args.values("--out").last().cloned();
+                schema(Behavior::Snapshot { out }, args)
+            }
+            "diff-schema" => {
+                let args = Args::split(name, args, &[], &[])?;
+                let [old, file] = <[String; 2]>::try_from(args.positional)
+                    .map_err(|_| "diff-schema needs an old and a new schema".to_string())?;
+                Ok(Command::Schema {
+                    behavior: Behavior::DiffSchema { old },
+                    file,
+                })
+            }
+            "check-compat" => {
+                let args = Args::split(name, args, &[], &["--against", "--policy"])?;
+                let Some(against) = args.values("--against").last().cloned() else {
+                    return Err("check-compat needs --against <snapshot>".to_string());
+                };
+                let policy = match args.values("--policy").last() {
+                    Some(policy) => CompatPolicy::from_string(policy).ok_or_else(|| {
+                        format!("--policy {policy} is not supported (strict or lenient)")
+                    })?,
+                    None => CompatPolicy::Strict,
+                };
+                schema(Behavior::CheckCompat { against, policy }, args)
+            }
+            "next-version" => {
+                let args = Args::split(name, args, &["--write"], &["--against"])?;
+                let Some(against) = args.values("--against").last().cloned() else {
+                    return Err("next-version needs --against <snapshot>".to_string());
+                };
+                let write = args.has("--write");
+                schema(Behavior::NextVersion { against, write }, args)
+            }
+            "mock" => {
+                let args = Args::split(name, args, &[], &["--count", "--format", "--seed"])?;
+                let count = match args.values("--count").last() {
+                    Some(count) => count
+                        .parse()
+                        .map_err(|_| format!("--count {count} is not a number"))?,
+                    None => 10,
+                };
+                let format = match args.values("--format").last() {
+                    Some(format) => MockFormat::from_string(format).ok_or_else(|| {
+                        format!("--format {format} is not supported (json or sql)")
+                    })?,
+                    None => MockFormat::Json,
+                };
+                let seed = match args.values("--seed").last() {
+                    Some(seed) => seed
+                        .parse()
+                        .map_err(|_| format!("--seed {seed} is not a number"))?,
+                    None => 1,
+                };
+                schema(
+                    Behavior::Mock {
+                        count,
+                        format,
+                        seed,
+                    },
+                    args,
+                )
+            }
+            "verify-queries" => {
+                let args = Args::split(name, args, &[], &["--db"])?;
+                let Some(connection) = args.values("--db").last().cloned() else {
+                    return Err("verify-queries needs --db <connection>".to_string());
+                };
+                schema(Behavior::VerifyQueries { connection }, args)
+            }
+            "blueprints" => {
+                let args = Args::split(name, args, &[], &[])?;
+                match args.positional.len() {
+                    0 => Ok(Command::Blueprints),
+                    _ => schema(Behavior::Blueprints, args),
+                }
+            }
+            "blueprint-check" => {
+                let args = Args::split(name, args, &["--no-strict"], &[])?;
+                let strict = !args.has("--no-strict");
+                Ok(Command::BlueprintCheck {
+                    file: args.one_file(name)?,
+                    strict,
+                })
+            }
+            "init" => {
+                let args = Args::split(name, args, &[], &["--blueprints"])?;
+                if args.positional.len() > 1 {
+                    return Err("init takes at most one directory".to_string());
+                }
+                Ok(Command::Init {
+                    blueprints: args.values("--blueprints").last().cloned(),
+                    dir: args.positional.into_iter().next(),
+                })
+            }
+            "import" => Cli::import(args),
+            _ if args.is_empty() => Ok(Command::Schema {
+                behavior: Behavior::Build,
+                file: name.to_string(),
+            }),
+            _ => Err(format!("unknown command '{name}'")),
+        }
+    }
+
+    /// Parses `repack import <kind> ...`.
+    fn import(args: &[String]) -> Result<Command, String> {
+        let Some((kind, args)) = args.split_first() else {
+            return Err("import needs a kind: postgres, json, or openapi".to_string());
+        };
+        let source = match kind.as_str() {
+            "postgres" => ImportSource::Postgres {
+                connection: Args::split(kind, args, &[], &[])?.one_file(kind)?,
+            },
+            "json" => {
+                let args = Args::split(kind, args, &[], &["--name"])?;
+                ImportSource::Json {
+                    name: args.values("--name").last().cloned(),
+                    file: args.one_file(kind)?,
+                }
+            }
+            "openapi" => ImportSource::OpenApi {
+                file: Args::split(kind, args, &[], &[])?.one_file(kind)?,
+            },
+            _ => return Err(format!("cannot import from '{kind}'")),
+        };
+        Ok(Command::Import(source))
+    }
+}
+
+/// A subcommand's arguments, split by the flags it accepts.
+struct Args {
+    /// Switches given, such as `--check`
+    switches: Vec<String>,
+    /// Flags given with a value, in order, such as `--allow unused`
+    values: Vec<(String, String)>,
+    /// Everything else, in order
+    positional: Vec<String>,
+}
+impl Args {
+    /// Splits arguments into the `switches` and `valued` flags a subcommand
+    /// accepts and its positional arguments. A lone `-` is positional, so it
+    /// can stand for stdin.
+    ///
+    /// # Returns
+    /// * `Ok(Args)` with the split arguments
+    /// * `Err(String)` for an unknown flag or a flag missing its value
+    fn split(
+        command: &str,
+        args: &[String],
+        switches: &[&str],
+        valued: &[&str],
+    ) -> Result<Args, String> {
+        let mut split = Args {
+            switches: Vec::new(),
+            values: Vec::new(),
+            positional: Vec::new(),
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if !arg.starts_with('-') || arg == "-" {
+                split.positional.push(arg.clone());
+                continue;
+            }
+            let (name, inline) = match arg.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (arg.as_str(), None),
+            };
+            if switches.contains(&name) && inline.is_none() {
+                split.switches.push(name.to_string());
+            } else if valued.contains(&name) {
+                let Some(value) = inline.or_else(|| args.next().cloned()) else {
+                    return Err(format!("{name} needs a value"));
+                };
+                split.values.push((name.to_string(), value));
+            } else {
+                return Err(format!("{command} does not accept {arg}"));
+            }
+        }
+        Ok(split)
+    }
+
+    fn has(&self, switch: &str) -> bool {
+        self.switches.iter().any(|s| s == switch)
+    }
+
+    fn values<'a>(&'a self, flag: &'a str) -> impl Iterator<Item = &'a String> {
+        self.values
+            .iter()
+            .filter(move |(name, _)| name == flag)
+            .map(|(_, value)| value)
+    }
+
+    /// The single positional argument, such as the schema file.
+    fn one_file(self, command: &str) -> Result<String, String> {
+        let mut positional = self.positional.into_iter();
+        match (positional.next(), positional.next()) {
+            (Some(file), None) => Ok(file),
+            (None, _) => Err(format!("{command} needs a file")),
+            (Some(_), Some(extra)) => Err(format!("{command} got an extra argument '{extra}'")),
+        }
+    }
+}
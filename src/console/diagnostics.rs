@@ -0,0 +1,40 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static COLOR: AtomicBool = AtomicBool::new(false);
+
+/// A text style for diagnostics, written as an ANSI escape.
+#[derive(Debug, Clone, Copy)]
+pub enum Style {
+    Red,
+    Yellow,
+    Bold,
+    Dim,
+}
+impl Style {
+    fn code(&self) -> &'static str {
+        match self {
+            Style::Red => "31",
+            Style::Yellow => "33",
+            Style::Bold => "1",
+            Style::Dim => "2",
+        }
+    }
+}
+
+/// Turns colored diagnostics on or off.
+///
+/// Colors stay off when `NO_COLOR` is set to a non-empty value, whatever is
+/// passed, so the console only needs to say whether it writes to a terminal.
+pub fn set_color(terminal: bool) {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    COLOR.store(terminal && !no_color, Ordering::Relaxed);
+}
+
+/// Wraps `text` in the style's escape codes if colors are on.
+pub fn paint(text: &str, style: Style) -> String {
+    if COLOR.load(Ordering::Relaxed) && !text.is_empty() {
+        format!("\x1B[{}m{text}\x1B[0m", style.code())
+    } else {
+        text.to_string()
+    }
+}
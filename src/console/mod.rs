@@ -0,0 +1,199 @@
+use std::{
+    io::{IsTerminal, Write},
+    sync::atomic::{AtomicBool, AtomicU8, Ordering},
+};
+
+use crate::syntax::RepackError;
+
+mod diagnostics;
+
+pub use diagnostics::*;
+
+const WIDTH: usize = 60;
+
+/// How much the console prints, set with `--quiet` and `--verbose`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[repr(u8)]
+pub enum Verbosity {
+    /// Only errors, warnings, and prompts
+    Quiet,
+    /// Progress and status messages
+    #[default]
+    Normal,
+    /// Progress, status messages, and details such as each file written
+    Verbose,
+}
+
+/// How errors and warnings are printed, set with `--error-format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorFormat {
+    /// Readable messages, colored on a terminal
+    #[default]
+    Human,
+    /// One JSON object per line on stderr, for editors and CI
+    Json,
+}
+impl ErrorFormat {
+    pub fn from_string(val: &str) -> Option<ErrorFormat> {
+        Some(match val {
+            "human" => ErrorFormat::Human,
+            "json" => ErrorFormat::Json,
+            _ => return None,
+        })
+    }
+}
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(Verbosity::Normal as u8);
+/// Whether progress is redrawn in place with ANSI escapes.
+static INTERACTIVE: AtomicBool = AtomicBool::new(false);
+/// Whether diagnostics are printed as JSON lines.
+static JSON_ERRORS: AtomicBool = AtomicBool::new(false);
+/// Set when stdout carries data (`repack inspect`, `--stdout`), so everything
+/// else goes to stderr.
+static STDOUT_IS_DATA: AtomicBool = AtomicBool::new(false);
+
+/// Progress and diagnostic output for the command line.
+///
+/// On a terminal, progress is redrawn in place. Otherwise (piped to a file or
+/// in CI), or with `--verbose`, each update is printed as its own line.
+/// Errors always go to stderr unless the progress display owns the terminal.
+pub struct Console;
+impl Console {
+    /// Sets the verbosity and detects whether stdout is a terminal.
+    pub fn init(verbosity: Verbosity) {
+        VERBOSITY.store(verbosity as u8, Ordering::Relaxed);
+        INTERACTIVE.store(
+            verbosity == Verbosity::Normal && std::io::stdout().is_terminal(),
+            Ordering::Relaxed,
+        );
+        Console::detect_color();
+    }
+
+    /// Enables colors if errors are written to a terminal.
+    fn detect_color() {
+        set_color(if Console::interactive() {
+            std::io::stdout().is_terminal()
+        } else {
+            std::io::stderr().is_terminal()
+        });
+    }
+
+    /// Sets how [`Console::report`] prints errors and warnings.
+    pub fn set_error_format(format: ErrorFormat) {
+        JSON_ERRORS.store(format == ErrorFormat::Json, Ordering::Relaxed);
+    }
+
+    /// Sends all console output to stderr, leaving stdout for data.
+    pub fn set_stdout_is_data() {
+        STDOUT_IS_DATA.store(true, Ordering::Relaxed);
+        INTERACTIVE.store(false, Ordering::Relaxed);
+        Console::detect_color();
+    }
+
+    fn shows(verbosity: Verbosity) -> bool {
+        VERBOSITY.load(Ordering::Relaxed) >= verbosity as u8
+    }
+
+    fn interactive() -> bool {
+        INTERACTIVE.load(Ordering::Relaxed)
+    }
+
+    /// Prints a plain line to stdout, or stderr if stdout carries data.
+    fn line(message: &str) {
+        if STDOUT_IS_DATA.load(Ordering::Relaxed) {
+            eprintln!("{message}");
+        } else {
+            println!("{message}");
+        }
+    }
+
+    pub fn begin() {
+        if Console::interactive() {
+            println!("[] Loading...");
+        }
+    }
+
+    /// Reports the current step out of the total.
+    pub fn update_ct(i: usize, n: usize, title: &str) {
+        if Console::interactive() {
+            print!("\x1B[1A");
+            print!("\r\x1B[2K[{i}/{n}] {title:<WIDTH$}\n");
+            let _ = std::io::stdout().flush();
+        } else if Console::shows(Verbosity::Normal) {
+            Console::line(&format!("[{i}/{n}] {title}"));
+        }
+    }
+
+    /// Reports a status message below the current step.
+    pub fn update_msg(msg: &str) {
+        if Console::interactive() {
+            print!("\r\x1B[2K  {msg:<WIDTH$}");
+            let _ = std::io::stdout().flush();
+        } else if Console::shows(Verbosity::Normal) {
+            Console::line(&format!("  {msg}"));
+        }
+    }
+
+    /// Reports a detail shown only with `--verbose`.
+    pub fn detail(msg: &str) {
+        if Console::shows(Verbosity::Verbose) {
+            Console::line(&format!("  {msg}"));
+        }
+    }
+
+    pub fn finalize() {
+        if Console::interactive() {
+            println!()
+        }
+    }
+
+    /// Reports an error or warning, at every verbosity.
+    pub fn error(message: &str) {
+        if Console::interactive() {
+            print!("\n{message}");
+            let _ = std::io::stdout().flush();
+        } else {
+            eprintln!("{message}");
+        }
+    }
+
+    /// Reports a schema or blueprint diagnostic, at every verbosity.
+    ///
+    /// With `--error-format json`, it is written to stderr as a single line of
+    /// JSON so tools can read it alongside the progress output.
+    pub fn report(error: RepackError) {
+        if JSON_ERRORS.load(Ordering::Relaxed) {
+            eprintln!("{}", error.to_json().to_compact_string());
+        } else {
+            Console::error(&error.into_string());
+        }
+    }
+
+    /// Asks a yes/no question, at every verbosity, and reads the answer from stdin.
+    pub fn ask_confirmation(question: &str) -> bool {
+        if Console::interactive() {
+            Console::update_msg(question);
+        } else {
+            eprint!("{question} ");
+        }
+        let mut input = String::new();
+        if std::io::stdin().read_line(&mut input).is_err() {
+            return false;
+        }
+        if Console::interactive() {
+            print!("\x1B[1A");
+        }
+        matches!(input.trim().to_lowercase().as_str(), "y" | "yes")
+    }
+
+    /// Asks a question on stdin, returning the trimmed answer, or `default`
+    /// if the answer is empty or stdin could not be read.
+    pub fn ask(question: &str, default: &str) -> String {
+        eprint!("{question} [{default}] ");
+        let mut input = String::new();
+        if std::io::stdin().read_line(&mut input).is_err() || input.trim().is_empty() {
+            return default.to_string();
+        }
+        input.trim().to_string()
+    }
+}
@@ -0,0 +1,173 @@
+/// Lines of unchanged text shown around each change.
+const CONTEXT: usize = 3;
+
+/// Past this many changed lines, a file is shown as entirely replaced rather
+/// than searching further for the smallest diff.
+const MAX_EDITS: usize = 2000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Edit {
+    Keep,
+    Delete,
+    Insert,
+}
+
+/// Formats the changes from `old` to `new` as a unified diff, like `diff -u`.
+///
+/// A missing `old` file is shown as created, coming from `/dev/null`.
+///
+/// # Returns
+/// The diff, or an empty string if the contents are the same
+pub fn unified_diff(old: Option<&str>, new: &str, name: &str) -> String {
+    if old == Some(new) {
+        return String::new();
+    }
+    let old_lines = old
+        .unwrap_or_default()
+        .split_inclusive('\n')
+        .collect::<Vec<_>>();
+    let new_lines = new.split_inclusive('\n').collect::<Vec<_>>();
+    let edits = shortest_edits(&old_lines, &new_lines).unwrap_or_else(|| {
+        let mut edits = vec![Edit::Delete; old_lines.len()];
+        edits.extend(vec![Edit::Insert; new_lines.len()]);
+        edits
+    });
+
+    // Each edit with the line it applies to in the old and new files.
+    let mut ops = Vec::with_capacity(edits.len());
+    let (mut a, mut b) = (0, 0);
+    for edit in edits {
+        ops.push((edit, a, b));
+        match edit {
+            Edit::Keep => {
+                a += 1;
+                b += 1;
+            }
+            Edit::Delete => a += 1,
+            Edit::Insert => b += 1,
+        }
+    }
+
+    let mut out = match old {
+        Some(_) => format!("--- a/{name}\n+++ b/{name}\n"),
+        None => format!("--- /dev/null\n+++ b/{name}\n"),
+    };
+    let mut idx = 0;
+    while idx < ops.len() {
+        if ops[idx].0 == Edit::Keep {
+            idx += 1;
+            continue;
+        }
+        // A hunk runs until the gap to the next change is too wide to share context.
+        let start = idx.saturating_sub(CONTEXT);
+        let mut last_change = idx;
+        for (at, op) in ops.iter().enumerate().skip(idx) {
+            if op.0 != Edit::Keep {
+                last_change = at;
+            } else if at - last_change > CONTEXT * 2 {
+                break;
+            }
+        }
+        let end = (last_change + 1 + CONTEXT).min(ops.len());
+        let hunk = &ops[start..end];
+        let old_count = hunk.iter().filter(|op| op.0 != Edit::Insert).count();
+        let new_count = hunk.iter().filter(|op| op.0 != Edit::Delete).count();
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            range(hunk[0].1, old_count),
+            range(hunk[0].2, new_count)
+        ));
+        for (edit, a, b) in hunk {
+            let (prefix, line) = match edit {
+                Edit::Keep => (' ', old_lines[*a]),
+                Edit::Delete => ('-', old_lines[*a]),
+                Edit::Insert => ('+', new_lines[*b]),
+            };
+            out.push(prefix);
+            out.push_str(line);
+            if !line.ends_with('\n') {
+                out.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+        idx = end;
+    }
+    out
+}
+
+/// A hunk's line range in one file, counted from 1.
+fn range(start: usize, count: usize) -> String {
+    match count {
+        // An empty range names the line before it.
+        0 => format!("{start},0"),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{count}", start + 1),
+    }
+}
+
+/// The fewest deletions and insertions turning `old` into `new`, found with
+/// Myers' algorithm.
+///
+/// # Returns
+/// * `Some(Vec<Edit>)` with one edit per line kept, deleted, or inserted
+/// * `None` if more than `MAX_EDITS` lines changed
+fn shortest_edits(old: &[&str], new: &[&str]) -> Option<Vec<Edit>> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max = (n + m) as usize;
+    let offset = max as isize + 1;
+    // The furthest x reached on each diagonal k = x - y, indexed by k + offset.
+    let mut furthest = vec![0isize; 2 * max + 3];
+    // The diagonals around the origin before each round, used to walk back.
+    let mut trace = Vec::new();
+    let goes_down =
+        |v: &[isize], k: isize, d: isize, at: usize| k == -d || (k != d && v[at - 1] < v[at + 1]);
+    'search: for d in 0..=max as isize {
+        if d as usize > MAX_EDITS {
+            return None;
+        }
+        trace.push(furthest[(offset - d) as usize..=(offset + d) as usize].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let at = (k + offset) as usize;
+            let mut x = if goes_down(&furthest, k, d, at) {
+                furthest[at + 1]
+            } else {
+                furthest[at - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            furthest[at] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().skip(1).rev() {
+        let d = d as isize;
+        let k = x - y;
+        // The snapshot holds diagonals -d..=d, so k + d indexes it.
+        let at = (k + d) as usize;
+        let prev_k = if goes_down(v, k, d, at) { k + 1 } else { k - 1 };
+        let prev_x = v[(prev_k + d) as usize];
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            edits.push(Edit::Keep);
+            x -= 1;
+            y -= 1;
+        }
+        edits.push(if x == prev_x {
+            Edit::Insert
+        } else {
+            Edit::Delete
+        });
+        x = prev_x;
+        y = prev_y;
+    }
+    edits.extend(std::iter::repeat_n(Edit::Keep, x as usize));
+    edits.reverse();
+    Some(edits)
+}
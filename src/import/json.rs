@@ -0,0 +1,290 @@
+use super::{ImportedField, ImportedStruct, pascal_case, render_schema};
+use crate::syntax::{Json, RepackError, RepackErrorKind};
+
+/// Builds a schema by inferring structs from sample JSON payloads.
+///
+/// The document may be a single object or an array of objects; each object is
+/// one sample of the struct. A key missing from some samples, or `null` in any
+/// of them, becomes an optional field. Nested objects become their own structs,
+/// named after their key, and arrays become array fields typed by their
+/// elements. Strings are `uuid` or `datetime` when every sample looks like
+/// one. Numbers are `int32` or `int64` when every sample is whole, and
+/// `float64` otherwise. Keys that are not snake_case are renamed, with a
+/// comment keeping the original key.
+///
+/// # Arguments
+/// * `file` - Path to the JSON document, also used in the header comment
+/// * `source` - The JSON document
+/// * `name` - The name of the top-level struct
+///
+/// # Returns
+/// * `Ok(String)` with the schema text
+/// * `Err(RepackError)` if the JSON is malformed or holds no objects
+pub fn import_json(file: &str, source: &str, name: &str) -> Result<String, RepackError> {
+    let root =
+        Json::parse(source).map_err(|e| RepackError::global(RepackErrorKind::InvalidJson, e))?;
+    let samples: Vec<&Json> = match &root {
+        Json::Array(items) => items.iter().collect(),
+        other => vec![other],
+    };
+    if samples.is_empty() || !samples.iter().all(|s| matches!(s, Json::Object(_))) {
+        return Err(RepackError::global(
+            RepackErrorKind::InvalidJson,
+            "expected an object or an array of objects".to_string(),
+        ));
+    }
+    let mut strcts = Vec::new();
+    infer_struct(&pascal_case(name), "", &samples, &mut strcts);
+    Ok(render_schema(
+        &format!("JSON samples in {file}"),
+        &[],
+        &strcts,
+    ))
+}
+
+/// Adds a struct inferred from `samples` (all objects) and any nested structs.
+///
+/// # Returns
+/// The name given to the struct, which is made unique by prefixing the parent name
+fn infer_struct(
+    name: &str,
+    parent: &str,
+    samples: &[&Json],
+    strcts: &mut Vec<ImportedStruct>,
+) -> String {
+    let mut name = name.to_string();
+    if strcts.iter().any(|s| s.name == name) {
+        name = format!("{parent}{name}");
+    }
+    let base = name.clone();
+    let mut suffix = 2;
+    while strcts.iter().any(|s| s.name == name) {
+        name = format!("{base}{suffix}");
+        suffix += 1;
+    }
+    // Reserve the slot so the parent is written before the structs it contains.
+    let idx = strcts.len();
+    strcts.push(ImportedStruct {
+        name: name.clone(),
+        table: None,
+        fields: Vec::new(),
+        functions: Vec::new(),
+    });
+
+    let mut keys: Vec<&str> = Vec::new();
+    for sample in samples {
+        if let Json::Object(pairs) = sample {
+            for (key, _) in pairs {
+                if !keys.contains(&key.as_str()) {
+                    keys.push(key);
+                }
+            }
+        }
+    }
+    let mut fields = Vec::new();
+    for key in keys {
+        let values: Vec<&Json> = samples
+            .iter()
+            .filter_map(|s| s.get(key))
+            .filter(|v| **v != Json::Null)
+            .collect();
+        let mut field = infer_field(key, &name, &values, strcts);
+        field.optional = values.len() < samples.len();
+        fields.push(field.with_key(key));
+    }
+    strcts[idx].fields = fields;
+    name
+}
+
+/// Infers the type of a field from its non-null values.
+fn infer_field(
+    key: &str,
+    parent: &str,
+    values: &[&Json],
+    strcts: &mut Vec<ImportedStruct>,
+) -> ImportedField {
+    let mut field = ImportedField {
+        name: key.to_string(),
+        typ: "string".to_string(),
+        optional: false,
+        array: false,
+        functions: Vec::new(),
+        comment: None,
+        commented_out: false,
+    };
+    if !values.is_empty() && values.iter().all(|v| matches!(v, Json::Array(_))) {
+        let elements: Vec<&Json> = values
+            .iter()
+            .filter_map(|v| v.as_array())
+            .flatten()
+            .filter(|v| **v != Json::Null)
+            .collect();
+        field.array = true;
+        if elements.iter().any(|v| matches!(v, Json::Array(_))) {
+            field.comment = Some("nested arrays are not supported".to_string());
+        } else {
+            let (typ, comment) = infer_type(key, parent, &elements, strcts);
+            field.typ = typ;
+            field.comment = comment;
+        }
+    } else {
+        let (typ, comment) = infer_type(key, parent, values, strcts);
+        field.typ = typ;
+        field.comment = comment;
+    }
+    field
+}
+
+/// Infers a single (non-array) type, adding a struct when the values are objects.
+fn infer_type(
+    key: &str,
+    parent: &str,
+    values: &[&Json],
+    strcts: &mut Vec<ImportedStruct>,
+) -> (String, Option<String>) {
+    let all = |check: fn(&Json) -> bool| values.iter().all(|v| check(v));
+    if values.is_empty() {
+        return (
+            "string".to_string(),
+            Some("no values in samples".to_string()),
+        );
+    }
+    if all(|v| matches!(v, Json::Bool(_))) {
+        return ("boolean".to_string(), None);
+    }
+    if all(|v| matches!(v, Json::Number(_))) {
+        let numbers = values.iter().filter_map(|v| match v {
+            Json::Number(n) => Some(*n),
+            _ => None,
+        });
+        let typ = if numbers.clone().any(|n| n.fract() != 0.0) {
+            "float64"
+        } else if numbers
+            .clone()
+            .all(|n| n >= i32::MIN as f64 && n <= i32::MAX as f64)
+        {
+            "int32"
+        } else {
+            "int64"
+        };
+        return (typ.to_string(), None);
+    }
+    if all(|v| matches!(v, Json::String(_))) {
+        let strings = values.iter().filter_map(|v| v.as_str());
+        let typ = if strings.clone().all(is_uuid) {
+            "uuid"
+        } else if strings.clone().all(is_datetime) {
+            "datetime"
+        } else {
+            "string"
+        };
+        return (typ.to_string(), None);
+    }
+    if all(|v| matches!(v, Json::Object(_))) {
+        return (
+            infer_struct(&pascal_case(key), parent, values, strcts),
+            None,
+        );
+    }
+    (
+        "string".to_string(),
+        Some("mixed types in samples".to_string()),
+    )
+}
+
+fn is_uuid(val: &str) -> bool {
+    val.len() == 36
+        && val.chars().enumerate().all(|(idx, c)| match idx {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+/// Whether a string starts like an ISO 8601 timestamp, e.g. `2024-01-31T12:00`.
+fn is_datetime(val: &str) -> bool {
+    let bytes = val.as_bytes();
+    bytes.len() >= 16
+        && bytes.iter().take(16).enumerate().all(|(idx, c)| match idx {
+            4 | 7 => *c == b'-',
+            10 => *c == b'T' || *c == b' ',
+            13 => *c == b':',
+            _ => c.is_ascii_digit(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The schema's lines with runs of whitespace collapsed, so tests don't
+    /// depend on the column alignment `repack fmt` adds.
+    fn lines(source: &str) -> Vec<String> {
+        import_json("sample.json", source, "sample")
+            .unwrap()
+            .lines()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+            .collect()
+    }
+
+    #[test]
+    fn infers_types_from_every_sample() {
+        let schema = lines(
+            r#"[
+                {"id": "0b0e5c8e-8c2c-4c8b-9f3e-1a2b3c4d5e6f", "count": 3, "score": 1.5,
+                 "at": "2024-01-31T12:00:00Z", "ok": true, "tags": ["x"]},
+                {"id": "0b0e5c8e-8c2c-4c8b-9f3e-1a2b3c4d5e6f", "count": 4000000000, "score": 2,
+                 "at": "2024-02-01 10:00", "ok": false, "tags": []}
+            ]"#,
+        );
+        for line in [
+            "struct Sample {",
+            "id uuid",
+            "count int64",
+            "score float64",
+            "at datetime",
+            "ok boolean",
+            "tags string[]",
+        ] {
+            assert!(
+                schema.contains(&line.to_string()),
+                "missing '{line}' in {schema:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn marks_missing_and_null_keys_optional() {
+        let schema = lines(r#"[{"a": 1, "b": null}, {"a": 2, "b": 3}, {"b": 4}]"#);
+        assert!(schema.contains(&"a int32?".to_string()));
+        assert!(schema.contains(&"b int32?".to_string()));
+    }
+
+    #[test]
+    fn nests_objects_and_keeps_original_keys() {
+        let schema = lines(r#"{"userName": "a", "owner": {"id": 1}, "user": {"owner": {"x": 1}}}"#);
+        assert!(schema.contains(&"user_name string // key: userName".to_string()));
+        assert!(schema.contains(&"owner Owner".to_string()));
+        assert!(schema.contains(&"struct Owner {".to_string()));
+        // A second struct with the same name is prefixed with its parent's.
+        assert!(schema.contains(&"owner UserOwner".to_string()));
+        assert!(schema.contains(&"struct UserOwner {".to_string()));
+    }
+
+    #[test]
+    fn flags_values_it_cannot_type() {
+        let schema = lines(r#"[{"a": 1, "b": [[1]], "c": null}, {"a": "x"}]"#);
+        assert!(schema.contains(&"a string // mixed types in samples".to_string()));
+        assert!(schema.contains(&"b string[]? // nested arrays are not supported".to_string()));
+        assert!(schema.contains(&"c string? // no values in samples".to_string()));
+    }
+
+    #[test]
+    fn rejects_documents_without_objects() {
+        for source in ["[]", "[1, 2]", "\"text\"", "[{}, 3]", "{\"a\": }", ""] {
+            assert!(
+                import_json("sample.json", source, "sample").is_err(),
+                "accepted {source:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,152 @@
+mod json;
+mod openapi;
+mod postgres;
+mod yaml;
+
+pub use json::import_json;
+pub use openapi::import_openapi;
+pub use postgres::import_postgres;
+
+use crate::{blueprint::split_words, syntax::format_schema};
+
+/// A field of an imported struct, before it is written out as schema text.
+pub struct ImportedField {
+    pub name: String,
+    /// A repack type name (core type, struct, or enum)
+    pub typ: String,
+    pub optional: bool,
+    pub array: bool,
+    /// Field functions as written in the schema, e.g. `db:pk`
+    pub functions: Vec<String>,
+    /// Trailing comment, used to flag anything that could not be mapped
+    pub comment: Option<String>,
+    /// Written as a comment, for a field repack would reject
+    pub commented_out: bool,
+}
+
+impl ImportedField {
+    /// Renames the field to snake_case after a source key such as `createdAt`.
+    ///
+    /// When the name changes, the original key is kept in the comment.
+    pub fn with_key(mut self, key: &str) -> ImportedField {
+        self.name = snake_case(key);
+        if self.name != key {
+            let original = format!("key: {key}");
+            self.comment = Some(match self.comment {
+                Some(comment) => format!("{original}, {comment}"),
+                None => original,
+            });
+        }
+        self
+    }
+}
+
+/// A struct produced by an importer.
+pub struct ImportedStruct {
+    pub name: String,
+    pub table: Option<String>,
+    pub fields: Vec<ImportedField>,
+    /// Struct functions as written in the schema, e.g. `db:index(a, b)`
+    pub functions: Vec<String>,
+}
+
+/// An enum produced by an importer. Each case has an optional explicit value.
+pub struct ImportedEnum {
+    pub name: String,
+    pub cases: Vec<(String, Option<String>)>,
+}
+
+/// Writes imported definitions as a formatted `.repack` schema.
+///
+/// # Arguments
+/// * `source` - Where the definitions came from, noted in a header comment
+/// * `enums` - Enums, written first
+/// * `strcts` - Structs, in the order given
+///
+/// # Returns
+/// The schema text, formatted like `repack fmt` would
+pub fn render_schema(source: &str, enums: &[ImportedEnum], strcts: &[ImportedStruct]) -> String {
+    let mut out = format!("// Imported from {source} by `repack import`.\n");
+    for enm in enums {
+        out.push_str(&format!("\nenum {} {{\n", enm.name));
+        for (case, value) in &enm.cases {
+            match value {
+                Some(value) => out.push_str(&format!("{case} \"{value}\"\n")),
+                None => out.push_str(&format!("{case}\n")),
+            }
+        }
+        out.push_str("}\n");
+    }
+    for strct in strcts {
+        out.push_str(&format!("\nstruct {}", strct.name));
+        if let Some(table) = &strct.table {
+            out.push_str(&format!(" @{table}"));
+        }
+        out.push_str(" {\n");
+        for field in &strct.fields {
+            if field.commented_out {
+                out.push_str("// ");
+            }
+            out.push_str(&format!("{} {}", field.name, field.typ));
+            if field.array {
+                out.push_str("[]");
+            }
+            if field.optional {
+                out.push('?');
+            }
+            for function in &field.functions {
+                out.push_str(&format!(" {function}"));
+            }
+            if let Some(comment) = &field.comment {
+                out.push_str(&format!(" // {comment}"));
+            }
+            out.push('\n');
+        }
+        if !strct.functions.is_empty() {
+            out.push('\n');
+            for function in &strct.functions {
+                out.push_str(&format!("{function}\n"));
+            }
+        }
+        out.push_str("}\n");
+    }
+    format_schema(&out)
+}
+
+/// Splits a name into lowercase words, treating anything that cannot appear
+/// in a repack identifier as a separator.
+fn words(name: &str) -> Vec<String> {
+    let cleaned: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    split_words(&cleaned)
+}
+
+/// Converts a name such as `user_accounts` into `UserAccounts`.
+pub fn pascal_case(name: &str) -> String {
+    let words = words(name);
+    let out: String = words
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect();
+    match out.chars().next() {
+        Some(first) if first.is_ascii_alphabetic() => out,
+        _ => format!("T{out}"),
+    }
+}
+
+/// Converts a name such as `createdAt` into `created_at`.
+pub fn snake_case(name: &str) -> String {
+    let out = words(name).join("_");
+    match out.chars().next() {
+        Some(first) if first.is_ascii_alphabetic() => out,
+        _ => format!("_{out}"),
+    }
+}
@@ -0,0 +1,463 @@
+use super::{
+    ImportedEnum, ImportedField, ImportedStruct, pascal_case, render_schema, yaml::parse_yaml,
+};
+use crate::syntax::{Json, RepackError, RepackErrorKind};
+
+/// How many `$ref`s are followed before giving up, so reference loops cannot recurse forever.
+const MAX_REF_DEPTH: usize = 16;
+
+/// Builds a schema from the component schemas of an OpenAPI document.
+///
+/// Both YAML and JSON documents are read, from OpenAPI 3 (`components.schemas`)
+/// or Swagger 2 (`definitions`). Object schemas become structs and string
+/// enums become enums. Properties are optional unless listed in `required`
+/// and not `nullable`. `$ref`s point at the struct or enum for that schema, or
+/// at its type when the schema is a plain alias. Inline objects and enums get
+/// their own definitions, named after the parent and property. `allOf` members
+/// are merged into one struct. Formats map onto repack types where one exists:
+/// `uuid`, `date-time`/`date` to `datetime`, `byte`/`binary` to `bytes`, and
+/// `int32`/`int64`.
+///
+/// # Arguments
+/// * `file` - Path to the document, used in the header comment
+/// * `source` - The document
+///
+/// # Returns
+/// * `Ok(String)` with the schema text
+/// * `Err(RepackError)` if the document cannot be read or has no schemas
+pub fn import_openapi(file: &str, source: &str) -> Result<String, RepackError> {
+    let doc = if source.trim_start().starts_with('{') {
+        Json::parse(source)
+    } else {
+        parse_yaml(source)
+    }
+    .map_err(|e| RepackError::global(RepackErrorKind::InvalidImportSource, e))?;
+    let schemas = match doc
+        .get("components")
+        .and_then(|c| c.get("schemas"))
+        .or_else(|| doc.get("definitions"))
+    {
+        Some(Json::Object(schemas)) => schemas,
+        _ => {
+            return Err(RepackError::global(
+                RepackErrorKind::InvalidImportSource,
+                format!("{file} has no components.schemas or definitions"),
+            ));
+        }
+    };
+
+    let mut converter = Converter {
+        schemas,
+        strcts: Vec::new(),
+        enums: Vec::new(),
+    };
+    for (name, schema) in schemas {
+        if is_enum(schema) {
+            converter.add_enum(&pascal_case(name), schema);
+        } else if is_object(schema) {
+            converter.add_struct(&pascal_case(name), schema);
+        }
+    }
+    break_cycles(&mut converter.strcts);
+    Ok(render_schema(
+        &format!("OpenAPI document {file}"),
+        &converter.enums,
+        &converter.strcts,
+    ))
+}
+
+fn is_enum(schema: &Json) -> bool {
+    schema.get("enum").is_some() && !is_object(schema)
+}
+
+fn is_object(schema: &Json) -> bool {
+    types(schema).contains(&"object")
+        || schema.get("properties").is_some()
+        || schema
+            .get("allOf")
+            .and_then(Json::as_array)
+            .is_some_and(|all| all.len() > 1)
+}
+
+/// The schema's `type`, which OpenAPI 3.1 allows to be a list.
+fn types(schema: &Json) -> Vec<&str> {
+    match schema.get("type") {
+        Some(Json::String(typ)) => vec![typ],
+        Some(Json::Array(types)) => types.iter().filter_map(Json::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn is_nullable(schema: &Json) -> bool {
+    schema
+        .get("nullable")
+        .and_then(Json::as_bool)
+        .unwrap_or(false)
+        || types(schema).contains(&"null")
+}
+
+/// Comments out fields that make a struct contain itself, directly or through
+/// other structs, which repack rejects as a circular dependency.
+///
+/// Fields are kept in the order they are written, so in a `Pet.owner` and
+/// `Owner.pets` pair the later `Owner.pets` is the one commented out.
+fn break_cycles(strcts: &mut [ImportedStruct]) {
+    let mut edges: Vec<(String, String)> = Vec::new();
+    for idx in 0..strcts.len() {
+        let from = strcts[idx].name.clone();
+        for field_idx in 0..strcts[idx].fields.len() {
+            let to = strcts[idx].fields[field_idx].typ.clone();
+            if !strcts.iter().any(|s| s.name == to) {
+                continue;
+            }
+            let Some(path) = path_between(&edges, &to, &from) else {
+                edges.push((from.clone(), to));
+                continue;
+            };
+            let field = &mut strcts[idx].fields[field_idx];
+            let note = format!("circular reference: {from} -> {}", path.join(" -> "));
+            field.comment = Some(match field.comment.take() {
+                Some(comment) => format!("{note}, {comment}"),
+                None => note,
+            });
+            field.commented_out = true;
+        }
+    }
+}
+
+/// The structs on a path of `edges` from `from` to `to`, both included.
+fn path_between(edges: &[(String, String)], from: &str, to: &str) -> Option<Vec<String>> {
+    let mut paths = vec![vec![from.to_string()]];
+    let mut seen = vec![from];
+    while let Some(path) = paths.pop() {
+        let last = path.last()?;
+        if last == to {
+            return Some(path);
+        }
+        for (_, next) in edges.iter().filter(|(start, _)| start == last) {
+            if !seen.contains(&next.as_str()) {
+                seen.push(next);
+                let mut path = path.clone();
+                path.push(next.to_string());
+                paths.push(path);
+            }
+        }
+    }
+    None
+}
+
+/// The resolved type of a property, before it becomes a field.
+struct PropertyType {
+    typ: String,
+    array: bool,
+    nullable: bool,
+    comment: Option<String>,
+}
+
+struct Converter<'a> {
+    schemas: &'a [(String, Json)],
+    strcts: Vec<ImportedStruct>,
+    enums: Vec<ImportedEnum>,
+}
+impl<'a> Converter<'a> {
+    /// Finds the schema a `#/components/schemas/Name` reference points at.
+    fn lookup(&self, reference: &str) -> Option<(&'a str, &'a Json)> {
+        let name = reference.rsplit('/').next()?;
+        self.schemas
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(n, s)| (n.as_str(), s))
+    }
+
+    /// Returns `name`, or `name` with a number added if a definition already uses it.
+    fn unique_name(&self, name: &str) -> String {
+        let taken = |n: &str| {
+            self.strcts.iter().any(|s| s.name == n) || self.enums.iter().any(|e| e.name == n)
+        };
+        let mut candidate = name.to_string();
+        let mut suffix = 2;
+        while taken(&candidate) {
+            candidate = format!("{name}{suffix}");
+            suffix += 1;
+        }
+        candidate
+    }
+
+    fn add_enum(&mut self, name: &str, schema: &Json) -> String {
+        let name = self.unique_name(name);
+        let cases = schema
+            .get("enum")
+            .and_then(Json::as_array)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|value| match value {
+                Json::String(s) => Some(s.to_string()),
+                Json::Number(n) => Some(n.to_string()),
+                Json::Bool(b) => Some(b.to_string()),
+                _ => None,
+            })
+            .map(|value| {
+                let case = pascal_case(&value);
+                let value = (case != value).then_some(value);
+                (case, value)
+            })
+            .collect();
+        self.enums.push(ImportedEnum {
+            name: name.clone(),
+            cases,
+        });
+        name
+    }
+
+    fn add_struct(&mut self, name: &str, schema: &'a Json) -> String {
+        let name = self.unique_name(name);
+        // Reserve the slot so the struct is written before inline structs it contains.
+        let idx = self.strcts.len();
+        self.strcts.push(ImportedStruct {
+            name: name.clone(),
+            table: None,
+            fields: Vec::new(),
+            functions: Vec::new(),
+        });
+        let mut properties = Vec::new();
+        let mut required = Vec::new();
+        self.collect_properties(schema, &mut properties, &mut required, 0);
+
+        let mut fields = Vec::new();
+        for (key, property) in properties {
+            let property_type =
+                self.property_type(property, &format!("{name}{}", pascal_case(key)), 0);
+            let field = ImportedField {
+                name: key.to_string(),
+                typ: property_type.typ,
+                optional: property_type.nullable || !required.contains(&key),
+                array: property_type.array,
+                functions: Vec::new(),
+                comment: property_type.comment,
+                commented_out: false,
+            };
+            fields.push(field.with_key(key));
+        }
+        self.strcts[idx].fields = fields;
+        name
+    }
+
+    /// Gathers properties and required keys, following `allOf` members and their `$ref`s.
+    fn collect_properties(
+        &self,
+        schema: &'a Json,
+        properties: &mut Vec<(&'a str, &'a Json)>,
+        required: &mut Vec<&'a str>,
+        depth: usize,
+    ) {
+        if depth > MAX_REF_DEPTH {
+            return;
+        }
+        if let Some((_, target)) = schema
+            .get("$ref")
+            .and_then(Json::as_str)
+            .and_then(|r| self.lookup(r))
+        {
+            self.collect_properties(target, properties, required, depth + 1);
+        }
+        for member in schema
+            .get("allOf")
+            .and_then(Json::as_array)
+            .unwrap_or_default()
+        {
+            self.collect_properties(member, properties, required, depth + 1);
+        }
+        if let Some(Json::Object(pairs)) = schema.get("properties") {
+            for (key, property) in pairs {
+                match properties.iter_mut().find(|(k, _)| k == key) {
+                    Some(existing) => existing.1 = property,
+                    None => properties.push((key, property)),
+                }
+            }
+        }
+        required.extend(
+            schema
+                .get("required")
+                .and_then(Json::as_array)
+                .unwrap_or_default()
+                .iter()
+                .filter_map(Json::as_str),
+        );
+    }
+
+    /// Maps a property schema to a repack type, adding inline structs and enums.
+    ///
+    /// `inline_name` names any struct or enum defined by the property itself.
+    fn property_type(&mut self, schema: &'a Json, inline_name: &str, depth: usize) -> PropertyType {
+        let mut result = PropertyType {
+            typ: "string".to_string(),
+            array: false,
+            nullable: is_nullable(schema),
+            comment: None,
+        };
+        if depth > MAX_REF_DEPTH {
+            result.comment = Some("reference loop".to_string());
+            return result;
+        }
+        if let Some(reference) = schema.get("$ref").and_then(Json::as_str) {
+            match self.lookup(reference) {
+                Some((name, target)) if is_enum(target) || is_object(target) => {
+                    result.typ = pascal_case(name);
+                }
+                Some((_, target)) => {
+                    let nullable = result.nullable;
+                    result = self.property_type(target, inline_name, depth + 1);
+                    result.nullable |= nullable;
+                }
+                None => result.comment = Some(format!("unresolved reference: {reference}")),
+            }
+            return result;
+        }
+
+        // `allOf: [$ref]` and `oneOf: [$ref, {type: null}]` wrap a single schema.
+        for key in ["allOf", "oneOf", "anyOf"] {
+            let Some(members) = schema.get(key).and_then(Json::as_array) else {
+                continue;
+            };
+            let (nulls, others): (Vec<&'a Json>, Vec<&'a Json>) =
+                members.iter().partition(|m| types(m) == ["null"]);
+            if let [single] = others.as_slice() {
+                let nullable = result.nullable || !nulls.is_empty();
+                result = self.property_type(single, inline_name, depth + 1);
+                result.nullable |= nullable;
+            } else if key == "allOf" {
+                result.typ = self.add_struct(inline_name, schema);
+            } else {
+                result.comment = Some(format!("{key} is not supported"));
+            }
+            return result;
+        }
+
+        if is_enum(schema) {
+            result.typ = self.add_enum(inline_name, schema);
+            return result;
+        }
+        if is_object(schema) && schema.get("properties").is_some() {
+            result.typ = self.add_struct(inline_name, schema);
+            return result;
+        }
+        let format = schema
+            .get("format")
+            .and_then(Json::as_str)
+            .unwrap_or_default();
+        let typ = types(schema)
+            .into_iter()
+            .find(|t| *t != "null")
+            .unwrap_or_default();
+        result.typ = match (typ, format) {
+            ("array", _) => {
+                let Some(items) = schema.get("items") else {
+                    result.array = true;
+                    result.comment = Some("no item type given".to_string());
+                    return result;
+                };
+                let mut inner = self.property_type(items, inline_name, depth + 1);
+                if inner.array {
+                    inner.typ = "string".to_string();
+                    inner.comment = Some("nested arrays are not supported".to_string());
+                }
+                result.array = true;
+                result.comment = inner.comment;
+                inner.typ
+            }
+            ("string", "uuid") => "uuid".to_string(),
+            ("string", "date-time" | "date") => "datetime".to_string(),
+            ("string", "byte" | "binary") => "bytes".to_string(),
+            ("string", _) => "string".to_string(),
+            ("integer", "int32") => "int32".to_string(),
+            ("integer", _) => "int64".to_string(),
+            ("number", _) => "float64".to_string(),
+            ("boolean", _) => "boolean".to_string(),
+            ("object", _) => {
+                result.comment = Some("free-form object".to_string());
+                "string".to_string()
+            }
+            _ => {
+                result.comment = Some("no type given".to_string());
+                "string".to_string()
+            }
+        };
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The schema's lines with runs of whitespace collapsed, so tests don't
+    /// depend on the column alignment `repack fmt` adds.
+    fn lines(source: &str) -> Vec<String> {
+        import_openapi("api.yaml", source)
+            .unwrap()
+            .lines()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+            .collect()
+    }
+
+    #[test]
+    fn converts_objects_and_enums() {
+        let schema = lines(
+            "components:\n  schemas:\n    Status:\n      type: string\n      enum: [active, archived]\n    Pet:\n      type: object\n      required: [id]\n      properties:\n        id:\n          type: string\n          format: uuid\n        status:\n          $ref: '#/components/schemas/Status'\n        tags:\n          type: array\n          items:\n            type: string\n",
+        );
+        for line in [
+            "enum Status {",
+            "Active \"active\"",
+            "Archived \"archived\"",
+            "struct Pet {",
+            "id uuid",
+            "status Status?",
+            "tags string[]?",
+        ] {
+            assert!(
+                schema.iter().any(|l| l == line),
+                "missing {line:?} in {schema:#?}"
+            );
+        }
+    }
+
+    #[test]
+    fn comments_out_circular_references() {
+        let schema = lines(
+            "components:\n  schemas:\n    Pet:\n      type: object\n      properties:\n        parent:\n          $ref: '#/components/schemas/Pet'\n        owner:\n          $ref: '#/components/schemas/Owner'\n    Owner:\n      type: object\n      properties:\n        pets:\n          type: array\n          items:\n            $ref: '#/components/schemas/Pet'\n",
+        );
+        for line in [
+            "// parent Pet? // circular reference: Pet -> Pet",
+            "owner Owner?",
+            "// pets Pet[]? // circular reference: Owner -> Pet -> Owner",
+        ] {
+            assert!(
+                schema.iter().any(|l| l == line),
+                "missing {line:?} in {schema:#?}"
+            );
+        }
+    }
+
+    #[test]
+    fn keeps_shared_references_that_are_not_cycles() {
+        let schema = lines(
+            "components:\n  schemas:\n    Tag:\n      type: object\n      properties:\n        name:\n          type: string\n    Pet:\n      type: object\n      properties:\n        tag:\n          $ref: '#/components/schemas/Tag'\n        other:\n          $ref: '#/components/schemas/Tag'\n",
+        );
+        assert!(
+            schema
+                .iter()
+                .all(|l| !l.starts_with("//") || l.starts_with("// Imported"))
+        );
+    }
+
+    #[test]
+    fn rejects_documents_without_schemas() {
+        let err = import_openapi("api.yaml", "openapi: 3.0.0\npaths: {}\n").unwrap_err();
+        assert_eq!(
+            err.error_details.as_deref(),
+            Some("api.yaml has no components.schemas or definitions")
+        );
+        assert!(import_openapi("api.json", "{\"components\": ").is_err());
+        assert!(import_openapi("api.yaml", "a: [1\n").is_err());
+    }
+}
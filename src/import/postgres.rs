@@ -0,0 +1,240 @@
+use std::process::{Command, Stdio};
+
+use super::{ImportedEnum, ImportedField, ImportedStruct, pascal_case, render_schema};
+use crate::syntax::{RepackError, RepackErrorKind};
+
+const FIELD_SEPARATOR: &str = "\u{1f}";
+const RECORD_SEPARATOR: &str = "\u{1e}";
+
+const ENUMS_QUERY: &str = "SELECT t.typname, e.enumlabel
+FROM pg_type t
+JOIN pg_enum e ON e.enumtypid = t.oid
+JOIN pg_namespace n ON n.oid = t.typnamespace
+WHERE n.nspname = 'public'
+ORDER BY t.typname, e.enumsortorder";
+
+const COLUMNS_QUERY: &str = "SELECT c.table_name, c.column_name, c.data_type, c.udt_name,
+    c.is_nullable, COALESCE(c.column_default, ''), c.is_identity
+FROM information_schema.columns c
+JOIN information_schema.tables t
+    ON t.table_schema = c.table_schema AND t.table_name = c.table_name
+WHERE c.table_schema = 'public' AND t.table_type = 'BASE TABLE'
+ORDER BY c.table_name, c.ordinal_position";
+
+// Only single-column keys map onto field functions, so the column count is included.
+const CONSTRAINTS_QUERY: &str = "SELECT tc.table_name, kcu.column_name, tc.constraint_type,
+    COALESCE(ccu.table_name, ''), COALESCE(ccu.column_name, ''),
+    COUNT(*) OVER (PARTITION BY tc.constraint_name)
+FROM information_schema.table_constraints tc
+JOIN information_schema.key_column_usage kcu
+    ON kcu.constraint_name = tc.constraint_name AND kcu.table_schema = tc.table_schema
+LEFT JOIN information_schema.constraint_column_usage ccu
+    ON tc.constraint_type = 'FOREIGN KEY'
+    AND ccu.constraint_name = tc.constraint_name AND ccu.table_schema = tc.table_schema
+WHERE tc.table_schema = 'public'
+    AND tc.constraint_type IN ('PRIMARY KEY', 'UNIQUE', 'FOREIGN KEY')";
+
+// Indexes backing primary keys and unique constraints are covered by field functions.
+const INDEXES_QUERY: &str = "SELECT t.relname, i.relname, a.attname
+FROM pg_index x
+JOIN pg_class t ON t.oid = x.indrelid
+JOIN pg_class i ON i.oid = x.indexrelid
+JOIN pg_namespace n ON n.oid = t.relnamespace
+JOIN LATERAL unnest(x.indkey) WITH ORDINALITY AS k(attnum, ord) ON true
+JOIN pg_attribute a ON a.attrelid = t.oid AND a.attnum = k.attnum
+WHERE n.nspname = 'public' AND NOT x.indisprimary AND NOT x.indisunique
+ORDER BY t.relname, i.relname, k.ord";
+
+/// Runs a query with `psql` and splits the output into rows of columns.
+fn query(connection: &str, sql: &str) -> Result<Vec<Vec<String>>, RepackError> {
+    let output = Command::new("psql")
+        .args(["-X", "-q", "-A", "-t", "-v", "ON_ERROR_STOP=1"])
+        .args(["-F", FIELD_SEPARATOR, "-R", RECORD_SEPARATOR])
+        .args(["-d", connection, "-c", sql])
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|e| {
+            RepackError::global(
+                RepackErrorKind::ProcessExecutionFailed,
+                format!("could not run psql: {e}"),
+            )
+        })?;
+    if !output.status.success() {
+        return Err(RepackError::global(
+            RepackErrorKind::ProcessExecutionFailed,
+            "psql could not query the database".to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .split(RECORD_SEPARATOR)
+        .map(|row| row.trim_matches('\n'))
+        .filter(|row| !row.is_empty())
+        .map(|row| row.split(FIELD_SEPARATOR).map(|c| c.to_string()).collect())
+        .collect())
+}
+
+/// Maps a Postgres type name (`udt_name`, without the array underscore) to a repack type.
+fn core_type(udt: &str) -> Option<&'static str> {
+    Some(match udt {
+        "int2" => "int16",
+        "int4" => "int32",
+        "int8" => "int64",
+        "float4" | "float8" | "numeric" => "float64",
+        "bool" => "boolean",
+        "uuid" => "uuid",
+        "text" | "varchar" | "bpchar" | "citext" | "name" => "string",
+        "timestamptz" | "timestamp" | "date" => "datetime",
+        "bytea" => "bytes",
+        _ => return None,
+    })
+}
+
+/// Builds a schema from the `public` schema of a live PostgreSQL database.
+///
+/// Tables become structs named after the table, with `@table` set. Enum types
+/// become enums. Single-column primary keys, unique constraints, and foreign
+/// keys become `db:pk`, `db:unique`, and `db:fk`. Column defaults become
+/// `db:default` (except sequence defaults, which are left to the database), and
+/// identity columns get `db:identity`. Plain indexes become `db:index` on the
+/// struct. Columns of types repack has no equivalent for are imported as
+/// `string` with a comment naming the original type.
+///
+/// # Arguments
+/// * `connection` - A connection string or URI accepted by `psql`
+///
+/// # Returns
+/// * `Ok(String)` with the schema text
+/// * `Err(RepackError)` if `psql` is missing or a query fails
+pub fn import_postgres(connection: &str) -> Result<String, RepackError> {
+    let mut enums: Vec<ImportedEnum> = Vec::new();
+    let mut enum_types: Vec<(String, String)> = Vec::new();
+    for row in query(connection, ENUMS_QUERY)? {
+        let [typ, label] = row.as_slice() else {
+            continue;
+        };
+        let case = pascal_case(label);
+        let value = (case != *label).then(|| label.to_string());
+        match enum_types.iter().position(|(t, _)| t == typ) {
+            Some(idx) => enums[idx].cases.push((case, value)),
+            None => {
+                enum_types.push((typ.to_string(), pascal_case(typ)));
+                enums.push(ImportedEnum {
+                    name: pascal_case(typ),
+                    cases: vec![(case, value)],
+                });
+            }
+        }
+    }
+
+    let mut tables: Vec<String> = Vec::new();
+    let mut strcts: Vec<ImportedStruct> = Vec::new();
+    for row in query(connection, COLUMNS_QUERY)? {
+        let [table, column, data_type, udt, nullable, default, identity] = row.as_slice() else {
+            continue;
+        };
+        let array = data_type == "ARRAY";
+        let udt = if array {
+            udt.trim_start_matches('_')
+        } else {
+            udt.as_str()
+        };
+        let mut comment = None;
+        let typ = match (core_type(udt), enum_types.iter().find(|(t, _)| t == udt)) {
+            (Some(core), _) => core.to_string(),
+            (None, Some((_, name))) => name.to_string(),
+            (None, None) => {
+                comment = Some(format!("unmapped type: {udt}"));
+                "string".to_string()
+            }
+        };
+        let mut functions = Vec::new();
+        if identity == "YES" {
+            functions.push("db:identity".to_string());
+        } else if !default.is_empty() && !default.starts_with("nextval(") && !default.contains('"')
+        {
+            functions.push(format!("db:default(\"{default}\")"));
+        }
+        let field = ImportedField {
+            name: column.to_string(),
+            typ,
+            optional: nullable == "YES",
+            array,
+            functions,
+            comment,
+            commented_out: false,
+        };
+        match tables.iter().position(|t| t == table) {
+            Some(idx) => strcts[idx].fields.push(field),
+            None => {
+                tables.push(table.to_string());
+                strcts.push(ImportedStruct {
+                    name: pascal_case(table),
+                    table: Some(table.to_string()),
+                    fields: vec![field],
+                    functions: Vec::new(),
+                });
+            }
+        }
+    }
+
+    for row in query(connection, CONSTRAINTS_QUERY)? {
+        let [table, column, kind, foreign_table, foreign_column, columns] = row.as_slice() else {
+            continue;
+        };
+        if columns != "1" {
+            continue;
+        }
+        let Some(field) = tables
+            .iter()
+            .position(|t| t == table)
+            .and_then(|idx| strcts[idx].fields.iter_mut().find(|f| f.name == *column))
+        else {
+            continue;
+        };
+        let function = match kind.as_str() {
+            "PRIMARY KEY" => "db:pk".to_string(),
+            "UNIQUE" => "db:unique".to_string(),
+            _ => format!("db:fk(\"{foreign_table}\", \"{foreign_column}\")"),
+        };
+        // Keys come first so they stand out, ahead of defaults.
+        let position = field
+            .functions
+            .iter()
+            .position(|f| !f.starts_with("db:pk") && !f.starts_with("db:unique"))
+            .unwrap_or(field.functions.len());
+        field.functions.insert(position, function);
+    }
+
+    let mut index: Option<(String, String, Vec<String>)> = None;
+    let rows = query(connection, INDEXES_QUERY)?;
+    for row in rows.iter().map(Some).chain([None]) {
+        let next = row.and_then(|row| match row.as_slice() {
+            [table, name, column] => Some((table, name, column)),
+            _ => None,
+        });
+        if let Some((table, name, column)) = next
+            && let Some((t, n, columns)) = &mut index
+            && t == table
+            && n == name
+        {
+            columns.push(column.to_string());
+            continue;
+        }
+        if let Some((table, _, columns)) = index.take()
+            && let Some(idx) = tables.iter().position(|t| *t == table)
+        {
+            strcts[idx]
+                .functions
+                .push(format!("db:index({})", columns.join(", ")));
+        }
+        index = next.map(|(table, name, column)| {
+            (
+                table.to_string(),
+                name.to_string(),
+                vec![column.to_string()],
+            )
+        });
+    }
+
+    Ok(render_schema("PostgreSQL", &enums, &strcts))
+}
@@ -0,0 +1,376 @@
+use crate::syntax::Json;
+
+/// A non-blank line of a YAML document with its comment removed.
+struct Line {
+    indent: usize,
+    text: String,
+    number: usize,
+}
+
+/// Parses the subset of YAML used by OpenAPI documents into a [`Json`] value.
+///
+/// Supported are block mappings and sequences, single-line flow collections
+/// (`[a, b]`, `{a: 1}`), quoted and plain scalars, and `|`/`>` block scalars.
+/// Anchors, aliases, tags, and multi-document streams are not supported.
+///
+/// # Returns
+/// * `Ok(Json)` with the document
+/// * `Err(String)` describing the first line that could not be read
+pub fn parse_yaml(source: &str) -> Result<Json, String> {
+    let mut lines = Vec::new();
+    for (idx, raw) in source.lines().enumerate() {
+        let text = strip_comment(raw).trim_end();
+        if text.trim().is_empty() || text == "---" {
+            continue;
+        }
+        let indent = text.len() - text.trim_start().len();
+        lines.push(Line {
+            indent,
+            text: text.trim_start().to_string(),
+            number: idx + 1,
+        });
+    }
+    if lines.is_empty() {
+        return Ok(Json::Null);
+    }
+    let mut pos = 0;
+    let indent = lines[0].indent;
+    let value = block(&mut lines, &mut pos, indent)?;
+    match lines.get(pos) {
+        Some(line) => Err(format!("unexpected indentation (line {})", line.number)),
+        None => Ok(value),
+    }
+}
+
+/// Removes a `#` comment that is outside quotes and starts a word.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut prev = ' ';
+    for (idx, c) in line.char_indices() {
+        match (quote, c) {
+            // Apostrophes inside plain text (`it's`) do not start a quote.
+            (None, '"' | '\'') if prev.is_whitespace() || "[{,:".contains(prev) => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, '#') if prev.is_whitespace() => return &line[..idx],
+            _ => {}
+        }
+        prev = c;
+    }
+    line
+}
+
+/// Reads the mapping or sequence whose entries start at `indent`.
+fn block(lines: &mut [Line], pos: &mut usize, indent: usize) -> Result<Json, String> {
+    if lines[*pos].text == "-" || lines[*pos].text.starts_with("- ") {
+        let mut items = Vec::new();
+        while *pos < lines.len()
+            && lines[*pos].indent == indent
+            && (lines[*pos].text == "-" || lines[*pos].text.starts_with("- "))
+        {
+            let rest = lines[*pos].text[1..].trim_start().to_string();
+            if rest.is_empty() {
+                *pos += 1;
+                items.push(nested(lines, pos, indent, false)?);
+            } else {
+                // Treat `- key: value` as a mapping starting where the key does.
+                let offset = lines[*pos].text.len() - rest.len();
+                lines[*pos].indent += offset;
+                lines[*pos].text = rest;
+                let inner = lines[*pos].indent;
+                items.push(if is_mapping_entry(&lines[*pos].text) {
+                    block(lines, pos, inner)?
+                } else {
+                    let value = scalar(&lines[*pos].text, lines[*pos].number)?;
+                    *pos += 1;
+                    value
+                });
+            }
+        }
+        return Ok(Json::Array(items));
+    }
+
+    let mut pairs = Vec::new();
+    while *pos < lines.len() && lines[*pos].indent == indent {
+        let number = lines[*pos].number;
+        let Some((key, rest)) = split_entry(&lines[*pos].text) else {
+            return Err(format!("expected 'key: value' (line {number})"));
+        };
+        *pos += 1;
+        let value = match rest.as_str() {
+            "" => nested(lines, pos, indent, true)?,
+            r if r.starts_with('|') || r.starts_with('>') => {
+                let mut text = Vec::new();
+                while *pos < lines.len() && lines[*pos].indent > indent {
+                    text.push(lines[*pos].text.clone());
+                    *pos += 1;
+                }
+                Json::String(text.join(if r.starts_with('|') { "\n" } else { " " }))
+            }
+            r => scalar(r, number)?,
+        };
+        pairs.push((key, value));
+    }
+    Ok(Json::Object(pairs))
+}
+
+/// Reads the value below a `key:` or `-` line with nothing after it.
+///
+/// Sequences may sit at the same indentation as the key that owns them.
+fn nested(
+    lines: &mut [Line],
+    pos: &mut usize,
+    indent: usize,
+    allow_same_indent: bool,
+) -> Result<Json, String> {
+    match lines.get(*pos) {
+        Some(line) if line.indent > indent => {
+            let inner = line.indent;
+            block(lines, pos, inner)
+        }
+        Some(line)
+            if allow_same_indent
+                && line.indent == indent
+                && (line.text == "-" || line.text.starts_with("- ")) =>
+        {
+            block(lines, pos, indent)
+        }
+        _ => Ok(Json::Null),
+    }
+}
+
+fn is_mapping_entry(text: &str) -> bool {
+    !text.starts_with('[') && !text.starts_with('{') && split_entry(text).is_some()
+}
+
+/// Splits `key: value` into the unquoted key and the trimmed value.
+fn split_entry(text: &str) -> Option<(String, String)> {
+    if let Some(q) = text.chars().next().filter(|c| *c == '"' || *c == '\'') {
+        let end = text[1..].find(q)? + 1;
+        let rest = text[end + 1..].trim_start().strip_prefix(':')?;
+        return Some((text[1..end].to_string(), rest.trim().to_string()));
+    }
+    let idx = text
+        .char_indices()
+        .find(|(idx, c)| *c == ':' && text[idx + 1..].chars().next().is_none_or(|n| n == ' '))
+        .map(|(idx, _)| idx)?;
+    Some((
+        text[..idx].trim().to_string(),
+        text[idx + 1..].trim().to_string(),
+    ))
+}
+
+/// Reads a scalar or single-line flow collection.
+fn scalar(text: &str, number: usize) -> Result<Json, String> {
+    let text = text.trim();
+    if let Some(inner) = text.strip_prefix('[') {
+        let Some(inner) = inner.strip_suffix(']') else {
+            return Err(format!("unterminated '[' (line {number})"));
+        };
+        return flow_items(inner)
+            .iter()
+            .map(|item| scalar(item, number))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Json::Array);
+    }
+    if let Some(inner) = text.strip_prefix('{') {
+        let Some(inner) = inner.strip_suffix('}') else {
+            return Err(format!("unterminated '{{' (line {number})"));
+        };
+        let mut pairs = Vec::new();
+        for item in flow_items(inner) {
+            let Some((key, value)) = split_entry(&item) else {
+                return Err(format!("expected 'key: value' (line {number})"));
+            };
+            pairs.push((key, scalar(&value, number)?));
+        }
+        return Ok(Json::Object(pairs));
+    }
+    if text.len() >= 2 && text.starts_with('"') && text.ends_with('"') {
+        return Json::parse(text).map_err(|e| format!("{e} (line {number})"));
+    }
+    if text.len() >= 2 && text.starts_with('\'') && text.ends_with('\'') {
+        return Ok(Json::String(text[1..text.len() - 1].replace("''", "'")));
+    }
+    Ok(match text {
+        "" | "~" | "null" | "Null" | "NULL" => Json::Null,
+        "true" | "True" | "TRUE" => Json::Bool(true),
+        "false" | "False" | "FALSE" => Json::Bool(false),
+        _ => match text.parse::<f64>() {
+            Ok(n)
+                if text
+                    .chars()
+                    .all(|c| c.is_ascii_digit() || "-.eE".contains(c)) =>
+            {
+                Json::Number(n)
+            }
+            _ => Json::String(text.to_string()),
+        },
+    })
+}
+
+/// Splits the inside of a flow collection on commas outside quotes and brackets.
+fn flow_items(inner: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0;
+    let mut quote = None;
+    for c in inner.chars() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, '[' | '{') => depth += 1,
+            (None, ']' | '}') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                items.push(std::mem::take(&mut current).trim().to_string());
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    if !current.trim().is_empty() {
+        items.push(current.trim().to_string());
+    }
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_mappings_and_sequences() {
+        let doc = parse_yaml(
+            "openapi: 3.0.0\ncomponents:\n  schemas:\n    Pet:\n      required:\n      - id\n      - name\n      properties:\n        - id: 1\n          tag: x\n",
+        )
+        .unwrap();
+        let pet = doc
+            .get("components")
+            .unwrap()
+            .get("schemas")
+            .unwrap()
+            .get("Pet")
+            .unwrap();
+        assert_eq!(
+            pet.get("required"),
+            Some(&Json::Array(vec![Json::str("id"), Json::str("name")]))
+        );
+        assert_eq!(
+            pet.get("properties"),
+            Some(&Json::Array(vec![Json::object([
+                ("id", Json::Number(1.0)),
+                ("tag", Json::str("x"))
+            ])]))
+        );
+        assert_eq!(doc.get("openapi"), Some(&Json::str("3.0.0")));
+    }
+
+    #[test]
+    fn reads_scalars() {
+        let doc =
+            parse_yaml("a: 12\nb: -1.5\nc: true\nd: ~\ne: \"x\\ty\"\nf: 'it''s'\ng: 1.0.0\nh:\n")
+                .unwrap();
+        assert_eq!(
+            doc,
+            Json::object([
+                ("a", Json::Number(12.0)),
+                ("b", Json::Number(-1.5)),
+                ("c", Json::Bool(true)),
+                ("d", Json::Null),
+                ("e", Json::str("x\ty")),
+                ("f", Json::str("it's")),
+                ("g", Json::str("1.0.0")),
+                ("h", Json::Null),
+            ])
+        );
+    }
+
+    #[test]
+    fn reads_flow_collections() {
+        let doc = parse_yaml("a: [x, 'y, z', [1]]\nb: {k: v, n: {m: 1}}\nc: []\n").unwrap();
+        assert_eq!(
+            doc,
+            Json::object([
+                (
+                    "a",
+                    Json::Array(vec![
+                        Json::str("x"),
+                        Json::str("y, z"),
+                        Json::Array(vec![Json::Number(1.0)])
+                    ])
+                ),
+                (
+                    "b",
+                    Json::object([
+                        ("k", Json::str("v")),
+                        ("n", Json::object([("m", Json::Number(1.0))]))
+                    ])
+                ),
+                ("c", Json::Array(Vec::new())),
+            ])
+        );
+    }
+
+    #[test]
+    fn reads_block_scalars() {
+        let doc = parse_yaml("a: |\n  one\n  two\nb: >\n  one\n  two\nc: 1\n").unwrap();
+        assert_eq!(
+            doc,
+            Json::object([
+                ("a", Json::str("one\ntwo")),
+                ("b", Json::str("one two")),
+                ("c", Json::Number(1.0)),
+            ])
+        );
+    }
+
+    #[test]
+    fn strips_comments_outside_quotes() {
+        let doc =
+            parse_yaml("# header\n---\na: x # note\nb: \"# kept\"\nc: it's #1\nd: a#b\n").unwrap();
+        assert_eq!(
+            doc,
+            Json::object([
+                ("a", Json::str("x")),
+                ("b", Json::str("# kept")),
+                ("c", Json::str("it's")),
+                ("d", Json::str("a#b")),
+            ])
+        );
+    }
+
+    #[test]
+    fn reads_quoted_keys_and_urls() {
+        let doc = parse_yaml("\"200\": ok\n'a: b': c\nurl: http://x.io:80/\n").unwrap();
+        assert_eq!(
+            doc,
+            Json::object([
+                ("200", Json::str("ok")),
+                ("a: b", Json::str("c")),
+                ("url", Json::str("http://x.io:80/")),
+            ])
+        );
+    }
+
+    #[test]
+    fn empty_document_is_null() {
+        assert_eq!(parse_yaml(""), Ok(Json::Null));
+        assert_eq!(parse_yaml("# only a comment\n\n"), Ok(Json::Null));
+    }
+
+    #[test]
+    fn rejects_malformed_documents() {
+        for (source, error) in [
+            ("a: [1, 2\n", "unterminated '[' (line 1)"),
+            ("a: 1\nb: {k: v\n", "unterminated '{' (line 2)"),
+            ("a: {k}\n", "expected 'key: value' (line 1)"),
+            ("a: 1\njust text\n", "expected 'key: value' (line 2)"),
+            ("  a: 1\nb: 2\n", "unexpected indentation (line 2)"),
+            ("a:\n    b: 1\n  c: 2\n", "unexpected indentation (line 3)"),
+            ("a: \"x\\q\"\n", "line 1"),
+        ] {
+            let err = parse_yaml(source).unwrap_err();
+            assert!(err.contains(error), "{source:?}: {err}");
+        }
+    }
+}
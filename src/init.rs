@@ -0,0 +1,62 @@
+use crate::{
+    blueprint::{Blueprint, BlueprintKind},
+    syntax::format_schema,
+};
+
+/// The schema file `repack init` writes.
+pub const SCHEMA_FILE: &str = "project.repack";
+
+/// Blueprints a new project generates when none are chosen.
+pub const DEFAULT_BLUEPRINTS: [&str; 2] = ["typescript", "postgres"];
+
+/// The example definitions written below the outputs: an enum and a record
+/// that uses it, with a primary key, a unique column, and defaults.
+const EXAMPLE: &str = "enum UserRole #model {
+	Admin
+	Member
+}
+
+struct User @users #model {
+	id uuid db:pk db:default(\"gen_random_uuid()\")
+	name string
+	email string db:unique
+	role UserRole
+	created_at datetime db:default(\"NOW()\")
+}
+";
+
+/// Where a scaffolded output writes its files, relative to the schema.
+///
+/// Documentation goes in `docs`, configuration files in `config`, SQL in
+/// `db`, and everything else in `generated/<id>`.
+pub fn location(blueprint: &Blueprint) -> String {
+    match (&blueprint.kind, blueprint.id.as_str()) {
+        (BlueprintKind::Document, _) => "docs".to_string(),
+        (BlueprintKind::Configure, _) => "config".to_string(),
+        (_, "postgres") => "db".to_string(),
+        (_, id) => format!("generated/{id}"),
+    }
+}
+
+/// Writes a starter schema with an output block for each blueprint followed
+/// by an example enum and record, in canonical formatting.
+///
+/// Go outputs are given a `package` option, which its blueprint requires.
+pub fn scaffold(blueprints: &[&Blueprint]) -> String {
+    let mut schema =
+        String::from("// Generated by `repack init`. Build with `repack project.repack`.\n\n");
+    for blueprint in blueprints {
+        let location = location(blueprint);
+        if blueprint.id == "go" {
+            schema.push_str(&format!(
+                "output {} @{location} {{\n\tpackage models\n}}\n",
+                blueprint.id
+            ));
+        } else {
+            schema.push_str(&format!("output {} @{location};\n", blueprint.id));
+        }
+    }
+    schema.push('\n');
+    schema.push_str(EXAMPLE);
+    format_schema(&schema)
+}
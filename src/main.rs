@@ -1,139 +1,690 @@
-use std::{io::Write, path::PathBuf, process::exit};
+use std::{
+    collections::HashSet,
+    io::{IsTerminal, Read, Write},
+    path::PathBuf,
+    process::exit,
+    time::Instant,
+};
 
 use blueprint::BlueprintRenderer;
-use syntax::{FileContents, ParseResult, RepackError, RepackErrorKind};
+use syntax::{
+    ChangeImpact, FileContents, LintRule, ParseResult, RepackError, RepackErrorKind, VersionBump,
+    dependency_graph, format_schema, lint, next_version, schema_changes, schema_from_ir,
+    schema_to_ir, set_schema_meta, unused_definitions,
+};
 
-use crate::blueprint::BlueprintStore;
+use crate::blueprint::{BlueprintKind, BlueprintStore, check_blueprint};
+use crate::cli::{Behavior, Cli, Command, GlobalFlags, ImportSource};
+use crate::console::Console;
+use crate::timings::Timings;
 
 mod blueprint;
+mod cli;
+mod console;
+mod diff;
+mod import;
+mod init;
+mod mock;
+mod settings;
 mod syntax;
+mod timings;
+mod verify;
 
-const WIDTH: usize = 60;
+fn print_usage() {
+    let msg = include_bytes!("usage.txt");
+    _ = std::io::stdout().write_all(msg);
+    exit(1);
+}
 
-pub struct Console;
-impl Console {
-    fn begin() {
-        println!("[] Loading...");
-        print!("");
-    }
-    fn update_ct(i: usize, n: usize, title: &str) {
-        print!("\x1B[1A");
-        print!("\r\x1B[2K[{i}/{n}] {title:<WIDTH$}\n");
-        let _ = std::io::Write::flush(&mut std::io::stdout());
+/// Prints a table of blueprints with their kind, version, and repack requirement.
+fn list_blueprints(store: &BlueprintStore) {
+    Console::finalize();
+    println!(
+        "{:<20} {:<34} {:<10} {:<10} REQUIRES",
+        "ID", "NAME", "KIND", "VERSION"
+    );
+    for bp in store.blueprints() {
+        let kind = match bp.kind {
+            BlueprintKind::Build => "build",
+            BlueprintKind::Configure => "configure",
+            BlueprintKind::Document => "document",
+        };
+        println!(
+            "{:<20} {:<34} {:<10} {:<10} {}",
+            bp.id,
+            bp.name,
+            kind,
+            bp.version.as_deref().unwrap_or("-"),
+            bp.requires_repack.as_deref().unwrap_or("-")
+        );
     }
-    fn update_msg(msg: &str) {
-        print!("\r\x1B[2K  {msg:<WIDTH$}");
-        let _ = std::io::Write::flush(&mut std::io::stdout());
+}
+
+/// Runs `repack import <kind> <source>`, printing the generated schema to stdout.
+///
+/// Progress output is skipped so the result can be redirected into a file.
+fn run_import(source: ImportSource) {
+    let read = |file: &str| {
+        let Ok(source) = std::fs::read_to_string(file) else {
+            eprintln!("Unable to load requested file '{file}'");
+            exit(5);
+        };
+        source
+    };
+    let result = match source {
+        ImportSource::Postgres { connection } => import::import_postgres(&connection),
+        ImportSource::Json { file, name } => {
+            let name = name.unwrap_or_else(|| {
+                PathBuf::from(&file)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            });
+            import::import_json(&file, &read(&file), &name)
+        }
+        ImportSource::OpenApi { file } => import::import_openapi(&file, &read(&file)),
+    };
+    match result {
+        Ok(schema) => print!("{schema}"),
+        Err(e) => {
+            eprintln!("{}", e.into_string());
+            exit(1);
+        }
     }
-    fn finalize() {
-        println!()
+}
+
+/// Runs `repack init [--blueprints id,...] [dir]`, writing a starter
+/// `project.repack` and creating the directories its outputs write to.
+///
+/// Without `--blueprints`, the blueprints are asked for when stdin is a
+/// terminal, and otherwise default to TypeScript and PostgreSQL. An existing
+/// schema is never overwritten.
+fn run_init(dir: Option<String>, chosen: Option<String>) {
+    let dir = PathBuf::from(dir.as_deref().unwrap_or("."));
+    let file = dir.join(init::SCHEMA_FILE);
+    if file.exists() {
+        Console::error(&format!("{} already exists.", file.display()));
+        exit(1);
     }
-    fn error(message: &str) {
-        print!("\n{message}");
-        let _ = std::io::Write::flush(&mut std::io::stdout());
+    let store = match BlueprintStore::new() {
+        Ok(store) => store,
+        Err(e) => {
+            Console::report(e);
+            exit(1);
+        }
+    };
+    let chosen = chosen.unwrap_or_else(|| {
+        let default = init::DEFAULT_BLUEPRINTS.join(",");
+        if !std::io::stdin().is_terminal() {
+            return default;
+        }
+        let available = store
+            .blueprints()
+            .iter()
+            .map(|bp| bp.id.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        Console::ask(
+            &format!("Blueprints to generate, comma-separated ({available})?"),
+            &default,
+        )
+    });
+    let mut blueprints = Vec::new();
+    for id in chosen.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+        let Some(blueprint) = store.blueprint(id) else {
+            Console::error(&format!(
+                "No blueprint named '{id}'. Run `repack blueprints` to list them."
+            ));
+            exit(2);
+        };
+        blueprints.push(blueprint);
     }
-    fn ask_confirmation() -> bool {
-        let mut input = String::new();
-        if std::io::stdin().read_line(&mut input).is_err() {
-            return false;
+
+    Console::begin();
+    for location in blueprints.iter().map(|bp| dir.join(init::location(bp))) {
+        if let Err(e) = std::fs::create_dir_all(&location) {
+            Console::error(&format!("Could not create {}: {e}", location.display()));
+            exit(3);
         }
-        print!("\x1B[1A");
-        matches!(input.trim().to_lowercase().as_str(), "y" | "yes")
+        Console::detail(&format!("Created {}", location.display()));
+    }
+    if let Err(e) = std::fs::write(&file, init::scaffold(&blueprints)) {
+        Console::error(&format!("Could not write {}: {e}", file.display()));
+        exit(3);
     }
+    Console::update_msg(&format!(
+        "Created {} with {} output(s). Run `repack {}` to build.",
+        file.display(),
+        blueprints.len(),
+        file.display()
+    ));
+    Console::finalize();
 }
 
-/// Defines the operational mode for the repack code generator.
+/// Reads and resolves a schema other than the one a command runs on, such as
+/// the snapshot `repack diff-schema` compares against. Like the main schema,
+/// it may be a `.repack` file or JSON written by `repack snapshot` or
+/// `repack inspect`.
 ///
-/// This enum determines what action the tool will take when executed.
-/// The behavior is determined by command-line arguments passed to the application.
-enum Behavior {
-    /// Generate code files from the schema using blueprint templates.
-    /// This is the default mode that creates output files in target languages
-    /// like Rust, TypeScript, PostgreSQL, Go, and Markdown.
-    Build,
-    /// Remove previously generated code files, cleaning up the output directories.
-    /// Uses blueprint metadata to determine which files to delete.
-    Clean,
+/// Exits with status 5 if the file cannot be read, and 1 if it has errors.
+fn read_schema(file: &str) -> ParseResult {
+    let Ok(source) = std::fs::read_to_string(file) else {
+        Console::error(&format!("Unable to load requested file '{file}'"));
+        exit(5);
+    };
+    let parsed = if file.ends_with(".json") {
+        schema_from_ir(&source)
+    } else {
+        ParseResult::read_contents(FileContents::new(file))
+    };
+    match parsed.and_then(ParseResult::resolve) {
+        Ok(result) => result,
+        Err(errors) => {
+            for e in errors {
+                Console::report(e);
+            }
+            exit(1);
+        }
+    }
 }
 
-fn print_usage() {
-    let msg = include_bytes!("usage.txt");
-    _ = std::io::stdout().write_all(msg);
-    exit(1);
+/// Runs `repack blueprint-check [--no-strict] file.blueprint`, reporting
+/// problems in a blueprint without rendering it.
+///
+/// The check is strict unless `--no-strict` is given, which reports tags the
+/// renderer would ignore as warnings instead of errors.
+///
+/// Exits with status 1 if any errors are found, or any warnings with
+/// `--deny-warnings`.
+fn run_blueprint_check(file: &str, strict: bool, deny_warnings: bool) {
+    Console::begin();
+    let Ok(contents) = std::fs::read(file) else {
+        Console::error(&format!("Unable to load requested file '{file}'"));
+        exit(5);
+    };
+    let findings = check_blueprint(&contents, strict);
+    let warnings = findings.iter().filter(|f| f.is_warning()).count();
+    let errors = findings.len() - warnings;
+    for mut finding in findings {
+        if finding.specifier.is_empty() {
+            finding.specifier = format!(" ({file})");
+        }
+        Console::report(finding);
+    }
+    Console::update_msg(&format!("{errors} error(s), {warnings} warning(s)."));
+    Console::finalize();
+    if errors > 0 || (deny_warnings && warnings > 0) {
+        exit(1);
+    }
 }
 
 /// Entry point for the repack code generation tool.
 ///
 /// This function orchestrates the complete code generation process:
 /// 1. Parses command-line arguments to determine operation mode and input file
-/// 2. Loads and parses the .repack schema file with tokenization
+/// 2. Loads and parses the .repack schema file with tokenization, or reads a
+///    `.json` file in the format written by `repack inspect`
 /// 3. Loads built-in blueprints (rust, typescript, postgres, go, markdown)
 /// 4. Loads any external blueprint files specified in the schema
 /// 5. Filters and processes outputs based on blueprint types and categories
 /// 6. Executes the requested operation (build, clean, document, or configure)
 ///
-/// The tool supports four operation modes:
+/// The tool supports these operation modes:
 /// - `repack build file.repack` - Generate code files (default)
+/// - `repack build --check file.repack` - Fail if generated files are out of date
+/// - `repack check file.repack` - The same as `repack build --check`
 /// - `repack clean file.repack` - Remove generated files
 /// - `repack document file.repack` - Generate documentation
-/// - `repack configure env file.repack` - Generate configuration files
+/// - `repack configure file.repack` - Generate configuration files
+/// - `repack diff file.repack` - Show what regenerating would change
+/// - `repack graph file.repack` - Write a DOT dependency graph
+/// - `repack fmt [--check] file.repack` - Format the schema
+/// - `repack lint [--allow rule]... file.repack` - Report lint findings
+/// - `repack blueprints [file.repack]` - List available blueprints
+/// - `repack inspect [--format json] file.repack` - Print the schema as JSON
+/// - `repack snapshot [--out file.json] file.repack` - Write the schema's JSON IR for later comparison
+/// - `repack diff-schema old.json new.repack` - Report schema changes since a snapshot
+/// - `repack check-compat --against old.json [--policy strict|lenient] file.repack` - Fail on incompatible changes
+/// - `repack next-version --against old.json [--write] file.repack` - Suggest the next semantic version
+/// - `repack mock [--count n] [--format json|sql] [--seed n] file.repack` - Print fake records
+/// - `repack verify-queries file.repack --db <connection>` - Plan every query against Postgres
+/// - `repack blueprint-check [--no-strict] file.blueprint` - Check a blueprint without a schema
+/// - `repack init [--blueprints id,...] [dir]` - Write a starter schema
+/// - `repack import postgres <connection>` - Print a schema read from a database
+/// - `repack import json file.json [--name Name]` - Print a schema inferred from samples
+/// - `repack import openapi spec.yaml` - Print a schema from OpenAPI component schemas
+///
+/// Any command accepts `--deny-warnings`, which makes warnings fail the run,
+/// `--offline`, which loads remote blueprints only from the cache,
+/// `--stdout`, which prints generated files instead of writing them,
+/// `--allow-outside-root`, which lets blueprints write files outside the
+/// output location, `--strict`, which fails on blueprint tags that would
+/// otherwise be ignored, `--yes`/`--no-exec`, which run or skip `[exec]`
+/// commands without prompting, `--timings[=file.json]`, which reports how
+/// long each phase and output took, `--quiet`/`--verbose`, which print
+/// less or more, `--dry-run`, which lists the files a build or clean would
+/// change without touching them, `--define key=value`, which sets an option
+/// on every output, and `--error-format json`, which prints diagnostics as
+/// JSON lines. A file of `-` reads the schema from stdin. Flags that take a
+/// value may also be written `--flag=value`. Defaults for them are read from
+/// a `repack.toml` or `.repackrc` in the current directory or a parent,
+/// unless `--no-config` is given.
+///
+/// The exit status is 0 on success, 1 for errors in the schema or a
+/// blueprint, 2 if a blueprint is missing, 3 if a file could not be read or
+/// written, 4 if `--check` finds a file out of date, and 5 if the schema file
+/// could not be read.
 fn main() {
-    Console::begin();
     let mut task_index = 1;
     let mut task_count = 1;
-    let args: Vec<String> = std::env::args().collect();
-    if args.len() < 2 {
-        print_usage();
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let mut defaults = GlobalFlags::default();
+    if !args.iter().any(|a| a == "--no-config")
+        && let Some(path) = settings::find()
+        && let Err(e) = settings::load(&path, &mut defaults)
+    {
+        eprintln!("{e}");
+        exit(1);
     }
-
-    let (command, file) = match (args.get(1), args.get(2)) {
-        (Some(file), None) => (Behavior::Build, file),
-        (Some(arg), Some(file)) if arg == "build" => (Behavior::Build, file),
-        (Some(arg), Some(file)) if arg == "clean" => (Behavior::Clean, file),
-        _ => {
+    let Cli { command, flags } = match Cli::parse(&args, defaults) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("{e}\n");
             print_usage();
             return;
         }
     };
+    let GlobalFlags {
+        deny_warnings,
+        offline,
+        stdout,
+        allow_outside_root,
+        strict,
+        exec_policy,
+        timings: timings_target,
+        verbosity,
+        dry_run,
+        prune,
+        defines,
+        error_format,
+        output_overrides,
+    } = flags;
+    let mut timings = Timings::default();
+    Console::init(verbosity);
+    Console::set_error_format(error_format);
 
-    Console::update_ct(task_index, task_count, "Planning...");
+    let (command, file) = match command {
+        Command::Schema { behavior, file } => (behavior, file),
+        Command::Import(source) => {
+            run_import(source);
+            return;
+        }
+        Command::Init { dir, blueprints } => {
+            run_init(dir, blueprints);
+            return;
+        }
+        Command::BlueprintCheck { file, strict } => {
+            run_blueprint_check(&file, strict, deny_warnings);
+            return;
+        }
+        Command::Blueprints => {
+            Console::begin();
+            match BlueprintStore::new() {
+                Ok(store) => list_blueprints(&store),
+                Err(e) => {
+                    Console::report(e);
+                    exit(1);
+                }
+            }
+            return;
+        }
+    };
+    let file = file.as_str();
+    // Inspect and mock write data to stdout, so it must not be mixed with progress output.
+    // So does graph with `--stdout` or a schema read from stdin.
+    let prints_data = matches!(
+        command,
+        Behavior::Inspect
+            | Behavior::Mock { .. }
+            | Behavior::DiffSchema { .. }
+            | Behavior::NextVersion { .. }
+    ) || (matches!(command, Behavior::Graph) && file == "-");
+    if prints_data || stdout || dry_run || matches!(command, Behavior::Diff | Behavior::Check) {
+        Console::set_stdout_is_data();
+    }
+
+    if let Behavior::Format { check } = command {
+        Console::begin();
+        if file.ends_with(".json") {
+            Console::error(&format!(
+                "{file} is schema JSON; only .repack files can be formatted."
+            ));
+            Console::finalize();
+            exit(1);
+        }
+        let Ok(source) = std::fs::read_to_string(file) else {
+            Console::error(&format!("Unable to load requested file '{file}'"));
+            exit(5);
+        };
+        let formatted = format_schema(&source);
+        if formatted == source {
+            Console::update_msg(&format!("{file} is formatted."));
+        } else if check {
+            Console::error(&format!(
+                "{file} is not formatted. Run `repack fmt {file}`."
+            ));
+            Console::finalize();
+            exit(4);
+        } else if let Err(e) = std::fs::write(file, formatted) {
+            Console::error(&format!("Could not write {file}: {e}"));
+            exit(1);
+        } else {
+            Console::update_msg(&format!("Formatted {file}."));
+        }
+        Console::finalize();
+        return;
+    }
 
-    let contents = FileContents::new(file);
-    let parse_result = match ParseResult::from_contents(contents) {
+    // A `.json` file holds a schema in the IR written by `repack inspect`.
+    let parse_start = Instant::now();
+    let parsed = if file == "-" {
+        let mut source = String::new();
+        if let Err(e) = std::io::stdin().read_to_string(&mut source) {
+            Console::error(&format!("Unable to read the schema from stdin: {e}"));
+            exit(5);
+        }
+        let mut contents = FileContents::empty();
+        contents.add_string(&source);
+        ParseResult::read_contents(contents)
+    } else if file.ends_with(".json") {
+        let Ok(source) = std::fs::read_to_string(file) else {
+            Console::error(&format!("Unable to load requested file '{file}'"));
+            exit(5);
+        };
+        schema_from_ir(&source)
+    } else {
+        ParseResult::read_contents(FileContents::new(file))
+    };
+    timings.phase("parse", parse_start);
+    let resolve_start = Instant::now();
+    let resolved = parsed.and_then(ParseResult::resolve);
+    timings.phase("resolve", resolve_start);
+    let mut parse_result = match resolved {
         Ok(res) => res,
         Err(e) => {
             for err in e {
-                Console::error(&err.into_string());
+                Console::report(err);
             }
             exit(1);
         }
     };
+    for output in parse_result.languages.iter_mut() {
+        output.options.extend(defines.iter().cloned());
+        for (_, option, value) in output_overrides.iter().filter(|o| o.0 == output.profile) {
+            match option.as_str() {
+                "location" => output.location = Some(value.to_string()),
+                _ => _ = output.options.insert(option.to_string(), value.to_string()),
+            }
+        }
+    }
+    // Outputs located at "-" print their files, so progress is hidden for them too.
+    if parse_result
+        .languages
+        .iter()
+        .any(|o| o.location.as_deref() == Some("-"))
+    {
+        Console::set_stdout_is_data();
+    }
+    Console::begin();
+    Console::update_ct(task_index, task_count, "Planning...");
+    let mut allowed_lints = parse_result.allowed_lints.clone();
+    if let Behavior::Lint { allow } = &command {
+        allowed_lints.extend(allow);
+    }
+    let mut warnings = std::mem::take(&mut parse_result.warnings);
+    if !allowed_lints.contains(&LintRule::Unused) {
+        warnings.append(&mut unused_definitions(&parse_result));
+    }
+    let warning_count = warnings.len();
+    for warning in warnings {
+        Console::report(warning);
+    }
+    if deny_warnings && warning_count > 0 {
+        Console::error(&format!(
+            "{warning_count} warning(s) treated as errors (--deny-warnings)."
+        ));
+        Console::finalize();
+        exit(1);
+    }
+
+    if let Behavior::Mock {
+        count,
+        format,
+        seed,
+    } = command
+    {
+        print!("{}", mock::mock(&parse_result, count, format, seed));
+        return;
+    }
 
+    if let Behavior::Inspect = command {
+        print!("{}", schema_to_ir(&parse_result).to_pretty_string());
+        return;
+    }
+
+    if let Behavior::Snapshot { out } = &command {
+        let path = match out {
+            Some(out) => PathBuf::from(out),
+            None => PathBuf::from(&file).with_extension("snapshot.json"),
+        };
+        let snapshot = schema_to_ir(&parse_result).to_pretty_string();
+        if let Err(e) = std::fs::write(&path, snapshot) {
+            Console::error(&format!("Could not write {}: {}", path.display(), e));
+            exit(1);
+        }
+        Console::update_ct(task_index, task_count, "⚡️ Completed");
+        Console::update_msg(&format!("Snapshot written to {}.", path.display()));
+        Console::finalize();
+        return;
+    }
+
+    if let Behavior::DiffSchema { old } = &command {
+        let changes = schema_changes(&read_schema(old), &parse_result);
+        for change in &changes {
+            println!("{change}");
+        }
+        Console::update_msg(&format!("{} schema change(s) since {old}.", changes.len()));
+        Console::finalize();
+        return;
+    }
+
+    if let Behavior::CheckCompat { against, policy } = &command {
+        let (mut rejected, mut allowed) = (0, 0);
+        for change in schema_changes(&read_schema(against), &parse_result) {
+            let impact = change.impact();
+            if impact == ChangeImpact::Compatible {
+                continue;
+            }
+            let error = RepackError::global(
+                RepackErrorKind::IncompatibleChange,
+                format!("{change} ({impact})"),
+            );
+            if policy.allows(impact) {
+                allowed += 1;
+                Console::report(error.warning());
+            } else {
+                rejected += 1;
+                Console::report(error);
+            }
+        }
+        if rejected > 0 {
+            Console::update_msg(&format!(
+                "{rejected} incompatible change(s) since {against}."
+            ));
+        } else {
+            Console::update_msg(&format!("Schema is compatible with {against}."));
+        }
+        Console::finalize();
+        if rejected > 0 || (deny_warnings && allowed > 0) {
+            exit(1);
+        }
+        return;
+    }
+
+    if let Behavior::NextVersion { against, write } = &command {
+        let old = read_schema(against);
+        let changes = schema_changes(&old, &parse_result);
+        let bump = changes
+            .iter()
+            .map(|change| change.version_bump())
+            .max()
+            .unwrap_or(VersionBump::Patch);
+        // The snapshot holds the released version, which the schema may
+        // already have moved past.
+        let version = |result: &ParseResult| {
+            result
+                .meta
+                .iter()
+                .find(|(key, _)| key == "version")
+                .map(|(_, value)| value.clone())
+        };
+        let current = version(&old)
+            .or_else(|| version(&parse_result))
+            .unwrap_or_else(|| "0.0.0".to_string());
+        let Some(next) = next_version(&current, bump) else {
+            Console::error(&format!("Version '{current}' is not MAJOR.MINOR.PATCH."));
+            exit(1);
+        };
+        for change in &changes {
+            Console::detail(&format!("{}: {change}", change.version_bump()));
+        }
+        println!("{next}");
+        Console::update_msg(&format!(
+            "{} schema change(s) since {against}: {bump} release, {current} -> {next}.",
+            changes.len()
+        ));
+        if *write {
+            if file == "-" || file.ends_with(".json") {
+                Console::error("--write needs a .repack file to update.");
+                exit(1);
+            }
+            let updated = std::fs::read_to_string(file)
+                .ok()
+                .and_then(|source| set_schema_meta(&source, "version", &next));
+            let Some(updated) = updated else {
+                Console::error(&format!("Could not find the schema block in {file}."));
+                exit(1);
+            };
+            if let Err(e) = std::fs::write(file, updated) {
+                Console::error(&format!("Could not write {file}: {e}"));
+                exit(1);
+            }
+            Console::update_msg(&format!("Set version {next} in {file}."));
+        }
+        Console::finalize();
+        return;
+    }
+
+    if let Behavior::Graph = command {
+        if stdout || file == "-" {
+            print!("{}", dependency_graph(&parse_result));
+            return;
+        }
+        let path = PathBuf::from(&file).with_extension("dot");
+        if let Err(e) = std::fs::write(&path, dependency_graph(&parse_result)) {
+            Console::error(&format!("Could not write {}: {}", path.display(), e));
+            exit(1);
+        }
+        Console::update_ct(task_index, task_count, "⚡️ Completed");
+        Console::update_msg(&format!("Graph written to {}.", path.display()));
+        Console::finalize();
+        return;
+    }
+
+    if let Behavior::Lint { .. } = command {
+        let findings = lint(&parse_result, &allowed_lints);
+        let count = findings.len();
+        for finding in findings {
+            Console::report(finding);
+        }
+        Console::update_ct(task_index, task_count, "⚡️ Completed");
+        Console::update_msg(&format!("{count} lint finding(s)."));
+        Console::finalize();
+        if deny_warnings && count > 0 {
+            exit(1);
+        }
+        return;
+    }
+
+    let blueprints_start = Instant::now();
     let mut store = match BlueprintStore::new() {
         Ok(res) => res,
         Err(e) => {
-            println!("{}", e.into_string());
+            Console::report(e);
             exit(1);
         }
     };
     for add in &parse_result.include_blueprints {
-        let mut path = PathBuf::from(&file);
-        path.pop();
-        path.push(add);
-        if store.load_file(&path).is_err() {
-            let path_str = path.to_str().unwrap_or("<invalid path>");
-            Console::error(
-                &RepackError::global(RepackErrorKind::CannotRead, path_str.to_string())
-                    .into_string(),
+        let loaded = if add.starts_with("http://") || add.starts_with("https://") {
+            store.load_remote(add, offline)
+        } else {
+            let mut path = PathBuf::from(&file);
+            path.pop();
+            path.push(add);
+            store.load_file(&path)
+        };
+        if let Err(e) = loaded {
+            // A blueprint that can't be found or downloaded is missing; one that
+            // fails to parse has errors like any other input.
+            let missing = matches!(
+                e.error,
+                RepackErrorKind::CannotRead
+                    | RepackErrorKind::BlueprintNotCached
+                    | RepackErrorKind::ProcessExecutionFailed
             );
-            exit(1);
+            Console::report(e);
+            exit(if missing { 2 } else { 1 });
+        }
+    }
+
+    timings.phase("blueprints", blueprints_start);
+
+    if let Behavior::Blueprints = command {
+        list_blueprints(&store);
+        return;
+    }
+
+    if let Behavior::VerifyQueries { connection } = &command {
+        let Some(postgres) = store.blueprint("postgres") else {
+            Console::error("[postgres] Could not find this blueprint.");
+            exit(2)
+        };
+        Console::update_ct(task_index, task_count, "Verifying queries...");
+        match verify::verify_queries(&parse_result, postgres, connection) {
+            Ok((checked, failures)) => {
+                let failed = failures.len();
+                for failure in failures {
+                    Console::report(failure);
+                }
+                Console::update_msg(&format!("{checked} queries checked, {failed} failed."));
+                Console::finalize();
+                if failed > 0 {
+                    exit(1);
+                }
+            }
+            Err(e) => {
+                Console::report(e);
+                Console::finalize();
+                exit(1);
+            }
         }
+        return;
     }
 
     let outputs = parse_result
         .languages
         .iter()
-        .map(|lng| {
+        .filter_map(|lng| {
             let Some(bp) = store.blueprint(&lng.profile) else {
                 Console::error(&format!(
                     "[{}] Could not find this blueprint. Have you imported it?",
@@ -141,13 +692,40 @@ fn main() {
                 ));
                 exit(2)
             };
-            match command {
-                Behavior::Build => ("Building", lng, bp),
-                Behavior::Clean => ("Cleaning", lng, bp),
+            match (&command, &bp.kind) {
+                (Behavior::Build, BlueprintKind::Build) => Some(("Building", lng, bp)),
+                (Behavior::Configure, BlueprintKind::Configure) => Some(("Configuring", lng, bp)),
+                (Behavior::Document, BlueprintKind::Document) => Some(("Documenting", lng, bp)),
+                (Behavior::Build, BlueprintKind::Document | BlueprintKind::Configure) => {
+                    let render = match bp.kind {
+                        BlueprintKind::Configure => "configure",
+                        _ => "document",
+                    };
+                    Console::report(
+                        RepackError::from_lang_with_msg(
+                            RepackErrorKind::OutputNotRendered,
+                            lng,
+                            format!("run `repack {render} {file}` to render it"),
+                        )
+                        .warning(),
+                    );
+                    None
+                }
+                (Behavior::Clean, _) => Some(("Cleaning", lng, bp)),
+                (Behavior::Diff, _) => Some(("Comparing", lng, bp)),
+                (Behavior::Check, BlueprintKind::Build) => Some(("Checking", lng, bp)),
+                _ => None,
             }
         })
         .collect::<Vec<_>>();
     task_count += outputs.len();
+    let output_count = outputs.len();
+    let mut changed = 0;
+    let mut pruned = 0;
+    let mut generated = HashSet::new();
+    let mut ungenerated = Vec::new();
+    let mut failed = 0;
+    let mut status = 0;
 
     for (task_string, output, bp) in outputs {
         task_index += 1;
@@ -157,22 +735,156 @@ fn main() {
             &format!("{} {}...", task_string, bp.name),
         );
         let mut builder = BlueprintRenderer::new(&parse_result, bp, output);
+        builder.stdout = stdout || output.location.as_deref() == Some("-");
+        builder.allow_outside_root = allow_outside_root;
+        builder.exec_policy = exec_policy;
+        builder.strict = strict;
+        if dry_run || matches!(command, Behavior::Diff | Behavior::Check) {
+            builder.diff = true;
+        }
+        let result = match command {
+            Behavior::Build
+            | Behavior::Configure
+            | Behavior::Document
+            | Behavior::Diff
+            | Behavior::Check => builder.build(None),
+            Behavior::Clean => builder.clean(),
+            Behavior::Graph
+            | Behavior::Format { .. }
+            | Behavior::Lint { .. }
+            | Behavior::Blueprints
+            | Behavior::Inspect
+            | Behavior::Snapshot { .. }
+            | Behavior::DiffSchema { .. }
+            | Behavior::CheckCompat { .. }
+            | Behavior::NextVersion { .. }
+            | Behavior::Mock { .. }
+            | Behavior::VerifyQueries { .. } => Ok(()),
+        };
+        if let Err(e) = result {
+            failed += 1;
+            if status == 0 {
+                status = exit_code(&e);
+            }
+            Console::report(e);
+        }
+        for (path, diff) in std::mem::take(&mut builder.changed) {
+            changed += 1;
+            match command {
+                Behavior::Check => Console::error(&format!("{} is out of date.", path.display())),
+                Behavior::Clean if dry_run => println!("Would remove {}", path.display()),
+                _ if dry_run => println!("Would write {}", path.display()),
+                _ => print!("{diff}"),
+            }
+        }
+        generated.extend(std::mem::take(&mut builder.generated));
+        ungenerated.extend(
+            std::mem::take(&mut builder.ungenerated)
+                .into_iter()
+                .map(|path| (output, path)),
+        );
+        timings.output(&output.profile, builder.stats);
+    }
+    // Outputs sharing a location see each other's files, so a file is only
+    // left over if no output generates it.
+    ungenerated.retain(|(_, path)| !generated.contains(path));
+    ungenerated.sort_by(|a, b| a.1.cmp(&b.1));
+    ungenerated.dedup_by(|a, b| a.1 == b.1);
+    for (output, path) in ungenerated {
         match command {
-            Behavior::Build => match builder.build(None) {
-                Ok(_) => {}
-                Err(e) => {
-                    Console::error(&e.into_string());
+            Behavior::Check if prune => {
+                changed += 1;
+                Console::error(&format!("{} is no longer generated.", path.display()));
+            }
+            Behavior::Diff if prune && !dry_run => {
+                changed += 1;
+                Console::error(&format!("{} would be removed.", path.display()));
+            }
+            _ if prune && dry_run => {
+                changed += 1;
+                println!("Would remove {}", path.display());
+            }
+            _ if prune => {
+                if std::fs::remove_file(&path).is_err() {
+                    status = 1;
+                    Console::report(RepackError::from_lang_with_msg(
+                        RepackErrorKind::CannotWrite,
+                        output,
+                        path.display().to_string(),
+                    ));
+                    continue;
                 }
-            },
-            Behavior::Clean => match builder.clean() {
-                Ok(_) => {}
-                Err(e) => {
-                    Console::error(&e.into_string());
+                Console::detail(&format!("Removed {}", path.display()));
+                // Will not delete if dir is not empty.
+                if let Some(dir) = path.parent() {
+                    _ = std::fs::remove_dir(dir);
                 }
-            },
+                pruned += 1;
+            }
+            Behavior::Build | Behavior::Configure | Behavior::Document | Behavior::Clean
+                if !dry_run =>
+            {
+                Console::report(
+                    RepackError::from_lang_with_msg(
+                        RepackErrorKind::UngeneratedFile,
+                        output,
+                        path.display().to_string(),
+                    )
+                    .warning(),
+                );
+            }
+            _ => {}
         }
     }
     Console::update_ct(task_index, task_count, "⚡️ Completed");
-    Console::update_msg("Project built.");
+    if failed > 0 {
+        Console::update_msg(&format!("{failed} of {output_count} output(s) failed."));
+    } else if dry_run || matches!(command, Behavior::Diff) {
+        Console::update_msg(&format!("{changed} file(s) would change."));
+    } else if let Behavior::Check = command {
+        if changed > 0 {
+            status = 4;
+            let flag = if prune { "--prune " } else { "" };
+            Console::update_msg(&format!(
+                "{changed} file(s) out of date. Run `repack build {flag}{file}`."
+            ));
+        } else {
+            Console::update_msg("Generated files are up to date.");
+        }
+    } else if pruned > 0 {
+        Console::update_msg(&format!(
+            "Project built. Removed {pruned} file(s) no longer generated."
+        ));
+    } else {
+        Console::update_msg("Project built.");
+    }
     Console::finalize();
+
+    match timings_target {
+        Some(Some(path)) => {
+            if let Err(e) = std::fs::write(&path, timings.to_json().to_pretty_string()) {
+                Console::error(&format!("Could not write {path}: {e}"));
+                exit(1);
+            }
+        }
+        Some(None) => eprint!("{}", timings.to_table()),
+        None => {}
+    }
+    if status != 0 {
+        exit(status);
+    }
+}
+
+/// The exit status for an error that stopped an output: 3 if a file could not
+/// be read, written, or removed, and 1 for errors in the schema or blueprint.
+fn exit_code(e: &RepackError) -> i32 {
+    match e.error {
+        RepackErrorKind::CannotRead
+        | RepackErrorKind::CannotWrite
+        | RepackErrorKind::PathNotValid
+        | RepackErrorKind::OutputOutsideRoot
+        | RepackErrorKind::MissingMarkers
+        | RepackErrorKind::KeptCodeRemoved => 3,
+        _ => 1,
+    }
 }
@@ -0,0 +1,624 @@
+use crate::syntax::{
+    CoreType, CustomFieldType, Field, FieldType, Json, ParseResult, RepackStruct, quote_ident,
+};
+
+/// What `repack mock` writes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MockFormat {
+    /// One JSON object with an array of records per struct
+    Json,
+    /// `INSERT` statements for each struct stored in its own table
+    Sql,
+}
+impl MockFormat {
+    pub fn from_string(val: &str) -> Option<MockFormat> {
+        match val {
+            "json" => Some(MockFormat::Json),
+            "sql" => Some(MockFormat::Sql),
+            _ => None,
+        }
+    }
+}
+
+/// Optional fields are left null one time in this many.
+const NULL_ONE_IN: u64 = 5;
+
+/// The most items generated for an array field.
+const MAX_ARRAY_LEN: u64 = 3;
+
+/// Generated datetimes fall in the year after this one, given in days since
+/// 1970-01-01 (2024-01-01).
+const EPOCH_DAYS: i64 = 19723;
+
+const FIRST_NAMES: &[&str] = &[
+    "Ada",
+    "Alan",
+    "Grace",
+    "Linus",
+    "Margaret",
+    "Dennis",
+    "Barbara",
+    "Ken",
+    "Radia",
+    "Edsger",
+    "Frances",
+    "Donald",
+    "Hedy",
+    "Tim",
+    "Katherine",
+    "John",
+];
+const LAST_NAMES: &[&str] = &[
+    "Lovelace",
+    "Turing",
+    "Hopper",
+    "Torvalds",
+    "Hamilton",
+    "Ritchie",
+    "Liskov",
+    "Thompson",
+    "Perlman",
+    "Dijkstra",
+    "Allen",
+    "Knuth",
+    "Lamarr",
+    "Berners-Lee",
+    "Johnson",
+    "McCarthy",
+];
+const WORDS: &[&str] = &[
+    "amber", "bright", "cedar", "delta", "ember", "falcon", "granite", "harbor", "island",
+    "juniper", "kestrel", "lumen", "meadow", "nimbus", "orchid", "prairie", "quartz", "river",
+    "summit", "timber", "umber", "valley", "willow", "zephyr",
+];
+const CITIES: &[&str] = &[
+    "Lisbon",
+    "Osaka",
+    "Toronto",
+    "Nairobi",
+    "Oslo",
+    "Austin",
+    "Melbourne",
+    "Bogotá",
+];
+const COUNTRIES: &[&str] = &[
+    "Portugal",
+    "Japan",
+    "Canada",
+    "Kenya",
+    "Norway",
+    "United States",
+    "Australia",
+    "Colombia",
+];
+const STREETS: &[&str] = &[
+    "Main St",
+    "Oak Ave",
+    "Harbor Rd",
+    "Hill St",
+    "Park Ln",
+    "Elm St",
+];
+const COLORS: &[&str] = &["red", "green", "blue", "teal", "orange", "purple", "gray"];
+
+/// Generates fake records for every struct in the schema.
+///
+/// Values follow each field's type, enum cases, and `validate:` rules, and
+/// text is chosen from the field's name where it suggests one (`email`,
+/// `name`, `city`, and so on). Optional fields are sometimes null, and array
+/// fields hold up to three items. `db:pk` and `db:unique` fields get distinct
+/// values, and `db:fk` fields take a value from a record already generated
+/// for the referenced table; structs are resolved in dependency order, so the
+/// SQL inserts referenced rows first.
+///
+/// The same seed always produces the same data.
+///
+/// # Arguments
+/// * `result` - The resolved schema
+/// * `count` - The number of records per struct
+/// * `format` - JSON with an array per struct, or SQL inserts per table
+/// * `seed` - Seeds the random choices
+pub fn mock(result: &ParseResult, count: usize, format: MockFormat, seed: u64) -> String {
+    let mut mocker = Mocker {
+        result,
+        rng: Rng(seed),
+        records: Vec::new(),
+    };
+    for strct in &result.strcts {
+        let mut records = Vec::with_capacity(count);
+        for idx in 0..count {
+            let record = mocker.record(strct, idx, &records);
+            records.push(record);
+        }
+        mocker.records.push((strct, records));
+    }
+    match format {
+        MockFormat::Json => mocker.to_json().to_pretty_string(),
+        MockFormat::Sql => mocker.to_sql(),
+    }
+}
+
+/// A generated record: each field with its value.
+type Record<'a> = Vec<(&'a Field, Json)>;
+
+struct Mocker<'a> {
+    result: &'a ParseResult,
+    rng: Rng,
+    /// The records generated so far, which foreign keys refer to
+    records: Vec<(&'a RepackStruct, Vec<Record<'a>>)>,
+}
+impl<'a> Mocker<'a> {
+    /// Generates the `idx`th record of a struct, after those in `done`.
+    fn record(&mut self, strct: &'a RepackStruct, idx: usize, done: &[Record<'a>]) -> Record<'a> {
+        strct
+            .fields
+            .iter()
+            .map(|field| (field, self.field_value(field, idx, done)))
+            .collect()
+    }
+
+    fn field_value(&mut self, field: &Field, idx: usize, done: &[Record<'a>]) -> Json {
+        let unique =
+            field.function("db", "pk").is_some() || field.function("db", "unique").is_some();
+        if let Some(fk) = field.function("db", "fk")
+            && let [table, column, ..] = fk.args.as_slice()
+        {
+            let values = self.referenced(table, column);
+            if !values.is_empty() {
+                let pos = match unique {
+                    true => idx % values.len(),
+                    false => self.rng.below(values.len() as u64) as usize,
+                };
+                let value = values[pos].clone();
+                return if field.array {
+                    Json::Array(vec![value])
+                } else {
+                    value
+                };
+            }
+        }
+        if field.optional && !unique && self.rng.below(NULL_ONE_IN) == 0 {
+            return Json::Null;
+        }
+        if field.array {
+            let len = self.rng.below(MAX_ARRAY_LEN + 1);
+            return Json::Array((0..len).map(|_| self.value(field, idx, false)).collect());
+        }
+        let value = self.value(field, idx, unique);
+        let taken = |value: &Json| {
+            done.iter().any(|record| {
+                record
+                    .iter()
+                    .any(|(f, v)| f.name == field.name && v == value)
+            })
+        };
+        match value {
+            Json::String(text) if unique && taken(&Json::String(text.clone())) => {
+                Json::String(format!("{text}-{idx}"))
+            }
+            value => value,
+        }
+    }
+
+    /// The non-null values of a column in the records generated for a table,
+    /// named by its table or struct name.
+    fn referenced(&self, table: &str, column: &str) -> Vec<Json> {
+        self.records
+            .iter()
+            .filter(|(strct, _)| {
+                strct.inherits.is_none()
+                    && (strct.table_name.as_deref() == Some(table) || strct.name == table)
+            })
+            .flat_map(|(_, records)| records)
+            .filter_map(|record| {
+                record
+                    .iter()
+                    .find(|(field, _)| field.column_name() == column)
+                    .map(|(_, value)| value.clone())
+            })
+            .filter(|value| *value != Json::Null)
+            .collect()
+    }
+
+    /// A single value of the field's type, ignoring `optional` and `array`.
+    fn value(&mut self, field: &Field, idx: usize, unique: bool) -> Json {
+        match &field.field_type {
+            Some(FieldType::Core(core)) => self.core(core, field, idx, unique),
+            Some(FieldType::Custom(name, CustomFieldType::Enum)) => {
+                let cases = self
+                    .result
+                    .enums
+                    .iter()
+                    .find(|enm| enm.name == *name)
+                    .map(|enm| enm.options.as_slice())
+                    .unwrap_or_default();
+                if cases.is_empty() {
+                    return Json::Null;
+                }
+                let case = &cases[self.rng.below(cases.len() as u64) as usize];
+                Json::str(case.value.as_deref().unwrap_or(&case.name))
+            }
+            Some(FieldType::Custom(name, CustomFieldType::Object)) => self.object(name, None),
+            Some(FieldType::Custom(name, CustomFieldType::Union)) => {
+                let variants = self
+                    .result
+                    .unions
+                    .iter()
+                    .find(|union| union.name == *name)
+                    .map(|union| union.variants.as_slice())
+                    .unwrap_or_default();
+                if variants.is_empty() {
+                    return Json::Null;
+                }
+                let variant = &variants[self.rng.below(variants.len() as u64) as usize];
+                self.object(variant, Some(variant))
+            }
+            None => Json::Null,
+        }
+    }
+
+    /// A nested struct as an object, with a `type` key first for union variants.
+    fn object(&mut self, name: &str, tag: Option<&str>) -> Json {
+        let Some(strct) = self.result.strcts.iter().find(|s| s.name == name) else {
+            return Json::Null;
+        };
+        let mut pairs = Vec::new();
+        if let Some(tag) = tag {
+            pairs.push(("type".to_string(), Json::str(tag)));
+        }
+        for (field, value) in self.record(strct, 0, &[]) {
+            pairs.push((field.name.to_string(), value));
+        }
+        Json::Object(pairs)
+    }
+
+    fn core(&mut self, core: &CoreType, field: &Field, idx: usize, unique: bool) -> Json {
+        let name = field.name.to_lowercase();
+        let (min, max) = bounds(field);
+        match core {
+            CoreType::String => Json::String(self.text(field, &name, idx)),
+            CoreType::Int64
+            | CoreType::Int32
+            | CoreType::Int16
+            | CoreType::Uint64
+            | CoreType::Uint32 => {
+                let (low, high) = match name.as_str() {
+                    n if n.contains("age") => (18, 90),
+                    n if n.contains("year") => (1990, 2024),
+                    n if n.contains("rating") || n.contains("stars") => (1, 5),
+                    n if n.contains("count") || n.contains("quantity") || n.contains("qty") => {
+                        (0, 100)
+                    }
+                    _ if matches!(core, CoreType::Int16) => (1, 100),
+                    _ => (1, 1000),
+                };
+                let low = min.map(|m| m.ceil() as i64).unwrap_or(low);
+                let high = max.map(|m| m.floor() as i64).unwrap_or(high.max(low));
+                let value = match unique {
+                    true => low.saturating_add(idx as i64),
+                    false => self.rng.between(low, high),
+                };
+                Json::Number(value as f64)
+            }
+            CoreType::Float64 => {
+                let (low, high) = match name.as_str() {
+                    n if n.starts_with("lat") => (-90.0, 90.0),
+                    n if n.starts_with("lon") || n.starts_with("lng") => (-180.0, 180.0),
+                    _ => (0.0, 1000.0),
+                };
+                let low = min.unwrap_or(low);
+                let high = max.unwrap_or(f64::max(high, low));
+                let fraction = self.rng.below(10_001) as f64 / 10_000.0;
+                Json::Number(((low + (high - low) * fraction) * 100.0).round() / 100.0)
+            }
+            CoreType::Boolean => Json::Bool(self.rng.below(2) == 0),
+            CoreType::DateTime => Json::String(self.datetime()),
+            CoreType::Uuid => Json::String(self.uuid()),
+            CoreType::Bytes => Json::String(format!("{:016x}", self.rng.next())),
+        }
+    }
+
+    /// Text for a string field, suggested by its name and checked against
+    /// its length rules.
+    fn text(&mut self, field: &Field, name: &str, idx: usize) -> String {
+        let first = self.rng.pick(FIRST_NAMES);
+        let last = self.rng.pick(LAST_NAMES);
+        let word = self.rng.pick(WORDS);
+        let handle = format!("{}.{}{idx}", first, last.replace('-', "")).to_lowercase();
+        let mut text = if field.function("validate", "email").is_some() || name.contains("email") {
+            format!("{handle}@example.com")
+        } else if field.function("validate", "url").is_some()
+            || name.contains("url")
+            || name.contains("website")
+        {
+            format!("https://example.com/{word}/{idx}")
+        } else if name.contains("first") || name.contains("given") {
+            first.to_string()
+        } else if name.contains("last") || name.contains("surname") || name.contains("family") {
+            last.to_string()
+        } else if name.contains("username") || name.contains("login") || name.contains("handle") {
+            handle
+        } else if name.contains("name") {
+            format!("{first} {last}")
+        } else if name.contains("phone") {
+            format!("+1-555-01{:02}", self.rng.below(100))
+        } else if name.contains("city") {
+            self.rng.pick(CITIES).to_string()
+        } else if name.contains("country") {
+            self.rng.pick(COUNTRIES).to_string()
+        } else if name.contains("address") || name.contains("street") {
+            format!("{} {}", self.rng.between(1, 9999), self.rng.pick(STREETS))
+        } else if name.contains("zip") || name.contains("postal") {
+            format!("{:05}", self.rng.below(100_000))
+        } else if name.contains("color") || name.contains("colour") {
+            self.rng.pick(COLORS).to_string()
+        } else if name.contains("password") || name.contains("hash") || name.contains("token") {
+            format!("{:016x}{:016x}", self.rng.next(), self.rng.next())
+        } else if name.contains("slug") {
+            format!("{word}-{}", self.rng.pick(WORDS))
+        } else if [
+            "description",
+            "body",
+            "bio",
+            "content",
+            "summary",
+            "comment",
+            "note",
+        ]
+        .iter()
+        .any(|n| name.contains(n))
+        {
+            self.sentence(8)
+        } else {
+            let len = self.rng.between(1, 3) as usize;
+            self.words(len)
+        };
+        let (min_len, max_len) = lengths(field);
+        while text.chars().count() < min_len.unwrap_or(0) {
+            text.push(' ');
+            text.push_str(self.rng.pick(WORDS));
+        }
+        if let Some(max_len) = max_len {
+            text = text
+                .chars()
+                .take(max_len)
+                .collect::<String>()
+                .trim_end()
+                .to_string();
+        }
+        text
+    }
+
+    fn words(&mut self, count: usize) -> String {
+        (0..count)
+            .map(|_| self.rng.pick(WORDS))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn sentence(&mut self, count: usize) -> String {
+        let words = self.words(count);
+        let mut chars = words.chars();
+        match chars.next() {
+            Some(c) => format!("{}{}.", c.to_uppercase(), chars.as_str()),
+            None => words,
+        }
+    }
+
+    /// A UTC time within a year of [`EPOCH_DAYS`], as `2024-03-09T14:05:00Z`.
+    fn datetime(&mut self) -> String {
+        let secs = self.rng.below(365 * 86_400) as i64;
+        let (year, month, day) = civil_date(EPOCH_DAYS + secs / 86_400);
+        let time = secs % 86_400;
+        format!(
+            "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+            time / 3600,
+            time % 3600 / 60,
+            time % 60
+        )
+    }
+
+    /// A random (version 4) UUID.
+    fn uuid(&mut self) -> String {
+        let high = (self.rng.next() & !0xf000) | 0x4000;
+        let low = (self.rng.next() & !(0xc << 60)) | (0x8 << 60);
+        format!(
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            high >> 32,
+            (high >> 16) & 0xffff,
+            high & 0xffff,
+            low >> 48,
+            low & 0xffff_ffff_ffff
+        )
+    }
+
+    fn to_json(&self) -> Json {
+        Json::Object(
+            self.records
+                .iter()
+                .map(|(strct, records)| {
+                    let records = records
+                        .iter()
+                        .map(|record| {
+                            Json::Object(
+                                record
+                                    .iter()
+                                    .map(|(field, value)| (field.name.to_string(), value.clone()))
+                                    .collect(),
+                            )
+                        })
+                        .collect();
+                    (strct.name.to_string(), Json::Array(records))
+                })
+                .collect(),
+        )
+    }
+
+    /// `INSERT` statements for each struct that owns its table, in one
+    /// transaction. Tables are qualified with the `schema` option of a
+    /// `postgres` output, if there is one. Columns that are computed
+    /// (`db:as`, `db:generated`, `db:identity`) or read from another table
+    /// are left to the database.
+    fn to_sql(&self) -> String {
+        let schema = self
+            .result
+            .languages
+            .iter()
+            .find(|output| output.profile == "postgres")
+            .and_then(|output| output.options.get("schema"));
+        let mut sql = String::from("BEGIN;\n");
+        for (strct, records) in &self.records {
+            let Some(table) = strct.table_name.as_deref() else {
+                continue;
+            };
+            if strct.inherits.is_some() || records.is_empty() {
+                continue;
+            }
+            let columns = strct
+                .fields
+                .iter()
+                .enumerate()
+                .filter(|(_, field)| {
+                    field.field_location.is_none()
+                        && ["as", "generated", "identity"]
+                            .iter()
+                            .all(|name| field.function("db", name).is_none())
+                })
+                .map(|(idx, _)| idx)
+                .collect::<Vec<_>>();
+            if columns.is_empty() {
+                continue;
+            }
+            let table = match schema {
+                Some(schema) => format!("{}.{}", quote_ident(schema), quote_ident(table)),
+                None => quote_ident(table),
+            };
+            let names = columns
+                .iter()
+                .map(|idx| quote_ident(strct.fields[*idx].column_name()))
+                .collect::<Vec<_>>();
+            sql.push_str(&format!(
+                "\nINSERT INTO {table} ({}) VALUES",
+                names.join(", ")
+            ));
+            for (pos, record) in records.iter().enumerate() {
+                let values = columns
+                    .iter()
+                    .map(|idx| sql_literal(record[*idx].0, &record[*idx].1))
+                    .collect::<Vec<_>>();
+                let end = if pos + 1 == records.len() { ";" } else { "," };
+                sql.push_str(&format!("\n\t({}){end}", values.join(", ")));
+            }
+            sql.push('\n');
+        }
+        sql.push_str("\nCOMMIT;\n");
+        sql
+    }
+}
+
+/// The bounds set by `validate:range`, `validate:min`, and `validate:max`.
+fn bounds(field: &Field) -> (Option<f64>, Option<f64>) {
+    let arg = |name: &str, pos: usize| {
+        field
+            .function("validate", name)
+            .and_then(|f| f.args.get(pos))
+            .and_then(|a| a.parse::<f64>().ok())
+    };
+    (
+        arg("min", 0).or_else(|| arg("range", 0)),
+        arg("max", 0).or_else(|| arg("range", 1)),
+    )
+}
+
+/// The lengths set by `validate:min_len`, `validate:not_empty`, and `validate:max_len`.
+fn lengths(field: &Field) -> (Option<usize>, Option<usize>) {
+    let arg = |name: &str| {
+        field
+            .function("validate", name)
+            .and_then(|f| f.args.first())
+            .and_then(|a| a.parse::<usize>().ok())
+    };
+    let min = arg("min_len").or(field.function("validate", "not_empty").map(|_| 1));
+    (min, arg("max_len"))
+}
+
+/// Writes a generated value as an SQL literal for the field's column.
+fn sql_literal(field: &Field, value: &Json) -> String {
+    let quoted = |text: &str| format!("'{}'", text.replace('\'', "''"));
+    match value {
+        Json::Null => "NULL".to_string(),
+        // Written as an array literal, which Postgres converts to the column's
+        // element type, including enums.
+        Json::Array(items) if field.array => {
+            let items = items
+                .iter()
+                .map(|item| match item {
+                    Json::Null => "NULL".to_string(),
+                    Json::String(text) => array_element(text),
+                    Json::Object(_) => array_element(&item.to_compact_string()),
+                    item => item.to_compact_string(),
+                })
+                .collect::<Vec<_>>();
+            quoted(&format!("{{{}}}", items.join(",")))
+        }
+        Json::Bool(b) => if *b { "TRUE" } else { "FALSE" }.to_string(),
+        Json::Number(n) => n.to_string(),
+        Json::String(text)
+            if matches!(field.field_type, Some(FieldType::Core(CoreType::Bytes))) =>
+        {
+            format!("'\\x{text}'")
+        }
+        Json::String(text) => quoted(text),
+        Json::Array(_) | Json::Object(_) => quoted(&value.to_compact_string()),
+    }
+}
+
+/// Double-quotes an element of a Postgres array literal.
+fn array_element(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Converts days since 1970-01-01 to a `(year, month, day)` date.
+fn civil_date(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// A small deterministic generator (splitmix64), so a seed always produces
+/// the same data.
+struct Rng(u64);
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`, or 0 if `n` is 0.
+    fn below(&mut self, n: u64) -> u64 {
+        if n == 0 { 0 } else { self.next() % n }
+    }
+
+    /// A number in `low..=high`, or `low` if the range is empty.
+    fn between(&mut self, low: i64, high: i64) -> i64 {
+        if high <= low {
+            return low;
+        }
+        let span = high.abs_diff(low).saturating_add(1);
+        low.wrapping_add(self.below(span) as i64)
+    }
+
+    fn pick(&mut self, items: &[&'static str]) -> &'static str {
+        items[self.below(items.len() as u64) as usize]
+    }
+}
@@ -0,0 +1,296 @@
+use std::path::{Path, PathBuf};
+
+use crate::blueprint::ExecPolicy;
+use crate::cli::GlobalFlags;
+use crate::console::{ErrorFormat, Verbosity};
+
+/// The names a project settings file may have, checked in this order.
+pub const SETTINGS_FILES: [&str; 2] = ["repack.toml", ".repackrc"];
+
+/// Finds the project settings file in the current directory, or in the
+/// nearest parent directory that has one.
+pub fn find() -> Option<PathBuf> {
+    let mut dir = std::env::current_dir().ok()?;
+    loop {
+        if let Some(file) = SETTINGS_FILES
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|file| file.is_file())
+        {
+            return Some(file);
+        }
+        if !dir.pop() {
+            return None;
+        }
+    }
+}
+
+/// Reads default flags from a settings file, written in a small subset of
+/// TOML:
+///
+/// ```toml
+/// error_format = "json"
+/// exec_policy = "skip"
+/// deny_warnings = true
+///
+/// [define]
+/// package = "models"
+///
+/// [output.go]
+/// location = "internal/models"
+/// ```
+///
+/// Top-level keys name global flags. `[define]` sets options on every output
+/// like `--define`, and `[output.<blueprint>]` sets options (or `location`)
+/// for the outputs using that blueprint. Values are quoted strings or
+/// booleans, and `#` starts a comment.
+///
+/// The flags are filled in before the command line is parsed, so command-line
+/// flags win.
+///
+/// # Returns
+/// * `Ok(())` once every setting is applied
+/// * `Err(String)` naming the line of the first setting that can't be used
+pub fn load(path: &Path, flags: &mut GlobalFlags) -> Result<(), String> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| format!("Unable to read {}: {e}", path.display()))?;
+    let mut section = String::new();
+    for (idx, line) in source.lines().enumerate() {
+        let err = |msg: String| format!("{}:{}: {msg}", path.display(), idx + 1);
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = name.trim().to_string();
+            if section != "define" && !section.starts_with("output.") {
+                return Err(err(format!("unknown section [{section}]")));
+            }
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(err(format!("expected key = value, found {line}")));
+        };
+        let (key, value) = (key.trim(), value.trim());
+        let value = Value::parse(value)
+            .ok_or_else(|| err(format!("{value} is not a quoted string or boolean")))?;
+        if section == "define" {
+            flags.defines.push((key.to_string(), value.text()));
+        } else if let Some(profile) = section.strip_prefix("output.") {
+            flags
+                .output_overrides
+                .push((profile.to_string(), key.to_string(), value.text()));
+        } else {
+            apply(flags, key, value).map_err(err)?;
+        }
+    }
+    Ok(())
+}
+
+/// Applies a top-level setting to the flags.
+fn apply(flags: &mut GlobalFlags, key: &str, value: Value) -> Result<(), String> {
+    let invalid = |expected: &str| format!("{key} must be {expected}");
+    match (key, value) {
+        ("deny_warnings", Value::Bool(b)) => flags.deny_warnings = b,
+        ("offline", Value::Bool(b)) => flags.offline = b,
+        ("allow_outside_root", Value::Bool(b)) => flags.allow_outside_root = b,
+        ("strict", Value::Bool(b)) => flags.strict = b,
+        ("prune", Value::Bool(b)) => flags.prune = b,
+        ("timings", Value::Bool(b)) => flags.timings = b.then_some(None),
+        ("timings", Value::String(file)) => flags.timings = Some(Some(file)),
+        ("exec_policy", Value::String(policy)) => {
+            flags.exec_policy = match ExecPolicy::from_string(&policy) {
+                Some(ExecPolicy::Ask) => None,
+                Some(policy) => Some(policy),
+                None => return Err(invalid("one of ask, yes, skip, deny")),
+            }
+        }
+        ("error_format", Value::String(format)) => {
+            flags.error_format =
+                ErrorFormat::from_string(&format).ok_or_else(|| invalid("one of human, json"))?;
+        }
+        ("verbosity", Value::String(verbosity)) => {
+            flags.verbosity = match verbosity.as_str() {
+                "quiet" => Verbosity::Quiet,
+                "normal" => Verbosity::Normal,
+                "verbose" => Verbosity::Verbose,
+                _ => return Err(invalid("one of quiet, normal, verbose")),
+            }
+        }
+        ("deny_warnings" | "offline" | "allow_outside_root" | "strict" | "prune", _) => {
+            return Err(invalid("true or false"));
+        }
+        ("exec_policy" | "error_format" | "verbosity", _) => {
+            return Err(invalid("a quoted string"));
+        }
+        _ => return Err(format!("unknown setting {key}")),
+    }
+    Ok(())
+}
+
+/// Removes a `#` comment, ignoring any `#` inside a quoted string.
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    let mut escaped = false;
+    for (idx, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..idx],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// A setting's value.
+enum Value {
+    String(String),
+    Bool(bool),
+}
+impl Value {
+    /// Parses `"text"` (with `\"` and `\\` escapes), `true`, or `false`.
+    fn parse(value: &str) -> Option<Value> {
+        match value {
+            "true" => return Some(Value::Bool(true)),
+            "false" => return Some(Value::Bool(false)),
+            _ => {}
+        }
+        let inner = value.strip_prefix('"')?.strip_suffix('"')?;
+        let mut text = String::new();
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => text.push(chars.next()?),
+                '"' => return None,
+                c => text.push(c),
+            }
+        }
+        Some(Value::String(text))
+    }
+
+    /// The value as an option's text.
+    fn text(self) -> String {
+        match self {
+            Value::String(text) => text,
+            Value::Bool(b) => b.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Loads `source` as a settings file into default flags.
+    fn load_str(name: &str, source: &str) -> Result<GlobalFlags, String> {
+        let path = std::env::temp_dir().join(format!("repack-settings-{name}.toml"));
+        std::fs::write(&path, source).unwrap();
+        let mut flags = GlobalFlags::default();
+        let result = load(&path, &mut flags);
+        std::fs::remove_file(&path).unwrap();
+        result.map(|_| flags).map_err(|e| {
+            e.strip_prefix(&format!("{}:", path.display()))
+                .unwrap_or(&e)
+                .to_string()
+        })
+    }
+
+    #[test]
+    fn applies_flags_and_sections() {
+        let flags = load_str(
+            "sections",
+            "# project defaults\nerror_format = \"json\"\nexec_policy = \"skip\"\ndeny_warnings = true # strict CI\ntimings = \"t.json\"\n\n[define]\npackage = \"models\"\nflag = false\n\n[ output.go ]\nlocation = \"internal/#models\"\n",
+        )
+        .unwrap();
+        assert_eq!(flags.error_format, ErrorFormat::Json);
+        assert_eq!(flags.exec_policy, Some(ExecPolicy::Skip));
+        assert!(flags.deny_warnings);
+        assert_eq!(flags.timings, Some(Some("t.json".to_string())));
+        assert_eq!(
+            flags.defines,
+            vec![
+                ("package".to_string(), "models".to_string()),
+                ("flag".to_string(), "false".to_string()),
+            ]
+        );
+        assert_eq!(
+            flags.output_overrides,
+            vec![(
+                "go".to_string(),
+                "location".to_string(),
+                "internal/#models".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn ask_policy_leaves_the_default() {
+        let flags = load_str("ask", "exec_policy = \"ask\"\nverbosity = \"quiet\"\n").unwrap();
+        assert_eq!(flags.exec_policy, None);
+        assert_eq!(flags.verbosity, Verbosity::Quiet);
+    }
+
+    #[test]
+    fn reads_escapes_in_strings() {
+        let flags = load_str("escapes", "[define]\nsep = \"a\\\"b\\\\c # d\"\n").unwrap();
+        assert_eq!(flags.defines[0].1, "a\"b\\c # d");
+    }
+
+    #[test]
+    fn reports_the_line_of_bad_settings() {
+        for (name, source, error) in [
+            ("section", "[build]\n", "1: unknown section [build]"),
+            (
+                "no-equals",
+                "\n\ndeny_warnings\n",
+                "3: expected key = value, found deny_warnings",
+            ),
+            (
+                "unquoted",
+                "error_format = json\n",
+                "1: json is not a quoted string or boolean",
+            ),
+            (
+                "unterminated",
+                "[define]\na = \"x\n",
+                "2: \"x is not a quoted string or boolean",
+            ),
+            (
+                "inner-quote",
+                "[define]\na = \"x\"y\"\n",
+                "2: \"x\"y\" is not a quoted string or boolean",
+            ),
+            (
+                "bool-type",
+                "prune = \"yes\"\n",
+                "1: prune must be true or false",
+            ),
+            (
+                "string-type",
+                "verbosity = true\n",
+                "1: verbosity must be a quoted string",
+            ),
+            (
+                "bad-value",
+                "error_format = \"xml\"\n",
+                "1: error_format must be one of human, json",
+            ),
+            ("unknown", "colour = true\n", "1: unknown setting colour"),
+        ] {
+            assert_eq!(
+                load_str(name, source).err().as_deref(),
+                Some(error),
+                "{source:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn missing_file_is_an_error() {
+        let mut flags = GlobalFlags::default();
+        let err = load(Path::new("/nonexistent/repack.toml"), &mut flags).unwrap_err();
+        assert!(err.starts_with("Unable to read /nonexistent/repack.toml"));
+    }
+}
@@ -0,0 +1,196 @@
+use std::fmt::Display;
+
+use super::{FileContents, RepackError, RepackErrorKind, Token};
+
+/// A boolean expression over categories, selecting what an output includes.
+///
+/// Written after the output's profile: `#api & !#internal`, `#(model | api)`,
+/// or a plain list such as `#model #api`, where listed categories are
+/// alternatives. `!` binds tightest, then `&`, then `|`. Names inside
+/// parentheses may leave out the `#`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CategoryExpr {
+    Category(String),
+    Not(Box<CategoryExpr>),
+    All(Vec<CategoryExpr>),
+    Any(Vec<CategoryExpr>),
+}
+impl CategoryExpr {
+    /// Reads an expression starting at the current token.
+    ///
+    /// At the top level, reading stops at the first token that cannot continue
+    /// the expression (such as `@`, `{`, or `;`), which is left unread.
+    pub fn read(contents: &mut FileContents) -> Result<CategoryExpr, RepackError> {
+        read_any(contents, false)
+    }
+
+    /// Parses an expression from text, as stored in schema JSON.
+    pub fn parse(source: &str) -> Result<CategoryExpr, RepackError> {
+        let mut contents = FileContents::empty();
+        contents.add_string(source);
+        let expr = read_any(&mut contents, false)?;
+        match peek(&mut contents, true) {
+            None => Ok(expr),
+            Some(token) => Err(syntax_error(format!("unexpected {token:?}"))),
+        }
+    }
+
+    /// Whether an item tagged with `categories` is selected.
+    pub fn matches(&self, categories: &[String]) -> bool {
+        match self {
+            CategoryExpr::Category(name) => categories.contains(name),
+            CategoryExpr::Not(inner) => !inner.matches(categories),
+            CategoryExpr::All(terms) => terms.iter().all(|t| t.matches(categories)),
+            CategoryExpr::Any(terms) => terms.iter().any(|t| t.matches(categories)),
+        }
+    }
+
+    /// Combines two expressions so either may match.
+    pub fn or(self, other: CategoryExpr) -> CategoryExpr {
+        match self {
+            CategoryExpr::Any(mut terms) => {
+                terms.push(other);
+                CategoryExpr::Any(terms)
+            }
+            expr => CategoryExpr::Any(vec![expr, other]),
+        }
+    }
+
+    /// The category names, if the expression is only a list of alternatives.
+    pub fn as_list(&self) -> Option<Vec<String>> {
+        match self {
+            CategoryExpr::Category(name) => Some(vec![name.to_string()]),
+            CategoryExpr::Any(terms) => terms
+                .iter()
+                .map(|t| match t {
+                    CategoryExpr::Category(name) => Some(name.to_string()),
+                    _ => None,
+                })
+                .collect(),
+            _ => None,
+        }
+    }
+}
+impl Display for CategoryExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Operands binding more loosely than their parent need parentheses.
+        let operand = |expr: &CategoryExpr, f: &mut std::fmt::Formatter<'_>, strict: bool| {
+            let loose = match expr {
+                CategoryExpr::Any(_) => true,
+                CategoryExpr::All(_) => strict,
+                _ => false,
+            };
+            if loose {
+                write!(f, "({expr})")
+            } else {
+                write!(f, "{expr}")
+            }
+        };
+        match self {
+            CategoryExpr::Category(name) => write!(f, "#{name}"),
+            CategoryExpr::Not(inner) => {
+                write!(f, "!")?;
+                operand(inner, f, true)
+            }
+            CategoryExpr::All(terms) | CategoryExpr::Any(terms) => {
+                let all = matches!(self, CategoryExpr::All(_));
+                for (idx, term) in terms.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, "{}", if all { " & " } else { " | " })?;
+                    }
+                    if all {
+                        operand(term, f, false)?;
+                    } else {
+                        write!(f, "{term}")?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn syntax_error(msg: String) -> RepackError {
+    RepackError::global(
+        RepackErrorKind::SyntaxError,
+        format!("category expression: {msg}"),
+    )
+}
+
+/// Skips line breaks inside parentheses, where an expression may span lines.
+fn peek(contents: &mut FileContents, nested: bool) -> Option<Token> {
+    while nested && contents.peek() == Some(&Token::NewLine) {
+        contents.skip();
+    }
+    contents.peek().cloned()
+}
+
+/// Whether the token can start a term. Bare names only count inside parentheses.
+fn starts_term(token: &Option<Token>, nested: bool) -> bool {
+    match token {
+        Some(Token::Pound | Token::Exclamation | Token::OpenParen) => true,
+        Some(Token::Literal(_)) => nested,
+        _ => false,
+    }
+}
+
+fn read_any(contents: &mut FileContents, nested: bool) -> Result<CategoryExpr, RepackError> {
+    let mut terms = vec![read_all(contents, nested)?];
+    loop {
+        let next = peek(contents, nested);
+        if next == Some(Token::Pipe) {
+            contents.skip();
+        } else if !starts_term(&next, nested) {
+            break;
+        }
+        terms.push(read_all(contents, nested)?);
+    }
+    Ok(match terms.len() {
+        1 => terms.remove(0),
+        _ => CategoryExpr::Any(terms),
+    })
+}
+
+fn read_all(contents: &mut FileContents, nested: bool) -> Result<CategoryExpr, RepackError> {
+    let mut terms = vec![read_term(contents, nested)?];
+    while peek(contents, nested) == Some(Token::Ampersand) {
+        contents.skip();
+        terms.push(read_term(contents, nested)?);
+    }
+    Ok(match terms.len() {
+        1 => terms.remove(0),
+        _ => CategoryExpr::All(terms),
+    })
+}
+
+fn read_term(contents: &mut FileContents, nested: bool) -> Result<CategoryExpr, RepackError> {
+    let mut token = peek(contents, true);
+    if token == Some(Token::Exclamation) {
+        contents.skip();
+        return Ok(CategoryExpr::Not(Box::new(read_term(contents, nested)?)));
+    }
+    let pound = token == Some(Token::Pound);
+    if pound {
+        contents.skip();
+        token = contents.peek().cloned();
+    }
+    match token {
+        Some(Token::Literal(name)) if pound || nested => {
+            contents.skip();
+            Ok(CategoryExpr::Category(name))
+        }
+        Some(Token::OpenParen) => {
+            contents.skip();
+            let expr = read_any(contents, true)?;
+            if peek(contents, true) != Some(Token::CloseParen) {
+                return Err(syntax_error("expected ')'".to_string()));
+            }
+            contents.skip();
+            Ok(expr)
+        }
+        Some(other) => Err(syntax_error(format!(
+            "expected a category, found {other:?}"
+        ))),
+        None => Err(syntax_error("expected a category".to_string())),
+    }
+}
@@ -0,0 +1,671 @@
+use std::fmt::Display;
+
+use super::{Field, ParseResult, RepackEnum, RepackEnumCase, RepackStruct, RepackUnion};
+
+/// A field's type as it would be written in a schema, such as `int32`,
+/// `string[]`, or `Role?`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldShape {
+    /// The core type, or the struct, enum, or union
+    pub name: String,
+    pub array: bool,
+    pub optional: bool,
+}
+impl FieldShape {
+    pub fn of(field: &Field) -> FieldShape {
+        FieldShape {
+            name: field
+                .field_type
+                .as_ref()
+                .map(|t| t.to_string())
+                .unwrap_or_else(|| field.field_type_string.clone()),
+            array: field.array,
+            optional: field.optional,
+        }
+    }
+}
+impl Display for FieldShape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            self.name,
+            if self.array { "[]" } else { "" },
+            if self.optional { "?" } else { "" }
+        )
+    }
+}
+
+/// One difference between two versions of a schema, found by [`schema_changes`].
+///
+/// Enum cases are told apart by the value they are stored as, which is their
+/// name unless a value is given.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SchemaChange {
+    StructAdded(String),
+    StructRemoved(String),
+    /// A struct replaced by one with a different name but the same table, or
+    /// the same fields if neither has a table
+    StructRenamed {
+        from: String,
+        to: String,
+    },
+    FieldAdded {
+        strct: String,
+        field: String,
+        shape: FieldShape,
+        /// Whether the field has a `db:default`
+        has_default: bool,
+    },
+    FieldRemoved {
+        strct: String,
+        field: String,
+        shape: FieldShape,
+    },
+    /// A field marked `renamed_from` its old name
+    FieldRenamed {
+        strct: String,
+        from: String,
+        to: String,
+    },
+    FieldTypeChanged {
+        strct: String,
+        field: String,
+        from: FieldShape,
+        to: FieldShape,
+    },
+    EnumAdded(String),
+    EnumRemoved(String),
+    CaseAdded {
+        enm: String,
+        case: String,
+    },
+    CaseRemoved {
+        enm: String,
+        case: String,
+    },
+    /// A case marked `renamed_from` its old value, or one whose value is
+    /// kept under a new name
+    CaseRenamed {
+        enm: String,
+        from: String,
+        to: String,
+    },
+    UnionAdded(String),
+    UnionRemoved(String),
+    VariantAdded {
+        union: String,
+        variant: String,
+    },
+    VariantRemoved {
+        union: String,
+        variant: String,
+    },
+}
+impl Display for SchemaChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::StructAdded(name) => write!(f, "added struct {name}"),
+            Self::StructRemoved(name) => write!(f, "removed struct {name}"),
+            Self::StructRenamed { from, to } => write!(f, "renamed struct {from} to {to}"),
+            Self::FieldAdded {
+                strct,
+                field,
+                shape,
+                ..
+            } => write!(f, "added field {strct}.{field} {shape}"),
+            Self::FieldRemoved {
+                strct,
+                field,
+                shape,
+            } => write!(f, "removed field {strct}.{field} {shape}"),
+            Self::FieldRenamed { strct, from, to } => {
+                write!(f, "renamed field {strct}.{from} to {to}")
+            }
+            Self::FieldTypeChanged {
+                strct,
+                field,
+                from,
+                to,
+            } => write!(f, "changed field {strct}.{field} from {from} to {to}"),
+            Self::EnumAdded(name) => write!(f, "added enum {name}"),
+            Self::EnumRemoved(name) => write!(f, "removed enum {name}"),
+            Self::CaseAdded { enm, case } => write!(f, "added case {enm}.{case}"),
+            Self::CaseRemoved { enm, case } => write!(f, "removed case {enm}.{case}"),
+            Self::CaseRenamed { enm, from, to } => {
+                write!(f, "renamed case {enm}.{from} to {to}")
+            }
+            Self::UnionAdded(name) => write!(f, "added union {name}"),
+            Self::UnionRemoved(name) => write!(f, "removed union {name}"),
+            Self::VariantAdded { union, variant } => {
+                write!(f, "added variant {variant} to union {union}")
+            }
+            Self::VariantRemoved { union, variant } => {
+                write!(f, "removed variant {variant} from union {union}")
+            }
+        }
+    }
+}
+
+/// How a [`SchemaChange`] affects data and code written against the old schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChangeImpact {
+    /// Nothing existing is affected, such as an added struct or optional field
+    Compatible,
+    /// Existing data stays valid, but clients may need updating: renames,
+    /// widened types, fields that became optional, and added enum cases and
+    /// union variants
+    Risky,
+    /// Existing data or clients stop working: removals, narrowed or changed
+    /// types, and required fields added without a `db:default`
+    Breaking,
+}
+impl Display for ChangeImpact {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Compatible => "compatible",
+            Self::Risky => "risky",
+            Self::Breaking => "breaking",
+        })
+    }
+}
+
+impl SchemaChange {
+    /// How this change affects data and code written against the old schema.
+    pub fn impact(&self) -> ChangeImpact {
+        match self {
+            Self::StructAdded(_) | Self::EnumAdded(_) | Self::UnionAdded(_) => {
+                ChangeImpact::Compatible
+            }
+            Self::FieldAdded {
+                shape, has_default, ..
+            } => match shape.optional || *has_default {
+                true => ChangeImpact::Compatible,
+                false => ChangeImpact::Breaking,
+            },
+            Self::StructRenamed { .. }
+            | Self::FieldRenamed { .. }
+            | Self::CaseAdded { .. }
+            | Self::CaseRenamed { .. }
+            | Self::VariantAdded { .. } => ChangeImpact::Risky,
+            Self::StructRemoved(_)
+            | Self::FieldRemoved { .. }
+            | Self::EnumRemoved(_)
+            | Self::CaseRemoved { .. }
+            | Self::UnionRemoved(_)
+            | Self::VariantRemoved { .. } => ChangeImpact::Breaking,
+            Self::FieldTypeChanged { from, to, .. } => {
+                let same = from.name == to.name || widens(&from.name, &to.name);
+                if !same || from.array != to.array || (from.optional && !to.optional) {
+                    ChangeImpact::Breaking
+                } else {
+                    ChangeImpact::Risky
+                }
+            }
+        }
+    }
+}
+
+/// Whether every value of the core type `from` fits in `to` exactly.
+fn widens(from: &str, to: &str) -> bool {
+    matches!(
+        (from, to),
+        ("int16", "int32" | "int64" | "float64")
+            | ("int32", "int64" | "float64")
+            | ("uint32", "uint64" | "int64" | "float64")
+    )
+}
+
+/// The part of a semantic version a release has to raise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum VersionBump {
+    Patch,
+    Minor,
+    Major,
+}
+impl Display for VersionBump {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Patch => "patch",
+            Self::Minor => "minor",
+            Self::Major => "major",
+        })
+    }
+}
+
+impl SchemaChange {
+    /// The version bump this change calls for in packages generated from the
+    /// schema: major for anything that breaks code written against the old
+    /// version, including renames and type changes, and minor for additions.
+    pub fn version_bump(&self) -> VersionBump {
+        match (self.impact(), self) {
+            (ChangeImpact::Compatible, _)
+            | (ChangeImpact::Risky, Self::CaseAdded { .. } | Self::VariantAdded { .. }) => {
+                VersionBump::Minor
+            }
+            _ => VersionBump::Major,
+        }
+    }
+}
+
+/// Suggests the version that follows `current` for a release with `bump`.
+///
+/// Versions are `MAJOR.MINOR.PATCH`, optionally starting with `v`, which is
+/// kept. Before 1.0.0 the minor number is treated as the major one, as Cargo
+/// and npm do: a major bump raises the minor number and a minor bump the
+/// patch number.
+///
+/// # Returns
+/// * `Some(String)` with the next version
+/// * `None` if `current` is not a plain `MAJOR.MINOR.PATCH` version
+pub fn next_version(current: &str, bump: VersionBump) -> Option<String> {
+    let (prefix, version) = match current.strip_prefix('v') {
+        Some(version) => ("v", version),
+        None => ("", current),
+    };
+    let parts = version
+        .split('.')
+        .map(|part| part.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    let [major, minor, patch] = parts.as_slice() else {
+        return None;
+    };
+    let bump = match (*major, bump) {
+        (0, VersionBump::Major) => VersionBump::Minor,
+        (0, VersionBump::Minor) => VersionBump::Patch,
+        (_, bump) => bump,
+    };
+    let (major, minor, patch) = match bump {
+        VersionBump::Major => (major + 1, 0, 0),
+        VersionBump::Minor => (*major, minor + 1, 0),
+        VersionBump::Patch => (*major, *minor, patch + 1),
+    };
+    Some(format!("{prefix}{major}.{minor}.{patch}"))
+}
+
+/// Which changes `repack check-compat` rejects.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompatPolicy {
+    /// Reject every change that is not [`ChangeImpact::Compatible`]
+    Strict,
+    /// Reject only [`ChangeImpact::Breaking`] changes
+    Lenient,
+}
+impl CompatPolicy {
+    pub fn from_string(val: &str) -> Option<CompatPolicy> {
+        match val {
+            "strict" => Some(CompatPolicy::Strict),
+            "lenient" => Some(CompatPolicy::Lenient),
+            _ => None,
+        }
+    }
+
+    /// Whether a change with this impact passes the policy.
+    pub fn allows(&self, impact: ChangeImpact) -> bool {
+        match self {
+            CompatPolicy::Strict => impact == ChangeImpact::Compatible,
+            CompatPolicy::Lenient => impact != ChangeImpact::Breaking,
+        }
+    }
+}
+
+/// Compares two versions of a schema, such as a snapshot written by
+/// `repack snapshot` and the current file.
+///
+/// Structs, enums, and unions are matched by name, and fields by name within
+/// their struct. A struct that disappears while another appears with the same
+/// table (or, without tables, the same field names) is reported as renamed,
+/// as is a field or enum case marked `renamed_from` what it replaced.
+///
+/// # Arguments
+/// * `old` - The earlier schema
+/// * `new` - The later schema
+///
+/// # Returns
+/// The changes to structs in the order of the new schema, then changes to
+/// their fields, then removed structs, followed by enums and unions likewise
+pub fn schema_changes(old: &ParseResult, new: &ParseResult) -> Vec<SchemaChange> {
+    let mut changes = Vec::new();
+
+    let mut removed = old
+        .strcts
+        .iter()
+        .filter(|o| !new.strcts.iter().any(|s| s.name == o.name))
+        .collect::<Vec<_>>();
+    let mut pairs = Vec::new();
+    let mut renames = Vec::new();
+    for strct in &new.strcts {
+        if let Some(previous) = old.strcts.iter().find(|o| o.name == strct.name) {
+            pairs.push((previous, strct));
+        } else if let Some(pos) = removed.iter().position(|o| same_struct(o, strct)) {
+            let previous = removed.remove(pos);
+            changes.push(SchemaChange::StructRenamed {
+                from: previous.name.clone(),
+                to: strct.name.clone(),
+            });
+            renames.push((previous.name.as_str(), strct.name.as_str()));
+            pairs.push((previous, strct));
+        } else {
+            changes.push(SchemaChange::StructAdded(strct.name.clone()));
+        }
+    }
+    for (previous, strct) in pairs {
+        field_changes(previous, strct, &renames, &mut changes);
+    }
+    for strct in removed {
+        changes.push(SchemaChange::StructRemoved(strct.name.clone()));
+    }
+
+    for enm in &new.enums {
+        match old.enums.iter().find(|o| o.name == enm.name) {
+            Some(previous) => case_changes(previous, enm, &mut changes),
+            None => changes.push(SchemaChange::EnumAdded(enm.name.clone())),
+        }
+    }
+    for enm in &old.enums {
+        if !new.enums.iter().any(|e| e.name == enm.name) {
+            changes.push(SchemaChange::EnumRemoved(enm.name.clone()));
+        }
+    }
+
+    for union in &new.unions {
+        match old.unions.iter().find(|o| o.name == union.name) {
+            Some(previous) => variant_changes(previous, union, &mut changes),
+            None => changes.push(SchemaChange::UnionAdded(union.name.clone())),
+        }
+    }
+    for union in &old.unions {
+        if !new.unions.iter().any(|u| u.name == union.name) {
+            changes.push(SchemaChange::UnionRemoved(union.name.clone()));
+        }
+    }
+    changes
+}
+
+/// Whether `new` looks like `old` under another name.
+fn same_struct(old: &RepackStruct, new: &RepackStruct) -> bool {
+    match (&old.table_name, &new.table_name) {
+        (Some(old), Some(new)) => old == new,
+        (None, None) => {
+            !old.fields.is_empty()
+                && old.fields.len() == new.fields.len()
+                && old
+                    .fields
+                    .iter()
+                    .all(|f| new.fields.iter().any(|n| n.name == f.name))
+        }
+        _ => false,
+    }
+}
+
+/// Compares the fields of two versions of a struct. A field whose type is a
+/// struct that was renamed is unchanged if only the name differs, since the
+/// rename is already reported.
+fn field_changes(
+    old: &RepackStruct,
+    new: &RepackStruct,
+    renames: &[(&str, &str)],
+    changes: &mut Vec<SchemaChange>,
+) {
+    let strct = new.name.clone();
+    let mut matched = Vec::new();
+    for field in &new.fields {
+        let previous = match old.fields.iter().find(|f| f.name == field.name) {
+            Some(previous) => previous,
+            None => {
+                let renamed = field.renamed_from().and_then(|from| {
+                    old.fields
+                        .iter()
+                        .find(|f| f.name == from && !new.fields.iter().any(|n| n.name == f.name))
+                });
+                let Some(previous) = renamed else {
+                    changes.push(SchemaChange::FieldAdded {
+                        strct: strct.clone(),
+                        field: field.name.clone(),
+                        shape: FieldShape::of(field),
+                        has_default: field.function("db", "default").is_some(),
+                    });
+                    continue;
+                };
+                changes.push(SchemaChange::FieldRenamed {
+                    strct: strct.clone(),
+                    from: previous.name.clone(),
+                    to: field.name.clone(),
+                });
+                previous
+            }
+        };
+        matched.push(previous.name.as_str());
+        let (from, to) = (FieldShape::of(previous), FieldShape::of(field));
+        let renamed = renames.contains(&(from.name.as_str(), to.name.as_str()));
+        if from != to && !(renamed && from.array == to.array && from.optional == to.optional) {
+            changes.push(SchemaChange::FieldTypeChanged {
+                strct: strct.clone(),
+                field: field.name.clone(),
+                from,
+                to,
+            });
+        }
+    }
+    for field in &old.fields {
+        if !matched.contains(&field.name.as_str()) {
+            changes.push(SchemaChange::FieldRemoved {
+                strct: strct.clone(),
+                field: field.name.clone(),
+                shape: FieldShape::of(field),
+            });
+        }
+    }
+}
+
+fn case_changes(old: &RepackEnum, new: &RepackEnum, changes: &mut Vec<SchemaChange>) {
+    let value = |case: &RepackEnumCase| case.value.clone().unwrap_or(case.name.clone());
+    let mut matched = Vec::new();
+    for case in &new.options {
+        let stored = value(case);
+        if let Some(previous) = old.options.iter().find(|o| value(o) == stored) {
+            matched.push(stored.clone());
+            if previous.name != case.name {
+                changes.push(SchemaChange::CaseRenamed {
+                    enm: new.name.clone(),
+                    from: previous.name.clone(),
+                    to: case.name.clone(),
+                });
+            }
+            continue;
+        }
+        let renamed = case.renamed_from().and_then(|from| {
+            old.options
+                .iter()
+                .find(|o| value(o) == from && !new.options.iter().any(|n| value(n) == from))
+        });
+        match renamed {
+            Some(previous) => {
+                matched.push(value(previous));
+                changes.push(SchemaChange::CaseRenamed {
+                    enm: new.name.clone(),
+                    from: value(previous),
+                    to: stored,
+                });
+            }
+            None => changes.push(SchemaChange::CaseAdded {
+                enm: new.name.clone(),
+                case: stored,
+            }),
+        }
+    }
+    for case in &old.options {
+        if !matched.contains(&value(case)) {
+            changes.push(SchemaChange::CaseRemoved {
+                enm: new.name.clone(),
+                case: value(case),
+            });
+        }
+    }
+}
+
+fn variant_changes(old: &RepackUnion, new: &RepackUnion, changes: &mut Vec<SchemaChange>) {
+    for variant in &new.variants {
+        if !old.variants.contains(variant) {
+            changes.push(SchemaChange::VariantAdded {
+                union: new.name.clone(),
+                variant: variant.clone(),
+            });
+        }
+    }
+    for variant in &old.variants {
+        if !new.variants.contains(variant) {
+            changes.push(SchemaChange::VariantRemoved {
+                union: new.name.clone(),
+                variant: variant.clone(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shape(name: &str, array: bool, optional: bool) -> FieldShape {
+        FieldShape {
+            name: name.to_string(),
+            array,
+            optional,
+        }
+    }
+
+    fn type_change(from: FieldShape, to: FieldShape) -> SchemaChange {
+        SchemaChange::FieldTypeChanged {
+            strct: "User".to_string(),
+            field: "age".to_string(),
+            from,
+            to,
+        }
+    }
+
+    #[test]
+    fn bumps_versions() {
+        for (current, bump, next) in [
+            ("1.2.3", VersionBump::Patch, "1.2.4"),
+            ("1.2.3", VersionBump::Minor, "1.3.0"),
+            ("1.2.3", VersionBump::Major, "2.0.0"),
+            ("v1.9.9", VersionBump::Major, "v2.0.0"),
+            ("0.4.2", VersionBump::Major, "0.5.0"),
+            ("0.4.2", VersionBump::Minor, "0.4.3"),
+            ("0.4.2", VersionBump::Patch, "0.4.3"),
+            ("0.0.1", VersionBump::Major, "0.1.0"),
+        ] {
+            assert_eq!(
+                next_version(current, bump).as_deref(),
+                Some(next),
+                "{current} {bump}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_versions_that_are_not_plain() {
+        for current in [
+            "",
+            "1.2",
+            "1.2.3.4",
+            "1.2.x",
+            "1.2.3-beta",
+            "V1.2.3",
+            "vv1.2.3",
+            "-1.2.3",
+            "1..3",
+        ] {
+            assert_eq!(
+                next_version(current, VersionBump::Patch),
+                None,
+                "{current:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn rates_type_changes() {
+        for (from, to, impact) in [
+            (
+                shape("int32", false, false),
+                shape("int64", false, false),
+                ChangeImpact::Risky,
+            ),
+            (
+                shape("int64", false, false),
+                shape("int32", false, false),
+                ChangeImpact::Breaking,
+            ),
+            (
+                shape("int32", false, false),
+                shape("int32", false, true),
+                ChangeImpact::Risky,
+            ),
+            (
+                shape("int32", false, true),
+                shape("int32", false, false),
+                ChangeImpact::Breaking,
+            ),
+            (
+                shape("int32", false, false),
+                shape("int32", true, false),
+                ChangeImpact::Breaking,
+            ),
+            (
+                shape("int32", false, false),
+                shape("string", false, false),
+                ChangeImpact::Breaking,
+            ),
+        ] {
+            let change = type_change(from, to);
+            assert_eq!(change.impact(), impact, "{change}");
+            assert_eq!(change.version_bump(), VersionBump::Major, "{change}");
+        }
+    }
+
+    #[test]
+    fn additions_call_for_minor_releases() {
+        let optional = SchemaChange::FieldAdded {
+            strct: "User".to_string(),
+            field: "nickname".to_string(),
+            shape: shape("string", false, true),
+            has_default: false,
+        };
+        assert_eq!(optional.impact(), ChangeImpact::Compatible);
+        assert_eq!(optional.version_bump(), VersionBump::Minor);
+
+        let required = SchemaChange::FieldAdded {
+            strct: "User".to_string(),
+            field: "email".to_string(),
+            shape: shape("string", false, false),
+            has_default: false,
+        };
+        assert_eq!(required.impact(), ChangeImpact::Breaking);
+        assert_eq!(required.version_bump(), VersionBump::Major);
+
+        let case = SchemaChange::CaseAdded {
+            enm: "Role".to_string(),
+            case: "Guest".to_string(),
+        };
+        assert_eq!(case.impact(), ChangeImpact::Risky);
+        assert_eq!(case.version_bump(), VersionBump::Minor);
+
+        let renamed = SchemaChange::StructRenamed {
+            from: "User".to_string(),
+            to: "Account".to_string(),
+        };
+        assert_eq!(renamed.impact(), ChangeImpact::Risky);
+        assert_eq!(renamed.version_bump(), VersionBump::Major);
+    }
+
+    #[test]
+    fn policies_allow_impacts() {
+        assert!(CompatPolicy::Strict.allows(ChangeImpact::Compatible));
+        assert!(!CompatPolicy::Strict.allows(ChangeImpact::Risky));
+        assert!(CompatPolicy::Lenient.allows(ChangeImpact::Risky));
+        assert!(!CompatPolicy::Lenient.allows(ChangeImpact::Breaking));
+        assert_eq!(CompatPolicy::from_string("loose"), None);
+    }
+}
@@ -0,0 +1,127 @@
+use super::{CoreType, CustomFieldType, FieldType, RepackEnum};
+
+/// SQL keywords accepted as a default for any type, compared without case.
+const DEFAULT_KEYWORDS: [&str; 5] = [
+    "null",
+    "current_timestamp",
+    "current_date",
+    "current_time",
+    "localtimestamp",
+];
+
+/// Converts a `db:default(value)` argument into the SQL written for the column.
+///
+/// Function calls such as `NOW()` or `gen_random_uuid()`, values that are
+/// already cast (`'a'::text`), and keywords such as `CURRENT_TIMESTAMP` are
+/// kept as written. Anything else is a literal, which may be given with or
+/// without single quotes, and must parse for the field's type:
+/// - An enum default names one of its cases and becomes the case's quoted value
+/// - A `uuid` default must be a UUID, and a `datetime` default a date such as
+///   `2024-01-31` with an optional time
+/// - Numbers must parse for their type, and booleans must be `true` or `false`
+/// - `string` and `bytes` defaults are quoted if they are not already
+///
+/// Array defaults are kept as written.
+///
+/// # Returns
+/// * `Ok(String)` with the SQL for the default
+/// * `Err(String)` describing why the value does not fit the field's type
+pub fn typed_default(
+    value: &str,
+    field_type: &FieldType,
+    array: bool,
+    enums: &[RepackEnum],
+) -> Result<String, String> {
+    let value = value.trim();
+    let is_call = value.contains('(') && value.ends_with(')');
+    if array
+        || is_call
+        || value.contains("::")
+        || DEFAULT_KEYWORDS.contains(&value.to_lowercase().as_str())
+    {
+        return Ok(value.to_string());
+    }
+    let literal = value
+        .strip_prefix('\'')
+        .and_then(|v| v.strip_suffix('\''))
+        .unwrap_or(value);
+    let invalid = || format!("{value} is not a valid {field_type}");
+    let quoted = |v: &str| format!("'{}'", v.replace('\'', "''"));
+    match field_type {
+        FieldType::Custom(name, CustomFieldType::Enum) => {
+            let enm = enums.iter().find(|e| e.name == *name).ok_or_else(invalid)?;
+            let case = enm
+                .options
+                .iter()
+                .find(|c| c.name == literal || c.value.as_deref() == Some(literal))
+                .ok_or_else(|| {
+                    format!(
+                        "{literal} is not a case of {name} (expected one of {})",
+                        enm.options
+                            .iter()
+                            .map(|c| c.name.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                })?;
+            Ok(quoted(case.value.as_deref().unwrap_or(&case.name)))
+        }
+        FieldType::Custom(_, CustomFieldType::Object | CustomFieldType::Union) => {
+            Ok(value.to_string())
+        }
+        FieldType::Core(core) => {
+            let valid = match core {
+                CoreType::String | CoreType::Bytes => true,
+                CoreType::Int16 => literal.parse::<i16>().is_ok(),
+                CoreType::Int32 => literal.parse::<i32>().is_ok(),
+                CoreType::Int64 => literal.parse::<i64>().is_ok(),
+                CoreType::Uint32 => literal.parse::<u32>().is_ok(),
+                CoreType::Uint64 => literal.parse::<u64>().is_ok(),
+                CoreType::Float64 => literal.parse::<f64>().is_ok(),
+                CoreType::Boolean => matches!(literal.to_lowercase().as_str(), "true" | "false"),
+                CoreType::Uuid => is_uuid(literal),
+                CoreType::DateTime => is_datetime(literal),
+            };
+            if !valid {
+                return Err(invalid());
+            }
+            Ok(match core {
+                CoreType::Boolean => literal.to_lowercase(),
+                CoreType::String | CoreType::Bytes | CoreType::Uuid | CoreType::DateTime => {
+                    if literal == value {
+                        quoted(literal)
+                    } else {
+                        value.to_string()
+                    }
+                }
+                _ => literal.to_string(),
+            })
+        }
+    }
+}
+
+/// Whether a value is a UUID written as 8-4-4-4-12 hex digits.
+fn is_uuid(value: &str) -> bool {
+    let groups = value.split('-').collect::<Vec<_>>();
+    groups.len() == 5
+        && groups
+            .iter()
+            .zip([8, 4, 4, 4, 12])
+            .all(|(g, len)| g.len() == len && g.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Whether a value starts with a `YYYY-MM-DD` date, as timestamps do.
+fn is_datetime(value: &str) -> bool {
+    let Some(date) = value.get(..10) else {
+        return false;
+    };
+    let parts = date.split('-').collect::<Vec<_>>();
+    parts.len() == 3
+        && parts
+            .iter()
+            .zip([4, 2, 2])
+            .all(|(p, len)| p.len() == len && p.chars().all(|c| c.is_ascii_digit()))
+        && value[10..]
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || " :.+-".contains(c))
+}
@@ -2,6 +2,76 @@ use std::collections::VecDeque;
 
 use super::{RepackError, RepackErrorKind, RepackStruct};
 
+/// Finds the struct a dependency name refers to, by struct name or table name.
+fn resolve<'a>(strcts: &'a [RepackStruct], name: &str) -> Option<&'a RepackStruct> {
+    strcts.iter().find(|obj| {
+        *obj.name == *name
+            || obj
+                .table_name
+                .as_ref()
+                .map(|x| *x == *name)
+                .unwrap_or(false)
+    })
+}
+
+/// Formats a cycle of struct names as `A -> B (field b) -> A (db:fk on a_id)`.
+///
+/// Each hop is annotated with what creates the edge, so the offending field,
+/// join, or inheritance can be found without tracing the schema by hand.
+fn describe_cycle(strcts: &[RepackStruct], cycle: &[String]) -> String {
+    let mut out = cycle[0].to_string();
+    for hop in cycle.windows(2) {
+        let via = resolve(strcts, &hop[0])
+            .and_then(|obj| {
+                obj.dependencies().into_iter().find(|dep| {
+                    resolve(strcts, &dep.target).is_some_and(|target| target.name == hop[1])
+                })
+            })
+            .map(|dep| format!(" ({})", dep.via))
+            .unwrap_or_default();
+        out.push_str(&format!(" -> {}{}", hop[1], via));
+    }
+    out
+}
+
+/// Reorders structs so each comes after every struct it depends on.
+///
+/// This is a topological sort that keeps declaration order where dependencies
+/// allow, so `CREATE` statements can run in order and `DROP` statements in
+/// reverse, however deep the chain. References a struct makes to itself are
+/// ignored. Structs left in a cycle keep their order at the end, for
+/// [`graph_valid`] to report.
+pub fn sort_by_dependencies(strcts: &mut Vec<RepackStruct>) {
+    let deps: Vec<Vec<usize>> = strcts
+        .iter()
+        .enumerate()
+        .map(|(idx, obj)| {
+            obj.depends_on()
+                .iter()
+                .filter_map(|dep| {
+                    strcts.iter().position(|other| {
+                        other.name == *dep || other.table_name.as_ref() == Some(dep)
+                    })
+                })
+                .filter(|dep| *dep != idx)
+                .collect()
+        })
+        .collect();
+    let mut placed = vec![false; strcts.len()];
+    let mut order = Vec::with_capacity(strcts.len());
+    while order.len() < strcts.len() {
+        let next = (0..strcts.len())
+            .find(|idx| !placed[*idx] && deps[*idx].iter().all(|dep| placed[*dep]))
+            // Only a cycle remains; keep the rest in declaration order.
+            .or_else(|| (0..strcts.len()).find(|idx| !placed[*idx]));
+        let Some(next) = next else { break };
+        placed[next] = true;
+        order.push(next);
+    }
+    let mut slots: Vec<Option<RepackStruct>> = strcts.drain(..).map(Some).collect();
+    strcts.extend(order.into_iter().filter_map(|idx| slots[idx].take()));
+}
+
 pub fn graph_valid(strcts: &[RepackStruct]) -> Result<(), RepackError> {
     let mut graph: VecDeque<Vec<String>> = VecDeque::new();
     for obj in strcts.iter() {
@@ -9,33 +79,38 @@ pub fn graph_valid(strcts: &[RepackStruct]) -> Result<(), RepackError> {
     }
     while let Some(eval) = graph.pop_front() {
         let name = eval.last().unwrap();
-        let Some(eval_object) = strcts.iter().find(|obj| {
-            *obj.name == *name
-                || obj
-                    .table_name
-                    .as_ref()
-                    .map(|x| *x == *name)
-                    .unwrap_or(false)
-        }) else {
+        let Some(eval_object) = resolve(strcts, name) else {
             return Err(RepackError::global(
                 RepackErrorKind::UnknownObject,
                 format!("'{}' => '{}'", eval.last().unwrap(), eval.first().unwrap()),
             ));
         };
-        if let Some(error) = eval_object
+        // Track struct names rather than table names so `db:fk` edges close cycles too.
+        let deps: Vec<String> = eval_object
             .depends_on()
+            .into_iter()
+            .map(|dep| {
+                resolve(strcts, &dep)
+                    .map(|obj| obj.name.clone())
+                    .unwrap_or(dep)
+            })
+            .collect();
+        if let Some(start) = deps
             .iter()
-            .find(|dep| eval.contains(dep))
+            .find_map(|dep| eval.iter().position(|x| x == dep))
         {
+            // Start the reported cycle at the struct the error is attached to.
+            let mut cycle = vec![eval_object.name.clone()];
+            cycle.extend_from_slice(&eval[start..]);
             return Err(RepackError::from_obj_with_msg(
                 RepackErrorKind::CircularDependancy,
                 eval_object,
-                error.to_string(),
+                describe_cycle(strcts, &cycle),
             ));
         } else {
-            for dep in eval_object.depends_on() {
+            for dep in deps {
                 let mut new_path = eval.clone();
-                new_path.push(dep.clone());
+                new_path.push(dep);
                 graph.push_back(new_path);
             }
         }
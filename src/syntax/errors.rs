@@ -1,13 +1,14 @@
 use crate::blueprint::BlueprintSnippetDetails;
+use crate::console::{Style, paint};
 
-use super::{Field, Output, RepackStruct};
+use super::{Field, Json, Output, RepackEnum, RepackMixin, RepackStruct, Snippet};
 
 /// Enumeration of all possible error types that can occur during schema processing.
 ///
 /// Each error kind represents a specific category of validation, parsing, or generation
 /// error. The u32 representation provides unique error codes for debugging and logging.
 /// Error codes are used in formatted error messages as E0001, E0002, etc.
-#[derive(Debug)]
+#[derive(Debug, Default, Clone, Copy)]
 #[repr(u32)]
 pub enum RepackErrorKind {
     CircularDependancy,
@@ -38,13 +39,43 @@ pub enum RepackErrorKind {
     PathNotValid,
     ParseIncomplete,
     FieldNotFound,
+    InvalidValidation,
+    MissingPrimaryKey,
+    NameNotSnakeCase,
+    TooFewEnumCases,
+    UnusedDefinition,
+    ImportCycle,
+    BlueprintNotCached,
+    IncompatibleBlueprint,
+    InvalidJson,
+    InvalidImportSource,
+    OutputOutsideRoot,
+    InvalidExecPolicy,
+    ExecDenied,
+    QueryFailedToPlan,
+    QueryWithoutTable,
+    UnknownBlueprintTag,
+    MissingMarkers,
+    KeptCodeRemoved,
+    InvalidFieldOrder,
+    ReservedWord,
+    NameCollision,
+    FeatureNotSupported,
+    InvalidDefault,
+    MixinNotFound,
+    MixinConflict,
+    InvalidUnion,
+    InvalidConst,
+    SensitiveNotRestricted,
+    EnvVarNotSet,
+    SchemaMetaNotFound,
+    InvalidFileName,
+    UngeneratedFile,
+    IncompatibleChange,
+    OutputNotRendered,
+    #[default]
     UnknownError,
 }
-impl Default for RepackErrorKind {
-    fn default() -> Self {
-        Self::UnknownError
-    }
-}
 impl RepackErrorKind {
     pub fn as_string(&self) -> &'static str {
         match self {
@@ -79,6 +110,50 @@ impl RepackErrorKind {
             Self::PathNotValid => "Path could not be converted to string:",
             Self::ParseIncomplete => "Parsing failed, expected token not found:",
             Self::FieldNotFound => "Field could not be found:",
+            Self::InvalidValidation => "Validation rule is not valid:",
+            Self::MissingPrimaryKey => "Struct is stored in a table but has no primary key.",
+            Self::NameNotSnakeCase => "Field name should be snake_case.",
+            Self::TooFewEnumCases => "Enum should have at least 2 cases.",
+            Self::UnusedDefinition => {
+                "Definition is not rendered by any output or referenced by another definition."
+            }
+            Self::ImportCycle => "Schema imports form a cycle:",
+            Self::BlueprintNotCached => "Remote blueprint is not cached and --offline was given:",
+            Self::IncompatibleBlueprint => "Blueprint does not support this version of repack:",
+            Self::InvalidJson => "JSON document is not valid:",
+            Self::InvalidImportSource => "Import source could not be read:",
+            Self::InvalidExecPolicy => "exec_policy must be ask, yes, skip, or deny:",
+            Self::ExecDenied => "Blueprint tried to run a command, but exec_policy is deny:",
+            Self::QueryFailedToPlan => "Query failed to plan against the database:",
+            Self::QueryWithoutTable => "Queries can only be declared on structs with a table:",
+            Self::UnknownBlueprintTag => "Blueprint tag is not recognized:",
+            Self::MissingMarkers => {
+                "File has no repack:begin and repack:end lines to write between:"
+            }
+            Self::KeptCodeRemoved => "Keep region holds code but is no longer generated:",
+            Self::InvalidFieldOrder => "field_order must be alpha or schema:",
+            Self::NameCollision => "Different names are written the same by this output:",
+            Self::FeatureNotSupported => "Feature is not supported by this output's blueprint:",
+            Self::InvalidDefault => "Default value does not fit the field's type:",
+            Self::MixinNotFound => "Expected to use mixin, but it couldn't be found:",
+            Self::MixinConflict => "A mixin adds a name that is already defined:",
+            Self::InvalidUnion => "Union variants must be distinct structs:",
+            Self::InvalidConst => "Constant value does not fit its type:",
+            Self::SensitiveNotRestricted => {
+                "Struct has sensitive fields but is not tagged #restricted:"
+            }
+            Self::EnvVarNotSet => "Output refers to an environment variable that is not set:",
+            Self::SchemaMetaNotFound => "Schema metadata is not defined in a schema block:",
+            Self::InvalidFileName => "File name does not follow the blueprint's [meta file_case]:",
+            Self::UngeneratedFile => "File was generated by this output but no longer is:",
+            Self::IncompatibleChange => "Schema change is not backward compatible:",
+            Self::OutputNotRendered => "Output is not rendered by this command:",
+            Self::ReservedWord => {
+                "Name is a reserved word in this output's language (rename it for this output):"
+            }
+            Self::OutputOutsideRoot => {
+                "File is outside the output location (pass --allow-outside-root to permit):"
+            }
         }
     }
 }
@@ -87,11 +162,14 @@ impl RepackError {
     /// Converts the error into a formatted string message for display.
     ///
     /// This method creates a comprehensive error message that includes:
-    /// - Error code (E0001 format)
+    /// - Error code (E0001 format, or W0001 for warnings)
     /// - Context location (language -> struct.field)
     /// - Error description and details
     /// - Stack trace for nested errors
     ///
+    /// When the console uses colors, the code is red (yellow for warnings) and
+    /// the location bold.
+    ///
     /// # Returns
     /// A formatted string suitable for console output or logging
     pub fn into_string(self) -> String {
@@ -100,15 +178,66 @@ impl RepackError {
         let stack = if self.stack.is_empty() {
             String::new()
         } else {
-            format!("\n\n--- Context: ---\n{}", self.stack.join("\n"))
+            format!(
+                "\n\n{}\n{}",
+                paint("--- Context: ---", Style::Dim),
+                self.stack.join("\n")
+            )
+        };
+        let (level, style) = match self.severity {
+            RepackErrorSeverity::Error => ('E', Style::Red),
+            RepackErrorSeverity::Warning => ('W', Style::Yellow),
         };
+        let code = format!("[{}{:04}]", level, self.error as u32);
         format!(
-            "[E{:04}]{} {} {}{}",
-            self.error as u32, self.specifier, msg, details, stack
+            "{}{} {} {}{}",
+            paint(&code, style),
+            paint(&self.specifier, Style::Bold),
+            msg,
+            details,
+            stack
         )
     }
 }
 
+impl RepackError {
+    /// Converts the error into a JSON object for `--error-format json`, with
+    /// its `code` (such as `E0001`), `severity`, `location`, `message`,
+    /// `details`, and `context`. No colors are applied.
+    pub fn to_json(&self) -> Json {
+        let (level, severity) = match self.severity {
+            RepackErrorSeverity::Error => ('E', "error"),
+            RepackErrorSeverity::Warning => ('W', "warning"),
+        };
+        let location = self
+            .specifier
+            .trim()
+            .trim_start_matches('(')
+            .trim_end_matches(')');
+        let code = format!("{level}{:04}", self.error as u32);
+        let message = self.error.as_string().trim_end().trim_end_matches(':');
+        Json::object([
+            ("code", Json::String(code)),
+            ("severity", Json::str(severity)),
+            ("location", Json::str(location)),
+            ("message", Json::str(message)),
+            ("details", Json::opt_str(self.error_details.as_ref())),
+            ("context", Json::strs(&self.stack)),
+        ])
+    }
+}
+
+/// How serious a [`RepackError`] is.
+///
+/// Errors stop the command. Warnings are printed but only fail the command
+/// when `--deny-warnings` is passed.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum RepackErrorSeverity {
+    #[default]
+    Error,
+    Warning,
+}
+
 /// Represents a complete error with context information for debugging.
 ///
 /// RepackError combines an error type with contextual information about where
@@ -124,6 +253,8 @@ pub struct RepackError {
     pub error_details: Option<String>,
     /// Stack trace for nested processing contexts (e.g., snippet processing)
     pub stack: Vec<String>,
+    /// Whether this is a fatal error or a warning
+    pub severity: RepackErrorSeverity,
 }
 
 impl RepackError {
@@ -150,7 +281,6 @@ impl RepackError {
     /// # Arguments
     /// * `error` - The type of error that occurred
     /// * `obj` - The struct where the error was found
-    #[allow(dead_code)]
     pub fn from_obj(error: RepackErrorKind, obj: &RepackStruct) -> RepackError {
         RepackError {
             error,
@@ -160,6 +290,43 @@ impl RepackError {
         }
     }
 
+    /// Creates an error associated with a specific enum.
+    ///
+    /// # Arguments
+    /// * `error` - The type of error that occurred
+    /// * `enm` - The enum where the error was found
+    pub fn from_enum(error: RepackErrorKind, enm: &RepackEnum) -> RepackError {
+        RepackError {
+            error,
+            specifier: format!(" ({})", enm.name),
+            stack: Vec::new(),
+            ..Default::default()
+        }
+    }
+
+    /// Creates an error associated with a specific snippet.
+    ///
+    /// # Arguments
+    /// * `error` - The type of error that occurred
+    /// * `snippet` - The snippet where the error was found
+    pub fn from_snippet(error: RepackErrorKind, snippet: &Snippet) -> RepackError {
+        RepackError {
+            error,
+            specifier: format!(" ({})", snippet.name),
+            stack: Vec::new(),
+            ..Default::default()
+        }
+    }
+
+    pub fn from_mixin(error: RepackErrorKind, mixin: &RepackMixin) -> RepackError {
+        RepackError {
+            error,
+            specifier: format!(" ({})", mixin.name),
+            stack: Vec::new(),
+            ..Default::default()
+        }
+    }
+
     pub fn from_obj_with_msg(
         error: RepackErrorKind,
         obj: &RepackStruct,
@@ -170,6 +337,7 @@ impl RepackError {
             specifier: format!(" ({})", obj.name),
             error_details: Some(msg),
             stack: Vec::new(),
+            ..Default::default()
         }
     }
 
@@ -202,6 +370,7 @@ impl RepackError {
             specifier: format!(" ({}.{})", obj.name, field.name),
             error_details: Some(msg),
             stack: Vec::new(),
+            ..Default::default()
         }
     }
 
@@ -241,6 +410,7 @@ impl RepackError {
             specifier: format!(" ({} -> {})", lang.profile, obj.name),
             error_details: Some(msg),
             stack: Vec::new(),
+            ..Default::default()
         }
     }
 
@@ -256,6 +426,7 @@ impl RepackError {
             specifier: format!(" ({} -> {}.{})", lang.profile, obj.name, field.name),
             error_details: Some(msg),
             stack: Vec::new(),
+            ..Default::default()
         }
     }
 
@@ -265,11 +436,27 @@ impl RepackError {
             specifier: format!(" ({})", lang.profile),
             error_details: Some(msg),
             stack: Vec::new(),
+            ..Default::default()
         }
     }
 
+    /// Marks this diagnostic as a warning.
+    pub fn warning(mut self) -> RepackError {
+        self.severity = RepackErrorSeverity::Warning;
+        self
+    }
+
+    pub fn is_warning(&self) -> bool {
+        self.severity == RepackErrorSeverity::Warning
+    }
+
     pub fn add_to_stack(&mut self, snip: &BlueprintSnippetDetails) {
-        self.stack
-            .push(format!("\t- {} {}", snip.main_token, snip.secondary_token));
+        let tag = format!("{} {}", snip.main_token, snip.secondary_token);
+        self.stack.push(format!(
+            "\t- {} (line {}, column {})",
+            tag.trim_end(),
+            snip.line,
+            snip.column
+        ));
     }
 }
@@ -0,0 +1,410 @@
+use super::Token;
+
+/// A single lexical item on a schema line, as seen by the formatter.
+///
+/// Unlike the parser's token stream, the formatter needs to know which
+/// literals were quoted and whether the source separated items with
+/// whitespace, so it can re-emit them without changing their meaning.
+#[derive(Debug, Clone)]
+enum Item {
+    Token(Token),
+    /// A string, including its quotes and escapes exactly as written.
+    Quoted(String),
+}
+impl Item {
+    fn text(&self) -> String {
+        match self {
+            Item::Quoted(s) => s.to_string(),
+            Item::Token(token) => match token {
+                Token::OpenParen => "(",
+                Token::CloseParen => ")",
+                Token::OpenBracket => "[",
+                Token::CloseBracket => "]",
+                Token::OpenBrace => "{",
+                Token::CloseBrace => "}",
+                Token::Period => ".",
+                Token::Comma => ",",
+                Token::Plus => "+",
+                Token::Minus => "-",
+                Token::Pound => "#",
+                Token::NewLine => "",
+                Token::Question => "?",
+                Token::Exclamation => "!",
+                Token::At => "@",
+                Token::Colon => ":",
+                Token::Semicolon => ";",
+                Token::Equal => "=",
+                Token::Ampersand => "&",
+                Token::Pipe => "|",
+                Token::Literal(lit) => lit,
+                Token::OutputType => "output",
+                Token::StructType => "struct",
+                Token::SnippetType => "snippet",
+                Token::EnumType => "enum",
+                Token::Where => "where",
+                Token::Import => "import",
+                Token::With => "with",
+                Token::Blueprint => "blueprint",
+                Token::Query => "query",
+                Token::Join => "join",
+                Token::Insert => "insert",
+                Token::Except => "except",
+                Token::Update => "update",
+                Token::One => "one",
+                Token::Many => "many",
+            }
+            .to_string(),
+        }
+    }
+
+    fn is(&self, token: &Token) -> bool {
+        matches!(self, Item::Token(t) if t == token)
+    }
+
+    fn is_literal(&self) -> bool {
+        matches!(self, Item::Token(Token::Literal(_)))
+    }
+}
+
+/// One source line: its items (each flagged with whether whitespace preceded
+/// it) and an optional trailing `//` comment.
+#[derive(Debug, Default)]
+struct Line {
+    items: Vec<(bool, Item)>,
+    comment: Option<String>,
+}
+
+/// Splits schema source into lines of items, keeping comments and quoting.
+fn lex(source: &str) -> Vec<Line> {
+    let mut lines = vec![Line::default()];
+    let mut buf = String::new();
+    let mut spaced = false;
+    let mut iter = source.chars().peekable();
+
+    fn flush(buf: &mut String, spaced: &mut bool, line: &mut Line) {
+        if !buf.is_empty() {
+            line.items
+                .push((*spaced, Item::Token(Token::from_string(buf))));
+            buf.clear();
+            *spaced = false;
+        }
+    }
+
+    while let Some(c) = iter.next() {
+        let Some(line) = lines.last_mut() else {
+            break;
+        };
+        match c {
+            '"' => {
+                flush(&mut buf, &mut spaced, line);
+                let mut quoted = String::from('"');
+                if iter.clone().take(2).eq(['"', '"']) {
+                    for q in iter.by_ref() {
+                        quoted.push(q);
+                        if quoted.len() >= 6 && quoted.ends_with("\"\"\"") {
+                            break;
+                        }
+                    }
+                } else {
+                    while let Some(q) = iter.next() {
+                        quoted.push(q);
+                        if q == '\\' {
+                            quoted.extend(iter.next());
+                        } else if q == '"' {
+                            break;
+                        }
+                    }
+                }
+                line.items.push((spaced, Item::Quoted(quoted)));
+                spaced = false;
+            }
+            '/' if iter.peek() == Some(&'/') => {
+                flush(&mut buf, &mut spaced, line);
+                let mut comment = String::from("/");
+                while let Some(q) = iter.next_if(|q| *q != '\n') {
+                    comment.push(q);
+                }
+                line.comment = Some(comment.trim_end().to_string());
+            }
+            '\n' => {
+                flush(&mut buf, &mut spaced, line);
+                lines.push(Line::default());
+                spaced = false;
+            }
+            c if c.is_ascii_whitespace() => {
+                flush(&mut buf, &mut spaced, line);
+                spaced = true;
+            }
+            c => match u8::try_from(c).ok().and_then(Token::from_byte) {
+                Some(token) => {
+                    flush(&mut buf, &mut spaced, line);
+                    line.items.push((spaced, Item::Token(token)));
+                    spaced = false;
+                }
+                None => buf.push(c),
+            },
+        }
+    }
+    if let Some(line) = lines.last_mut() {
+        flush(&mut buf, &mut spaced, line);
+    }
+    lines
+}
+
+/// Joins items with canonical spacing.
+///
+/// Whitespace from the source is collapsed to a single space, commas are
+/// always followed by one space, and parentheses never have inner padding.
+fn render_items(items: &[(bool, Item)]) -> String {
+    let mut out = String::new();
+    for (idx, (spaced, item)) in items.iter().enumerate() {
+        if idx > 0 {
+            let prev = &items[idx - 1].1;
+            let space = if item.is(&Token::Comma) || item.is(&Token::CloseParen) {
+                false
+            } else if prev.is(&Token::Comma) {
+                true
+            } else if prev.is(&Token::OpenParen) {
+                false
+            } else {
+                *spaced
+            };
+            if space {
+                out.push(' ');
+            }
+        }
+        out.push_str(&item.text());
+    }
+    out
+}
+
+/// The items of one field function, such as `db:default("NOW()")`.
+type FunctionGroup = Vec<(bool, Item)>;
+
+/// Splits a field line into its head (name and type) and its function
+/// groups (`ns:name` with optional arguments).
+///
+/// Returns `None` when the line does not look like a field, in which case
+/// it is printed as-is.
+fn split_field(items: &[(bool, Item)]) -> Option<(usize, Vec<FunctionGroup>)> {
+    if items.len() < 2 || !items[0].1.is_literal() || items[1].1.is(&Token::Colon) {
+        return None;
+    }
+    let is_group_start = |i: usize| {
+        items.len() > i + 2
+            && items[i].1.is_literal()
+            && items[i + 1].1.is(&Token::Colon)
+            && !items[i + 1].0
+            && items[i + 2].1.is_literal()
+    };
+    let head = (2..items.len())
+        .find(|i| is_group_start(*i))
+        .unwrap_or(items.len());
+    let mut groups = Vec::new();
+    let mut i = head;
+    while i < items.len() {
+        if !is_group_start(i) {
+            return None;
+        }
+        let mut end = i + 3;
+        if end < items.len() && items[end].1.is(&Token::OpenParen) && !items[end].0 {
+            let mut depth = 0;
+            while end < items.len() {
+                if items[end].1.is(&Token::OpenParen) {
+                    depth += 1;
+                } else if items[end].1.is(&Token::CloseParen) {
+                    depth -= 1;
+                    if depth == 0 {
+                        end += 1;
+                        break;
+                    }
+                }
+                end += 1;
+            }
+        }
+        let mut group = items[i..end].to_vec();
+        group[0].0 = true;
+        groups.push(group);
+        i = end;
+    }
+    Some((head, groups))
+}
+
+/// Re-emits a schema in canonical formatting.
+///
+/// The output uses one tab of indentation per open brace, collapses runs of
+/// blank lines, aligns field types within each run of consecutive fields in
+/// `struct`, `snippet`, and `mixin` bodies, and orders field functions by namespace
+/// (keeping their relative order within a namespace). Comments and quoted
+/// strings are preserved. Formatting is idempotent.
+///
+/// # Arguments
+/// * `source` - The schema source text
+///
+/// # Returns
+/// The formatted schema, ending in a single newline
+pub fn format_schema(source: &str) -> String {
+    // Field lines carry their name separately so the type column can be aligned.
+    struct Rendered {
+        depth: usize,
+        name: Option<String>,
+        body: String,
+        comment: Option<String>,
+    }
+
+    let mut rendered: Vec<Option<Rendered>> = Vec::new();
+    let mut depth: usize = 0;
+    let mut blocks: Vec<bool> = Vec::new();
+
+    for line in lex(source) {
+        if line.items.is_empty() && line.comment.is_none() {
+            rendered.push(None);
+            continue;
+        }
+        let leading_close = line
+            .items
+            .iter()
+            .take_while(|(_, i)| i.is(&Token::CloseBrace))
+            .count();
+        let line_depth = depth.saturating_sub(leading_close);
+        let in_fields = blocks.last().copied().unwrap_or(false) && leading_close == 0;
+
+        for (_, item) in &line.items {
+            if item.is(&Token::OpenBrace) {
+                let first = line.items.first().map(|(_, i)| i);
+                blocks.push(first.is_some_and(|f| {
+                    f.is(&Token::StructType)
+                        || f.is(&Token::SnippetType)
+                        || f.is(&Token::Literal("mixin".to_string()))
+                }));
+                depth += 1;
+            } else if item.is(&Token::CloseBrace) {
+                blocks.pop();
+                depth = depth.saturating_sub(1);
+            }
+        }
+
+        let field = if in_fields {
+            split_field(&line.items)
+        } else {
+            None
+        };
+        rendered.push(Some(match field {
+            Some((head, mut groups)) => {
+                groups.sort_by(|a, b| a[0].1.text().cmp(&b[0].1.text()));
+                let mut rest = line.items[1..head].to_vec();
+                rest[0].0 = false;
+                rest.extend(groups.into_iter().flatten());
+                Rendered {
+                    depth: line_depth,
+                    name: Some(line.items[0].1.text()),
+                    body: render_items(&rest),
+                    comment: line.comment,
+                }
+            }
+            None => Rendered {
+                depth: line_depth,
+                name: None,
+                body: render_items(&line.items),
+                comment: line.comment,
+            },
+        }));
+    }
+
+    // Drop blank lines at the edges of the file and of blocks, and collapse runs.
+    let mut lines: Vec<Option<Rendered>> = Vec::new();
+    for line in rendered {
+        match line {
+            None => {
+                let after_open = match lines.last() {
+                    Some(Some(prev)) => prev.body.ends_with('{') && prev.comment.is_none(),
+                    _ => true,
+                };
+                if !after_open {
+                    lines.push(None);
+                }
+            }
+            Some(line) => {
+                if line.body.starts_with('}') {
+                    while matches!(lines.last(), Some(None)) {
+                        lines.pop();
+                    }
+                }
+                lines.push(Some(line));
+            }
+        }
+    }
+    while matches!(lines.last(), Some(None)) {
+        lines.pop();
+    }
+
+    // Align field types within runs of consecutive field lines.
+    let mut widths = vec![0; lines.len()];
+    let mut start = 0;
+    while start < lines.len() {
+        let run = lines[start..]
+            .iter()
+            .take_while(|l| matches!(l, Some(Rendered { name: Some(_), .. })))
+            .count();
+        let width = lines[start..start + run]
+            .iter()
+            .filter_map(|l| l.as_ref().and_then(|l| l.name.as_ref()))
+            .map(|n| n.chars().count())
+            .max()
+            .unwrap_or(0);
+        for w in &mut widths[start..start + run] {
+            *w = width;
+        }
+        start += run.max(1);
+    }
+
+    let mut out = String::new();
+    for (line, width) in lines.iter().zip(widths) {
+        if let Some(line) = line {
+            out.push_str(&"\t".repeat(line.depth));
+            let mut text = match &line.name {
+                Some(name) => format!("{name:<width$} {}", line.body),
+                None => line.body.to_string(),
+            };
+            if let Some(comment) = &line.comment {
+                if !text.is_empty() {
+                    text.push(' ');
+                }
+                text.push_str(comment);
+            }
+            out.push_str(text.trim_end());
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Sets `key` in the schema's `schema { ... }` block to the quoted `value`,
+/// replacing the line that sets it or adding one at the end of the block.
+/// A schema without a block gets one at the top. The rest of the source is
+/// kept as written.
+///
+/// # Returns
+/// * `Some(String)` with the updated source
+/// * `None` if the block is not closed
+pub fn set_schema_meta(source: &str, key: &str, value: &str) -> Option<String> {
+    let entry = format!("\t{key} \"{value}\"");
+    let mut lines = source.lines().map(|l| l.to_string()).collect::<Vec<_>>();
+    let start = lines.iter().position(|line| {
+        line.trim()
+            .strip_prefix("schema")
+            .is_some_and(|rest| rest.trim() == "{")
+    });
+    let Some(start) = start else {
+        return Some(format!("schema {{\n{entry}\n}}\n\n{source}"));
+    };
+    let end = start + lines[start..].iter().position(|l| l.trim() == "}")?;
+    let existing = (start + 1..end).find(|i| lines[*i].split_whitespace().next() == Some(key));
+    match existing {
+        Some(pos) => lines[pos] = entry,
+        None => lines.insert(end, entry),
+    }
+    let mut updated = lines.join("\n");
+    updated.push('\n');
+    Some(updated)
+}
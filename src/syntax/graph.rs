@@ -0,0 +1,85 @@
+use super::{DependencyKind, ParseResult, StructDependency};
+
+/// Renders the struct and enum dependency graph of a schema in GraphViz DOT format.
+///
+/// Every struct and enum becomes a node, grouped into a cluster per category.
+/// Since DOT nodes can only live in one cluster, entities with several
+/// categories are placed in the cluster of their first category. Edges follow
+/// [`super::RepackStruct::dependencies`]: inheritance, struct and enum fields,
+/// `db.fk` references (resolved from table names), and joins.
+///
+/// # Arguments
+/// * `result` - The parsed schema
+///
+/// # Returns
+/// The DOT source for a `digraph`
+pub fn dependency_graph(result: &ParseResult) -> String {
+    let mut out = String::from("digraph repack {\n\trankdir=LR;\n\tnode [shape=box];\n");
+
+    let mut clusters: Vec<(&str, Vec<String>)> = Vec::new();
+    let mut loose = Vec::new();
+    let nodes = result
+        .strcts
+        .iter()
+        .map(|s| (s.categories.first(), format!("\"{}\";", s.name)))
+        .chain(result.enums.iter().map(|e| {
+            (
+                e.categories.first(),
+                format!("\"{}\" [shape=ellipse];", e.name),
+            )
+        }));
+    for (category, node) in nodes {
+        match category {
+            Some(category) => match clusters.iter_mut().find(|(name, _)| name == category) {
+                Some((_, members)) => members.push(node),
+                None => clusters.push((category, vec![node])),
+            },
+            None => loose.push(node),
+        }
+    }
+    for (idx, (category, members)) in clusters.iter().enumerate() {
+        out.push_str(&format!(
+            "\tsubgraph cluster_{idx} {{\n\t\tlabel=\"{category}\";\n"
+        ));
+        for node in members {
+            out.push_str(&format!("\t\t{node}\n"));
+        }
+        out.push_str("\t}\n");
+    }
+    for node in loose {
+        out.push_str(&format!("\t{node}\n"));
+    }
+
+    for strct in &result.strcts {
+        for StructDependency { kind, target, .. } in strct.dependencies() {
+            // Foreign keys name a table; point at the struct that owns it.
+            let target = match kind {
+                DependencyKind::ForeignKey => result
+                    .strcts
+                    .iter()
+                    .find(|s| {
+                        s.name == target
+                            || (s.inherits.is_none() && s.table_name.as_ref() == Some(&target))
+                    })
+                    .map(|s| s.name.to_string())
+                    .unwrap_or(target),
+                _ => target,
+            };
+            let style = match kind {
+                DependencyKind::Inherits => "[arrowhead=empty, label=\"inherits\"]",
+                DependencyKind::Field => "",
+                DependencyKind::Enum => "[style=dashed]",
+                DependencyKind::ForeignKey => "[label=\"fk\"]",
+                DependencyKind::Join => "[style=dotted, label=\"join\"]",
+            };
+            let edge = format!("\t\"{}\" -> \"{}\" {}", strct.name, target, style);
+            let edge = format!("{};\n", edge.trim_end());
+            if !out.contains(&edge) {
+                out.push_str(&edge);
+            }
+        }
+    }
+
+    out.push_str("}\n");
+    out
+}
@@ -0,0 +1,674 @@
+use std::collections::HashMap;
+
+use super::{
+    CategoryExpr, ConfigEntry, CustomFieldType, Field, FieldExternalLocation, FieldFunction,
+    FieldType, HttpRoute, Json, ObjectFunction, Output, ParseResult, Query, QueryArg, QueryReturn,
+    RepackConfig, RepackConst, RepackEnum, RepackEnumCase, RepackError, RepackErrorKind,
+    RepackStruct, RepackStructJoin, RepackUnion, Snippet,
+};
+
+/// Version of the JSON intermediate representation.
+///
+/// Bump this when a change to the format would break existing consumers.
+pub const IR_VERSION: f64 = 1.0;
+
+/// Serializes a parsed schema into the JSON intermediate representation.
+///
+/// The IR contains everything blueprints see: structs (with snippets already
+/// expanded, inherited tables applied, and `insert`/`update` shorthands turned
+/// into queries), enums, unions, constants, configs, outputs, snippets, the `schema { ... }` metadata, and the blueprint files the schema
+/// loads. Field types are resolved, with `type_kind` telling core types,
+/// structs, enums, and unions apart. Query bodies are kept as written, before
+/// `$` interpolation.
+///
+/// # Arguments
+/// * `result` - The parsed schema
+///
+/// # Returns
+/// The IR as a JSON object
+pub fn schema_to_ir(result: &ParseResult) -> Json {
+    Json::object([
+        ("ir_version", Json::Number(IR_VERSION)),
+        (
+            "meta",
+            Json::Object(
+                result
+                    .meta
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), Json::str(v)))
+                    .collect(),
+            ),
+        ),
+        (
+            "structs",
+            Json::Array(result.strcts.iter().map(struct_to_ir).collect()),
+        ),
+        (
+            "enums",
+            Json::Array(result.enums.iter().map(enum_to_ir).collect()),
+        ),
+        (
+            "unions",
+            Json::Array(
+                result
+                    .unions
+                    .iter()
+                    .map(|union| {
+                        Json::object([
+                            ("name", Json::str(&union.name)),
+                            ("categories", Json::strs(&union.categories)),
+                            ("variants", Json::strs(&union.variants)),
+                        ])
+                    })
+                    .collect(),
+            ),
+        ),
+        (
+            "consts",
+            Json::Array(
+                result
+                    .consts
+                    .iter()
+                    .map(|cnst| {
+                        Json::object([
+                            ("name", Json::str(&cnst.name)),
+                            ("categories", Json::strs(&cnst.categories)),
+                            ("type", Json::str(&cnst.field_type_string)),
+                            ("value", Json::str(&cnst.value)),
+                        ])
+                    })
+                    .collect(),
+            ),
+        ),
+        (
+            "configs",
+            Json::Array(result.configs.iter().map(config_to_ir).collect()),
+        ),
+        (
+            "outputs",
+            Json::Array(
+                result
+                    .languages
+                    .iter()
+                    .map(|output| {
+                        let mut options = output.options.iter().collect::<Vec<_>>();
+                        options.sort();
+                        Json::object([
+                            ("profile", Json::str(&output.profile)),
+                            ("location", Json::opt_str(output.location.as_ref())),
+                            ("categories", output_categories(output.categories.as_ref())),
+                            ("exclude", Json::strs(&output.exclude)),
+                            (
+                                "options",
+                                Json::Object(
+                                    options
+                                        .into_iter()
+                                        .map(|(k, v)| (k.to_string(), Json::str(v)))
+                                        .collect(),
+                                ),
+                            ),
+                        ])
+                    })
+                    .collect(),
+            ),
+        ),
+        (
+            "snippets",
+            Json::Array(
+                result
+                    .snippets
+                    .iter()
+                    .map(|snippet| {
+                        Json::object([
+                            ("name", Json::str(&snippet.name)),
+                            (
+                                "fields",
+                                Json::Array(snippet.fields.iter().map(field_to_ir).collect()),
+                            ),
+                            (
+                                "functions",
+                                Json::Array(
+                                    snippet
+                                        .functions
+                                        .iter()
+                                        .map(|f| function_to_ir(&f.namespace, &f.name, &f.args))
+                                        .collect(),
+                                ),
+                            ),
+                        ])
+                    })
+                    .collect(),
+            ),
+        ),
+        ("blueprints", Json::strs(&result.include_blueprints)),
+    ])
+}
+
+fn struct_to_ir(strct: &RepackStruct) -> Json {
+    Json::object([
+        ("name", Json::str(&strct.name)),
+        ("table", Json::opt_str(strct.table_name.as_ref())),
+        ("inherits", Json::opt_str(strct.inherits.as_ref())),
+        ("categories", Json::strs(&strct.categories)),
+        (
+            "fields",
+            Json::Array(strct.fields.iter().map(field_to_ir).collect()),
+        ),
+        (
+            "functions",
+            Json::Array(
+                strct
+                    .functions
+                    .iter()
+                    .map(|f| function_to_ir(&f.namespace, &f.name, &f.args))
+                    .collect(),
+            ),
+        ),
+        (
+            "joins",
+            Json::Array(
+                strct
+                    .joins
+                    .iter()
+                    .map(|join| {
+                        Json::object([
+                            ("name", Json::str(&join.name)),
+                            ("entity", Json::str(&join.foreign_entity)),
+                            ("condition", Json::str(&join.contents)),
+                        ])
+                    })
+                    .collect(),
+            ),
+        ),
+        (
+            "queries",
+            Json::Array(
+                strct
+                    .queries
+                    .iter()
+                    .map(|query| {
+                        let returns = match query.ret_type {
+                            QueryReturn::None => "none",
+                            QueryReturn::One => "one",
+                            QueryReturn::Many => "many",
+                        };
+                        Json::object([
+                            ("name", Json::str(&query.name)),
+                            (
+                                "args",
+                                Json::Array(
+                                    query
+                                        .args
+                                        .iter()
+                                        .map(|arg| {
+                                            Json::object([
+                                                ("name", Json::str(&arg.name)),
+                                                ("type", Json::str(&arg.typ)),
+                                                ("optional", Json::Bool(arg.optional)),
+                                                ("array", Json::Bool(arg.array)),
+                                            ])
+                                        })
+                                        .collect(),
+                                ),
+                            ),
+                            ("contents", Json::str(&query.contents)),
+                            ("returns", Json::str(returns)),
+                            (
+                                "http",
+                                match &query.http {
+                                    Some(route) => Json::object([
+                                        ("method", Json::str(&route.method)),
+                                        ("path", Json::str(&route.path)),
+                                    ]),
+                                    None => Json::Null,
+                                },
+                            ),
+                        ])
+                    })
+                    .collect(),
+            ),
+        ),
+    ])
+}
+
+fn field_to_ir(field: &Field) -> Json {
+    let type_kind = match &field.field_type {
+        Some(FieldType::Core(_)) => "core",
+        Some(FieldType::Custom(_, CustomFieldType::Object)) => "struct",
+        Some(FieldType::Custom(_, CustomFieldType::Enum)) => "enum",
+        Some(FieldType::Custom(_, CustomFieldType::Union)) => "union",
+        None => "unresolved",
+    };
+    let field_type = field
+        .field_type
+        .as_ref()
+        .map(|t| t.to_string())
+        .unwrap_or_else(|| field.field_type_string.to_string());
+    Json::object([
+        ("name", Json::str(&field.name)),
+        ("type", Json::str(&field_type)),
+        ("type_kind", Json::str(type_kind)),
+        ("optional", Json::Bool(field.optional)),
+        ("array", Json::Bool(field.array)),
+        (
+            "location",
+            field
+                .field_location
+                .as_ref()
+                .map(|loc| {
+                    Json::object([
+                        ("location", Json::str(&loc.location)),
+                        ("field", Json::str(&loc.field)),
+                    ])
+                })
+                .unwrap_or(Json::Null),
+        ),
+        ("categories", Json::strs(&field.categories)),
+        (
+            "functions",
+            Json::Array(
+                field
+                    .functions
+                    .iter()
+                    .map(|f| function_to_ir(&f.namespace, &f.name, &f.args))
+                    .collect(),
+            ),
+        ),
+    ])
+}
+
+fn function_to_ir(namespace: &str, name: &str, args: &[String]) -> Json {
+    Json::object([
+        ("namespace", Json::str(namespace)),
+        ("name", Json::str(name)),
+        ("args", Json::strs(args)),
+    ])
+}
+
+fn enum_to_ir(enm: &RepackEnum) -> Json {
+    Json::object([
+        ("name", Json::str(&enm.name)),
+        ("categories", Json::strs(&enm.categories)),
+        (
+            "cases",
+            Json::Array(
+                enm.options
+                    .iter()
+                    .map(|case| {
+                        Json::object([
+                            ("name", Json::str(&case.name)),
+                            ("value", Json::opt_str(case.value.as_ref())),
+                            (
+                                "functions",
+                                Json::Array(
+                                    case.functions
+                                        .iter()
+                                        .map(|f| function_to_ir(&f.namespace, &f.name, &f.args))
+                                        .collect(),
+                                ),
+                            ),
+                        ])
+                    })
+                    .collect(),
+            ),
+        ),
+    ])
+}
+
+fn config_to_ir(config: &RepackConfig) -> Json {
+    Json::object([
+        ("name", Json::str(&config.name)),
+        ("categories", Json::strs(&config.categories)),
+        (
+            "entries",
+            Json::Array(
+                config
+                    .entries
+                    .iter()
+                    .map(|entry| {
+                        Json::object([
+                            ("name", Json::str(&entry.name)),
+                            ("type", Json::str(&entry.field_type_string)),
+                            ("optional", Json::Bool(entry.optional)),
+                            ("default", Json::opt_str(entry.default.as_ref())),
+                        ])
+                    })
+                    .collect(),
+            ),
+        ),
+    ])
+}
+
+/// Writes an output's categories as a list when they are plain alternatives,
+/// and as an expression string otherwise.
+fn output_categories(categories: Option<&CategoryExpr>) -> Json {
+    match categories {
+        None => Json::Array(Vec::new()),
+        Some(expr) => match expr.as_list() {
+            Some(list) => Json::strs(&list),
+            None => Json::str(&expr.to_string()),
+        },
+    }
+}
+
+/// Reads a schema from the JSON intermediate representation.
+///
+/// This accepts what [`schema_to_ir`] writes, so a schema can be produced by
+/// another tool and built like a `.repack` file. Like [`ParseResult::read_contents`],
+/// this returns unresolved definitions; [`ParseResult::resolve`] applies the
+/// same resolution and validation as for parsed schemas. Only the fields needed
+/// to rebuild the schema are read: resolved details such as `type_kind` are
+/// recomputed, and keys holding lists or flags may be left out.
+///
+/// # Arguments
+/// * `source` - The JSON document
+///
+/// # Returns
+/// * `Ok(ParseResult)` with the unresolved definitions
+/// * `Err(Vec<RepackError>)` if the JSON is malformed or does not match the format
+pub fn schema_from_ir(source: &str) -> Result<ParseResult, Vec<RepackError>> {
+    let root = Json::parse(source).map_err(|e| vec![invalid(e)])?;
+    read_ir(&root).map_err(|e| vec![e])
+}
+
+fn invalid(msg: String) -> RepackError {
+    RepackError::global(RepackErrorKind::InvalidJson, msg)
+}
+
+fn read_ir(root: &Json) -> Result<ParseResult, RepackError> {
+    if !matches!(root, Json::Object(_)) {
+        return Err(invalid("the document must be an object".to_string()));
+    }
+    match root.get("ir_version") {
+        None => {}
+        Some(Json::Number(version)) if *version <= IR_VERSION => {}
+        Some(other) => {
+            return Err(invalid(format!(
+                "ir_version {} is not supported (expected at most {IR_VERSION})",
+                other.to_pretty_string().trim_end()
+            )));
+        }
+    }
+    Ok(ParseResult {
+        strcts: each(root, "", "structs", read_struct)?,
+        languages: each(root, "", "outputs", read_output)?,
+        enums: each(root, "", "enums", read_enum)?,
+        unions: each(root, "", "unions", |union, path| {
+            Ok(RepackUnion {
+                name: string(union, path, "name")?,
+                categories: strings(union, path, "categories")?,
+                variants: strings(union, path, "variants")?,
+            })
+        })?,
+        consts: each(root, "", "consts", |cnst, path| {
+            let field_type_string = string(cnst, path, "type")?;
+            Ok(RepackConst {
+                name: string(cnst, path, "name")?,
+                categories: strings(cnst, path, "categories")?,
+                field_type: FieldType::from_string(&field_type_string),
+                field_type_string,
+                value: string(cnst, path, "value")?,
+            })
+        })?,
+        configs: each(root, "", "configs", read_config)?,
+        snippets: each(root, "", "snippets", |snippet, path| {
+            Ok(Snippet {
+                name: string(snippet, path, "name")?,
+                fields: each(snippet, path, "fields", read_field)?,
+                functions: each(snippet, path, "functions", read_object_function)?,
+            })
+        })?,
+        mixins: Vec::new(),
+        include_blueprints: strings(root, "", "blueprints")?,
+        allowed_lints: Vec::new(),
+        meta: read_meta(root)?,
+        warnings: Vec::new(),
+    })
+}
+
+fn read_struct(strct: &Json, path: &str) -> Result<RepackStruct, RepackError> {
+    let inherits = opt_string(strct, path, "inherits")?;
+    // Children get their parent's table during resolution. Reading it here
+    // would make them look like owners of the table when ordering structs.
+    let table_name = match inherits {
+        Some(_) => None,
+        None => opt_string(strct, path, "table")?,
+    };
+    Ok(RepackStruct {
+        name: string(strct, path, "name")?,
+        fields: each(strct, path, "fields", read_field)?,
+        inherits,
+        categories: strings(strct, path, "categories")?,
+        table_name,
+        use_snippets: Vec::new(),
+        mixins: Vec::new(),
+        functions: each(strct, path, "functions", read_object_function)?,
+        queries: each(strct, path, "queries", |query, path| {
+            let ret_type = match string(query, path, "returns")?.as_str() {
+                "none" => QueryReturn::None,
+                "one" => QueryReturn::One,
+                "many" => QueryReturn::Many,
+                other => {
+                    return Err(invalid(format!(
+                        "{path}.returns must be one, many, or none, not '{other}'"
+                    )));
+                }
+            };
+            Ok(Query {
+                name: string(query, path, "name")?,
+                args: each(query, path, "args", |arg, path| {
+                    Ok(QueryArg {
+                        name: string(arg, path, "name")?,
+                        typ: string(arg, path, "type")?,
+                        optional: flag(arg, path, "optional")?,
+                        array: flag(arg, path, "array")?,
+                    })
+                })?,
+                contents: string(query, path, "contents")?,
+                ret_type,
+                http: match query.get("http") {
+                    None | Some(Json::Null) => None,
+                    Some(route) => Some(HttpRoute {
+                        method: string(route, &format!("{path}.http"), "method")?,
+                        path: string(route, &format!("{path}.http"), "path")?,
+                    }),
+                },
+            })
+        })?,
+        joins: each(strct, path, "joins", |join, path| {
+            Ok(RepackStructJoin {
+                name: string(join, path, "name")?,
+                contents: string(join, path, "condition")?,
+                foreign_entity: string(join, path, "entity")?,
+            })
+        })?,
+        autoinsertqueries: Vec::new(),
+        autoupdatequeries: Vec::new(),
+    })
+}
+
+fn read_field(field: &Json, path: &str) -> Result<Field, RepackError> {
+    let field_location = match field.get("location") {
+        None | Some(Json::Null) => None,
+        Some(location) => {
+            let path = format!("{path}.location");
+            Some(FieldExternalLocation {
+                location: string(location, &path, "location")?,
+                field: string(location, &path, "field")?,
+            })
+        }
+    };
+    // Fields read from a join or `super` take their type from the referenced field.
+    let (field_type_string, field_type) = match &field_location {
+        Some(loc) => (format!("{}.{}", loc.location, loc.field), None),
+        None => {
+            let typ = string(field, path, "type")?;
+            let field_type = FieldType::from_string(&typ);
+            (typ, field_type)
+        }
+    };
+    Ok(Field {
+        name: string(field, path, "name")?,
+        field_type_string,
+        field_location,
+        field_type,
+        optional: flag(field, path, "optional")?,
+        array: flag(field, path, "array")?,
+        categories: strings(field, path, "categories")?,
+        functions: each(field, path, "functions", read_field_function)?,
+    })
+}
+
+fn read_field_function(function: &Json, path: &str) -> Result<FieldFunction, RepackError> {
+    Ok(FieldFunction {
+        namespace: string(function, path, "namespace")?,
+        name: string(function, path, "name")?,
+        args: strings(function, path, "args")?,
+    })
+}
+
+fn read_object_function(function: &Json, path: &str) -> Result<ObjectFunction, RepackError> {
+    Ok(ObjectFunction {
+        namespace: string(function, path, "namespace")?,
+        name: string(function, path, "name")?,
+        args: strings(function, path, "args")?,
+    })
+}
+
+fn read_enum(enm: &Json, path: &str) -> Result<RepackEnum, RepackError> {
+    Ok(RepackEnum {
+        name: string(enm, path, "name")?,
+        categories: strings(enm, path, "categories")?,
+        options: each(enm, path, "cases", |case, path| {
+            Ok(RepackEnumCase {
+                name: string(case, path, "name")?,
+                value: opt_string(case, path, "value")?,
+                functions: each(case, path, "functions", read_field_function)?,
+            })
+        })?,
+    })
+}
+
+fn read_config(config: &Json, path: &str) -> Result<RepackConfig, RepackError> {
+    Ok(RepackConfig {
+        name: string(config, path, "name")?,
+        categories: strings(config, path, "categories")?,
+        entries: each(config, path, "entries", |entry, path| {
+            let field_type_string = string(entry, path, "type")?;
+            Ok(ConfigEntry {
+                name: string(entry, path, "name")?,
+                field_type: FieldType::from_string(&field_type_string),
+                field_type_string,
+                optional: flag(entry, path, "optional")?,
+                default: opt_string(entry, path, "default")?,
+            })
+        })?,
+    })
+}
+
+/// Reads the schema metadata object, keeping its keys in order.
+fn read_meta(root: &Json) -> Result<Vec<(String, String)>, RepackError> {
+    match root.get("meta") {
+        None | Some(Json::Null) => Ok(Vec::new()),
+        Some(Json::Object(pairs)) => pairs
+            .iter()
+            .map(|(key, value)| match value.as_str() {
+                Some(value) => Ok((key.to_string(), value.to_string())),
+                None => Err(invalid(format!("meta.{key} must be a string"))),
+            })
+            .collect(),
+        Some(_) => Err(invalid("meta must be an object".to_string())),
+    }
+}
+
+fn read_output(output: &Json, path: &str) -> Result<Output, RepackError> {
+    let mut options = HashMap::new();
+    match output.get("options") {
+        None | Some(Json::Null) => {}
+        Some(Json::Object(pairs)) => {
+            for (key, value) in pairs {
+                let Some(value) = value.as_str() else {
+                    return Err(invalid(format!("{path}.options.{key} must be a string")));
+                };
+                options.insert(key.to_string(), value.to_string());
+            }
+        }
+        Some(_) => return Err(invalid(format!("{path}.options must be an object"))),
+    }
+    Ok(Output {
+        profile: string(output, path, "profile")?,
+        location: opt_string(output, path, "location")?,
+        categories: read_output_categories(output, path)?,
+        options,
+        exclude: strings(output, path, "exclude")?,
+    })
+}
+
+/// Reads an output's categories: a list of alternatives or an expression string.
+fn read_output_categories(output: &Json, path: &str) -> Result<Option<CategoryExpr>, RepackError> {
+    if let Some(Json::String(expr)) = output.get("categories") {
+        return CategoryExpr::parse(expr).map(Some).map_err(|e| {
+            let details = e.error_details.unwrap_or_default();
+            invalid(format!("{path}.categories: {details}"))
+        });
+    }
+    Ok(strings(output, path, "categories")?
+        .into_iter()
+        .map(CategoryExpr::Category)
+        .reduce(CategoryExpr::or))
+}
+
+/// Reads each element of an optional array with `read`, passing the element's path.
+fn each<T>(
+    value: &Json,
+    path: &str,
+    key: &str,
+    read: impl Fn(&Json, &str) -> Result<T, RepackError>,
+) -> Result<Vec<T>, RepackError> {
+    let path = if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{path}.{key}")
+    };
+    match value.get(key) {
+        None | Some(Json::Null) => Ok(Vec::new()),
+        Some(Json::Array(items)) => items
+            .iter()
+            .enumerate()
+            .map(|(idx, item)| read(item, &format!("{path}[{idx}]")))
+            .collect(),
+        Some(_) => Err(invalid(format!("{path} must be an array"))),
+    }
+}
+
+fn string(value: &Json, path: &str, key: &str) -> Result<String, RepackError> {
+    value
+        .get(key)
+        .and_then(Json::as_str)
+        .map(|s| s.to_string())
+        .ok_or_else(|| invalid(format!("{path}.{key} must be a string")))
+}
+
+fn opt_string(value: &Json, path: &str, key: &str) -> Result<Option<String>, RepackError> {
+    match value.get(key) {
+        None | Some(Json::Null) => Ok(None),
+        Some(Json::String(s)) => Ok(Some(s.to_string())),
+        Some(_) => Err(invalid(format!("{path}.{key} must be a string or null"))),
+    }
+}
+
+fn strings(value: &Json, path: &str, key: &str) -> Result<Vec<String>, RepackError> {
+    each(value, path, key, |item, path| {
+        item.as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| invalid(format!("{path} must be a string")))
+    })
+}
+
+fn flag(value: &Json, path: &str, key: &str) -> Result<bool, RepackError> {
+    match value.get(key) {
+        None | Some(Json::Null) => Ok(false),
+        Some(Json::Bool(b)) => Ok(*b),
+        Some(_) => Err(invalid(format!("{path}.{key} must be a boolean"))),
+    }
+}
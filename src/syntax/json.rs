@@ -0,0 +1,437 @@
+/// A JSON value, used for the schema's intermediate representation.
+///
+/// Repack has no dependencies, so this is a small self-contained model rather
+/// than serde. Object keys keep their insertion order so output is stable.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+impl Json {
+    /// Builds an object from `(key, value)` pairs.
+    pub fn object<const N: usize>(pairs: [(&str, Json); N]) -> Json {
+        Json::Object(pairs.into_iter().map(|(k, v)| (k.to_string(), v)).collect())
+    }
+
+    /// Builds a string value.
+    pub fn str(val: &str) -> Json {
+        Json::String(val.to_string())
+    }
+
+    /// Builds a string value, or `null` when absent.
+    pub fn opt_str(val: Option<&String>) -> Json {
+        val.map(|v| Json::str(v)).unwrap_or(Json::Null)
+    }
+
+    /// Builds an array of strings.
+    pub fn strs(vals: &[String]) -> Json {
+        Json::Array(vals.iter().map(|v| Json::str(v)).collect())
+    }
+
+    /// Parses a JSON document.
+    ///
+    /// # Returns
+    /// * `Ok(Json)` with the parsed value
+    /// * `Err(String)` describing the first syntax error and its line
+    pub fn parse(source: &str) -> Result<Json, String> {
+        let mut parser = Parser {
+            chars: source.chars().collect(),
+            pos: 0,
+        };
+        let value = parser.value()?;
+        parser.whitespace();
+        if parser.pos < parser.chars.len() {
+            return Err(parser.error("unexpected content after the document"));
+        }
+        Ok(value)
+    }
+
+    /// Looks up a key on an object. Returns `None` for other values.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(pairs) => pairs.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Serializes the value with two-space indentation.
+    pub fn to_pretty_string(&self) -> String {
+        let mut out = String::new();
+        self.write(&mut out, 0);
+        out.push('\n');
+        out
+    }
+
+    /// Serializes the value on a single line, for line-delimited output.
+    pub fn to_compact_string(&self) -> String {
+        let mut out = String::new();
+        self.write_compact(&mut out);
+        out
+    }
+
+    fn write_compact(&self, out: &mut String) {
+        match self {
+            Json::Array(items) => {
+                out.push('[');
+                for (idx, item) in items.iter().enumerate() {
+                    if idx > 0 {
+                        out.push(',');
+                    }
+                    item.write_compact(out);
+                }
+                out.push(']');
+            }
+            Json::Object(pairs) => {
+                out.push('{');
+                for (idx, (key, value)) in pairs.iter().enumerate() {
+                    if idx > 0 {
+                        out.push(',');
+                    }
+                    write_string(out, key);
+                    out.push(':');
+                    value.write_compact(out);
+                }
+                out.push('}');
+            }
+            scalar => scalar.write(out, 0),
+        }
+    }
+
+    fn write(&self, out: &mut String, depth: usize) {
+        let indent = |out: &mut String, depth: usize| out.push_str(&"  ".repeat(depth));
+        match self {
+            Json::Null => out.push_str("null"),
+            Json::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+            Json::Number(n) => out.push_str(&n.to_string()),
+            Json::String(s) => write_string(out, s),
+            Json::Array(items) if items.is_empty() => out.push_str("[]"),
+            Json::Array(items) => {
+                out.push_str("[\n");
+                for (idx, item) in items.iter().enumerate() {
+                    indent(out, depth + 1);
+                    item.write(out, depth + 1);
+                    if idx + 1 < items.len() {
+                        out.push(',');
+                    }
+                    out.push('\n');
+                }
+                indent(out, depth);
+                out.push(']');
+            }
+            Json::Object(pairs) if pairs.is_empty() => out.push_str("{}"),
+            Json::Object(pairs) => {
+                out.push_str("{\n");
+                for (idx, (key, value)) in pairs.iter().enumerate() {
+                    indent(out, depth + 1);
+                    write_string(out, key);
+                    out.push_str(": ");
+                    value.write(out, depth + 1);
+                    if idx + 1 < pairs.len() {
+                        out.push(',');
+                    }
+                    out.push('\n');
+                }
+                indent(out, depth);
+                out.push('}');
+            }
+        }
+    }
+}
+
+fn write_string(out: &mut String, val: &str) {
+    out.push('"');
+    for c in val.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+impl Parser {
+    fn error(&self, msg: &str) -> String {
+        let line = self.chars[..self.pos.min(self.chars.len())]
+            .iter()
+            .filter(|c| **c == '\n')
+            .count()
+            + 1;
+        format!("{msg} (line {line})")
+    }
+
+    fn whitespace(&mut self) {
+        while self
+            .chars
+            .get(self.pos)
+            .is_some_and(|c| c.is_ascii_whitespace())
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        self.whitespace();
+        if self.chars.get(self.pos) == Some(&c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{c}'")))
+        }
+    }
+
+    fn keyword(&mut self, word: &str, value: Json) -> Result<Json, String> {
+        let end = self.pos + word.len();
+        if end <= self.chars.len() && self.chars[self.pos..end].iter().copied().eq(word.chars()) {
+            self.pos = end;
+            Ok(value)
+        } else {
+            Err(self.error("unexpected token"))
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.whitespace();
+        match self.chars.get(self.pos) {
+            None => Err(self.error("unexpected end of document")),
+            Some('n') => self.keyword("null", Json::Null),
+            Some('t') => self.keyword("true", Json::Bool(true)),
+            Some('f') => self.keyword("false", Json::Bool(false)),
+            Some('"') => self.string().map(Json::String),
+            Some('[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                self.whitespace();
+                if self.chars.get(self.pos) == Some(&']') {
+                    self.pos += 1;
+                    return Ok(Json::Array(items));
+                }
+                loop {
+                    items.push(self.value()?);
+                    self.whitespace();
+                    match self.chars.get(self.pos) {
+                        Some(',') => self.pos += 1,
+                        Some(']') => {
+                            self.pos += 1;
+                            return Ok(Json::Array(items));
+                        }
+                        _ => return Err(self.error("expected ',' or ']'")),
+                    }
+                }
+            }
+            Some('{') => {
+                self.pos += 1;
+                let mut pairs = Vec::new();
+                self.whitespace();
+                if self.chars.get(self.pos) == Some(&'}') {
+                    self.pos += 1;
+                    return Ok(Json::Object(pairs));
+                }
+                loop {
+                    self.whitespace();
+                    if self.chars.get(self.pos) != Some(&'"') {
+                        return Err(self.error("expected an object key"));
+                    }
+                    let key = self.string()?;
+                    self.expect(':')?;
+                    pairs.push((key, self.value()?));
+                    self.whitespace();
+                    match self.chars.get(self.pos) {
+                        Some(',') => self.pos += 1,
+                        Some('}') => {
+                            self.pos += 1;
+                            return Ok(Json::Object(pairs));
+                        }
+                        _ => return Err(self.error("expected ',' or '}'")),
+                    }
+                }
+            }
+            Some(c) if *c == '-' || c.is_ascii_digit() => {
+                let start = self.pos;
+                while self
+                    .chars
+                    .get(self.pos)
+                    .is_some_and(|c| c.is_ascii_digit() || "+-.eE".contains(*c))
+                {
+                    self.pos += 1;
+                }
+                let text: String = self.chars[start..self.pos].iter().collect();
+                text.parse::<f64>()
+                    .map(Json::Number)
+                    .map_err(|_| self.error(&format!("invalid number '{text}'")))
+            }
+            Some(_) => Err(self.error("unexpected character")),
+        }
+    }
+
+    /// Reads a string starting at the opening quote.
+    fn string(&mut self) -> Result<String, String> {
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let Some(c) = self.chars.get(self.pos).copied() else {
+                return Err(self.error("unterminated string"));
+            };
+            self.pos += 1;
+            match c {
+                '"' => return Ok(out),
+                '\\' => {
+                    let Some(escape) = self.chars.get(self.pos).copied() else {
+                        return Err(self.error("unterminated string"));
+                    };
+                    self.pos += 1;
+                    match escape {
+                        '"' => out.push('"'),
+                        '\\' => out.push('\\'),
+                        '/' => out.push('/'),
+                        'b' => out.push('\u{8}'),
+                        'f' => out.push('\u{c}'),
+                        'n' => out.push('\n'),
+                        'r' => out.push('\r'),
+                        't' => out.push('\t'),
+                        'u' => {
+                            let mut code = self.hex4()?;
+                            // Characters outside the BMP are written as a surrogate pair.
+                            if (0xD800..0xDC00).contains(&code)
+                                && self.chars.get(self.pos) == Some(&'\\')
+                                && self.chars.get(self.pos + 1) == Some(&'u')
+                            {
+                                self.pos += 2;
+                                let low = self.hex4()?;
+                                code = 0x10000
+                                    + ((code - 0xD800) << 10)
+                                    + (low.wrapping_sub(0xDC00) & 0x3FF);
+                            }
+                            out.push(char::from_u32(code).unwrap_or('\u{FFFD}'));
+                        }
+                        _ => return Err(self.error(&format!("invalid escape '\\{escape}'"))),
+                    }
+                }
+                c => out.push(c),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let end = self.pos + 4;
+        let digits: String = self
+            .chars
+            .get(self.pos..end)
+            .unwrap_or_default()
+            .iter()
+            .collect();
+        let code =
+            u32::from_str_radix(&digits, 16).map_err(|_| self.error("invalid unicode escape"))?;
+        self.pos = end;
+        Ok(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_documents() {
+        let doc = Json::parse(r#" {"a": [1, -2.5e1, true, null], "b": {"c": "d"}, "e": []} "#);
+        assert_eq!(
+            doc,
+            Ok(Json::object([
+                (
+                    "a",
+                    Json::Array(vec![
+                        Json::Number(1.0),
+                        Json::Number(-25.0),
+                        Json::Bool(true),
+                        Json::Null
+                    ])
+                ),
+                ("b", Json::object([("c", Json::str("d"))])),
+                ("e", Json::Array(Vec::new())),
+            ]))
+        );
+    }
+
+    #[test]
+    fn keeps_key_order_and_round_trips() {
+        let source = r#"{"z": 1, "a": {"y": [true, "x\ny"]}, "m": {}}"#;
+        let doc = Json::parse(source).unwrap();
+        let keys = match &doc {
+            Json::Object(pairs) => pairs.iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>(),
+            _ => Vec::new(),
+        };
+        assert_eq!(keys, ["z", "a", "m"]);
+        assert_eq!(Json::parse(&doc.to_pretty_string()), Ok(doc.clone()));
+        assert_eq!(Json::parse(&doc.to_compact_string()), Ok(doc));
+    }
+
+    #[test]
+    fn reads_escapes() {
+        assert_eq!(
+            Json::parse(r#""a\"b\\c\/d\n\té😀""#),
+            Ok(Json::str("a\"b\\c/d\n\té😀"))
+        );
+        // A lone surrogate can't be a char, so it becomes the replacement character.
+        assert_eq!(Json::parse(r#""\ud800""#), Ok(Json::str("\u{FFFD}")));
+    }
+
+    #[test]
+    fn escapes_control_characters_when_writing() {
+        let doc = Json::str("tab\tquote\"bell\u{7}");
+        assert_eq!(doc.to_compact_string(), r#""tab\tquote\"bell\u0007""#);
+    }
+
+    #[test]
+    fn rejects_malformed_documents() {
+        let error = |source: &str| Json::parse(source).unwrap_err();
+        assert_eq!(error(""), "unexpected end of document (line 1)");
+        assert_eq!(error("[1, 2,]"), "unexpected character (line 1)");
+        assert_eq!(error("{\"a\": 1,}"), "expected an object key (line 1)");
+        assert_eq!(error("{\"a\" 1}"), "expected ':' (line 1)");
+        assert_eq!(error("[1 2]"), "expected ',' or ']' (line 1)");
+        assert_eq!(error("{\"a\": 1 \"b\": 2}"), "expected ',' or '}' (line 1)");
+        assert_eq!(error("\"abc"), "unterminated string (line 1)");
+        assert_eq!(error(r#""\x""#), "invalid escape '\\x' (line 1)");
+        assert_eq!(error(r#""\u12""#), "invalid unicode escape (line 1)");
+        assert_eq!(error("tru"), "unexpected token (line 1)");
+        assert_eq!(error("1.2.3"), "invalid number '1.2.3' (line 1)");
+        assert_eq!(
+            error("{} {}"),
+            "unexpected content after the document (line 1)"
+        );
+        assert_eq!(error("{\n\"a\":\n}"), "unexpected character (line 3)");
+    }
+}
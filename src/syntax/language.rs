@@ -1,4 +1,4 @@
-use super::{FileContents, RepackError, Token};
+use super::{CategoryExpr, FileContents, RepackError, RepackErrorKind, Token};
 use std::collections::HashMap;
 
 /// Represents an output configuration that specifies code generation targets.
@@ -13,9 +13,10 @@ pub struct Output {
     /// Optional directory path where generated files should be written
     /// If None, files are written to the current directory
     pub location: Option<String>,
-    /// List of categories to include in this output (e.g., "#model", "#api")
-    /// Only objects/enums tagged with these categories will be generated
-    pub categories: Vec<String>,
+    /// Which categories to include in this output (e.g., `#model #api` or
+    /// `#api & !#internal`). When set, only objects/enums whose categories
+    /// match are generated; untagged ones are always included.
+    pub categories: Option<CategoryExpr>,
     /// Additional options passed to the blueprint for customization
     /// Used for blueprint-specific configuration like package names
     pub options: HashMap<String, String>,
@@ -28,37 +29,66 @@ impl Output {
     ///
     /// This method reads output configuration syntax and constructs an Output instance
     /// with its blueprint profile, location, categories, and options. It handles the
-    /// syntax: `output profile @location #category1 #category2 { options }`
+    /// syntax: `output profile @location #category1 #category2 { options }`,
+    /// where the categories may be any [`CategoryExpr`].
     ///
     /// # Arguments
     /// * `contents` - Mutable reference to the file contents being parsed
     ///
     /// # Returns
-    /// An Output instance with all parsed configuration options
-    pub fn from_contents(contents: &mut FileContents) -> Option<Output> {
-        let name_opt = contents.next()?;
-        let Token::Literal(name_ref) = name_opt else {
-            return None;
+    /// * `Ok(Output)` with all parsed configuration options
+    /// * `Err(RepackError)` if the profile name is missing or the category expression is malformed
+    pub fn from_contents(contents: &mut FileContents) -> Result<Output, RepackError> {
+        let Some(Token::Literal(name_ref)) = contents.next() else {
+            return Err(RepackError::global(
+                RepackErrorKind::ParseIncomplete,
+                "output profile name".to_string(),
+            ));
         };
-        let output_language = name_ref.to_string();
+        let mut output_language = name_ref.to_string();
+        // Profile names may be hyphenated, e.g. `typescript-zod`
+        while contents.peek() == Some(&Token::Minus) {
+            contents.skip();
+            output_language.push('-');
+            let Some(part) = contents.take_literal() else {
+                return Err(RepackError::global(
+                    RepackErrorKind::ParseIncomplete,
+                    format!("output profile name after '{output_language}'"),
+                ));
+            };
+            output_language.push_str(&part);
+        }
         let mut location = None;
         let mut options = HashMap::new();
-        let mut categories = Vec::new();
+        let mut categories: Option<CategoryExpr> = None;
         let exclude = Vec::new();
 
         let mut empty = false;
-        while let Some(token) = contents.next() {
+        loop {
+            let Some(token) = contents.peek() else {
+                return Err(RepackError::global(
+                    RepackErrorKind::ParseIncomplete,
+                    format!("';' or '{{' after output {output_language}"),
+                ));
+            };
+            if matches!(token, Token::Pound | Token::Exclamation | Token::OpenParen) {
+                // Separate expressions (`#a #b`) are alternatives.
+                let expr = CategoryExpr::read(contents)?;
+                categories = Some(match categories {
+                    Some(prev) => prev.or(expr),
+                    None => expr,
+                });
+                continue;
+            }
+            let Some(token) = contents.next() else {
+                break;
+            };
             match token {
                 Token::At => {
                     if let Some(Token::Literal(lit)) = contents.next() {
                         location = Some(lit.to_string());
                     }
                 }
-                Token::Pound => {
-                    if let Some(Token::Literal(lit)) = contents.next() {
-                        categories.push(lit.to_string());
-                    }
-                }
                 Token::OpenBrace => {
                     break;
                 }
@@ -71,7 +101,13 @@ impl Output {
         }
 
         if !empty {
-            while let Some(token) = contents.next() {
+            loop {
+                let Some(token) = contents.next() else {
+                    return Err(RepackError::global(
+                        RepackErrorKind::ParseIncomplete,
+                        format!("'}}' to close output {output_language}"),
+                    ));
+                };
                 match token {
                     Token::Literal(lit) => {
                         let key = lit.to_string();
@@ -91,13 +127,40 @@ impl Output {
             }
         }
 
-        Some(Output {
+        let mut output = Output {
             profile: output_language,
             location,
             categories,
             exclude,
             options,
-        })
+        };
+        output.interpolate_env()?;
+        Ok(output)
+    }
+
+    /// Replaces `${NAME}` in the location and option values with the
+    /// environment variable `NAME`, so paths and settings can differ between
+    /// machines, as in `@"${OUT_DIR}/models"`.
+    ///
+    /// # Returns
+    /// * `Ok(())` once every reference is replaced
+    /// * `Err(RepackError)` naming the first variable that is not set, or a
+    ///   `${` without a closing `}`
+    fn interpolate_env(&mut self) -> Result<(), RepackError> {
+        if let Some(location) = &self.location {
+            let location = interpolate_env(location).map_err(|(kind, e)| {
+                RepackError::from_lang_with_msg(kind, self, format!("{e} in location"))
+            })?;
+            self.location = Some(location);
+        }
+        let mut options = std::mem::take(&mut self.options);
+        for (key, value) in options.iter_mut() {
+            *value = interpolate_env(value).map_err(|(kind, e)| {
+                RepackError::from_lang_with_msg(kind, self, format!("{e} in option {key}"))
+            })?;
+        }
+        self.options = options;
+        Ok(())
     }
 
     pub fn errors(&self) -> Vec<RepackError> {
@@ -110,3 +173,31 @@ impl Output {
         Vec::new()
     }
 }
+
+/// Replaces each `${NAME}` in `value` with the environment variable `NAME`.
+///
+/// # Returns
+/// * `Ok(String)` with every reference replaced
+/// * `Err((RepackErrorKind, String))` naming the variable that is not set, or
+///   the value with an unclosed `${`
+fn interpolate_env(value: &str) -> Result<String, (RepackErrorKind, String)> {
+    let mut out = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        out.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('}') else {
+            return Err((
+                RepackErrorKind::ParseIncomplete,
+                format!("'}}' after ${{ in {value}"),
+            ));
+        };
+        let name = &rest[start + 2..start + end];
+        let Ok(var) = std::env::var(name) else {
+            return Err((RepackErrorKind::EnvVarNotSet, format!("${{{name}}}")));
+        };
+        out.push_str(&var);
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
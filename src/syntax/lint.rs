@@ -0,0 +1,249 @@
+use super::{DependencyKind, FileContents, ParseResult, RepackError, RepackErrorKind, Token};
+
+/// A style or hygiene rule checked by `repack lint`.
+///
+/// Every rule is enabled by default. Rules can be turned off in the schema with
+/// a `lint { allow rule_name }` block or on the command line with
+/// `--allow rule_name`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LintRule {
+    /// Structs stored in a table (and not inheriting it) need a `db:pk` field.
+    PrimaryKey,
+    /// Field names must be snake_case.
+    SnakeCase,
+    /// Enums must have at least two cases.
+    EnumCases,
+    /// Structs, enums, snippets, and mixins must be used. See [`unused_definitions`].
+    Unused,
+    /// Structs with `sensitive` fields must be tagged with the [`RESTRICTED_CATEGORY`].
+    SensitiveCategory,
+}
+
+/// The category a struct with `sensitive` fields is expected to carry, so
+/// outputs must opt in to rendering it.
+pub const RESTRICTED_CATEGORY: &str = "restricted";
+
+impl LintRule {
+    pub fn from_string(val: &str) -> Option<LintRule> {
+        match val {
+            "primary_key" => Some(LintRule::PrimaryKey),
+            "snake_case" => Some(LintRule::SnakeCase),
+            "enum_cases" => Some(LintRule::EnumCases),
+            "unused" => Some(LintRule::Unused),
+            "sensitive_category" => Some(LintRule::SensitiveCategory),
+            _ => None,
+        }
+    }
+
+    /// Parses a `lint { ... }` block, returning the rules it allows.
+    ///
+    /// Each line of the block has the form `allow rule_name`.
+    ///
+    /// # Arguments
+    /// * `contents` - Mutable reference to the file contents, positioned after `lint`
+    ///
+    /// # Returns
+    /// * `Ok(Vec<LintRule>)` with the allowed rules
+    /// * `Err(RepackError)` if the block is malformed or names an unknown rule
+    pub fn read_block(contents: &mut FileContents) -> Result<Vec<LintRule>, RepackError> {
+        if !matches!(contents.take(), Some(Token::OpenBrace)) {
+            return Err(RepackError::global(
+                RepackErrorKind::SyntaxError,
+                "lint block. Expected '{'.".to_string(),
+            ));
+        }
+        let mut allowed = Vec::new();
+        while let Some(token) = contents.take() {
+            match token {
+                Token::CloseBrace => return Ok(allowed),
+                Token::Literal(lit) if lit == "allow" => {
+                    let rule = contents.take_literal().unwrap_or_default();
+                    let Some(rule) = LintRule::from_string(&rule) else {
+                        return Err(RepackError::global(
+                            RepackErrorKind::SyntaxError,
+                            format!("lint block. Unknown rule '{rule}'."),
+                        ));
+                    };
+                    allowed.push(rule);
+                }
+                Token::NewLine => {}
+                other => {
+                    return Err(RepackError::global(
+                        RepackErrorKind::SyntaxError,
+                        format!("lint block. Expected 'allow', got {other:?}."),
+                    ));
+                }
+            }
+        }
+        Err(RepackError::global(
+            RepackErrorKind::ParseIncomplete,
+            "'}' to close the lint block".to_string(),
+        ))
+    }
+}
+
+/// Checks a parsed schema against every lint rule that is not allowed.
+///
+/// The `unused` rule is not checked here: [`unused_definitions`] runs for
+/// every command, so its warnings are reported alongside parse warnings.
+///
+/// # Arguments
+/// * `result` - The parsed schema
+/// * `allowed` - Rules to skip, from the schema's `lint` block and the command line
+///
+/// # Returns
+/// One warning per finding, in schema order
+pub fn lint(result: &ParseResult, allowed: &[LintRule]) -> Vec<RepackError> {
+    let mut findings = Vec::new();
+    let enabled = |rule: LintRule| !allowed.contains(&rule);
+
+    for strct in &result.strcts {
+        if enabled(LintRule::PrimaryKey)
+            && strct.table_name.is_some()
+            && strct.inherits.is_none()
+            && !strct
+                .fields
+                .iter()
+                .any(|f| f.function("db", "pk").is_some())
+        {
+            findings.push(RepackError::from_obj(
+                RepackErrorKind::MissingPrimaryKey,
+                strct,
+            ));
+        }
+        if enabled(LintRule::SnakeCase) {
+            for field in &strct.fields {
+                let snake = field
+                    .name
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+                if !snake {
+                    findings.push(RepackError::from_field(
+                        RepackErrorKind::NameNotSnakeCase,
+                        strct,
+                        field,
+                    ));
+                }
+            }
+        }
+        if enabled(LintRule::SensitiveCategory)
+            && !strct.categories.iter().any(|c| c == RESTRICTED_CATEGORY)
+        {
+            let sensitive = strct
+                .fields
+                .iter()
+                .filter(|f| f.is_sensitive())
+                .map(|f| f.name.as_str())
+                .collect::<Vec<_>>();
+            if !sensitive.is_empty() {
+                findings.push(RepackError::from_obj_with_msg(
+                    RepackErrorKind::SensitiveNotRestricted,
+                    strct,
+                    sensitive.join(", "),
+                ));
+            }
+        }
+    }
+
+    for enm in &result.enums {
+        if enabled(LintRule::EnumCases) && enm.options.len() < 2 {
+            findings.push(RepackError::from_enum(
+                RepackErrorKind::TooFewEnumCases,
+                enm,
+            ));
+        }
+    }
+
+    findings.into_iter().map(RepackError::warning).collect()
+}
+
+/// Finds structs, enums, snippets, and mixins that nothing uses.
+///
+/// A struct or enum is unused when no output renders it (category filters
+/// considered) and no other struct, query argument, or config key refers to
+/// it. A snippet or mixin is unused when no struct includes it. Schemas
+/// without any outputs only have their snippets and mixins checked.
+///
+/// # Arguments
+/// * `result` - The parsed schema
+///
+/// # Returns
+/// One warning per unused definition
+pub fn unused_definitions(result: &ParseResult) -> Vec<RepackError> {
+    let mut findings = Vec::new();
+    if !result.languages.is_empty() {
+        for strct in &result.strcts {
+            let emitted = result.languages.iter().any(|lang| {
+                result
+                    .included_strcts(lang.categories.as_ref(), &lang.exclude)
+                    .iter()
+                    .any(|s| s.name == strct.name)
+            });
+            if !emitted && !referenced(result, &strct.name, strct.table_name.as_ref()) {
+                findings.push(RepackError::from_obj(
+                    RepackErrorKind::UnusedDefinition,
+                    strct,
+                ));
+            }
+        }
+        for enm in &result.enums {
+            let emitted = result.languages.iter().any(|lang| {
+                result
+                    .included_enums(lang.categories.as_ref(), &lang.exclude)
+                    .iter()
+                    .any(|e| e.name == enm.name)
+            });
+            if !emitted && !referenced(result, &enm.name, None) {
+                findings.push(RepackError::from_enum(
+                    RepackErrorKind::UnusedDefinition,
+                    enm,
+                ));
+            }
+        }
+    }
+    for snippet in &result.snippets {
+        if !result
+            .strcts
+            .iter()
+            .any(|s| s.use_snippets.contains(&snippet.name))
+        {
+            findings.push(RepackError::from_snippet(
+                RepackErrorKind::UnusedDefinition,
+                snippet,
+            ));
+        }
+    }
+    for mixin in &result.mixins {
+        if !result.strcts.iter().any(|s| s.mixins.contains(&mixin.name)) {
+            findings.push(RepackError::from_mixin(
+                RepackErrorKind::UnusedDefinition,
+                mixin,
+            ));
+        }
+    }
+    findings.into_iter().map(RepackError::warning).collect()
+}
+
+/// Whether any struct, query argument, or config key refers to `name` (or,
+/// for foreign keys, to `table`).
+fn referenced(result: &ParseResult, name: &str, table: Option<&String>) -> bool {
+    let by_struct = result.strcts.iter().any(|s| {
+        s.name != name
+            && s.dependencies().iter().any(|dep| {
+                dep.target == name
+                    || (dep.kind == DependencyKind::ForeignKey && Some(&dep.target) == table)
+            })
+    });
+    let by_query = result
+        .strcts
+        .iter()
+        .flat_map(|s| s.queries.iter())
+        .flat_map(|q| q.args.iter())
+        .any(|arg| arg.typ == name);
+    let by_config = result
+        .configs
+        .iter()
+        .flat_map(|c| c.entries.iter())
+        .any(|entry| entry.field_type_string == name);
+    by_struct || by_query || by_config
+}
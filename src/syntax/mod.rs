@@ -1,28 +1,54 @@
+mod category;
+mod changes;
+mod defaults;
 mod dependancies;
 mod errors;
+mod formatter;
+mod graph;
+mod ir;
+mod json;
 mod language;
+mod lint;
 mod parser;
 mod query;
+mod repack_config;
+mod repack_const;
 mod repack_enum;
 mod repack_field;
 mod repack_field_function;
+mod repack_mixin;
 mod repack_struct;
 mod repack_struct_function;
+mod repack_union;
 mod result;
 mod snippet;
 mod tokens;
 mod types;
+mod validation;
 
+pub use category::CategoryExpr;
+pub use changes::*;
+pub use defaults::typed_default;
 pub use errors::*;
+pub use formatter::{format_schema, set_schema_meta};
+pub use graph::dependency_graph;
+pub use ir::*;
+pub use json::Json;
 pub use language::Output;
+pub use lint::*;
 pub use parser::FileContents;
+pub use query::*;
+pub use repack_config::*;
+pub use repack_const::*;
 pub use repack_enum::*;
 pub use repack_field::*;
 pub use repack_field_function::*;
+pub use repack_mixin::*;
 pub use repack_struct::*;
 pub use repack_struct_function::*;
+pub use repack_union::*;
 pub use result::ParseResult;
 pub use snippet::*;
 pub use tokens::*;
 pub use types::*;
-pub use query::*;
+pub use validation::*;
@@ -1,6 +1,20 @@
-use std::{env, fs, io::Read, path::PathBuf, process::exit};
+use std::{
+    collections::{HashSet, VecDeque},
+    env, fs,
+    io::Read,
+    path::{Path, PathBuf},
+    process::exit,
+};
 
-use super::Token;
+use super::{RepackError, RepackErrorKind, Token};
+
+/// A schema file that has been tokenized into a [`FileContents`].
+struct SourceFile {
+    /// Canonicalized path, used to detect repeated imports
+    path: PathBuf,
+    /// Index of the file's first token
+    start: usize,
+}
 
 /// Represents the tokenized contents of a schema file with parsing state.
 ///
@@ -14,10 +28,15 @@ pub struct FileContents {
     pub root: String,
     /// Current parsing position in the token stream
     pub index: usize,
+    /// Every file loaded so far, in load order
+    files: Vec<SourceFile>,
+    /// Import edges as (importer, imported) indexes into `files`
+    imports: Vec<(usize, usize)>,
+    /// Indexes of the literals that were written in double quotes
+    quoted: HashSet<usize>,
 }
 
 impl FileContents {
-    #[allow(dead_code)]
     pub fn empty() -> Self {
         FileContents {
             contents: Vec::new(),
@@ -26,6 +45,9 @@ impl FileContents {
                 .and_then(|p| p.to_str().map(|s| s.to_string()))
                 .unwrap_or_else(|| ".".to_string()),
             index: 0,
+            files: Vec::new(),
+            imports: Vec::new(),
+            quoted: HashSet::new(),
         }
     }
     /// Creates a new FileContents by reading and tokenizing the specified file.
@@ -46,54 +68,236 @@ impl FileContents {
             contents: Vec::new(),
             root: path.to_str().unwrap_or(".").to_string(),
             index: 0,
+            files: Vec::new(),
+            imports: Vec::new(),
+            quoted: HashSet::new(),
         };
-        contents.add(filename);
+        // The root file has no importer, so it cannot form a cycle.
+        _ = contents.add_imported(filename, None, false);
         contents
     }
 
     /// Adds additional file contents relative to the root directory.
     ///
-    /// This method supports both individual files and directory wildcards (ending with *).
-    /// For wildcards, it reads all .repack files in the specified directory.
+    /// This method supports both individual files and glob patterns using `*`,
+    /// `?`, `**` and `{a,b}`. Patterns load every matching .repack file in
+    /// sorted path order.
     /// Used for processing include directives in schema files.
     ///
+    /// Files that were already loaded are skipped, so overlapping imports never
+    /// duplicate definitions. An explicit import that closes a loop (for example
+    /// `a` imports `b` which imports `a`) is an error; a wildcard that matches
+    /// the importing file simply skips it.
+    ///
     /// # Arguments
-    /// * `filename` - Relative path to file or directory pattern to include
-    pub fn add_relative(&mut self, filename: &str) {
-        let mut path = PathBuf::from(&self.root);
-        if filename.ends_with("*") {
-            path.push(filename);
-            path.pop();
-            let Ok(mut folder_contents) = fs::read_dir(&path) else {
-                println!(
-                    "[EXIT] Unable to load requested folder '{}'",
-                    path.to_str().unwrap_or("<invalid path>")
-                );
-                exit(5);
-            };
-            while let Some(Ok(file)) = folder_contents.next() {
-                let path = file.path();
-                if let Some(extension) = path.extension() {
-                    if extension == "repack" {
-                        if let Some(path_str) = path.to_str() {
-                            self.add(path_str);
-                        }
-                    }
+    /// * `filename` - Relative path or glob pattern to include
+    ///
+    /// # Returns
+    /// * `Ok(())` if the files were loaded or skipped
+    /// * `Err(RepackError)` if the import creates a cycle or a pattern's folder is missing
+    pub fn add_relative(&mut self, filename: &str) -> Result<(), RepackError> {
+        let importer = self.current_file();
+        for pattern in expand_braces(filename) {
+            let mut path = PathBuf::from(&self.root);
+            if !pattern.contains(['*', '?']) {
+                path.push(&pattern);
+                if let Some(path_str) = path.to_str() {
+                    self.add_imported(path_str, importer, true)?;
                 }
+                continue;
             }
-        } else {
-            path.push(filename);
-            if let Some(path_str) = path.to_str() {
-                self.add(path_str);
+            let segments = pattern.split('/').collect::<Vec<_>>();
+            let literal = segments
+                .iter()
+                .take_while(|segment| !segment.contains(['*', '?']))
+                .count();
+            path.extend(&segments[..literal]);
+            if !path.is_dir() {
+                return Err(RepackError::global(
+                    RepackErrorKind::InvalidImportSource,
+                    format!("{} (no such folder)", path.display()),
+                ));
+            }
+            let mut paths = Vec::new();
+            glob_files(&path, &segments[literal..], &mut paths);
+            // Sort so definitions load in the same order on every platform.
+            paths.retain(|path| path.extension().is_some_and(|ext| ext == "repack"));
+            paths.sort();
+            for path in paths {
+                if let Some(path_str) = path.to_str() {
+                    self.add_imported(path_str, importer, false)?;
+                }
             }
         }
+        Ok(())
+    }
+
+    /// Returns the index of the file the current token came from.
+    ///
+    /// Each file's tokens are appended as one contiguous run, so the owner is
+    /// the last file that started at or before the current position.
+    fn current_file(&self) -> Option<usize> {
+        let position = self.index.saturating_sub(1);
+        self.files.iter().rposition(|file| file.start <= position)
+    }
+
+    /// Prepares an error from a definition that failed to parse and skips past it.
+    ///
+    /// Errors without a struct or field are labelled with the file they came
+    /// from. Tokens are then skipped up to the next `struct`, `enum`, `snippet`,
+    /// `output`, `import`, or `blueprint`, or a `config`, `union`, `const`,
+    /// `mixin`, `lint`, or `schema` definition, so later definitions are still
+    /// read and their errors reported together.
+    pub fn recover(&mut self, mut error: RepackError) -> RepackError {
+        if error.specifier.is_empty()
+            && let Some(file) = self
+                .current_file()
+                .and_then(|idx| self.files[idx].path.file_name())
+        {
+            error.specifier = format!(" ({})", file.to_string_lossy());
+        }
+        while let Some(token) = self.peek() {
+            if matches!(
+                token,
+                Token::StructType
+                    | Token::EnumType
+                    | Token::SnippetType
+                    | Token::OutputType
+                    | Token::Import
+                    | Token::Blueprint
+            ) || self.at_word_definition()
+            {
+                break;
+            }
+            self.skip();
+        }
+        error
+    }
+
+    /// Whether the current token starts a definition introduced by a word
+    /// rather than a keyword token, such as `config Server {`.
+    ///
+    /// Fields may use these words as names, so the word has to start a line
+    /// and be followed by the rest of a definition's header.
+    fn at_word_definition(&self) -> bool {
+        let Some(Token::Literal(word)) = self.peek() else {
+            return false;
+        };
+        if self.index > 0
+            && !matches!(
+                self.contents[self.index - 1],
+                Token::NewLine | Token::CloseBrace | Token::Semicolon
+            )
+        {
+            return false;
+        }
+        let rest = &self.contents[self.index + 1..];
+        match word.as_str() {
+            "lint" | "schema" => matches!(rest, [Token::OpenBrace, ..]),
+            "config" | "mixin" => {
+                matches!(
+                    rest,
+                    [Token::Literal(_), Token::OpenBrace | Token::Pound, ..]
+                )
+            }
+            "union" => matches!(rest, [Token::Literal(_), Token::Equal, ..]),
+            "const" => {
+                matches!(rest, [Token::Literal(_), Token::Literal(_), ..])
+                    && rest
+                        .iter()
+                        .take_while(|token| **token != Token::NewLine)
+                        .any(|token| *token == Token::Equal)
+            }
+            _ => false,
+        }
+    }
+
+    /// Loads a file unless it was loaded before, recording the import edge.
+    ///
+    /// # Arguments
+    /// * `filename` - Path to the file to read and tokenize
+    /// * `importer` - The file containing the import, if any
+    /// * `explicit` - Whether the file was named directly rather than matched by a wildcard
+    fn add_imported(
+        &mut self,
+        filename: &str,
+        importer: Option<usize>,
+        explicit: bool,
+    ) -> Result<(), RepackError> {
+        let canonical = fs::canonicalize(filename).unwrap_or_else(|_| PathBuf::from(filename));
+        let existing = self.files.iter().position(|file| file.path == canonical);
+        let (Some(target), Some(importer)) = (existing, importer) else {
+            if existing.is_none() {
+                self.load(filename, canonical, importer);
+            }
+            return Ok(());
+        };
+        if target == importer && !explicit {
+            return Ok(());
+        }
+        if let Some(path) = self.import_path(target, importer) {
+            if explicit {
+                let root = self.files.first().and_then(|f| f.path.parent());
+                let cycle = path
+                    .iter()
+                    .chain([&target])
+                    .map(|idx| {
+                        let path = &self.files[*idx].path;
+                        root.and_then(|root| path.strip_prefix(root).ok())
+                            .unwrap_or(path)
+                            .display()
+                            .to_string()
+                    })
+                    .collect::<Vec<_>>();
+                return Err(RepackError::global(
+                    RepackErrorKind::ImportCycle,
+                    cycle.join(" -> "),
+                ));
+            }
+            return Ok(());
+        }
+        self.imports.push((importer, target));
+        Ok(())
+    }
+
+    /// Tokenizes a file that has not been loaded yet.
+    fn load(&mut self, filename: &str, canonical: PathBuf, importer: Option<usize>) {
+        let idx = self.files.len();
+        self.files.push(SourceFile {
+            path: canonical,
+            start: self.contents.len(),
+        });
+        if let Some(importer) = importer {
+            self.imports.push((importer, idx));
+        }
+        self.add(filename);
+    }
+
+    /// Finds a chain of imports leading from `from` to `to`, inclusive.
+    fn import_path(&self, from: usize, to: usize) -> Option<Vec<usize>> {
+        let mut queue = VecDeque::from([vec![from]]);
+        let mut seen = vec![from];
+        while let Some(path) = queue.pop_front() {
+            let last = *path.last()?;
+            if last == to {
+                return Some(path);
+            }
+            for (_, next) in self.imports.iter().filter(|(src, _)| *src == last) {
+                if !seen.contains(next) {
+                    seen.push(*next);
+                    let mut path = path.clone();
+                    path.push(*next);
+                    queue.push_back(path);
+                }
+            }
+        }
+        None
     }
 
     /// Reads and tokenizes a specific file, appending its tokens to the contents.
     ///
-    /// This method handles the low-level file reading and tokenization process,
-    /// including comment parsing, string literal handling, and token recognition.
-    /// The tokenization process respects quoted strings and line comments (//).
+    /// This method handles the low-level file reading; see [`FileContents::add_string`]
+    /// for how the contents are tokenized.
     ///
     /// # Arguments
     /// * `filename` - Absolute path to the file to read and tokenize
@@ -104,61 +308,7 @@ impl FileContents {
         };
         let mut contents = vec![];
         _ = file.read_to_end(&mut contents);
-
-        let mut iter = contents.into_iter().peekable();
-
-        let mut buf: String = String::new();
-        let mut in_comment = false;
-        let mut in_quote = false;
-        loop {
-            let Some(byte) = iter.next() else {
-                break;
-            };
-            if byte == b'"' {
-                if in_quote {
-                    self.contents.push(Token::Literal(buf));
-                    buf = String::new();
-                } else if !buf.is_empty() {
-                    let token = Token::from_string(&buf);
-                    self.contents.push(token);
-                }
-                in_quote = !in_quote;
-                continue;
-            }
-            if in_quote {
-                buf.push(byte as char);
-            } else {
-                if byte == b'/' {
-                    if let Some(next_byte) = iter.peek() {
-                        if *next_byte == b'/' {
-                            in_comment = true;
-                            continue;
-                        }
-                    }
-                }
-                if !in_comment {
-                    match Token::from_byte(byte) {
-                        Some(token) => {
-                            if !buf.is_empty() {
-                                self.contents.push(Token::from_string(&buf));
-                                buf.clear();
-                            }
-                            self.contents.push(token);
-                        }
-                        None => {
-                            if !byte.is_ascii_whitespace() {
-                                buf.push(byte as char);
-                            } else if !buf.is_empty() {
-                                self.contents.push(Token::from_string(&buf));
-                                buf.clear();
-                            }
-                        }
-                    }
-                } else if byte == b'\n' || byte == b'\r' {
-                    in_comment = false;
-                }
-            }
-        }
+        self.tokenize(&contents);
     }
 
     /// Returns the current token without advancing the parsing position.
@@ -220,8 +370,23 @@ impl FileContents {
         }
     }
 
+    /// Whether the current token is a literal that was written in double quotes,
+    /// such as `"deprecated"` rather than `deprecated`.
+    pub fn peek_quoted(&self) -> bool {
+        self.quoted.contains(&self.index)
+    }
+
+    /// Consumes the current token if it is a colon.
+    ///
+    /// # Returns
+    /// * `true` if a colon was consumed
+    /// * `false` if the current token is anything else, which is left in place
     pub fn take_colon(&mut self) -> bool {
-        matches!(self.take(), Some(Token::Colon))
+        let colon = matches!(self.peek(), Some(Token::Colon));
+        if colon {
+            self.skip();
+        }
+        colon
     }
 
     pub fn peek_equals(&mut self) -> bool {
@@ -236,63 +401,414 @@ impl FileContents {
         self.index += 1;
     }
 
-    #[allow(dead_code)]
+    /// Tokenizes a string and appends its tokens.
+    ///
+    /// Line comments (`//`) are skipped. Strings are written in double quotes and may span lines. Inside them,
+    /// `\"`, `\\`, `\n`, and `\t` are escapes, and a `\` at the end of a line
+    /// joins it to the next, skipping the next line's indentation. Any other
+    /// backslash is kept as written. Triple-quoted strings (`"""`) take their
+    /// contents as written, with their shared indentation removed.
     pub fn add_string(&mut self, string: &str) {
-        let contents = string.bytes();
+        self.tokenize(string.as_bytes());
+    }
 
-        let mut iter = contents.into_iter().peekable();
+    fn tokenize(&mut self, bytes: &[u8]) {
+        let mut idx = 0;
 
         let mut buf: String = String::new();
         let mut in_comment = false;
-        let mut in_quote = false;
-        loop {
-            let Some(byte) = iter.next() else {
-                break;
-            };
-            if byte == b'"' {
-                if in_quote {
-                    self.contents.push(Token::Literal(buf));
-                    buf = String::new();
-                } else if !buf.is_empty() {
-                    let token = Token::from_string(&buf);
-                    self.contents.push(token);
+        while let Some(&byte) = bytes.get(idx) {
+            idx += 1;
+            if byte == b'"' && !in_comment {
+                if !buf.is_empty() {
+                    self.contents.push(Token::from_string(&buf));
+                    buf.clear();
+                }
+                let literal = if bytes[idx..].starts_with(b"\"\"") {
+                    idx += 2;
+                    read_block_string(bytes, &mut idx)
+                } else {
+                    read_string(bytes, &mut idx)
+                };
+                // An unterminated string runs to the end of the input and is dropped.
+                if let Some(literal) = literal {
+                    self.quoted.insert(self.contents.len());
+                    self.contents.push(Token::Literal(literal));
                 }
-                in_quote = !in_quote;
                 continue;
             }
-            if in_quote {
-                buf.push(byte as char);
-            } else {
-                if byte == b'/' {
-                    if let Some(next_byte) = iter.peek() {
-                        if *next_byte == b'/' {
-                            in_comment = true;
-                            continue;
+            if byte == b'/' && bytes.get(idx) == Some(&b'/') {
+                in_comment = true;
+                continue;
+            }
+            if !in_comment {
+                match Token::from_byte(byte) {
+                    Some(token) => {
+                        if !buf.is_empty() {
+                            self.contents.push(Token::from_string(&buf));
+                            buf.clear();
                         }
+                        self.contents.push(token);
                     }
-                }
-                if !in_comment {
-                    match Token::from_byte(byte) {
-                        Some(token) => {
-                            if !buf.is_empty() {
-                                self.contents.push(Token::from_string(&buf));
-                                buf.clear();
-                            }
-                            self.contents.push(token);
-                        }
-                        None => {
-                            if !byte.is_ascii_whitespace() {
-                                buf.push(byte as char);
-                            } else if !buf.is_empty() {
-                                self.contents.push(Token::from_string(&buf));
-                                buf.clear();
-                            }
+                    None => {
+                        if !byte.is_ascii_whitespace() {
+                            buf.push(byte as char);
+                        } else if !buf.is_empty() {
+                            self.contents.push(Token::from_string(&buf));
+                            buf.clear();
                         }
                     }
-                } else if byte == b'\n' || byte == b'\r' {
-                    in_comment = false;
                 }
+            } else if byte == b'\n' || byte == b'\r' {
+                in_comment = false;
+                // The line a comment trails still ends here.
+                if let Some(token) = Token::from_byte(byte) {
+                    self.contents.push(token);
+                }
+            }
+        }
+        // A name at the very end of the input has no separator after it.
+        if !buf.is_empty() {
+            self.contents.push(Token::from_string(&buf));
+        }
+    }
+}
+
+/// Reads a double-quoted string after its opening quote, applying escapes.
+///
+/// # Returns
+/// * `Some(String)` with the contents, leaving `idx` after the closing quote
+/// * `None` if the string is never closed
+fn read_string(bytes: &[u8], idx: &mut usize) -> Option<String> {
+    let mut out = Vec::new();
+    while let Some(&byte) = bytes.get(*idx) {
+        *idx += 1;
+        match byte {
+            b'"' => return Some(String::from_utf8_lossy(&out).to_string()),
+            b'\\' => match bytes.get(*idx) {
+                Some(b'"') => out.push(b'"'),
+                Some(b'\\') => out.push(b'\\'),
+                Some(b'n') => out.push(b'\n'),
+                Some(b't') => out.push(b'\t'),
+                Some(b'\n' | b'\r') => {
+                    while bytes.get(*idx).is_some_and(|b| b.is_ascii_whitespace()) {
+                        *idx += 1;
+                    }
+                    continue;
+                }
+                _ => {
+                    out.push(byte);
+                    continue;
+                }
+            },
+            _ => {
+                out.push(byte);
+                continue;
+            }
+        }
+        *idx += 1;
+    }
+    None
+}
+
+/// Reads a triple-quoted string after its opening quotes.
+///
+/// # Returns
+/// * `Some(String)` with the dedented contents, leaving `idx` after the closing quotes
+/// * `None` if the string is never closed
+fn read_block_string(bytes: &[u8], idx: &mut usize) -> Option<String> {
+    let start = *idx;
+    let len = bytes[start..].windows(3).position(|w| w == b"\"\"\"")?;
+    *idx = start + len + 3;
+    Some(dedent(&String::from_utf8_lossy(&bytes[start..start + len])))
+}
+
+/// Tidies the contents of a triple-quoted string so it can be indented with
+/// the schema around it.
+///
+/// A line break right after the opening quotes and a blank last line before
+/// the closing quotes are removed, as is the indentation shared by every
+/// non-blank line.
+fn dedent(raw: &str) -> String {
+    let raw = raw
+        .strip_prefix("\r\n")
+        .or_else(|| raw.strip_prefix('\n'))
+        .unwrap_or(raw);
+    let mut lines = raw.lines().collect::<Vec<_>>();
+    if lines.last().is_some_and(|line| line.trim().is_empty()) {
+        lines.pop();
+    }
+    let indent = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .map(|line| {
+            if line.trim().is_empty() {
+                ""
+            } else {
+                line.get(indent..).unwrap_or(line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Expands each `{a,b}` group in an import pattern, in the order written.
+fn expand_braces(pattern: &str) -> Vec<String> {
+    let Some(open) = pattern.find('{') else {
+        return vec![pattern.to_string()];
+    };
+    let mut depth = 0;
+    let mut bounds = vec![open];
+    for (idx, c) in pattern.char_indices().skip_while(|(idx, _)| *idx < open) {
+        match c {
+            '{' => depth += 1,
+            ',' if depth == 1 => bounds.push(idx),
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    bounds.push(idx);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    // An unclosed brace is kept as part of the name.
+    if depth != 0 {
+        return vec![pattern.to_string()];
+    }
+    let (head, tail) = (&pattern[..open], &pattern[bounds[bounds.len() - 1] + 1..]);
+    bounds
+        .windows(2)
+        .flat_map(|w| expand_braces(&format!("{}{}{}", head, &pattern[w[0] + 1..w[1]], tail)))
+        .collect()
+}
+
+/// Collects the files under `dir` matched by the remaining glob segments.
+///
+/// `*` matches any run of characters within a name and `?` any single one;
+/// neither matches a leading `.`. A `**` segment matches any number of
+/// folders, including none.
+fn glob_files(dir: &Path, segments: &[&str], found: &mut Vec<PathBuf>) {
+    let Some((segment, rest)) = segments.split_first() else {
+        return;
+    };
+    if !segment.contains(['*', '?']) {
+        let path = dir.join(segment);
+        if rest.is_empty() && path.is_file() {
+            found.push(path);
+        } else if !rest.is_empty() && path.is_dir() {
+            glob_files(&path, rest, found);
+        }
+        return;
+    }
+    if *segment == "**" {
+        glob_files(dir, rest, found);
+    }
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let pattern = segment.chars().collect::<Vec<_>>();
+    for entry in entries.flatten() {
+        let name = entry
+            .file_name()
+            .to_string_lossy()
+            .chars()
+            .collect::<Vec<_>>();
+        if name.first() == Some(&'.') {
+            continue;
+        }
+        let path = entry.path();
+        if *segment == "**" {
+            if path.is_dir() {
+                glob_files(&path, segments, found);
             }
+        } else if matches_name(&pattern, &name) {
+            if rest.is_empty() && path.is_file() {
+                found.push(path);
+            } else if !rest.is_empty() && path.is_dir() {
+                glob_files(&path, rest, found);
+            }
+        }
+    }
+}
+
+/// Whether `name` matches one glob segment containing `*` and `?`.
+fn matches_name(pattern: &[char], name: &[char]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some('*'), _) => {
+            matches_name(&pattern[1..], name)
+                || (!name.is_empty() && matches_name(pattern, &name[1..]))
+        }
+        (Some('?'), Some(_)) => matches_name(&pattern[1..], &name[1..]),
+        (Some(p), Some(n)) if p == n => matches_name(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::ParseResult;
+
+    /// The messages of the errors from parsing `source`, or none if it parses.
+    fn parse_errors(source: &str) -> Vec<String> {
+        let mut contents = FileContents::empty();
+        contents.add_string(source);
+        match ParseResult::read_contents(contents) {
+            Ok(_) => Vec::new(),
+            Err(errors) => errors
+                .into_iter()
+                .map(|e| e.error_details.unwrap_or_default())
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn reports_definitions_cut_off_at_the_end() {
+        for (source, error) in [
+            ("enum E {", "'}' to close enum E"),
+            ("enum E", "'{' to open enum E"),
+            ("struct User @", "'{' to open struct User"),
+            ("struct User {\n", "'}' to close struct User"),
+            ("snippet S", "'{' to open snippet S"),
+            ("mixin M {", "'}' to close mixin M"),
+            ("config C {\n port int32 8", "'}' to close config C"),
+            ("output rust @", "';' or '{' after output rust"),
+            ("output rust {\n package models", "'}' to close output rust"),
+            ("import", "file after import"),
+            ("blueprint", "file after blueprint"),
+        ] {
+            assert_eq!(parse_errors(source), [error], "{source:?}");
+        }
+        assert!(parse_errors("enum E {\n A\n B\n}\noutput rust @o;").is_empty());
+    }
+
+    #[test]
+    fn recovers_at_every_kind_of_definition() {
+        let errors = parse_errors(
+            "struct A {\n a ?\n}\nconfig Server {\n host\n}\n\
+             struct B {\n a ?\n}\nlint {\n bogus\n}\n\
+             struct C {\n a ?\n}\nconst X int32 =\n\
+             struct D {\n a ?\n}\nunion U =\n\
+             struct E {\n a ?\n}\nmixin M {\n a ?\n}\n\
+             struct F {\n a ?\n}\nschema {\n x\n}\n",
+        );
+        assert_eq!(errors.len(), 12, "{errors:#?}");
+        for error in [
+            "type for config key 'Server.host'",
+            "lint block. Expected 'allow', got Literal(\"bogus\").",
+            "value for const X",
+            "variant of union U",
+            "field in M",
+            "value for schema x",
+        ] {
+            assert!(
+                errors.iter().any(|e| e == error),
+                "missing {error:?} in {errors:#?}"
+            );
         }
     }
+
+    #[test]
+    fn fields_named_like_definitions_do_not_end_recovery() {
+        let errors = parse_errors("struct A {\n a ?\n config string\n lint string\n}\n");
+        assert_eq!(errors, ["field in A"]);
+    }
+
+    fn matches(pattern: &str, name: &str) -> bool {
+        let pattern = pattern.chars().collect::<Vec<_>>();
+        let name = name.chars().collect::<Vec<_>>();
+        matches_name(&pattern, &name)
+    }
+
+    #[test]
+    fn expands_brace_groups_in_order() {
+        assert_eq!(expand_braces("models/user.repack"), ["models/user.repack"]);
+        assert_eq!(
+            expand_braces("shared/{b,a}.repack"),
+            ["shared/b.repack", "shared/a.repack"]
+        );
+        assert_eq!(expand_braces("{x,y}/{1,2}"), ["x/1", "x/2", "y/1", "y/2"]);
+        assert_eq!(expand_braces("a{b,c{d,e}}f"), ["abf", "acdf", "acef"]);
+        assert_eq!(expand_braces("a{,b}"), ["a", "ab"]);
+    }
+
+    #[test]
+    fn keeps_unclosed_braces() {
+        assert_eq!(expand_braces("a{b,c"), ["a{b,c"]);
+        assert_eq!(expand_braces("a}b"), ["a}b"]);
+    }
+
+    #[test]
+    fn matches_names_with_wildcards() {
+        assert!(matches("*.repack", "user.repack"));
+        assert!(matches("*.repack", ".repack"));
+        assert!(!matches("*.repack", "user.repack.bak"));
+        assert!(matches("user?.repack", "user1.repack"));
+        assert!(!matches("user?.repack", "user.repack"));
+        assert!(matches("*", ""));
+        assert!(matches("a*b*c", "aXbYbc"));
+        assert!(!matches("a*b*c", "aXbYb"));
+        assert!(matches("exact", "exact"));
+        assert!(!matches("exact", "exactly"));
+    }
+
+    #[test]
+    fn globs_nested_folders_and_skips_hidden_files() {
+        let root = env::temp_dir().join(format!("repack-glob-{}", std::process::id()));
+        for dir in ["models/a/b", "models/.hidden"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in [
+            "models/top.repack",
+            "models/a/one.repack",
+            "models/a/b/two.repack",
+            "models/a/notes.txt",
+            "models/.hidden/secret.repack",
+            "models/.dot.repack",
+        ] {
+            fs::write(root.join(file), "").unwrap();
+        }
+
+        let mut found = Vec::new();
+        glob_files(&root, &["models", "**", "*.repack"], &mut found);
+        found.sort();
+        let found = found
+            .iter()
+            .map(|path| {
+                path.strip_prefix(&root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                "models/a/b/two.repack",
+                "models/a/one.repack",
+                "models/top.repack"
+            ]
+        );
+
+        let mut found = Vec::new();
+        glob_files(&root, &["models", "*", "*"], &mut found);
+        found.sort();
+        assert_eq!(
+            found,
+            [
+                root.join("models/a/notes.txt"),
+                root.join("models/a/one.repack")
+            ]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
 }
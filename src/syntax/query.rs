@@ -1,4 +1,24 @@
-use super::{FileContents, RepackError, RepackErrorKind, RepackStruct, Token};
+use super::{
+    CustomFieldType, Field, FieldExternalLocation, FieldType, FileContents, RepackError,
+    RepackErrorKind, RepackStruct, Token,
+};
+
+/// How a rendered query's columns declare their types to the code that reads
+/// them, set with a blueprint's `[meta column_types]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColumnTypes {
+    /// sqlx's `AS "name?: Type"` overrides, so `query_as!` can read enum,
+    /// union, and optional columns
+    Sqlx,
+}
+impl ColumnTypes {
+    pub fn from_string(val: &str) -> Option<ColumnTypes> {
+        match val {
+            "sqlx" => Some(ColumnTypes::Sqlx),
+            _ => None,
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct QueryArg {
@@ -48,12 +68,113 @@ pub enum QueryReturn {
     Many,
 }
 
-#[derive(Debug)]
+/// The HTTP methods a route may use.
+const HTTP_METHODS: [&str; 5] = ["GET", "POST", "PUT", "PATCH", "DELETE"];
+
+/// The HTTP route a query is served on, written `http(GET "/users/:id")`
+/// after the query on the same line.
+#[derive(Debug, Clone)]
+pub struct HttpRoute {
+    pub method: String,
+    pub path: String,
+}
+impl HttpRoute {
+    fn parse(
+        query_name: &str,
+        reader: &mut FileContents,
+    ) -> Result<Option<HttpRoute>, RepackError> {
+        if !matches!(reader.peek(), Some(Token::Literal(lit)) if lit == "http") {
+            return Ok(None);
+        }
+        reader.skip();
+        let invalid = |msg: &str| {
+            RepackError::global(
+                RepackErrorKind::QueryInvalidSyntax,
+                format!("{query_name} ({msg})"),
+            )
+        };
+        if !matches!(reader.take(), Some(Token::OpenParen)) {
+            return Err(invalid("expected http(METHOD \"/path\")"));
+        }
+        let method = reader
+            .take_literal()
+            .map(|method| method.to_uppercase())
+            .filter(|method| HTTP_METHODS.contains(&method.as_str()))
+            .ok_or_else(|| invalid("the method must be GET, POST, PUT, PATCH, or DELETE"))?;
+        let path = reader
+            .take_literal()
+            .filter(|path| path.starts_with('/'))
+            .ok_or_else(|| invalid("the path must start with '/'"))?;
+        if !matches!(reader.take(), Some(Token::CloseParen)) {
+            return Err(invalid("expected ')' after the path"));
+        }
+        Ok(Some(HttpRoute { method, path }))
+    }
+
+    /// The names of the path's `:name` segments.
+    pub fn params(&self) -> impl Iterator<Item = &str> {
+        self.path
+            .split('/')
+            .filter_map(|segment| segment.strip_prefix(':'))
+    }
+
+    /// The path with each `:name` segment written as `{name}`, as axum and
+    /// OpenAPI expect.
+    pub fn template(&self) -> String {
+        self.path
+            .split('/')
+            .map(|segment| match segment.strip_prefix(':') {
+                Some(param) => format!("{{{param}}}"),
+                None => segment.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Whether an argument is read from the path. Leading underscores are
+    /// ignored, so `:id` fills `_id`.
+    pub fn binds(&self, arg: &str) -> bool {
+        self.params()
+            .any(|param| param == arg.trim_start_matches('_'))
+    }
+}
+
+/// What a query does to its tables, inferred from its SQL.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QueryKind {
+    Select,
+    Insert,
+    Update,
+    Delete,
+    Other,
+}
+impl QueryKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QueryKind::Select => "select",
+            QueryKind::Insert => "insert",
+            QueryKind::Update => "update",
+            QueryKind::Delete => "delete",
+            QueryKind::Other => "other",
+        }
+    }
+
+    /// Whether the query changes any rows.
+    pub fn writes(&self) -> bool {
+        matches!(
+            self,
+            QueryKind::Insert | QueryKind::Update | QueryKind::Delete
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Query {
     pub name: String,
     pub args: Vec<QueryArg>,
     pub contents: String,
     pub ret_type: QueryReturn,
+    pub http: Option<HttpRoute>,
 }
 impl Query {
     pub fn parse(obj_name: &str, reader: &mut FileContents) -> Result<Query, RepackError> {
@@ -72,6 +193,12 @@ impl Query {
                         reader.skip();
                         break;
                     }
+                    None => {
+                        return Err(RepackError::global(
+                            RepackErrorKind::ParseIncomplete,
+                            format!("')' after the arguments of {name}"),
+                        ));
+                    }
                     _ => {
                         reader.skip();
                     }
@@ -99,29 +226,124 @@ impl Query {
                 }
             }
         }
+        let http = HttpRoute::parse(&name, reader)?;
 
         Ok(Query {
             name,
             args,
             contents,
             ret_type,
+            http,
         })
     }
 
+    /// Infers what the query does from its keywords.
+    ///
+    /// The first `INSERT`, `UPDATE`, or `DELETE` decides the kind, so a write
+    /// wrapped in a `WITH ... SELECT` is still a write. Quoted text and the
+    /// words after `FOR` and `DO` (as in `FOR UPDATE` and `DO UPDATE`) are
+    /// skipped. Anything else that reads with `SELECT` is a select.
+    pub fn kind(&self) -> QueryKind {
+        let mut quoted = false;
+        let mut words = Vec::<String>::new();
+        let mut word = String::new();
+        for c in self.contents.chars().chain([' ']) {
+            if c == '\'' {
+                quoted = !quoted;
+            } else if !quoted && (c.is_alphanumeric() || c == '_' || c == '$' || c == '#') {
+                word.push(c);
+                continue;
+            }
+            if !word.is_empty() {
+                words.push(word.to_uppercase());
+                word.clear();
+            }
+        }
+        let mut select = false;
+        let mut skip = false;
+        for word in &words {
+            if std::mem::take(&mut skip) {
+                continue;
+            }
+            match word.as_str() {
+                "INSERT" => return QueryKind::Insert,
+                "UPDATE" => return QueryKind::Update,
+                "DELETE" => return QueryKind::Delete,
+                "SELECT" => select = true,
+                "FOR" | "DO" => skip = true,
+                _ => {}
+            }
+        }
+        if select {
+            QueryKind::Select
+        } else {
+            QueryKind::Other
+        }
+    }
+
+    /// The tables the query reads or writes: the struct's own table, then
+    /// the table of each join when the query uses `$locations`.
+    pub fn tables<'a>(
+        &self,
+        strct: &'a RepackStruct,
+        other_structs: &'a [RepackStruct],
+    ) -> Vec<&'a str> {
+        let mut tables = Vec::<&str>::new();
+        tables.extend(strct.table_name.as_deref());
+        if !self.contents.contains("$locations") && !self.contents.contains("$#locations") {
+            return tables;
+        }
+        for join in &strct.joins {
+            let table = other_structs
+                .iter()
+                .find(|x| x.name == join.foreign_entity)
+                .and_then(|fe| fe.table_name.as_deref());
+            if let Some(table) = table
+                && !tables.contains(&table)
+            {
+                tables.push(table);
+            }
+        }
+        tables
+    }
+
     /// Renders the query contents into a finalized SQL string with positional parameters.
     /// Unrecognized variables render as [err: name]. A trailing semicolon is appended.
+    /// Fails if the struct has no table, a joined struct has no table, or a join
+    /// condition uses a variable other than `$name`, `$super`, or the join's name.
     ///
     /// Interpolation rules:
     /// - $fields => comma list of table-qualified columns with AS aliases.
-    /// - $locations => base table plus JOIN fragments derived from struct joins.
-    /// - $table => base table name.
+    /// - $locations / $#locations => base table plus JOIN fragments derived from struct joins.
+    /// - $table / $#table => base table name.
     /// - $name / $#name => field reference (qualified vs isolated column name).
     /// - $argName => replaced with next positional parameter index ($1,$2,... in first appearance order).
+    ///
+    /// With a `schema`, table names are prefixed with it, except the base table
+    /// in `$#table` and `$#locations`, which name a CTE in `insert`/`update` queries.
+    ///
+    /// Schema, table, alias, and column names are passed through [`quote_ident`],
+    /// so names that are reserved words or contain other characters stay valid.
+    /// With `column_types`, each alias in `$fields` also declares the field's
+    /// type in that style (see [`column_alias`]).
     pub fn render(
         &self,
         strct: &RepackStruct,
         other_structs: &[RepackStruct],
+        schema: Option<&str>,
+        column_types: Option<ColumnTypes>,
     ) -> Result<String, RepackError> {
+        let base = strct.table_name.as_deref().ok_or_else(|| {
+            RepackError::from_obj_with_msg(
+                RepackErrorKind::QueryWithoutTable,
+                strct,
+                self.name.to_string(),
+            )
+        })?;
+        let qualify = |table: &str| match schema {
+            Some(schema) => format!("{}.{}", quote_ident(schema), quote_ident(table)),
+            None => quote_ident(table),
+        };
         let mut output = String::new();
 
         let mut buf = String::new();
@@ -174,38 +396,41 @@ impl Query {
                     for field in &strct.fields {
                         if let Some(location) = &field.field_location {
                             let table = if location.location == "super" {
-                                strct.table_name.as_ref().ok_or_else(|| {
-                                    RepackError::from_obj(
-                                        RepackErrorKind::ParentObjectDoesNotExist,
-                                        strct,
-                                    )
-                                })?
+                                base
                             } else {
                                 &location.location
                             };
-                            field_strings
-                                .push(format!("{}.{} AS {}", table, location.field, field.name))
+                            field_strings.push(format!(
+                                "{}.{} AS {}",
+                                quote_ident(table),
+                                quote_ident(referenced_column(strct, other_structs, location)),
+                                column_alias(field, column_types)
+                            ))
                         } else if let Some(alias) = field.function("db", "as") {
                             let def = String::new();
                             field_strings.push(format!(
                                 "{} AS {}",
                                 alias.args.first().unwrap_or(&def),
-                                field.name
+                                column_alias(field, column_types)
                             ))
                         } else {
                             field_strings.push(format!(
                                 "{}.{} AS {}",
-                                strct.table_name.as_ref().unwrap(),
-                                field.name,
-                                field.name
+                                quote_ident(base),
+                                quote_ident(field.column_name()),
+                                column_alias(field, column_types)
                             ))
                         }
                     }
-                    Some(field_strings.join(", "))
+                    field_strings.join(", ")
                 }
                 "locations" => {
                     let mut locations = Vec::<String>::new();
-                    locations.push(strct.table_name.clone().unwrap());
+                    locations.push(if isolated {
+                        quote_ident(base)
+                    } else {
+                        qualify(base)
+                    });
                     for join in &strct.joins {
                         let mut join_string = String::new();
                         let mut template_string_iter = join.contents.chars();
@@ -231,29 +456,34 @@ impl Query {
                             if join_string_temp.len() > 1 {
                                 let replace = match &join_string_temp[1..] {
                                     "name" => {
-                                        let fe = other_structs
+                                        let table = other_structs
                                             .iter()
                                             .find(|x| x.name == join.foreign_entity)
-                                            .unwrap();
-                                        // ^ This is safe to unwrap because we've already done the
-                                        // checking.
-                                        Some(format!(
-                                            "{} {}",
-                                            fe.table_name.clone().unwrap(),
-                                            join.name
-                                        ))
+                                            .and_then(|fe| fe.table_name.as_deref())
+                                            .ok_or_else(|| {
+                                                RepackError::from_obj_with_msg(
+                                                    RepackErrorKind::QueryWithoutTable,
+                                                    strct,
+                                                    format!(
+                                                        "{} (joined struct {} has no table)",
+                                                        self.name, join.foreign_entity
+                                                    ),
+                                                )
+                                            })?;
+                                        format!("{} {}", qualify(table), quote_ident(&join.name))
                                     }
-                                    "super" => Some(strct.table_name.clone().unwrap()),
+                                    "super" => quote_ident(base),
+                                    tn if tn == join.name => quote_ident(tn),
                                     tn => {
-                                        if tn == join.name {
-                                            Some(tn.to_string())
-                                        } else {
-                                            None
-                                        }
+                                        return Err(RepackError::from_obj_with_msg(
+                                            RepackErrorKind::QueryInvalidSyntax,
+                                            strct,
+                                            format!("unknown variable ${tn} in join {}", join.name),
+                                        ));
                                     }
                                 };
                                 join_string_temp.clear();
-                                join_string.push_str(&replace.unwrap());
+                                join_string.push_str(&replace);
                                 if !join_ct {
                                     break;
                                 }
@@ -266,41 +496,44 @@ impl Query {
                         }
                         locations.push(join_string);
                     }
-                    Some(locations.join(" "))
+                    locations.join(" ")
+                }
+                "table" => {
+                    if isolated {
+                        quote_ident(base)
+                    } else {
+                        qualify(base)
+                    }
                 }
-                "table" => strct.table_name.clone(),
                 val => {
                     if let Some(field) = strct.fields.iter().find(|x| x.name == val) {
                         if let Some(location) = &field.field_location {
                             let table = if location.location == "super" {
-                                strct.table_name.as_ref().unwrap()
+                                base
                             } else {
                                 &location.location
                             };
+                            let column = referenced_column(strct, other_structs, location);
                             if isolated {
-                                Some(location.field.clone())
+                                quote_ident(column)
                             } else {
-                                Some(format!("{}.{}", table, location.field))
+                                format!("{}.{}", quote_ident(table), quote_ident(column))
                             }
                         } else if isolated {
-                            Some(field.name.clone())
+                            quote_ident(field.column_name())
                         } else {
-                            Some(format!(
-                                "{}.{}",
-                                strct.table_name.as_ref().unwrap(),
-                                field.name
-                            ))
+                            format!("{}.{}", quote_ident(base), quote_ident(field.column_name()))
                         }
                     } else if let Some(idx) = self.args.iter().position(|x| x.name == val) {
-                        Some(format!("${}", idx + 1))
+                        format!("${}", idx + 1)
                     } else {
-                        Some(format!("[err: {val}]"))
+                        format!("[err: {val}]")
                     }
                 }
             };
             buf.clear();
 
-            output.push_str(&result.unwrap());
+            output.push_str(&result);
             if !ct {
                 break;
             }
@@ -312,11 +545,114 @@ impl Query {
     }
 }
 
+/// The column a `join.field` or `super.field` reference reads, following any
+/// `rename(db ...)` on the referenced field.
+fn referenced_column<'a>(
+    strct: &'a RepackStruct,
+    other_structs: &'a [RepackStruct],
+    location: &'a FieldExternalLocation,
+) -> &'a str {
+    let entity = if location.location == "super" {
+        strct.inherits.as_deref()
+    } else {
+        strct
+            .joins
+            .iter()
+            .find(|join| join.name == location.location)
+            .map(|join| join.foreign_entity.as_str())
+    };
+    entity
+        .and_then(|name| other_structs.iter().find(|s| s.name == name))
+        .and_then(|s| s.fields.iter().find(|f| f.name == location.field))
+        .map(|f| f.column_name())
+        .unwrap_or(&location.field)
+}
+
+/// Words Postgres reserves, which can't name a table or column unquoted.
+const RESERVED_WORDS: &str = "\
+    all analyse analyze and any array as asc asymmetric authorization binary both \
+    case cast check collate collation column concurrently constraint create cross \
+    current_catalog current_date current_role current_schema current_time \
+    current_timestamp current_user default deferrable desc distinct do else end \
+    except false fetch for foreign freeze from full grant group having ilike in \
+    initially inner intersect into is isnull join lateral leading left like limit \
+    localtime localtimestamp natural not notnull null offset on only or order outer \
+    overlaps placing primary references returning right select session_user similar \
+    some symmetric system_user table tablesample then to trailing true union unique \
+    user using variadic verbose when where window with";
+
+/// Quotes an SQL identifier if it can't be written bare.
+///
+/// Names made of letters, digits, `_`, and `$` that don't start with a digit and
+/// aren't reserved words are left as they are, so Postgres folds them to lower
+/// case as usual. Anything else is wrapped in double quotes, with embedded
+/// quotes doubled.
+pub fn quote_ident(name: &str) -> String {
+    let bare = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        && !RESERVED_WORDS
+            .split_whitespace()
+            .any(|word| word.eq_ignore_ascii_case(name));
+    if bare {
+        name.to_string()
+    } else {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    }
+}
+
+/// Quotes a column alias, keeping its case.
+///
+/// Unlike [`quote_ident`], a name with upper case letters is quoted as well:
+/// the alias names a field in the generated code, so `userName` has to come
+/// back as `userName` rather than folded to `username`.
+pub fn quote_alias(name: &str) -> String {
+    if name.chars().any(|c| c.is_uppercase()) {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    } else {
+        quote_ident(name)
+    }
+}
+
+/// The alias a field's column is selected as in `$fields`.
+///
+/// Without `column_types` this is the field's name. With
+/// [`ColumnTypes::Sqlx`], an optional field's alias ends in `?` and an enum
+/// or union field's alias names its Rust type, as in `AS "status?: Status"`,
+/// since `query_as!` can't infer either from the database.
+fn column_alias(field: &Field, column_types: Option<ColumnTypes>) -> String {
+    let Some(ColumnTypes::Sqlx) = column_types else {
+        return quote_alias(&field.name);
+    };
+    let name = if field.optional {
+        format!("{}?", field.name)
+    } else {
+        field.name.to_string()
+    };
+    let typ = match &field.field_type {
+        Some(FieldType::Custom(typ, CustomFieldType::Enum)) => typ.to_string(),
+        Some(FieldType::Custom(typ, CustomFieldType::Union)) => {
+            format!("sqlx::types::Json<{typ}>")
+        }
+        _ => return quote_alias(&name),
+    };
+    if field.array {
+        quote_alias(&format!("{name}: Vec<{typ}>"))
+    } else {
+        quote_alias(&format!("{name}: {typ}"))
+    }
+}
+
 #[derive(Debug)]
 pub struct AutoInsertQuery {
     pub name: String,
     pub args: Vec<String>,
     pub ret_type: QueryReturn,
+    pub http: Option<HttpRoute>,
 }
 
 impl AutoInsertQuery {
@@ -332,13 +668,17 @@ impl AutoInsertQuery {
         if matches!(reader.peek(), Some(Token::OpenParen)) {
             loop {
                 match reader.peek() {
-                    Some(Token::Literal(_)) => {
-                        args.push(reader.take_literal().unwrap());
-                    }
+                    Some(Token::Literal(_)) => args.extend(reader.take_literal()),
                     Some(Token::CloseParen) => {
                         reader.skip();
                         break;
                     }
+                    None => {
+                        return Err(RepackError::global(
+                            RepackErrorKind::ParseIncomplete,
+                            format!("')' after the arguments of {name}"),
+                        ));
+                    }
                     _ => {
                         reader.skip();
                     }
@@ -357,17 +697,26 @@ impl AutoInsertQuery {
                 }
             }
         }
+        let http = HttpRoute::parse(&name, reader)?;
 
         Ok(AutoInsertQuery {
             name,
             args,
             ret_type,
+            http,
         })
     }
 
     pub fn to_query(&self, strct: &RepackStruct) -> Result<Query, RepackError> {
+        if strct.table_name.is_none() {
+            return Err(RepackError::from_obj_with_msg(
+                RepackErrorKind::QueryWithoutTable,
+                strct,
+                self.name.to_string(),
+            ));
+        }
         let mut args = Vec::<QueryArg>::new();
-        let mut output = "WITH $table AS (INSERT INTO $table (".to_string();
+        let mut output = "WITH $#table AS (INSERT INTO $table (".to_string();
         let mut query_interpolate = String::new();
         for (idx, selected_field) in self.args.iter().enumerate() {
             let Some(matching_field) = strct.fields.iter().find(|x| x.name == *selected_field)
@@ -378,7 +727,7 @@ impl AutoInsertQuery {
                     selected_field.to_string(),
                 ));
             };
-            output.push_str(selected_field);
+            output.push_str(&quote_ident(matching_field.column_name()));
             query_interpolate.push_str(&format!("$__{selected_field}"));
 
             args.push(QueryArg {
@@ -403,13 +752,14 @@ impl AutoInsertQuery {
             }
         }
         output.push_str(&format!(
-            ") VALUES ({}) RETURNING *) SELECT $fields FROM $locations",
+            ") VALUES ({}) RETURNING *) SELECT $fields FROM $#locations",
             query_interpolate,
         ));
         Ok(Query {
             args,
             name: self.name.clone(),
             ret_type: self.ret_type.clone(),
+            http: self.http.clone(),
             contents: output,
         })
     }
@@ -421,6 +771,7 @@ pub struct AutoUpdateQuery {
     pub args: Vec<QueryArg>,
     pub contents: String,
     pub ret_type: QueryReturn,
+    pub http: Option<HttpRoute>,
 }
 impl AutoUpdateQuery {
     pub fn parse(
@@ -441,6 +792,12 @@ impl AutoUpdateQuery {
                         reader.skip();
                         break;
                     }
+                    None => {
+                        return Err(RepackError::global(
+                            RepackErrorKind::ParseIncomplete,
+                            format!("')' after the arguments of {name}"),
+                        ));
+                    }
                     _ => {
                         reader.skip();
                     }
@@ -468,24 +825,27 @@ impl AutoUpdateQuery {
                 }
             }
         }
+        let http = HttpRoute::parse(&name, reader)?;
 
         Ok(AutoUpdateQuery {
             name,
             args,
             ret_type,
             contents,
+            http,
         })
     }
 
     pub fn to_query(&self) -> Result<Query, RepackError> {
         let nested_contents = format!(
-            "WITH $table AS (UPDATE $table {} RETURNING *) SELECT $fields FROM $locations",
+            "WITH $#table AS (UPDATE $table {} RETURNING *) SELECT $fields FROM $#locations",
             self.contents
         );
         Ok(Query {
             args: self.args.clone(),
             name: self.name.clone(),
             ret_type: self.ret_type.clone(),
+            http: self.http.clone(),
             contents: nested_contents,
         })
     }
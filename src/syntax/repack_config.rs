@@ -0,0 +1,136 @@
+use super::{FieldType, FileContents, RepackError, RepackErrorKind, Token};
+
+/// A single key declared inside a `config` block.
+#[derive(Debug)]
+pub struct ConfigEntry {
+    /// The key name as written in the schema (e.g. `database_url`)
+    pub name: String,
+    /// The original type string from the schema, used for resolution and errors
+    pub field_type_string: String,
+    /// The resolved type; only core types and enums are allowed
+    pub field_type: Option<FieldType>,
+    /// Whether the key may be left unset
+    pub optional: bool,
+    /// The default value used when the key is not set
+    pub default: Option<String>,
+}
+
+/// Represents a configuration schema declared with `config Name { ... }`.
+///
+/// Configs describe the settings an application reads at runtime, such as
+/// environment variables. They are rendered by blueprints with
+/// `[meta kind]configure[/meta]` when running `repack configure`.
+#[derive(Debug)]
+pub struct RepackConfig {
+    /// The unique name identifier for this config
+    pub name: String,
+    /// Tags/categories for organizing and filtering configs during generation
+    pub categories: Vec<String>,
+    /// The declared keys, in declaration order
+    pub entries: Vec<ConfigEntry>,
+}
+impl RepackConfig {
+    /// Parses a config definition from the input file contents.
+    ///
+    /// Each line of the block declares a key with the syntax
+    /// `key type[?] [default]`, for example:
+    ///
+    /// ```repack
+    /// config Server {
+    ///     port int32 8080
+    ///     database_url string
+    ///     sentry_dsn string?
+    /// }
+    /// ```
+    ///
+    /// # Arguments
+    /// * `contents` - Mutable reference to the file contents being parsed
+    ///
+    /// # Returns
+    /// * `Ok(RepackConfig)` with all parsed entries
+    /// * `Err(RepackError)` if the config name or an entry type is missing
+    pub fn read_from_contents(contents: &mut FileContents) -> Result<RepackConfig, RepackError> {
+        let Some(name) = contents.take_literal() else {
+            return Err(RepackError::global(
+                RepackErrorKind::ParseIncomplete,
+                "config name".to_string(),
+            ));
+        };
+        let mut categories = Vec::new();
+        let mut entries = Vec::new();
+
+        'header: loop {
+            let Some(token) = contents.next() else {
+                return Err(RepackError::global(
+                    RepackErrorKind::ParseIncomplete,
+                    format!("'{{' to open config {name}"),
+                ));
+            };
+            match token {
+                Token::Pound => {
+                    if let Some(Token::Literal(lit)) = contents.next() {
+                        categories.push(lit.to_string());
+                    }
+                }
+                Token::OpenBrace => {
+                    break 'header;
+                }
+                _ => {}
+            }
+        }
+
+        'entries: loop {
+            let Some(token) = contents.take() else {
+                return Err(RepackError::global(
+                    RepackErrorKind::ParseIncomplete,
+                    format!("'}}' to close config {name}"),
+                ));
+            };
+            match token {
+                Token::CloseBrace => {
+                    break 'entries;
+                }
+                Token::Literal(key) => {
+                    let Some(field_type_string) = contents.take_literal() else {
+                        return Err(RepackError::global(
+                            RepackErrorKind::ParseIncomplete,
+                            format!("type for config key '{name}.{key}'"),
+                        ));
+                    };
+                    let optional = if contents.peek() == Some(&Token::Question) {
+                        contents.skip();
+                        true
+                    } else {
+                        false
+                    };
+                    let mut default = String::new();
+                    while let Some(tok) = contents.peek() {
+                        match tok {
+                            Token::NewLine | Token::CloseBrace => break,
+                            Token::Literal(val) => default.push_str(val),
+                            Token::Minus => default.push('-'),
+                            Token::Plus => default.push('+'),
+                            Token::Period => default.push('.'),
+                            _ => {}
+                        }
+                        contents.skip();
+                    }
+                    entries.push(ConfigEntry {
+                        name: key,
+                        field_type: FieldType::from_string(&field_type_string),
+                        field_type_string,
+                        optional,
+                        default: (!default.is_empty()).then_some(default),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        Ok(RepackConfig {
+            name,
+            categories,
+            entries,
+        })
+    }
+}
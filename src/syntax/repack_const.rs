@@ -0,0 +1,154 @@
+use super::{
+    CoreType, CustomFieldType, FieldType, FileContents, RepackEnum, RepackError, RepackErrorKind,
+    Token,
+};
+
+/// A named value defined once in the schema, such as a length limit shared by
+/// a database constraint and application validation.
+///
+/// Written `const MAX_NAME_LEN int32 = 80`. A function argument that is
+/// exactly a constant's name, as in `validate:max_len(MAX_NAME_LEN)`, is
+/// replaced by its value, and blueprints list constants with `[each const]`.
+#[derive(Debug)]
+pub struct RepackConst {
+    /// The name the constant is used by
+    pub name: String,
+    /// Tags/categories for organizing and filtering constants during generation
+    pub categories: Vec<String>,
+    /// The original type string from the schema, used for resolution and errors
+    pub field_type_string: String,
+    /// The resolved type; only numbers, booleans, strings, and enums are allowed
+    pub field_type: Option<FieldType>,
+    /// The value as written, or the case name for an enum constant
+    pub value: String,
+}
+impl RepackConst {
+    /// Parses a constant definition: `const NAME type #category = value`.
+    ///
+    /// The value runs to the end of the line; a string value may be quoted.
+    ///
+    /// # Returns
+    /// * `Ok(RepackConst)` with its unresolved type
+    /// * `Err(RepackError)` if the name, type, `=`, or value is missing
+    pub fn read_from_contents(contents: &mut FileContents) -> Result<RepackConst, RepackError> {
+        let Some(name) = contents.take_literal() else {
+            return Err(RepackError::global(
+                RepackErrorKind::ParseIncomplete,
+                "const name".to_string(),
+            ));
+        };
+        let Some(field_type_string) = contents.take_literal() else {
+            return Err(RepackError::global(
+                RepackErrorKind::ParseIncomplete,
+                format!("type for const {name}"),
+            ));
+        };
+        let mut categories = Vec::new();
+        loop {
+            match contents.take() {
+                Some(Token::Pound) => {
+                    if let Some(Token::Literal(lit)) = contents.peek() {
+                        categories.push(lit.to_string());
+                        contents.skip();
+                    }
+                }
+                Some(Token::Equal) => break,
+                _ => {
+                    return Err(RepackError::global(
+                        RepackErrorKind::ParseIncomplete,
+                        format!("'=' after const {name}"),
+                    ));
+                }
+            }
+        }
+
+        let mut value = String::new();
+        while let Some(tok) = contents.peek() {
+            match tok {
+                Token::NewLine => break,
+                Token::Literal(val) => value.push_str(val),
+                Token::Minus => value.push('-'),
+                Token::Plus => value.push('+'),
+                Token::Period => value.push('.'),
+                _ => {}
+            }
+            contents.skip();
+        }
+        if value.is_empty() {
+            return Err(RepackError::global(
+                RepackErrorKind::ParseIncomplete,
+                format!("value for const {name}"),
+            ));
+        }
+
+        Ok(RepackConst {
+            name,
+            categories,
+            field_type: FieldType::from_string(&field_type_string),
+            field_type_string,
+            value,
+        })
+    }
+
+    /// Resolves the constant's type and checks its value against it.
+    ///
+    /// An enum constant names one of the enum's cases, by name or value, and
+    /// its value becomes the case name.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the value fits the type
+    /// * `Err(String)` describing why the type or value is not allowed
+    pub fn resolve(&mut self, enums: &[RepackEnum]) -> Result<(), String> {
+        if self.field_type.is_none()
+            && let Some(enm) = enums.iter().find(|e| e.name == self.field_type_string)
+        {
+            let case = enm
+                .options
+                .iter()
+                .find(|c| c.name == self.value || c.value.as_deref() == Some(&self.value))
+                .ok_or_else(|| format!("{} is not a case of {}", self.value, enm.name))?;
+            self.value = case.name.clone();
+            self.field_type = Some(FieldType::Custom(enm.name.clone(), CustomFieldType::Enum));
+            return Ok(());
+        }
+        let valid = match &self.field_type {
+            Some(FieldType::Core(core)) => match core {
+                CoreType::String => true,
+                CoreType::Int16 => self.value.parse::<i16>().is_ok(),
+                CoreType::Int32 => self.value.parse::<i32>().is_ok(),
+                CoreType::Int64 => self.value.parse::<i64>().is_ok(),
+                CoreType::Uint32 => self.value.parse::<u32>().is_ok(),
+                CoreType::Uint64 => self.value.parse::<u64>().is_ok(),
+                CoreType::Float64 => self.value.parse::<f64>().is_ok(),
+                CoreType::Boolean => matches!(self.value.as_str(), "true" | "false"),
+                CoreType::Uuid | CoreType::DateTime | CoreType::Bytes => {
+                    return Err(format!(
+                        "{core} is not a constant type (expected a number, boolean, string, or enum)"
+                    ));
+                }
+            },
+            _ => {
+                return Err(format!("{} is not a defined type", self.field_type_string));
+            }
+        };
+        if !valid {
+            return Err(format!(
+                "{} is not a valid {}",
+                self.value, self.field_type_string
+            ));
+        }
+        Ok(())
+    }
+
+    /// The value as a literal in most languages: strings are double-quoted with
+    /// `\` and `"` escaped, and everything else is written as is.
+    pub fn literal(&self) -> String {
+        match &self.field_type {
+            Some(FieldType::Core(CoreType::String)) => format!(
+                "\"{}\"",
+                self.value.replace('\\', "\\\\").replace('"', "\\\"")
+            ),
+            _ => self.value.clone(),
+        }
+    }
+}
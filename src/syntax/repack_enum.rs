@@ -1,9 +1,33 @@
-use super::{FileContents, Token, RepackError, RepackErrorKind};
+use super::{FieldFunction, FileContents, RepackError, RepackErrorKind, Token};
+
+/// The annotations an enum case may take after its value.
+const CASE_ANNOTATIONS: [&str; 2] = ["deprecated", "renamed_from"];
 
 #[derive(Debug)]
 pub struct RepackEnumCase {
     pub name: String,
     pub value: Option<String>,
+    /// Annotations written after the value: `deprecated` and `renamed_from(old)`
+    pub functions: Vec<FieldFunction>,
+}
+impl RepackEnumCase {
+    /// The note given to `deprecated("...")`, or an empty string for a bare
+    /// `deprecated`; `None` if the case is not deprecated.
+    pub fn deprecation(&self) -> Option<&str> {
+        self.functions
+            .iter()
+            .find(|f| f.name == "deprecated")
+            .map(|f| f.args.first().map(|a| a.as_str()).unwrap_or(""))
+    }
+
+    /// The value the case was stored under before, from `renamed_from(old)`.
+    pub fn renamed_from(&self) -> Option<&str> {
+        self.functions
+            .iter()
+            .find(|f| f.name == "renamed_from")
+            .and_then(|f| f.args.first())
+            .map(|a| a.as_str())
+    }
 }
 
 /// Represents an enumeration type definition in the schema.
@@ -31,28 +55,32 @@ impl RepackEnum {
     /// * `contents` - Mutable reference to the file contents being parsed
     ///
     /// # Returns
-    /// A fully constructed Enum with all parsed options and metadata
-    ///
-    /// # Panics
-    /// Panics if the expected enum name is missing or malformed
+    /// * `Ok(RepackEnum)` with all parsed options and metadata
+    /// * `Err(RepackError)` if the enum name is missing or malformed
     pub fn read_from_contents(contents: &mut FileContents) -> Result<RepackEnum, RepackError> {
         let Some(name_opt) = contents.next() else {
             return Err(RepackError::global(
                 RepackErrorKind::ParseIncomplete,
-                "enum name".to_string()
+                "enum name".to_string(),
             ));
         };
         let Token::Literal(name_ref) = name_opt else {
             return Err(RepackError::global(
                 RepackErrorKind::ParseIncomplete,
-                format!("{name_opt:?}")
+                format!("enum name, found {name_opt:?}"),
             ));
         };
         let name = name_ref.to_string();
         let mut options = Vec::new();
         let mut categories = Vec::new();
 
-        'header: while let Some(token) = contents.next() {
+        'header: loop {
+            let Some(token) = contents.next() else {
+                return Err(RepackError::global(
+                    RepackErrorKind::ParseIncomplete,
+                    format!("'{{' to open enum {name}"),
+                ));
+            };
             match token {
                 Token::Pound => {
                     if let Some(Token::Literal(lit)) = contents.next() {
@@ -66,7 +94,13 @@ impl RepackEnum {
             }
         }
 
-        'cmd: while let Some(token) = contents.take() {
+        'cmd: loop {
+            let Some(token) = contents.take() else {
+                return Err(RepackError::global(
+                    RepackErrorKind::ParseIncomplete,
+                    format!("'}}' to close enum {name}"),
+                ));
+            };
             match token {
                 Token::CloseBrace => {
                     break 'cmd;
@@ -75,8 +109,21 @@ impl RepackEnum {
                     let mut cs = RepackEnumCase {
                         name: lit,
                         value: None,
+                        functions: Vec::new(),
                     };
-                    if let Some(Token::Literal(val)) = contents.take() { cs.value = Some(val) }
+                    // A quoted word is always the value, so a case may still be stored as "deprecated".
+                    if let Some(Token::Literal(val)) = contents.peek()
+                        && (contents.peek_quoted() || !CASE_ANNOTATIONS.contains(&val.as_str()))
+                    {
+                        cs.value = contents.take_literal();
+                    }
+                    while let Some(Token::Literal(func)) = contents.peek() {
+                        let func = func.to_string();
+                        contents.skip();
+                        if let Some(func) = FieldFunction::from_contents(func, contents) {
+                            cs.functions.push(func);
+                        }
+                    }
                     options.push(cs);
                 }
                 _ => {}
@@ -1,4 +1,4 @@
-use super::{FieldFunction, FieldType, FileContents, Token};
+use super::{CategoryExpr, FieldFunction, FieldType, FileContents, Token, ValidationRule};
 
 #[derive(Debug, Clone)]
 pub struct FieldExternalLocation {
@@ -31,6 +31,9 @@ pub struct Field {
     /// Custom functions or transformations applied to this field
     /// Used for computed properties, validation, and formatting
     pub functions: Vec<FieldFunction>,
+    /// Categories this field is tagged with (`#internal`)
+    /// Outputs whose categories don't match the tags leave the field out
+    pub categories: Vec<String>,
 }
 impl Field {
     /// Filters field functions by their namespace.
@@ -52,7 +55,83 @@ impl Field {
     }
 
     pub fn function(&self, ns: &str, name: &str) -> Option<&FieldFunction> {
-        self.functions.iter().find(|x| x.namespace == ns && x.name == name)
+        self.functions
+            .iter()
+            .find(|x| x.namespace == ns && x.name == name)
+    }
+
+    /// The name emitted for an output, from a `rename(target name)` function
+    /// whose target is one of `targets`, or the schema name if none is.
+    ///
+    /// # Arguments
+    /// * `targets` - Names the active output answers to, such as its profile and blueprint aliases
+    pub fn name_for(&self, targets: &[&str]) -> &str {
+        self.targeted("rename", targets).unwrap_or(&self.name)
+    }
+
+    /// The type written for an output in place of the blueprint's own, from a
+    /// `type_override(target type)` function whose target is one of `targets`.
+    pub fn type_override(&self, targets: &[&str]) -> Option<&str> {
+        self.targeted("type_override", targets)
+    }
+
+    /// The second argument of the first `function(target value)` whose target
+    /// is one of `targets`.
+    fn targeted(&self, function: &str, targets: &[&str]) -> Option<&str> {
+        self.functions_in_namespace("")
+            .into_iter()
+            .filter(|f| f.name == function)
+            .find_map(|f| match f.args.as_slice() {
+                [target, value] if targets.contains(&target.as_str()) => Some(value.as_str()),
+                _ => None,
+            })
+    }
+
+    /// The column this field is stored in, from `rename(db name)`.
+    pub fn column_name(&self) -> &str {
+        self.name_for(&["db"])
+    }
+
+    /// The note given to `deprecated("...")`, or an empty string for a bare
+    /// `deprecated`; `None` if the field is not deprecated.
+    pub fn deprecation(&self) -> Option<&str> {
+        self.function("", "deprecated")
+            .map(|f| f.args.first().map(|a| a.as_str()).unwrap_or(""))
+    }
+
+    /// The name the field was stored under before, from `renamed_from(old)`.
+    pub fn renamed_from(&self) -> Option<&str> {
+        self.function("", "renamed_from")
+            .and_then(|f| f.args.first())
+            .map(|a| a.as_str())
+    }
+
+    /// Whether the field holds personal or secret data, from `sensitive`.
+    pub fn is_sensitive(&self) -> bool {
+        self.function("", "sensitive").is_some()
+    }
+
+    /// Whether an output with the given category expression includes this field.
+    ///
+    /// Untagged fields are always included, as are all fields when the output
+    /// has no categories.
+    pub fn included_in(&self, categories: Option<&CategoryExpr>) -> bool {
+        match categories {
+            Some(expr) if !self.categories.is_empty() => expr.matches(&self.categories),
+            _ => true,
+        }
+    }
+
+    /// Parses the `validate:` functions on this field into validation rules.
+    ///
+    /// # Returns
+    /// * `Ok(Vec<ValidationRule>)` with one entry per rule, in declaration order
+    /// * `Err(String)` describing the first rule that is invalid for this field
+    pub fn validations(&self) -> Result<Vec<ValidationRule>, String> {
+        self.functions_in_namespace("validate")
+            .into_iter()
+            .map(|func| ValidationRule::from_function(func, self.field_type.as_ref()))
+            .collect()
     }
 
     /// Parses a Field definition from the input file contents.
@@ -65,6 +144,8 @@ impl Field {
     /// - Direct type references: `field_name Type`
     /// - Foreign key references: `field_name ref(Object.field)`
     ///
+    /// Category tags (`#internal`) may appear among the functions.
+    ///
     /// # Arguments
     /// * `name` - The field name as parsed from the schema
     /// * `contents` - Mutable reference to the file contents being parsed
@@ -72,7 +153,8 @@ impl Field {
     /// # Returns
     /// * `Some(Field)` if parsing succeeds
     /// * `None` if the field definition is malformed
-    pub fn from_contents(name: String, contents: &mut FileContents) -> Option<Field> { // Parses: name Type[[]][?] func*
+    pub fn from_contents(name: String, contents: &mut FileContents) -> Option<Field> {
+        // Parses: name Type[[]][?] func*
         let type_token = contents.take()?;
         let next_token = contents.peek()?;
         let mut field_location: Option<FieldExternalLocation> = None;
@@ -125,9 +207,15 @@ impl Field {
             _ => false,
         };
         let mut functions = Vec::new();
+        let mut categories = Vec::new();
 
         while let Some(token) = contents.take() {
             match token {
+                Token::Pound => {
+                    if let Some(Token::Literal(_)) = contents.peek() {
+                        categories.push(contents.take_literal()?);
+                    }
+                }
                 Token::Literal(name) => {
                     if let Some(func) = FieldFunction::from_contents(name, contents) {
                         functions.push(func);
@@ -148,6 +236,7 @@ impl Field {
             array: is_many,
             field_location,
             functions,
+            categories,
         })
     }
 }
@@ -7,31 +7,79 @@ pub struct FieldFunction {
     pub args: Vec<String>,
 }
 impl FieldFunction {
-    pub fn from_contents(namespace: String, contents: &mut FileContents) -> Option<FieldFunction> {
-        if contents.take()? != Token::Colon {
-            return None;
+    /// Whether a function without a namespace is one the schema knows, with
+    /// the right number of arguments: `rename(target name)`,
+    /// `type_override(target type)`, `deprecated` with an optional note,
+    /// `renamed_from(old)`, or `sensitive`.
+    pub fn is_known_annotation(&self) -> bool {
+        match self.name.as_str() {
+            "rename" | "type_override" => self.args.len() == 2,
+            "sensitive" => self.args.is_empty(),
+            "deprecated" => self.args.len() <= 1,
+            "renamed_from" => self.args.len() == 1,
+            _ => false,
         }
-        let Some(Token::Literal(name)) = contents.take() else {
-            return None;
+    }
+
+    /// Parses `namespace:name(args)`, or `name(args)` for functions without a
+    /// namespace such as `rename`, whose namespace is left empty. A bare word
+    /// such as `deprecated` is a function without a namespace or arguments.
+    pub fn from_contents(namespace: String, contents: &mut FileContents) -> Option<FieldFunction> {
+        let (namespace, name, has_args) = match contents.peek() {
+            Some(Token::Colon) => {
+                contents.skip();
+                let Some(Token::Literal(name)) = contents.take() else {
+                    return None;
+                };
+                let has_args = *contents.peek()? == Token::OpenParen;
+                if has_args {
+                    contents.skip();
+                }
+                (namespace, name, has_args)
+            }
+            Some(Token::OpenParen) => {
+                contents.skip();
+                (String::new(), namespace, true)
+            }
+            _ => (String::new(), namespace, false),
         };
         let mut args = Vec::<String>::new();
-        if *contents.peek()? == Token::OpenParen {
-            contents.skip();
-            // has args
+        if has_args {
+            // has args, separated by commas or whitespace
             let mut buf = String::new();
-            loop {
-                let Some(tok) = contents.take() else { break };
+            let mut has_value = false;
+            let mut last_literal = false;
+            while let Some(tok) = contents.take() {
                 match tok {
                     Token::Comma => {
                         args.push(buf);
                         buf = String::new();
+                        has_value = false;
+                        last_literal = false;
                     }
                     Token::CloseParen => {
-                        args.push(buf);
+                        if has_value || !args.is_empty() {
+                            args.push(buf);
+                        }
                         break;
                     }
                     Token::Literal(text) => {
+                        if last_literal {
+                            args.push(buf);
+                            buf = String::new();
+                        }
                         buf.push_str(&text);
+                        has_value = true;
+                        last_literal = true;
+                    }
+                    Token::Minus | Token::Plus | Token::Period => {
+                        buf.push(match tok {
+                            Token::Minus => '-',
+                            Token::Plus => '+',
+                            _ => '.',
+                        });
+                        has_value = true;
+                        last_literal = false;
                     }
                     _ => {}
                 };
@@ -0,0 +1,98 @@
+use super::{
+    Field, FileContents, ObjectFunction, RepackError, RepackErrorKind, RepackStructJoin, Token,
+    query::Query,
+};
+
+/// A set of fields, functions, joins, and queries shared by several structs.
+///
+/// A struct uses a mixin by listing it in its header (`struct Post @posts +Auditable`).
+/// Unlike a snippet, a mixin may also contribute joins and queries, and any name
+/// it contributes that the struct or another of its mixins already defines is an
+/// error unless the struct lists it in `mixin:overrides(...)`.
+#[derive(Debug)]
+pub struct RepackMixin {
+    /// The name structs use the mixin by
+    pub name: String,
+    /// Fields added to each struct using the mixin
+    pub fields: Vec<Field>,
+    /// Struct functions added to each struct using the mixin
+    pub functions: Vec<ObjectFunction>,
+    /// Joins added to each struct using the mixin
+    pub joins: Vec<RepackStructJoin>,
+    /// Queries added to each struct using the mixin, rendered against that struct
+    pub queries: Vec<Query>,
+}
+impl RepackMixin {
+    /// Parses a mixin definition: `mixin Name { ... }` with the same body
+    /// syntax as a struct, minus snippets and `insert`/`update` shorthands.
+    ///
+    /// # Returns
+    /// * `Ok(RepackMixin)` with its fields, functions, joins, and queries
+    /// * `Err(RepackError)` if the name, a field, a join, or a query is malformed
+    pub fn read_from_contents(contents: &mut FileContents) -> Result<RepackMixin, RepackError> {
+        let Some(name) = contents.take_literal() else {
+            return Err(RepackError::global(
+                RepackErrorKind::ParseIncomplete,
+                "mixin name".to_string(),
+            ));
+        };
+        let mut fields = Vec::new();
+        let mut functions = Vec::new();
+        let mut joins = Vec::new();
+        let mut queries = Vec::new();
+
+        loop {
+            let Some(next) = contents.take() else {
+                return Err(RepackError::global(
+                    RepackErrorKind::ParseIncomplete,
+                    format!("'{{' to open mixin {name}"),
+                ));
+            };
+            if next == Token::OpenBrace {
+                break;
+            }
+        }
+
+        'cmd: loop {
+            let Some(token) = contents.take() else {
+                return Err(RepackError::global(
+                    RepackErrorKind::ParseIncomplete,
+                    format!("'}}' to close mixin {name}"),
+                ));
+            };
+            match token {
+                Token::CloseBrace => break 'cmd,
+                Token::Literal(lit) => {
+                    if let Some(next) = contents.peek() {
+                        if *next == Token::Colon {
+                            if let Some(func) =
+                                ObjectFunction::from_contents(lit.to_string(), contents)
+                            {
+                                functions.push(func);
+                            }
+                        } else if let Some(field) = Field::from_contents(lit.to_string(), contents)
+                        {
+                            fields.push(field);
+                        } else {
+                            return Err(RepackError::global(
+                                RepackErrorKind::ParseIncomplete,
+                                format!("field in {name}"),
+                            ));
+                        }
+                    }
+                }
+                Token::Join => joins.push(RepackStructJoin::parse(contents)?),
+                Token::Query => queries.push(Query::parse(&name, contents)?),
+                _ => {}
+            }
+        }
+
+        Ok(RepackMixin {
+            name,
+            fields,
+            functions,
+            joins,
+            queries,
+        })
+    }
+}
@@ -1,11 +1,11 @@
 use std::collections::HashSet;
 
 use super::{
-    AutoInsertQuery, AutoUpdateQuery, Field, FieldType, FileContents, ObjectFunction, RepackError,
-    RepackErrorKind, Token, query::Query,
+    AutoInsertQuery, AutoUpdateQuery, CoreType, Field, FieldFunction, FieldType, FileContents,
+    ObjectFunction, RepackError, RepackErrorKind, Token, query::Query,
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RepackStructJoin {
     pub name: String,
     pub contents: String,
@@ -58,6 +58,33 @@ impl RepackStructJoin {
     }
 }
 
+/// Describes how a struct refers to another entity in the schema.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DependencyKind {
+    /// The struct inherits from the target (`: Parent`).
+    Inherits,
+    /// A field's type is the target struct.
+    Field,
+    /// A field's type is the target enum.
+    Enum,
+    /// A field references the target's table with `db.fk`.
+    ForeignKey,
+    /// The struct joins the target with `join(name Target)`.
+    Join,
+}
+
+/// One reference from a struct to another entity, as listed by
+/// [`RepackStruct::dependencies`].
+#[derive(Debug)]
+pub struct StructDependency {
+    /// How the struct refers to the target
+    pub kind: DependencyKind,
+    /// The referenced struct or enum name, or a table name for foreign keys
+    pub target: String,
+    /// What creates the reference, for diagnostics (e.g. `field user_id`)
+    pub via: String,
+}
+
 /// Represents a complete object definition in the schema system.
 ///
 /// Object is the core building block of the schema, containing all the metadata
@@ -83,6 +110,8 @@ pub struct RepackStruct {
     /// Names of code snippets to include in the generated code.
     /// Snippets provide custom code injection points for specialized logic.
     pub use_snippets: Vec<String>,
+    /// Names of the mixins whose members are added to this struct (`+Auditable`).
+    pub mixins: Vec<String>,
     /// Custom functions/methods defined for this object.
     /// These generate additional methods in the target language classes.
     pub functions: Vec<ObjectFunction>,
@@ -104,10 +133,8 @@ impl RepackStruct {
     /// * `contents` - Mutable reference to the file contents being parsed
     ///
     /// # Returns
-    /// A fully constructed Object with all parsed metadata and fields
-    ///
-    /// # Panics
-    /// Panics if the expected object name is missing or malformed
+    /// * `Ok(RepackStruct)` with all parsed metadata and fields
+    /// * `Err(RepackError)` if the name, a field, a join, or a query is malformed
     pub fn read_from_contents(contents: &mut FileContents) -> Result<RepackStruct, RepackError> {
         let Some(name_opt) = contents.next() else {
             return Err(RepackError::global(
@@ -118,7 +145,7 @@ impl RepackStruct {
         let Token::Literal(name_ref) = name_opt else {
             return Err(RepackError::global(
                 RepackErrorKind::ParseIncomplete,
-                format!("{name_opt:?}"),
+                format!("struct name, found {name_opt:?}"),
             ));
         };
         let name = name_ref.to_string();
@@ -127,13 +154,20 @@ impl RepackStruct {
         let mut inherits = None;
         let mut table_name = None;
         let mut use_snippets = Vec::new();
+        let mut mixins = Vec::new();
         let mut functions = Vec::new();
         let mut queries = Vec::new();
         let mut joins = Vec::new();
         let mut autoinsertqueries = Vec::new();
         let mut autoupdatequeries = Vec::new();
 
-        'header: while let Some(token) = contents.next() {
+        'header: loop {
+            let Some(token) = contents.next() else {
+                return Err(RepackError::global(
+                    RepackErrorKind::ParseIncomplete,
+                    format!("'{{' to open struct {name}"),
+                ));
+            };
             match token {
                 Token::At => {
                     table_name = match contents.next() {
@@ -152,6 +186,11 @@ impl RepackStruct {
                         categories.push(lit.to_string());
                     }
                 }
+                Token::Plus => {
+                    if let Some(Token::Literal(lit)) = contents.next() {
+                        mixins.push(lit.to_string());
+                    }
+                }
                 Token::OpenBrace => {
                     break 'header;
                 }
@@ -159,7 +198,13 @@ impl RepackStruct {
             }
         }
 
-        'cmd: while let Some(token) = contents.take() {
+        'cmd: loop {
+            let Some(token) = contents.take() else {
+                return Err(RepackError::global(
+                    RepackErrorKind::ParseIncomplete,
+                    format!("'}}' to close struct {name}"),
+                ));
+            };
             match token {
                 Token::CloseBrace => break 'cmd,
                 Token::Literal(lit) => {
@@ -217,6 +262,7 @@ impl RepackStruct {
             table_name,
             categories,
             use_snippets,
+            mixins,
             functions,
             queries,
             joins,
@@ -231,6 +277,7 @@ impl RepackStruct {
     /// - Validates field names are unique within each object
     /// - Ensures all field types are properly resolved
     /// - All objects must have unique field names and resolved field types
+    /// - Queries are only declared on structs stored in a table
     ///
     /// # Returns
     /// * `Some(Vec<RepackError>)` if validation errors are found
@@ -256,6 +303,51 @@ impl RepackStruct {
                 ));
                 continue;
             };
+            if let Err(msg) = field.validations() {
+                errors.push(RepackError::from_field_with_msg(
+                    RepackErrorKind::InvalidValidation,
+                    self,
+                    field,
+                    msg,
+                ));
+            }
+            for func in field.functions_in_namespace("") {
+                if !func.is_known_annotation() {
+                    errors.push(RepackError::from_field_with_msg(
+                        RepackErrorKind::FunctionInvalidSyntax,
+                        self,
+                        field,
+                        format!("{}({})", func.name, func.args.join(" ")),
+                    ));
+                }
+            }
+        }
+        if self.table_name.is_none() {
+            for query in &self.queries {
+                errors.push(RepackError::from_obj_with_msg(
+                    RepackErrorKind::QueryWithoutTable,
+                    self,
+                    query.name.to_string(),
+                ));
+            }
+        }
+        for query in &self.queries {
+            let Some(route) = &query.http else {
+                continue;
+            };
+            for param in route.params() {
+                if !query
+                    .args
+                    .iter()
+                    .any(|arg| arg.name.trim_start_matches('_') == param)
+                {
+                    errors.push(RepackError::from_obj_with_msg(
+                        RepackErrorKind::QueryInvalidSyntax,
+                        self,
+                        format!("{} (no argument for :{param})", query.name),
+                    ));
+                }
+            }
         }
         if errors.is_empty() {
             None
@@ -264,6 +356,44 @@ impl RepackStruct {
         }
     }
 
+    /// Whether the struct has `db:timestamps`, so its table keeps
+    /// `created_at` and `updated_at` current.
+    pub fn has_timestamps(&self) -> bool {
+        self.functions
+            .iter()
+            .any(|f| f.namespace == "db" && f.name == "timestamps")
+    }
+
+    /// Adds the `created_at` and `updated_at` fields `db:timestamps` maintains,
+    /// unless the struct already defines them.
+    ///
+    /// Both are `datetime` columns defaulting to `NOW()`. Structs without a
+    /// table are left alone; [`RepackStruct::errors`] warns about them instead.
+    pub fn add_timestamps(&mut self) {
+        if !self.has_timestamps() || self.table_name.is_none() {
+            return;
+        }
+        for name in ["created_at", "updated_at"] {
+            if self.fields.iter().any(|f| f.name == name) {
+                continue;
+            }
+            self.fields.push(Field {
+                name: name.to_string(),
+                field_type_string: CoreType::DateTime.to_string(),
+                field_location: None,
+                field_type: Some(FieldType::Core(CoreType::DateTime)),
+                optional: false,
+                array: false,
+                functions: vec![FieldFunction {
+                    namespace: "db".to_string(),
+                    name: "default".to_string(),
+                    args: vec!["NOW()".to_string()],
+                }],
+                categories: Vec::new(),
+            });
+        }
+    }
+
     /// Determines the dependency relationships for this object.
     ///
     /// Analyzes the object's inheritance and field references to identify
@@ -274,27 +404,68 @@ impl RepackStruct {
     /// A vector of object names that this object depends on, including:
     /// - Parent objects (via inheritance)
     /// - Referenced objects (via field types)
-    /// - Join target objects (via implicit joins)
+    /// - Referenced tables (via `db.fk`)
     pub fn depends_on(&self) -> Vec<String> {
         let mut dependencies = HashSet::new();
+        for dep in self.dependencies() {
+            match dep.kind {
+                DependencyKind::Inherits | DependencyKind::Field | DependencyKind::ForeignKey => {
+                    dependencies.insert(dep.target);
+                }
+                DependencyKind::Enum | DependencyKind::Join => {}
+            }
+        }
+        dependencies.into_iter().collect()
+    }
+
+    /// Lists every entity this object refers to, along with how it refers to it.
+    ///
+    /// Unlike [`RepackStruct::depends_on`], this includes enum fields and join
+    /// targets, which do not affect ordering but are useful for visualising the
+    /// schema. Entries are in declaration order and may repeat.
+    pub fn dependencies(&self) -> Vec<StructDependency> {
+        let mut dependencies = Vec::new();
         if let Some(inherit) = &self.inherits {
-            dependencies.insert(inherit.to_string());
+            dependencies.push(StructDependency {
+                kind: DependencyKind::Inherits,
+                target: inherit.to_string(),
+                via: "inherits".to_string(),
+            });
         }
         for field in &self.fields {
-            match field.field_type {
+            match &field.field_type {
                 Some(FieldType::Custom(_, super::CustomFieldType::Object)) | None => {
-                    dependencies.insert(field.field_type_string.to_string());
+                    dependencies.push(StructDependency {
+                        kind: DependencyKind::Field,
+                        target: field.field_type_string.to_string(),
+                        via: format!("field {}", field.name),
+                    });
                 }
-                _ => {}
-            }
-            match field.function("db", "fk").and_then(|x| x.args.first()) {
-                Some(val) => {
-                    dependencies.insert(val.to_string());
+                Some(FieldType::Custom(name, super::CustomFieldType::Enum)) => {
+                    dependencies.push(StructDependency {
+                        kind: DependencyKind::Enum,
+                        target: name.to_string(),
+                        via: format!("field {}", field.name),
+                    });
                 }
                 _ => {}
-            };
+            }
+            if let Some(val) = field.function("db", "fk").and_then(|x| x.args.first()) {
+                dependencies.push(StructDependency {
+                    kind: DependencyKind::ForeignKey,
+                    target: val.to_string(),
+                    via: format!("db:fk on {}", field.name),
+                });
+            }
         }
-        dependencies.into_iter().collect()
+        for join in &self.joins {
+            dependencies.push(StructDependency {
+                kind: DependencyKind::Join,
+                target: join.foreign_entity.to_string(),
+                via: format!("join {}", join.name),
+            });
+        }
+        dependencies
     }
 
     /// Filters object functions by their namespace.
@@ -19,8 +19,7 @@ impl ObjectFunction {
             contents.skip();
             // has args
             let mut buf = String::new();
-            loop {
-                let Some(tok) = contents.take() else { break };
+            while let Some(tok) = contents.take() {
                 match tok {
                     Token::Comma => {
                         args.push(buf);
@@ -33,12 +32,8 @@ impl ObjectFunction {
                     Token::Literal(text) => {
                         buf.push_str(&text);
                     }
-                    Token::NewLine => {
-                        break
-                    }
-                    _ => {
-                        return None
-                    }
+                    Token::NewLine => break,
+                    _ => return None,
                 };
             }
         }
@@ -0,0 +1,79 @@
+use super::{FileContents, RepackError, RepackErrorKind, Token};
+
+/// A value that is exactly one of several structs, such as a payment that is
+/// a card, a bank transfer, or cash.
+///
+/// Written `union Payment = Card | BankTransfer | Cash`. Each variant names a
+/// struct, and fields may use the union as their type. Generated code tells
+/// the variants apart by a `type` tag holding the variant's struct name.
+#[derive(Debug)]
+pub struct RepackUnion {
+    /// The name fields use the union by
+    pub name: String,
+    /// Tags/categories for organizing and filtering unions during generation
+    pub categories: Vec<String>,
+    /// The names of the structs the union may hold, in the order written
+    pub variants: Vec<String>,
+}
+impl RepackUnion {
+    /// Parses a union definition: `union Name #category = A | B | C`.
+    ///
+    /// The variants end at the end of the line, though a line ending in `|`
+    /// continues onto the next.
+    ///
+    /// # Returns
+    /// * `Ok(RepackUnion)` with its variants
+    /// * `Err(RepackError)` if the name, the `=`, or a variant is missing
+    pub fn read_from_contents(contents: &mut FileContents) -> Result<RepackUnion, RepackError> {
+        let Some(name) = contents.take_literal() else {
+            return Err(RepackError::global(
+                RepackErrorKind::ParseIncomplete,
+                "union name".to_string(),
+            ));
+        };
+        let mut categories = Vec::new();
+        loop {
+            match contents.take() {
+                Some(Token::Pound) => {
+                    if let Some(Token::Literal(lit)) = contents.peek() {
+                        categories.push(lit.to_string());
+                        contents.skip();
+                    }
+                }
+                Some(Token::Equal) => break,
+                _ => {
+                    return Err(RepackError::global(
+                        RepackErrorKind::ParseIncomplete,
+                        format!("'=' after union {name}"),
+                    ));
+                }
+            }
+        }
+
+        let mut variants = Vec::new();
+        loop {
+            while matches!(contents.peek(), Some(Token::NewLine)) {
+                contents.skip();
+            }
+            // Peek first so a missing variant leaves the next definition to be read.
+            let Some(Token::Literal(variant)) = contents.peek() else {
+                return Err(RepackError::global(
+                    RepackErrorKind::ParseIncomplete,
+                    format!("variant of union {name}"),
+                ));
+            };
+            variants.push(variant.to_string());
+            contents.skip();
+            if !matches!(contents.peek(), Some(Token::Pipe)) {
+                break;
+            }
+            contents.skip();
+        }
+
+        Ok(RepackUnion {
+            name,
+            categories,
+            variants,
+        })
+    }
+}
@@ -1,6 +1,11 @@
+use std::collections::{HashMap, HashSet};
+
 use super::{
-    CustomFieldType, FieldType, FileContents, Output, RepackEnum, RepackError, RepackErrorKind,
-    RepackStruct, Snippet, Token, dependancies::graph_valid, language,
+    CategoryExpr, CustomFieldType, FieldType, FileContents, LintRule, Output, RepackConfig,
+    RepackConst, RepackEnum, RepackError, RepackErrorKind, RepackMixin, RepackStruct, RepackUnion,
+    Snippet, Token,
+    dependancies::{graph_valid, sort_by_dependencies},
+    language, typed_default,
 };
 
 /// Represents the complete parsed schema with all defined entities and configurations.
@@ -16,67 +21,218 @@ pub struct ParseResult {
     pub languages: Vec<Output>,
     /// All parsed enumeration definitions
     pub enums: Vec<RepackEnum>,
+    /// All parsed union definitions
+    pub unions: Vec<RepackUnion>,
+    /// All parsed constants
+    pub consts: Vec<RepackConst>,
+    /// All parsed configuration schemas
+    pub configs: Vec<RepackConfig>,
+    /// All parsed snippets; their fields are already expanded into the structs using them
+    pub snippets: Vec<Snippet>,
+    /// All parsed mixins; their members are already added to the structs using them
+    pub mixins: Vec<RepackMixin>,
     /// List of external blueprint files to be loaded for code generation
     pub include_blueprints: Vec<String>,
+    /// Lint rules turned off by `lint { allow ... }` blocks
+    pub allowed_lints: Vec<LintRule>,
+    /// Project metadata from `schema { ... }` blocks, such as its name, in the
+    /// order written. Blueprints read it as `[schema.key]`.
+    pub meta: Vec<(String, String)>,
+    /// Non-fatal diagnostics found while validating the schema
+    pub warnings: Vec<RepackError>,
+}
+
+/// Parses a `schema { ... }` block of project metadata, each line a key
+/// followed by its value, such as `project billing`.
+///
+/// # Returns
+/// * `Ok(Vec<(String, String)>)` with the entries in the order written
+/// * `Err(RepackError)` if the block is malformed or a key has no value
+fn read_meta_block(contents: &mut FileContents) -> Result<Vec<(String, String)>, RepackError> {
+    if !matches!(contents.take(), Some(Token::OpenBrace)) {
+        return Err(RepackError::global(
+            RepackErrorKind::SyntaxError,
+            "schema block. Expected '{'.".to_string(),
+        ));
+    }
+    let mut entries = Vec::new();
+    while let Some(token) = contents.take() {
+        match token {
+            Token::CloseBrace => return Ok(entries),
+            Token::Literal(key) => {
+                let Some(value) = contents.take_literal() else {
+                    return Err(RepackError::global(
+                        RepackErrorKind::ParseIncomplete,
+                        format!("value for schema {key}"),
+                    ));
+                };
+                entries.push((key, value));
+            }
+            _ => {}
+        }
+    }
+    Err(RepackError::global(
+        RepackErrorKind::ParseIncomplete,
+        "'}' to close schema block".to_string(),
+    ))
 }
 
 impl ParseResult {
-    /// Parses the complete schema from tokenized file contents.
+    /// Parses all top-level definitions (objects, enums, outputs, imports)
+    /// from tokenized file contents.
     ///
-    /// This method performs the complete parsing pipeline:
-    /// 1. Parses all top-level definitions (objects, enums, outputs, imports)
-    /// 2. Expands snippet inclusions into objects
-    /// 3. Resolves dependency ordering for objects
-    /// 4. Resolves all field type references and relationships
-    /// 5. Validates the complete schema for consistency
+    /// The definitions are not yet checked against each other; call
+    /// [`ParseResult::resolve`] to get a usable schema.
     ///
     /// # Arguments
     /// * `contents` - The tokenized file contents to parse
     ///
     /// # Returns
-    /// * `Ok(ParseResult)` if parsing succeeds with a valid schema
-    /// * `Err(Vec<RepackError>)` if any validation or parsing errors occur
-    pub fn from_contents(mut contents: FileContents) -> Result<ParseResult, Vec<RepackError>> {
-        let mut errors = Vec::<RepackError>::new();
-
+    /// * `Ok(ParseResult)` with the unresolved definitions
+    /// * `Err(Vec<RepackError>)` with one error for each definition that cannot be parsed
+    pub fn read_contents(mut contents: FileContents) -> Result<ParseResult, Vec<RepackError>> {
         let mut strcts = Vec::new();
         let mut snippets = Vec::new();
+        let mut mixins = Vec::new();
         let mut languages = Vec::new();
         let mut enums = Vec::new();
+        let mut unions = Vec::new();
+        let mut consts = Vec::new();
+        let mut configs = Vec::new();
         let mut include_blueprints = Vec::new();
+        let mut allowed_lints = Vec::new();
+        let mut meta = Vec::new();
+        let mut errors = Vec::new();
 
         while let Some(token) = contents.next() {
             match *token {
                 Token::StructType => match RepackStruct::read_from_contents(&mut contents) {
                     Ok(s) => strcts.push(s),
-                    Err(e) => return Err(vec![e]),
+                    Err(e) => errors.push(contents.recover(e)),
                 },
                 Token::EnumType => match RepackEnum::read_from_contents(&mut contents) {
                     Ok(e) => enums.push(e),
-                    Err(e) => return Err(vec![e]),
+                    Err(e) => errors.push(contents.recover(e)),
                 },
                 Token::SnippetType => match Snippet::read_from_contents(&mut contents) {
                     Ok(s) => snippets.push(s),
-                    Err(e) => return Err(vec![e]),
+                    Err(e) => errors.push(contents.recover(e)),
                 },
-                Token::OutputType => {
-                    if let Some(language) = language::Output::from_contents(&mut contents) {
-                        languages.push(language);
+                // `config` is only a keyword at the top level so fields may still use the name.
+                Token::Literal(ref lit) if lit == "config" => {
+                    match RepackConfig::read_from_contents(&mut contents) {
+                        Ok(c) => configs.push(c),
+                        Err(e) => errors.push(contents.recover(e)),
                     }
                 }
-                Token::Import => {
-                    if let Some(Token::Literal(path)) = contents.take() {
-                        contents.add_relative(&path);
+                Token::Literal(ref lit) if lit == "union" => {
+                    match RepackUnion::read_from_contents(&mut contents) {
+                        Ok(u) => unions.push(u),
+                        Err(e) => errors.push(contents.recover(e)),
                     }
                 }
-                Token::Blueprint => {
-                    if let Some(Token::Literal(path)) = contents.take() {
-                        include_blueprints.push(path);
+                Token::Literal(ref lit) if lit == "const" => {
+                    match RepackConst::read_from_contents(&mut contents) {
+                        Ok(c) => consts.push(c),
+                        Err(e) => errors.push(contents.recover(e)),
                     }
                 }
+                Token::Literal(ref lit) if lit == "mixin" => {
+                    match RepackMixin::read_from_contents(&mut contents) {
+                        Ok(m) => mixins.push(m),
+                        Err(e) => errors.push(contents.recover(e)),
+                    }
+                }
+                Token::Literal(ref lit) if lit == "lint" => {
+                    match LintRule::read_block(&mut contents) {
+                        Ok(mut rules) => allowed_lints.append(&mut rules),
+                        Err(e) => errors.push(contents.recover(e)),
+                    }
+                }
+                Token::Literal(ref lit) if lit == "schema" => {
+                    match read_meta_block(&mut contents) {
+                        Ok(entries) => {
+                            for (key, value) in entries {
+                                meta.retain(|(k, _): &(String, String)| *k != key);
+                                meta.push((key, value));
+                            }
+                        }
+                        Err(e) => errors.push(contents.recover(e)),
+                    }
+                }
+                Token::OutputType => match language::Output::from_contents(&mut contents) {
+                    Ok(language) => languages.push(language),
+                    Err(e) => errors.push(contents.recover(e)),
+                },
+                Token::Import => match contents.take() {
+                    Some(Token::Literal(path)) => {
+                        if let Err(e) = contents.add_relative(&path) {
+                            errors.push(contents.recover(e));
+                        }
+                    }
+                    _ => errors.push(contents.recover(RepackError::global(
+                        RepackErrorKind::ParseIncomplete,
+                        "file after import".to_string(),
+                    ))),
+                },
+                Token::Blueprint => match contents.take() {
+                    Some(Token::Literal(path)) => include_blueprints.push(path),
+                    _ => errors.push(contents.recover(RepackError::global(
+                        RepackErrorKind::ParseIncomplete,
+                        "file after blueprint".to_string(),
+                    ))),
+                },
                 _ => {}
             }
         }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        Ok(ParseResult {
+            strcts,
+            languages,
+            enums,
+            unions,
+            consts,
+            configs,
+            snippets,
+            mixins,
+            include_blueprints,
+            allowed_lints,
+            meta,
+            warnings: Vec::new(),
+        })
+    }
+
+    /// Resolves and validates definitions that have been read but not checked.
+    ///
+    /// This is the part of parsing shared by every input format: it expands
+    /// snippets, orders structs by dependency, resolves field types, `super`
+    /// and join references, turns `insert`/`update` shorthands into queries,
+    /// and validates the result.
+    ///
+    /// # Returns
+    /// * `Ok(ParseResult)` if the schema is valid
+    /// * `Err(Vec<RepackError>)` with every error found
+    ///
+    /// Warnings never cause an `Err`; they are collected in `warnings`.
+    pub fn resolve(self) -> Result<ParseResult, Vec<RepackError>> {
+        let ParseResult {
+            mut strcts,
+            languages,
+            enums,
+            unions,
+            mut consts,
+            mut configs,
+            snippets,
+            mixins,
+            include_blueprints,
+            allowed_lints,
+            meta,
+            ..
+        } = self;
+        let mut errors = Vec::<RepackError>::new();
 
         // Expand all snippets.
         // This is important to do before dependancy checks
@@ -109,36 +265,55 @@ impl ParseResult {
             strct_snip_idx += 1;
         }
 
-        // Rearrange all objects in dependancy order
-        // for simple resolution.
-        let mut i = 0;
-        while i < strcts.len() {
-            let mut found_issue = false;
-            'dep_search: for dependancy in strcts[i].depends_on() {
-                let mut x = i;
-                while x < strcts.len() {
-                    if strcts[x].name == dependancy
-                        || strcts[x]
-                            .table_name
-                            .as_ref()
-                            .map(|x| *x == dependancy)
-                            .unwrap_or(false)
-                    {
-                        found_issue = true;
-                        break 'dep_search;
-                    }
-                    x += 1;
-                }
+        // Mixins come after snippets, so a snippet's fields count as the struct's own.
+        for strct in strcts.iter_mut() {
+            errors.append(&mut apply_mixins(strct, &mixins));
+        }
+
+        // `db:timestamps` adds its columns once snippets are in place, so a
+        // snippet that already defines them is respected.
+        for strct in strcts.iter_mut() {
+            strct.add_timestamps();
+        }
+
+        // Constants stand in for function arguments before those are checked,
+        // so `validate:max_len(MAX_NAME_LEN)` is validated with its value.
+        let mut const_names = HashSet::new();
+        for cnst in consts.iter_mut() {
+            if !const_names.insert(cnst.name.clone()) {
+                errors.push(RepackError::global(
+                    RepackErrorKind::InvalidConst,
+                    format!("{} is defined more than once", cnst.name),
+                ));
             }
-            if found_issue {
-                let dep = strcts.remove(i);
-                strcts.push(dep);
-                i = 0
-            } else {
-                i += 1;
+            if let Err(msg) = cnst.resolve(&enums) {
+                errors.push(RepackError::global(
+                    RepackErrorKind::InvalidConst,
+                    format!("{msg} (const {})", cnst.name),
+                ));
+            }
+        }
+        for strct in strcts.iter_mut() {
+            let field_args = strct
+                .fields
+                .iter_mut()
+                .flat_map(|field| field.functions.iter_mut())
+                .flat_map(|func| func.args.iter_mut());
+            let strct_args = strct
+                .functions
+                .iter_mut()
+                .flat_map(|func| func.args.iter_mut());
+            for arg in field_args.chain(strct_args) {
+                if let Some(cnst) = consts.iter().find(|c| c.name == *arg) {
+                    *arg = cnst.value.clone();
+                }
             }
         }
 
+        // Rearrange all objects in dependancy order
+        // for simple resolution.
+        sort_by_dependencies(&mut strcts);
+
         // Resolve references and do some error checking.
         let mut object_idx: usize = 0;
         while object_idx < strcts.len() {
@@ -258,22 +433,26 @@ impl ParseResult {
                             lookup_name.clone(),
                             CustomFieldType::Enum,
                         ));
+                    } else if unions.iter().any(|un| un.name == *lookup_name) {
+                        strcts[object_idx].fields[field_idx].field_type = Some(FieldType::Custom(
+                            lookup_name.clone(),
+                            CustomFieldType::Union,
+                        ));
                     }
                 }
                 // Ensure types are resolved
                 if let Some(FieldType::Custom(object_name, _)) =
                     &strcts[object_idx].fields[field_idx].field_type
+                    && !strcts.iter().any(|o| o.name == *object_name)
+                    && !enums.iter().any(|e| e.name == *object_name)
+                    && !unions.iter().any(|u| u.name == *object_name)
                 {
-                    if !strcts.iter().any(|o| o.name == *object_name)
-                        && !enums.iter().any(|e| e.name == *object_name)
-                    {
-                        errors.push(RepackError::from_field_with_msg(
-                            RepackErrorKind::CustomTypeNotDefined,
-                            &strcts[object_idx],
-                            &strcts[object_idx].fields[field_idx],
-                            object_name.to_string(),
-                        ));
-                    }
+                    errors.push(RepackError::from_field_with_msg(
+                        RepackErrorKind::CustomTypeNotDefined,
+                        &strcts[object_idx],
+                        &strcts[object_idx].fields[field_idx],
+                        object_name.to_string(),
+                    ));
                 }
                 field_idx += 1;
             }
@@ -308,6 +487,99 @@ impl ParseResult {
                 errors.append(&mut errs);
             }
         }
+        for object in &mut strcts {
+            for field_idx in 0..object.fields.len() {
+                let field = &object.fields[field_idx];
+                let (Some(field_type), Some(value)) = (
+                    field.field_type.as_ref(),
+                    field.function("db", "default").and_then(|f| f.args.first()),
+                ) else {
+                    continue;
+                };
+                match typed_default(value, field_type, field.array, &enums) {
+                    Ok(sql) => {
+                        if let Some(func) = object.fields[field_idx]
+                            .functions
+                            .iter_mut()
+                            .find(|f| f.namespace == "db" && f.name == "default")
+                        {
+                            func.args[0] = sql;
+                        }
+                    }
+                    Err(msg) => errors.push(RepackError::from_field_with_msg(
+                        RepackErrorKind::InvalidDefault,
+                        object,
+                        field,
+                        msg,
+                    )),
+                }
+            }
+        }
+        for union in &unions {
+            let mut seen = HashSet::new();
+            for variant in &union.variants {
+                if !strcts.iter().any(|s| s.name == *variant) {
+                    errors.push(RepackError::global(
+                        RepackErrorKind::InvalidUnion,
+                        format!("{variant} in union {} is not a struct", union.name),
+                    ));
+                } else if !seen.insert(variant) {
+                    errors.push(RepackError::global(
+                        RepackErrorKind::InvalidUnion,
+                        format!("{variant} appears more than once in union {}", union.name),
+                    ));
+                }
+            }
+        }
+        for enm in &enums {
+            for case in &enm.options {
+                for func in &case.functions {
+                    if !func.namespace.is_empty()
+                        || !matches!(func.name.as_str(), "deprecated" | "renamed_from")
+                        || !func.is_known_annotation()
+                    {
+                        errors.push(RepackError::global(
+                            RepackErrorKind::FunctionInvalidSyntax,
+                            format!(
+                                "{}({}) ({}.{})",
+                                func.name,
+                                func.args.join(" "),
+                                enm.name,
+                                case.name
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+        for config in &mut configs {
+            let mut keys = HashSet::new();
+            for entry in &mut config.entries {
+                if !keys.insert(entry.name.clone()) {
+                    errors.push(RepackError::global(
+                        RepackErrorKind::DuplicateFieldNames,
+                        format!("{}.{}", config.name, entry.name),
+                    ));
+                }
+                if entry.field_type.is_some() {
+                    continue;
+                }
+                if enums.iter().any(|e| e.name == entry.field_type_string) {
+                    entry.field_type = Some(FieldType::Custom(
+                        entry.field_type_string.clone(),
+                        CustomFieldType::Enum,
+                    ));
+                } else {
+                    errors.push(RepackError::global(
+                        RepackErrorKind::CustomTypeNotDefined,
+                        format!(
+                            "{} ({}.{})",
+                            entry.field_type_string, config.name, entry.name
+                        ),
+                    ));
+                }
+            }
+        }
         for language in &languages {
             let mut errs = language.errors();
             errors.append(&mut errs);
@@ -315,6 +587,7 @@ impl ParseResult {
         if let Err(e) = graph_valid(&strcts) {
             errors.push(e)
         }
+        let (warnings, errors): (Vec<_>, Vec<_>) = errors.into_iter().partition(|e| e.is_warning());
         if !errors.is_empty() {
             Err(errors)
         } else {
@@ -322,7 +595,15 @@ impl ParseResult {
                 strcts,
                 languages,
                 enums,
+                unions,
+                consts,
+                configs,
+                snippets,
+                mixins,
                 include_blueprints,
+                allowed_lints,
+                meta,
+                warnings,
             })
         }
     }
@@ -334,26 +615,29 @@ impl ParseResult {
     /// categories are included by default when no category filter is specified.
     ///
     /// # Arguments
-    /// * `categories` - List of categories to include (empty means include all)
+    /// * `categories` - The output's category expression (`None` means include all)
     /// * `excludes` - List of object names to explicitly exclude
     ///
     /// # Returns
     /// A vector of object references that match the filtering criteria
     pub fn included_strcts(
         &self,
-        categories: &[String],
+        categories: Option<&CategoryExpr>,
         excludes: &[String],
     ) -> Vec<&RepackStruct> {
         self.strcts
             .iter()
             .filter(|obj| {
-                if obj.categories.is_empty() || categories.is_empty() {
+                let Some(categories) = categories else {
+                    return true;
+                };
+                if obj.categories.is_empty() {
                     return true;
                 }
                 if excludes.contains(&obj.name) {
                     return false;
                 }
-                obj.categories.iter().any(|cat| categories.contains(cat))
+                categories.matches(&obj.categories)
             })
             .collect()
     }
@@ -364,23 +648,204 @@ impl ParseResult {
     /// based on category matching and exclusion rules.
     ///
     /// # Arguments
-    /// * `categories` - List of categories to include (empty means include all)
+    /// * `categories` - The output's category expression (`None` means include all)
     /// * `excludes` - List of enum names to explicitly exclude
     ///
     /// # Returns
     /// A vector of enum references that match the filtering criteria
-    pub fn included_enums(&self, categories: &[String], excludes: &[String]) -> Vec<&RepackEnum> {
+    pub fn included_configs(
+        &self,
+        categories: Option<&CategoryExpr>,
+        excludes: &[String],
+    ) -> Vec<&RepackConfig> {
+        self.configs
+            .iter()
+            .filter(|cfg| {
+                let Some(categories) = categories else {
+                    return true;
+                };
+                if cfg.categories.is_empty() {
+                    return true;
+                }
+                if excludes.contains(&cfg.name) {
+                    return false;
+                }
+                categories.matches(&cfg.categories)
+            })
+            .collect()
+    }
+
+    pub fn included_enums(
+        &self,
+        categories: Option<&CategoryExpr>,
+        excludes: &[String],
+    ) -> Vec<&RepackEnum> {
         self.enums
             .iter()
             .filter(|enm| {
-                if enm.categories.is_empty() || categories.is_empty() {
+                let Some(categories) = categories else {
+                    return true;
+                };
+                if enm.categories.is_empty() {
                     return true;
                 }
                 if excludes.contains(&enm.name) {
                     return false;
                 }
-                enm.categories.iter().any(|cat| categories.contains(cat))
+                categories.matches(&enm.categories)
             })
             .collect()
     }
+
+    /// Filters constants the same way as [`ParseResult::included_enums`].
+    pub fn included_consts(
+        &self,
+        categories: Option<&CategoryExpr>,
+        excludes: &[String],
+    ) -> Vec<&RepackConst> {
+        self.consts
+            .iter()
+            .filter(|cnst| {
+                let Some(categories) = categories else {
+                    return true;
+                };
+                if cnst.categories.is_empty() {
+                    return true;
+                }
+                if excludes.contains(&cnst.name) {
+                    return false;
+                }
+                categories.matches(&cnst.categories)
+            })
+            .collect()
+    }
+
+    /// Filters unions the same way as [`ParseResult::included_enums`].
+    pub fn included_unions(
+        &self,
+        categories: Option<&CategoryExpr>,
+        excludes: &[String],
+    ) -> Vec<&RepackUnion> {
+        self.unions
+            .iter()
+            .filter(|union| {
+                let Some(categories) = categories else {
+                    return true;
+                };
+                if union.categories.is_empty() {
+                    return true;
+                }
+                if excludes.contains(&union.name) {
+                    return false;
+                }
+                categories.matches(&union.categories)
+            })
+            .collect()
+    }
+}
+
+/// Adds the members of each mixin a struct lists to it, in the order listed.
+///
+/// Fields are added after the struct's own, and functions, joins, and queries
+/// after its own of the same kind. A field, join, or query whose name the struct
+/// or an earlier mixin already defines is a conflict, unless the struct names it
+/// in `mixin:overrides(...)`: then the struct's own definition is kept, or, if
+/// only mixins define it, the one from the mixin listed last.
+///
+/// # Returns
+/// One error for each mixin that doesn't exist and each conflicting name
+fn apply_mixins(strct: &mut RepackStruct, mixins: &[RepackMixin]) -> Vec<RepackError> {
+    let mut errors = Vec::new();
+    let overrides = strct
+        .functions_in_namespace("mixin")
+        .into_iter()
+        .filter(|f| f.name == "overrides")
+        .flat_map(|f| f.args.iter().map(|a| a.trim().to_string()))
+        .collect::<HashSet<_>>();
+    // Who defined each member, so a conflict can name both sides.
+    let mut owners = strct
+        .fields
+        .iter()
+        .map(|f| ("field", f.name.clone()))
+        .chain(strct.joins.iter().map(|j| ("join", j.name.clone())))
+        .chain(strct.queries.iter().map(|q| ("query", q.name.clone())))
+        .map(|member| (member, strct.name.clone()))
+        .collect::<HashMap<_, _>>();
+
+    for mixin_name in strct.mixins.clone() {
+        let Some(mixin) = mixins.iter().find(|m| m.name == mixin_name) else {
+            errors.push(RepackError::from_obj_with_msg(
+                RepackErrorKind::MixinNotFound,
+                strct,
+                mixin_name,
+            ));
+            continue;
+        };
+        // Whether to add a member: `Some(true)` if it replaces an earlier mixin's.
+        let mut admit = |kind: &'static str, name: &str| {
+            let member = (kind, name.to_string());
+            match owners.get(&member) {
+                None => {
+                    owners.insert(member, mixin.name.clone());
+                    Ok(Some(false))
+                }
+                Some(owner) if !overrides.contains(name) => Err(format!(
+                    "{kind} {name} from {} is also defined by {owner} (list it in mixin:overrides to choose)",
+                    mixin.name
+                )),
+                Some(owner) if *owner == strct.name => Ok(None),
+                Some(_) => {
+                    owners.insert(member, mixin.name.clone());
+                    Ok(Some(true))
+                }
+            }
+        };
+
+        let mut conflicts = Vec::new();
+        for field in &mixin.fields {
+            match admit("field", &field.name) {
+                Ok(Some(replaces)) => {
+                    if replaces {
+                        strct.fields.retain(|f| f.name != field.name);
+                    }
+                    strct.fields.push(field.clone());
+                }
+                Ok(None) => {}
+                Err(msg) => conflicts.push(msg),
+            }
+        }
+        for join in &mixin.joins {
+            match admit("join", &join.name) {
+                Ok(Some(replaces)) => {
+                    if replaces {
+                        strct.joins.retain(|j| j.name != join.name);
+                    }
+                    strct.joins.push(join.clone());
+                }
+                Ok(None) => {}
+                Err(msg) => conflicts.push(msg),
+            }
+        }
+        for query in &mixin.queries {
+            match admit("query", &query.name) {
+                Ok(Some(replaces)) => {
+                    if replaces {
+                        strct.queries.retain(|q| q.name != query.name);
+                    }
+                    strct.queries.push(query.clone());
+                }
+                Ok(None) => {}
+                Err(msg) => conflicts.push(msg),
+            }
+        }
+        strct.functions.extend(mixin.functions.iter().cloned());
+        for msg in conflicts {
+            errors.push(RepackError::from_obj_with_msg(
+                RepackErrorKind::MixinConflict,
+                strct,
+                msg,
+            ));
+        }
+    }
+    errors
 }
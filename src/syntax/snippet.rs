@@ -1,4 +1,4 @@
-use super::{Field, FileContents, ObjectFunction, Token, RepackError, RepackErrorKind};
+use super::{Field, FileContents, ObjectFunction, RepackError, RepackErrorKind, Token};
 
 #[derive(Debug)]
 pub struct Snippet {
@@ -12,26 +12,38 @@ impl Snippet {
         let Some(name_opt) = contents.next() else {
             return Err(RepackError::global(
                 RepackErrorKind::ParseIncomplete,
-                "snippet name".to_string()
+                "snippet name".to_string(),
             ));
         };
         let Token::Literal(name_ref) = name_opt else {
             return Err(RepackError::global(
                 RepackErrorKind::ParseIncomplete,
-                format!("{name_opt:?}")
+                format!("{name_opt:?}"),
             ));
         };
         let name = name_ref.to_string();
         let mut fields = Vec::new();
         let mut functions = Vec::new();
 
-        while let Some(next) = contents.take() {
+        loop {
+            let Some(next) = contents.take() else {
+                return Err(RepackError::global(
+                    RepackErrorKind::ParseIncomplete,
+                    format!("'{{' to open snippet {name}"),
+                ));
+            };
             if next == Token::OpenBrace {
                 break;
             }
         }
 
-        'cmd: while let Some(token) = contents.take() {
+        'cmd: loop {
+            let Some(token) = contents.take() else {
+                return Err(RepackError::global(
+                    RepackErrorKind::ParseIncomplete,
+                    format!("'}}' to close snippet {name}"),
+                ));
+            };
             match token {
                 Token::CloseBrace => {
                     break 'cmd;
@@ -44,7 +56,8 @@ impl Snippet {
                             {
                                 functions.push(func);
                             }
-                        } else if let Some(field) = Field::from_contents(lit.to_string(), contents) {
+                        } else if let Some(field) = Field::from_contents(lit.to_string(), contents)
+                        {
                             fields.push(field);
                         }
                     }
@@ -53,6 +66,10 @@ impl Snippet {
             }
         }
 
-        Ok(Snippet { name, fields, functions })
+        Ok(Snippet {
+            name,
+            fields,
+            functions,
+        })
     }
 }
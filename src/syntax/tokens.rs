@@ -23,6 +23,8 @@ pub enum Token {
     Colon,
     Semicolon,
     Equal,
+    Ampersand,
+    Pipe,
 
     Literal(String),
     OutputType,
@@ -73,6 +75,8 @@ impl Token {
             b'+' => Some(Token::Plus),
             b'-' => Some(Token::Minus),
             b'=' => Some(Token::Equal),
+            b'&' => Some(Token::Ampersand),
+            b'|' => Some(Token::Pipe),
             _ => None,
         }
     }
@@ -12,6 +12,9 @@ pub enum CustomFieldType {
     /// References an enumeration type defined elsewhere in the schema.
     /// Enums provide a way to define a fixed set of possible values for a field.
     Enum,
+    /// References a union defined elsewhere in the schema, holding exactly one
+    /// of several structs.
+    Union,
 }
 
 /// Represents the fundamental built-in data types supported by the schema system.
@@ -27,6 +30,12 @@ pub enum CoreType {
     Int64,
     /// 32-bit signed integer. Standard integer type for most numeric fields.
     Int32,
+    /// 16-bit signed integer. For small counters and compact columns.
+    Int16,
+    /// 64-bit unsigned integer. Not every target can represent the full range.
+    Uint64,
+    /// 32-bit unsigned integer.
+    Uint32,
     /// 64-bit floating point number. Used for decimal and scientific calculations.
     Float64,
     /// Boolean true/false value. Maps to bool/boolean in target languages.
@@ -39,6 +48,21 @@ pub enum CoreType {
     Bytes,
 }
 impl CoreType {
+    /// Every core type, in the order they are documented.
+    pub const ALL: [CoreType; 11] = [
+        Self::String,
+        Self::Int64,
+        Self::Int32,
+        Self::Int16,
+        Self::Uint64,
+        Self::Uint32,
+        Self::Float64,
+        Self::Boolean,
+        Self::DateTime,
+        Self::Uuid,
+        Self::Bytes,
+    ];
+
     /// Parses a string literal into a CoreType enum variant.
     ///
     /// This function is used during schema parsing to convert type names
@@ -61,6 +85,9 @@ impl CoreType {
             "string" => Self::String,
             "int64" => Self::Int64,
             "int32" => Self::Int32,
+            "int16" => Self::Int16,
+            "uint64" => Self::Uint64,
+            "uint32" => Self::Uint32,
             "float64" => Self::Float64,
             "boolean" => Self::Boolean,
             "datetime" => Self::DateTime,
@@ -76,6 +103,9 @@ impl Display for CoreType {
             Self::String => "string".to_string(),
             Self::Int64 => "int64".to_string(),
             Self::Int32 => "int32".to_string(),
+            Self::Int16 => "int16".to_string(),
+            Self::Uint64 => "uint64".to_string(),
+            Self::Uint32 => "uint32".to_string(),
             Self::Float64 => "float64".to_string(),
             Self::Boolean => "boolean".to_string(),
             Self::DateTime => "datetime".to_string(),
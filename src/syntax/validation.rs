@@ -0,0 +1,116 @@
+use super::{CoreType, FieldFunction, FieldType};
+
+/// The validation rules that can be attached to a field with the `validate` namespace.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationRule {
+    /// `validate:min_len(n)` - minimum string length
+    MinLen,
+    /// `validate:max_len(n)` - maximum string length
+    MaxLen,
+    /// `validate:range(min max)` - inclusive numeric range
+    Range,
+    /// `validate:min(n)` - inclusive numeric lower bound
+    Min,
+    /// `validate:max(n)` - inclusive numeric upper bound
+    Max,
+    /// `validate:regex("pattern")` - the string must match the pattern
+    Regex,
+    /// `validate:email` - the string must be an email address
+    Email,
+    /// `validate:url` - the string must be a URL
+    Url,
+    /// `validate:not_empty` - the string must not be empty
+    NotEmpty,
+}
+impl ValidationRule {
+    pub fn from_string(val: &str) -> Option<ValidationRule> {
+        Some(match val {
+            "min_len" => Self::MinLen,
+            "max_len" => Self::MaxLen,
+            "range" => Self::Range,
+            "min" => Self::Min,
+            "max" => Self::Max,
+            "regex" => Self::Regex,
+            "email" => Self::Email,
+            "url" => Self::Url,
+            "not_empty" => Self::NotEmpty,
+            _ => return None,
+        })
+    }
+
+    /// The number of arguments this rule requires.
+    fn arity(&self) -> usize {
+        match self {
+            Self::Range => 2,
+            Self::MinLen | Self::MaxLen | Self::Min | Self::Max | Self::Regex => 1,
+            Self::Email | Self::Url | Self::NotEmpty => 0,
+        }
+    }
+
+    /// Whether this rule applies to text (as opposed to numbers).
+    fn is_textual(&self) -> bool {
+        !matches!(self, Self::Range | Self::Min | Self::Max)
+    }
+
+    /// Converts a `validate:` field function into a validation rule.
+    ///
+    /// Validations are declared as field functions in the `validate` namespace, such as
+    /// `name string validate:max_len(80)`, and are checked against the field's type once
+    /// the schema is resolved.
+    ///
+    /// # Arguments
+    /// * `func` - A field function in the `validate` namespace
+    /// * `field_type` - The resolved type of the field, if known
+    ///
+    /// # Returns
+    /// * `Ok(ValidationRule)` if the rule exists and its arguments fit the field
+    /// * `Err(String)` describing why the rule is invalid
+    pub fn from_function(
+        func: &FieldFunction,
+        field_type: Option<&FieldType>,
+    ) -> Result<ValidationRule, String> {
+        let rule = ValidationRule::from_string(&func.name)
+            .ok_or_else(|| format!("unknown rule '{}'", func.name))?;
+        if func.args.len() != rule.arity() {
+            return Err(format!(
+                "'{}' expects {} argument(s), found {}",
+                func.name,
+                rule.arity(),
+                func.args.len()
+            ));
+        }
+        match rule {
+            ValidationRule::MinLen | ValidationRule::MaxLen
+                if func.args[0].parse::<usize>().is_err() =>
+            {
+                return Err(format!(
+                    "'{}' expects a length, found '{}'",
+                    func.name, func.args[0]
+                ));
+            }
+            ValidationRule::Range | ValidationRule::Min | ValidationRule::Max => {
+                if let Some(arg) = func.args.iter().find(|a| a.parse::<f64>().is_err()) {
+                    return Err(format!("'{}' expects a number, found '{arg}'", func.name));
+                }
+            }
+            _ => {}
+        }
+        if let Some(FieldType::Core(core)) = field_type {
+            let textual = matches!(core, CoreType::String);
+            let numeric = matches!(
+                core,
+                CoreType::Int16
+                    | CoreType::Int32
+                    | CoreType::Int64
+                    | CoreType::Uint32
+                    | CoreType::Uint64
+                    | CoreType::Float64
+            );
+            if (rule.is_textual() && !textual) || (!rule.is_textual() && !numeric) {
+                return Err(format!("'{}' cannot be applied to {core}", func.name));
+            }
+        }
+
+        Ok(rule)
+    }
+}
@@ -0,0 +1,118 @@
+use std::time::{Duration, Instant};
+
+use crate::{blueprint::RenderStats, syntax::Json};
+
+/// Durations and output sizes recorded for `--timings`.
+///
+/// Phases (parsing, resolution, loading blueprints) are timed in `main`, and
+/// each output adds the [`RenderStats`] from its build.
+#[derive(Default)]
+pub struct Timings {
+    phases: Vec<(String, Duration)>,
+    outputs: Vec<(String, RenderStats)>,
+}
+impl Timings {
+    /// Records a phase that started at `start` and ends now.
+    pub fn phase(&mut self, name: &str, start: Instant) {
+        self.phases.push((name.to_string(), start.elapsed()));
+    }
+
+    pub fn output(&mut self, name: &str, stats: RenderStats) {
+        self.outputs.push((name.to_string(), stats));
+    }
+
+    fn total(&self) -> Duration {
+        self.phases.iter().map(|(_, d)| *d).sum::<Duration>()
+            + self
+                .outputs
+                .iter()
+                .map(|(_, s)| s.render + s.write + s.format)
+                .sum::<Duration>()
+    }
+
+    /// The output with the most bytes, which usually dominates the write phase.
+    fn peak(&self) -> Option<&(String, RenderStats)> {
+        self.outputs.iter().max_by_key(|(_, s)| (s.bytes, s.files))
+    }
+
+    /// Formats the timings as plain-text tables, with durations in milliseconds.
+    pub fn to_table(&self) -> String {
+        let mut out = format!("{:<24}{:>10}\n", "phase", "ms");
+        for (name, duration) in &self.phases {
+            out.push_str(&format!("{name:<24}{:>10.2}\n", ms(*duration)));
+        }
+        out.push_str(&format!(
+            "\n{:<24}{:>10}{:>10}{:>10}{:>8}{:>12}\n",
+            "output", "render", "write", "format", "files", "bytes"
+        ));
+        for (name, s) in &self.outputs {
+            out.push_str(&format!(
+                "{name:<24}{:>10.2}{:>10.2}{:>10.2}{:>8}{:>12}\n",
+                ms(s.render),
+                ms(s.write),
+                ms(s.format),
+                s.files,
+                s.bytes
+            ));
+        }
+        let files: usize = self.outputs.iter().map(|(_, s)| s.files).sum();
+        let bytes: usize = self.outputs.iter().map(|(_, s)| s.bytes).sum();
+        out.push_str(&format!(
+            "\ntotal {:.2} ms, {files} file(s), {bytes} bytes\n",
+            ms(self.total())
+        ));
+        if let Some((name, s)) = self.peak() {
+            out.push_str(&format!(
+                "largest output: {name} ({} file(s), {} bytes)\n",
+                s.files, s.bytes
+            ));
+        }
+        out
+    }
+
+    pub fn to_json(&self) -> Json {
+        let phases = self
+            .phases
+            .iter()
+            .map(|(name, duration)| {
+                Json::object([
+                    ("name", Json::str(name)),
+                    ("ms", Json::Number(ms(*duration))),
+                ])
+            })
+            .collect();
+        let outputs = self
+            .outputs
+            .iter()
+            .map(|(name, s)| {
+                Json::object([
+                    ("name", Json::str(name)),
+                    ("render_ms", Json::Number(ms(s.render))),
+                    ("write_ms", Json::Number(ms(s.write))),
+                    ("format_ms", Json::Number(ms(s.format))),
+                    ("files", Json::Number(s.files as f64)),
+                    ("bytes", Json::Number(s.bytes as f64)),
+                ])
+            })
+            .collect();
+        let peak = match self.peak() {
+            Some((name, s)) => Json::object([
+                ("name", Json::str(name)),
+                ("files", Json::Number(s.files as f64)),
+                ("bytes", Json::Number(s.bytes as f64)),
+            ]),
+            None => Json::Null,
+        };
+        Json::object([
+            ("total_ms", Json::Number(ms(self.total()))),
+            ("phases", Json::Array(phases)),
+            ("outputs", Json::Array(outputs)),
+            ("largest_output", peak),
+        ])
+    }
+}
+
+/// Milliseconds, rounded to the microsecond.
+fn ms(duration: Duration) -> f64 {
+    (duration.as_secs_f64() * 1_000_000.0).round() / 1000.0
+}
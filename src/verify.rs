@@ -0,0 +1,131 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use crate::{
+    blueprint::{Blueprint, SnippetMainTokenName, SnippetSecondaryTokenName},
+    syntax::{CoreType, ParseResult, QueryArg, RepackError, RepackErrorKind, quote_ident},
+};
+
+/// Plans every query in the schema against a live Postgres database.
+///
+/// Each query is rendered as it is for generated code, prepared with its
+/// arguments' types, and run through `EXPLAIN EXECUTE` with null arguments
+/// inside a transaction that is rolled back, so nothing is written. Argument
+/// types come from the `[define]`s of the given blueprint (normally
+/// `postgres`); enum arguments use the enum's name, quoted as the blueprint
+/// creates it. Tables and enum types are qualified with the `schema` option of
+/// a `postgres` output, if there is one, as in the generated SQL.
+///
+/// # Arguments
+/// * `result` - The resolved schema
+/// * `postgres` - The blueprint whose type mappings name the argument types
+/// * `connection` - A `psql` connection string or database name
+///
+/// # Returns
+/// * `Ok((usize, Vec<RepackError>))` with the number of queries checked and one error per query that failed to plan
+/// * `Err(RepackError)` if `psql` could not be run
+pub fn verify_queries(
+    result: &ParseResult,
+    postgres: &Blueprint,
+    connection: &str,
+) -> Result<(usize, Vec<RepackError>), RepackError> {
+    let schema = result
+        .languages
+        .iter()
+        .find(|output| output.profile == "postgres")
+        .and_then(|output| output.options.get("schema"))
+        .map(|s| s.as_str());
+    let mut checked = 0;
+    let mut failures = Vec::new();
+    for strct in &result.strcts {
+        for query in &strct.queries {
+            checked += 1;
+            let sql = query.render(strct, &result.strcts, schema, postgres.column_types)?;
+            let types = query
+                .args
+                .iter()
+                .map(|arg| arg_type(postgres, arg, schema))
+                .collect::<Vec<_>>();
+            let nulls = vec!["NULL"; types.len()];
+            let (params, values) = if types.is_empty() {
+                (String::new(), String::new())
+            } else {
+                (
+                    format!("({})", types.join(", ")),
+                    format!("({})", nulls.join(", ")),
+                )
+            };
+            let script = format!(
+                "BEGIN;\nPREPARE repack_verify{params} AS {sql}\nEXPLAIN EXECUTE repack_verify{values};\nROLLBACK;\n"
+            );
+            if let Some(message) = run(connection, &script)? {
+                failures.push(RepackError::from_obj_with_msg(
+                    RepackErrorKind::QueryFailedToPlan,
+                    strct,
+                    format!("{}: {message}", query.name),
+                ));
+            }
+        }
+    }
+    Ok((checked, failures))
+}
+
+/// The Postgres type of a query argument, with `[]` for arrays. Enum types
+/// are qualified with `schema`.
+fn arg_type(postgres: &Blueprint, arg: &QueryArg, schema: Option<&str>) -> String {
+    let typ = match CoreType::from_string(&arg.typ) {
+        Some(core) => postgres
+            .utilities
+            .get(&(
+                SnippetMainTokenName::TypeDef,
+                SnippetSecondaryTokenName::from_type(&core),
+            ))
+            .cloned()
+            .unwrap_or_else(|| arg.typ.to_string()),
+        None => match schema {
+            Some(schema) => format!("{}.{}", quote_ident(schema), quote_ident(&arg.typ)),
+            None => quote_ident(&arg.typ),
+        },
+    };
+    if arg.array { format!("{typ}[]") } else { typ }
+}
+
+/// Runs a script with `psql`, stopping at the first error.
+///
+/// # Returns
+/// * `Ok(None)` if every statement succeeded
+/// * `Ok(Some(String))` with the error Postgres reported for a statement
+/// * `Err(RepackError)` if `psql` could not be started or could not connect
+fn run(connection: &str, script: &str) -> Result<Option<String>, RepackError> {
+    let failed = |msg: String| RepackError::global(RepackErrorKind::ProcessExecutionFailed, msg);
+    let mut psql = Command::new("psql")
+        .args(["-X", "-q", "-v", "ON_ERROR_STOP=1", "-d", connection])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| failed(format!("could not run psql: {e}")))?;
+    if let Some(mut stdin) = psql.stdin.take() {
+        stdin
+            .write_all(script.as_bytes())
+            .map_err(|e| failed(format!("could not run psql: {e}")))?;
+    }
+    let output = psql
+        .wait_with_output()
+        .map_err(|e| failed(format!("could not run psql: {e}")))?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    match output.status.code() {
+        Some(0) => Ok(None),
+        // psql exits with 3 when a statement in the script fails.
+        Some(3) => Ok(Some(
+            stderr
+                .lines()
+                .find_map(|line| line.split_once("ERROR:").map(|(_, msg)| msg.trim()))
+                .unwrap_or(stderr.trim())
+                .to_string(),
+        )),
+        _ => Err(failed(stderr.trim().to_string())),
+    }
+}
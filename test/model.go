@@ -1,7 +1,7 @@
 package main;
-import "time"
 import "database/sql"
 import "github.com/google/uuid"
+import "time"
 
 
 type UserType string
@@ -30,7 +30,7 @@ func ScanUser(val *User, row *sql.Rows) error {
 }
 
 func UserByEmail(db *sql.DB, _email string) (*User, error) {
-	rows, err := db.Query("SELECT users.id AS id, users.created_date AS created_date, users.last_login AS last_login, users.name AS name, users.email AS email, users.user_type AS user_type, users.subscription_id AS subscription_id, LOWER(name) || '_' || LOWER(email) AS email_id FROM users WHERE users.email = $1;", _email)
+	rows, err := db.Query(`SELECT users.id AS id, users.created_date AS created_date, users.last_login AS last_login, users.name AS name, users.email AS email, users.user_type AS user_type, users.subscription_id AS subscription_id, LOWER(name) || '_' || LOWER(email) AS email_id FROM users WHERE users.email = $1;`, _email)
 	if err != nil {
 		return nil, err		
 	}
@@ -43,7 +43,7 @@ func UserByEmail(db *sql.DB, _email string) (*User, error) {
 }
 func UsersByType(db *sql.DB, _typ UserType) ([]User, error) {
 	values := make([]User, 0)
-	rows, err := db.Query("SELECT users.id AS id, users.created_date AS created_date, users.last_login AS last_login, users.name AS name, users.email AS email, users.user_type AS user_type, users.subscription_id AS subscription_id, LOWER(name) || '_' || LOWER(email) AS email_id FROM users WHERE users.user_type = $1;", _typ)
+	rows, err := db.Query(`SELECT users.id AS id, users.created_date AS created_date, users.last_login AS last_login, users.name AS name, users.email AS email, users.user_type AS user_type, users.subscription_id AS subscription_id, LOWER(name) || '_' || LOWER(email) AS email_id FROM users WHERE users.user_type = $1;`, _typ)
 	if err != nil {
 		return values, err		
 	}
@@ -58,7 +58,7 @@ func UsersByType(db *sql.DB, _typ UserType) ([]User, error) {
 	return values, nil
 }
 func DeleteUserById(db *sql.DB, _id uuid.UUID) error {
-	rows, err := db.Query("DELETE FROM users WHERE users.id = $1;", _id)
+	rows, err := db.Query(`DELETE FROM users WHERE users.id = $1;`, _id)
 	if err != nil {
 		return err		
 	}
@@ -66,7 +66,7 @@ func DeleteUserById(db *sql.DB, _id uuid.UUID) error {
 	return nil
 }
 func CreateUser(db *sql.DB, __id uuid.UUID, __name string, __email string, __user_type UserType) (*User, error) {
-	rows, err := db.Query("WITH users AS (INSERT INTO users (id, name, email, user_type) VALUES ($1, $2, $3, $4) RETURNING *) SELECT users.id AS id, users.created_date AS created_date, users.last_login AS last_login, users.name AS name, users.email AS email, users.user_type AS user_type, users.subscription_id AS subscription_id, LOWER(name) || '_' || LOWER(email) AS email_id FROM users;", __id, __name, __email, __user_type)
+	rows, err := db.Query(`WITH users AS (INSERT INTO users (id, name, email, user_type) VALUES ($1, $2, $3, $4) RETURNING *) SELECT users.id AS id, users.created_date AS created_date, users.last_login AS last_login, users.name AS name, users.email AS email, users.user_type AS user_type, users.subscription_id AS subscription_id, LOWER(name) || '_' || LOWER(email) AS email_id FROM users;`, __id, __name, __email, __user_type)
 	if err != nil {
 		return nil, err		
 	}
@@ -78,7 +78,7 @@ func CreateUser(db *sql.DB, __id uuid.UUID, __name string, __email string, __use
 	return &value, ScanUser(&value, rows)
 }
 func UpdateUserEmail(db *sql.DB, _id uuid.UUID, _email string) error {
-	rows, err := db.Query("WITH users AS (UPDATE users SET email = $2 WHERE id = $1 RETURNING *) SELECT users.id AS id, users.created_date AS created_date, users.last_login AS last_login, users.name AS name, users.email AS email, users.user_type AS user_type, users.subscription_id AS subscription_id, LOWER(name) || '_' || LOWER(email) AS email_id FROM users;", _id, _email)
+	rows, err := db.Query(`WITH users AS (UPDATE users SET email = $2 WHERE id = $1 RETURNING *) SELECT users.id AS id, users.created_date AS created_date, users.last_login AS last_login, users.name AS name, users.email AS email, users.user_type AS user_type, users.subscription_id AS subscription_id, LOWER(name) || '_' || LOWER(email) AS email_id FROM users;`, _id, _email)
 	if err != nil {
 		return err		
 	}
@@ -104,7 +104,7 @@ func ScanUserWithToken(val *UserWithToken, row *sql.Rows) error {
 
 func UserToken(db *sql.DB, _id uuid.UUID) ([]UserWithToken, error) {
 	values := make([]UserWithToken, 0)
-	rows, err := db.Query("SELECT users.id AS user_id, t.token_value AS token_value FROM users INNER JOIN tokens t ON users.id = t.user_id WHERE users.id = $1;", _id)
+	rows, err := db.Query(`SELECT users.id AS user_id, t.token_value AS token_value FROM users INNER JOIN tokens t ON users.id = t.user_id WHERE users.id = $1;`, _id)
 	if err != nil {
 		return values, err		
 	}
@@ -3,8 +3,27 @@ BEGIN;
 DROP TABLE IF EXISTS users;
 DROP TABLE IF EXISTS tokens;
 DROP TABLE IF EXISTS users;
-DROP TYPE IF EXISTS UserType;
-CREATE TYPE UserType AS ENUM('Admin', 'User', 'Guest');
+DO $$
+DECLARE
+	dependents TEXT;
+BEGIN
+	SELECT string_agg(c.relname || '.' || a.attname, ', ') INTO dependents
+	FROM pg_attribute a
+	JOIN pg_class c ON c.oid = a.attrelid
+	JOIN pg_type t ON t.oid = a.atttypid
+	WHERE a.attnum > 0 AND NOT a.attisdropped
+	AND (a.atttypid = to_regtype('UserType') OR t.typelem = to_regtype('UserType'));
+	IF dependents IS NULL THEN
+		DROP TYPE IF EXISTS UserType;
+		CREATE TYPE UserType AS ENUM('Admin', 'User', 'Guest');
+	ELSIF EXISTS (SELECT 1 FROM pg_enum WHERE enumtypid = to_regtype('UserType') AND enumlabel NOT IN ('Admin', 'User', 'Guest')) THEN
+		RAISE EXCEPTION 'Cannot remove values from enum UserType while these columns use it: %', dependents;
+	ELSE
+		ALTER TYPE UserType ADD VALUE IF NOT EXISTS 'Admin';
+		ALTER TYPE UserType ADD VALUE IF NOT EXISTS 'User';
+		ALTER TYPE UserType ADD VALUE IF NOT EXISTS 'Guest';
+	END IF;
+END $$;
 CREATE TABLE users (
 	id UUID NOT NULL PRIMARY KEY,
 	created_date TIMESTAMPTZ NOT NULL DEFAULT NOW(),